@@ -0,0 +1,167 @@
+use dashmap::DashSet;
+use log::{debug, warn};
+use lru::LruCache;
+use serde::Deserialize;
+use serde_json::json;
+use solana_account_decoder_client_types::UiAccount;
+use std::{
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+const RPC_TIMEOUT_SECS: u64 = 5; // 单次RPC请求超时（秒）
+pub const DEFAULT_RPC_MAX_REQUESTS_PER_SEC: u32 = 10; // 默认每秒最多查询次数
+pub const DEFAULT_RPC_WAIT_TIMEOUT_MS: u64 = 500; // 默认解码时等待查询结果的时间（毫秒）
+const MISS_BACKOFF: Duration = Duration::from_secs(30); // 查询失败或账户不存在后，同一账户暂停查询的时间
+const RESULT_CAPACITY: usize = 1024; // 等待超时后才返回的查询结果最多保留条数
+const MISS_CAPACITY: usize = 10_000; // 最多记录的查询失败账户数
+
+/// 按需通过Solana JSON-RPC读取账户数据，用于缓存中没有绑定曲线数据时补充储备和价格
+///
+/// 查询是尽力而为的：超过每秒查询次数、同一账户已有查询进行中或最近查询失败时直接放弃；
+/// 解码只等待 `wait_timeout`，超时后查询在后台继续，结果保留到下次查找该账户时使用。
+pub struct AccountRpc {
+    client: reqwest::Client,
+    url: String,
+    max_requests_per_sec: u32,
+    wait_timeout: Duration,
+    window: Mutex<(Instant, u32)>,                  // (当前一秒窗口的开始时间, 窗口内已发出的查询数)
+    in_flight: DashSet<String>,                     // 正在查询的账户
+    results: Mutex<LruCache<String, Vec<u8>>>,      // 已查询到但尚未取走的账户数据
+    misses: Mutex<LruCache<String, Instant>>,       // 最近查询失败或不存在的账户 -> 失败时间
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    result: Option<RpcResult>,
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct RpcResult {
+    value: Option<UiAccount>,
+}
+
+impl AccountRpc {
+    pub fn new(url: String, max_requests_per_sec: u32, wait_timeout: Duration) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(RPC_TIMEOUT_SECS))
+            .build()?;
+        Ok(Self {
+            client,
+            url,
+            max_requests_per_sec: max_requests_per_sec.max(1),
+            wait_timeout,
+            window: Mutex::new((Instant::now(), 0)),
+            in_flight: DashSet::new(),
+            results: Mutex::new(LruCache::new(NonZeroUsize::new(RESULT_CAPACITY).unwrap_or(NonZeroUsize::MIN))),
+            misses: Mutex::new(LruCache::new(NonZeroUsize::new(MISS_CAPACITY).unwrap_or(NonZeroUsize::MIN))),
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn max_requests_per_sec(&self) -> u32 {
+        self.max_requests_per_sec
+    }
+
+    pub fn wait_timeout(&self) -> Duration {
+        self.wait_timeout
+    }
+
+    /// 取走之前查询到的账户数据（包括等待超时后才完成的查询）
+    pub fn take_result(&self, pubkey: &str) -> Option<Vec<u8>> {
+        self.results.lock().expect("rpc results lock poisoned").pop(pubkey)
+    }
+
+    /// 开始查询账户：同一账户已在查询、最近查询失败或超过每秒查询次数时返回false
+    pub fn try_begin(&self, pubkey: &str) -> bool {
+        if let Some(failed_at) = self.misses.lock().expect("rpc misses lock poisoned").get(pubkey) {
+            if failed_at.elapsed() < MISS_BACKOFF {
+                return false;
+            }
+        }
+        if self.in_flight.contains(pubkey) {
+            return false;
+        }
+        {
+            let mut window = self.window.lock().expect("rpc window lock poisoned");
+            if window.0.elapsed() >= Duration::from_secs(1) {
+                *window = (Instant::now(), 0);
+            }
+            if window.1 >= self.max_requests_per_sec {
+                debug!("[RPC] 超过每秒 {} 次查询，跳过账户({})", self.max_requests_per_sec, pubkey);
+                return false;
+            }
+            window.1 += 1;
+        }
+        self.in_flight.insert(pubkey.to_string())
+    }
+
+    /// 查询账户并保存结果（需先通过 `try_begin`），由 `take_result` 取走
+    pub async fn fetch(self: Arc<Self>, pubkey: String) {
+        match self.get_account_data(&pubkey).await {
+            Ok(Some(bytes)) => {
+                self.results.lock().expect("rpc results lock poisoned").put(pubkey.clone(), bytes);
+            }
+            Ok(None) => {
+                debug!("[RPC] 账户({})不存在", pubkey);
+                self.misses.lock().expect("rpc misses lock poisoned").put(pubkey.clone(), Instant::now());
+            }
+            Err(e) => {
+                warn!("[RPC] 查询账户({})失败: {}", pubkey, e);
+                self.misses.lock().expect("rpc misses lock poisoned").put(pubkey.clone(), Instant::now());
+            }
+        }
+        self.in_flight.remove(&pubkey);
+    }
+
+    /// 读取账户的原始数据（`getAccountInfo`），账户不存在时返回None
+    pub async fn get_account_data(&self, pubkey: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getAccountInfo",
+            "params": [pubkey, { "encoding": "base64", "commitment": "confirmed" }],
+        });
+        let response: RpcResponse = self
+            .client
+            .post(&self.url)
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if let Some(error) = response.error {
+            return Err(anyhow::anyhow!("RPC返回错误: {}", error));
+        }
+        let Some(account) = response.result.and_then(|result| result.value) else {
+            return Ok(None);
+        };
+        account
+            .data
+            .decode()
+            .map(Some)
+            .ok_or_else(|| anyhow::anyhow!("无法解码账户({})数据", pubkey))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rpc_fetches_are_rate_limited_and_deduplicated() {
+        let rpc = AccountRpc::new("http://127.0.0.1:1".to_string(), 2, Duration::from_millis(10)).unwrap();
+        assert!(rpc.try_begin("a"));
+        // 同一账户已在查询中
+        assert!(!rpc.try_begin("a"));
+        assert!(rpc.try_begin("b"));
+        // 超过每秒2次
+        assert!(!rpc.try_begin("c"));
+    }
+}
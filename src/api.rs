@@ -0,0 +1,115 @@
+use crate::{metrics::METRICS, TransactionCache};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use log::info;
+use serde_json::json;
+use std::sync::Arc;
+
+/// 启动缓存查询HTTP服务
+///
+/// - `GET /tx/{signature}`: 缓存的买入/卖出交易数据
+/// - `GET /mint/{mint}/latest`: 代币最新的账户数据
+/// - `GET /mint/{mint}/reserves`: 代币最新的储备信息
+/// - `GET /mint/{mint}/candle`: 代币当前未收盘的K线（需配置 candle_interval_secs）
+/// - `GET /mint/{mint}/volume`: 代币滑动窗口内的买卖笔数和成交额（需配置 volume_window_secs 或 volume_spike_sol）
+/// - `GET /global`: 最近一次观察到的Global账户协议参数（需启用账户监控）
+/// - `GET /stats`: 累计处理数和内存缓存当前条目数，与指标服务的 `/stats` 相同
+pub async fn serve(port: u16, cache: Arc<TransactionCache>) -> anyhow::Result<()> {
+    let app = Router::new()
+        .route("/tx/:signature", get(get_transaction))
+        .route("/mint/:mint/latest", get(get_latest_account_data))
+        .route("/mint/:mint/reserves", get(get_latest_reserves))
+        .route("/mint/:mint/candle", get(get_current_candle))
+        .route("/mint/:mint/volume", get(get_mint_volume))
+        .route("/global", get(get_global_state))
+        .route("/stats", get(get_stats))
+        .with_state(cache);
+
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await?;
+    info!("[API] 缓存查询服务已启动: http://0.0.0.0:{}", port);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+fn not_found(what: &str, key: &str) -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": format!("未找到{}: {}", what, key) })),
+    )
+        .into_response()
+}
+
+async fn get_transaction(
+    State(cache): State<Arc<TransactionCache>>,
+    Path(signature): Path<String>,
+) -> Response {
+    let found = cache
+        .get_buy_transaction(&signature)
+        .map(|data| ("buy", data))
+        .or_else(|| cache.get_sell_transaction(&signature).map(|data| ("sell", data)));
+    match found {
+        Some((kind, data)) => Json(json!({
+            "signature": signature,
+            "type": kind,
+            "data": data,
+        }))
+        .into_response(),
+        None => not_found("交易", &signature),
+    }
+}
+
+async fn get_latest_account_data(
+    State(cache): State<Arc<TransactionCache>>,
+    Path(mint): Path<String>,
+) -> Response {
+    match cache.get_latest_account_data(&mint) {
+        Some(data) => Json(json!({ "mint": mint, "data": data })).into_response(),
+        None => not_found("代币账户数据", &mint),
+    }
+}
+
+async fn get_latest_reserves(
+    State(cache): State<Arc<TransactionCache>>,
+    Path(mint): Path<String>,
+) -> Response {
+    match cache.get_latest_reserves(&mint) {
+        Some(reserves) => Json(json!({ "mint": mint, "reserves": reserves })).into_response(),
+        None => not_found("代币储备", &mint),
+    }
+}
+
+async fn get_current_candle(
+    State(cache): State<Arc<TransactionCache>>,
+    Path(mint): Path<String>,
+) -> Response {
+    match cache.get_current_candle(&mint) {
+        Some(candle) => Json(json!({ "mint": mint, "candle": candle })).into_response(),
+        None => not_found("代币K线", &mint),
+    }
+}
+
+async fn get_mint_volume(
+    State(cache): State<Arc<TransactionCache>>,
+    Path(mint): Path<String>,
+) -> Response {
+    match cache.get_mint_volume(&mint) {
+        Some(volume) => Json(json!({ "mint": mint, "volume": volume })).into_response(),
+        None => not_found("代币成交量", &mint),
+    }
+}
+
+async fn get_global_state(State(cache): State<Arc<TransactionCache>>) -> Response {
+    match cache.get_global_state() {
+        Some(global) => Json(json!({ "global": global })).into_response(),
+        None => not_found("Global账户", "global"),
+    }
+}
+
+async fn get_stats(State(cache): State<Arc<TransactionCache>>) -> Json<serde_json::Value> {
+    Json(METRICS.stats_json(Some(&cache)))
+}
@@ -0,0 +1,350 @@
+//! 读取监控程序写入Redis的缓存数据
+//!
+//! Redis中的键（未配置 `redis_key_prefix` 时）：
+//! - `{signature}`: 买入/卖出交易的文本数据（交易日志加上关联的曲线账户、储备、价格和创作者金库）
+//! - `tx:{signature}`: `redis_trade_format = "hash"` 时的买入/卖出交易哈希，字段与CPI日志JSON相同
+//! - `{账户地址}`: 账户信息文本，绑定曲线账户包含储备和complete标志
+//! - `trades:{mint}`: 代币最近买卖交易的签名（有序集合，score为槽位），按 `mint_trade_index_len` 截断
+//! - `graduation:{mint}`: 代币毕业事件（JSON）
+//! - `candles:{周期}s:{mint}`: 收盘的K线（有序集合，score为K线开始时间）
+//! - `pump:curve2mint`、`pump:vault2creator`、`pump:mint2creator`: 学到的曲线账户/创作者金库/代币映射（哈希），
+//!   每个哈希只保留最近使用的10万条映射
+//!
+//! 配置了前缀时所有键都以 `{prefix}:` 开头，交易文本和账户数据另加 `trade:`/`acct:` 类型段，
+//! 映射哈希以前缀代替 `pump`，例如 `bot1:trade:{signature}`、`bot1:acct:{账户地址}`、`bot1:curve2mint`。
+//!
+//! 写入和读取使用同一组键名函数和解析函数，格式变化时由测试发现。
+
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 买卖交易写入Redis的格式
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum RedisTradeFormat {
+    /// 以签名为键的文本（交易日志加上关联数据）
+    #[default]
+    #[serde(rename = "text")]
+    Text,
+    /// 以 `tx:{signature}` 为键的哈希，每个CPI日志字段一项，可用 `HGET` 读取单个字段
+    #[serde(rename = "hash")]
+    Hash,
+}
+
+/// 按 `redis_key_prefix` 生成的Redis键名，前缀为空时与未加前缀的旧键名相同
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RedisKeys {
+    prefix: String,
+}
+
+impl RedisKeys {
+    /// 忽略前缀两端的空白和末尾的 `:`
+    pub fn new(prefix: Option<&str>) -> Self {
+        Self {
+            prefix: prefix.map(|prefix| prefix.trim().trim_end_matches(':').to_string()).unwrap_or_default(),
+        }
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    fn prefixed(&self, key: String) -> String {
+        if self.prefix.is_empty() {
+            key
+        } else {
+            format!("{}:{}", self.prefix, key)
+        }
+    }
+
+    // 没有前缀时直接使用签名或地址作为键
+    fn typed(&self, kind: &str, id: &str) -> String {
+        if self.prefix.is_empty() {
+            id.to_string()
+        } else {
+            format!("{}:{}:{}", self.prefix, kind, id)
+        }
+    }
+
+    // 映射哈希没有前缀时使用 `pump` 命名空间
+    fn mapping(&self, name: &str) -> String {
+        let namespace = if self.prefix.is_empty() { "pump" } else { &self.prefix };
+        format!("{}:{}", namespace, name)
+    }
+
+    /// 交易数据（文本格式）的Redis键
+    pub fn trade(&self, signature: &str) -> String {
+        self.typed("trade", signature)
+    }
+
+    /// 交易哈希的Redis键
+    pub fn trade_hash(&self, signature: &str) -> String {
+        self.prefixed(format!("tx:{}", signature))
+    }
+
+    /// 账户数据的Redis键
+    pub fn account(&self, pubkey: &str) -> String {
+        self.typed("acct", pubkey)
+    }
+
+    /// 代币最近交易签名索引的Redis键
+    pub fn mint_trades(&self, mint: &str) -> String {
+        self.prefixed(format!("trades:{}", mint))
+    }
+
+    /// 毕业事件的Redis键
+    pub fn graduation(&self, mint: &str) -> String {
+        self.prefixed(format!("graduation:{}", mint))
+    }
+
+    /// 收盘K线有序集合的Redis键
+    pub fn candles(&self, interval_secs: u64, mint: &str) -> String {
+        self.prefixed(format!("candles:{}s:{}", interval_secs, mint))
+    }
+
+    /// 曲线账户 -> mint 映射哈希
+    pub fn curve_to_mint(&self) -> String {
+        self.mapping("curve2mint")
+    }
+
+    /// 创作者金库 -> 创建者 映射哈希
+    pub fn vault_to_creator(&self) -> String {
+        self.mapping("vault2creator")
+    }
+
+    /// mint -> 创建者 映射哈希
+    pub fn mint_to_creator(&self) -> String {
+        self.mapping("mint2creator")
+    }
+}
+
+/// 从缓存的交易文本解析出的买卖交易
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TradeRecord {
+    pub transaction_type: String,       // Buy 或 Sell
+    pub failed: bool,                   // 交易是否执行失败
+    pub mint: String,
+    pub token_amount: u64,
+    pub sol_cost: Option<f64>,          // 买入的实际SOL花费
+    pub max_sol_cost: Option<f64>,      // 买入的SOL上限
+    pub sol_output: Option<f64>,        // 卖出实际收到的SOL
+    pub min_sol_output: Option<f64>,    // 卖出的最少SOL输出
+    pub slippage_pct: Option<f64>,
+    pub time: String,
+    pub slot: Option<u64>,
+    pub signature: String,
+    pub signer: String,
+    pub fee_payer: Option<String>,      // 手续费支付者，旧数据中没有
+    pub curve_account: Option<String>,
+    pub virtual_token_reserves: Option<u64>,
+    pub virtual_sol_reserves: Option<u64>,
+    pub price: Option<f64>,
+    pub creator_vault: Option<String>,
+}
+
+/// 从缓存的绑定曲线账户文本解析出的储备
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CurveReserves {
+    pub curve_account: String,
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub token_total_supply: u64,
+    pub complete: bool,
+    pub creator: Option<String>,
+}
+
+// 查找 `标签: 值` 格式的行，返回第一处匹配的值
+fn field<'a>(text: &'a str, label: &str) -> Option<&'a str> {
+    text.lines()
+        .map(str::trim)
+        .find_map(|line| line.strip_prefix(label)?.strip_prefix(':'))
+        .map(str::trim)
+}
+
+// 查找单独一行的 `标题:`，返回其后的第一行
+fn section<'a>(text: &'a str, header: &str) -> Option<&'a str> {
+    let mut lines = text.lines().map(str::trim);
+    lines.find(|line| *line == header)?;
+    lines.find(|line| !line.is_empty())
+}
+
+fn sol_field(text: &str, label: &str) -> Option<f64> {
+    field(text, label)?.trim_end_matches("SOL").trim().parse().ok()
+}
+
+impl TradeRecord {
+    /// 解析 `cache_buy_transaction`/`cache_sell_transaction` 写入的交易文本
+    pub fn parse(text: &str) -> Option<Self> {
+        let type_line = text.lines().map(str::trim).find(|line| line.contains("TYPE:"))?;
+        let failed = type_line.starts_with("[FAILED]");
+        let transaction_type = type_line.split_once("TYPE:")?.1.trim().to_string();
+        Some(Self {
+            transaction_type,
+            failed,
+            mint: field(text, "MINT")?.to_string(),
+            token_amount: field(text, "TOKEN AMOUNT")?.parse().ok()?,
+            sol_cost: sol_field(text, "SOL COST"),
+            max_sol_cost: sol_field(text, "MAX SOL COST"),
+            sol_output: sol_field(text, "SOL OUTPUT"),
+            min_sol_output: sol_field(text, "MIN SOL OUTPUT"),
+            slippage_pct: field(text, "SLIPPAGE").and_then(|value| value.trim_end_matches('%').parse().ok()),
+            time: field(text, "TIME").unwrap_or_default().to_string(),
+            slot: field(text, "SLOT").and_then(|value| value.parse().ok()),
+            signature: field(text, "SIGNATURE")?.to_string(),
+            signer: field(text, "签名者地址").unwrap_or("未知").to_string(),
+            fee_payer: field(text, "手续费支付者").map(str::to_string),
+            curve_account: section(text, "关联曲线账户:").map(str::to_string),
+            virtual_token_reserves: field(text, "虚拟代币储备").and_then(|value| value.parse().ok()),
+            virtual_sol_reserves: field(text, "虚拟SOL储备").and_then(|value| value.parse().ok()),
+            price: field(text, "当前价格").and_then(|value| value.trim_end_matches("SOL").trim().parse().ok()),
+            creator_vault: section(text, "创作者金库地址:").map(str::to_string),
+        })
+    }
+}
+
+fn hash_number<T: std::str::FromStr>(fields: &HashMap<String, String>, name: &str) -> Option<T> {
+    fields.get(name)?.parse().ok()
+}
+
+impl TradeRecord {
+    /// 从交易哈希（CPI日志字段）构建，哈希中没有创作者金库
+    pub fn from_hash(fields: &HashMap<String, String>) -> Option<Self> {
+        let text = |name: &str| fields.get(name).cloned();
+        let transaction_type = text("transaction_type")?;
+        let sol_amount = hash_number(fields, "sol_amount");
+        let is_buy = transaction_type == "Buy";
+        Some(Self {
+            failed: fields.get("failed").is_some_and(|value| value == "true"),
+            mint: text("mint")?,
+            token_amount: hash_number(fields, "token_amount")?,
+            sol_cost: if is_buy { hash_number(fields, "actual_sol_cost") } else { None },
+            max_sol_cost: if is_buy { sol_amount } else { None },
+            sol_output: if is_buy { None } else { hash_number(fields, "actual_sol_output") },
+            min_sol_output: if is_buy { None } else { sol_amount },
+            slippage_pct: hash_number(fields, "slippage_pct"),
+            time: text("time").unwrap_or_default(),
+            slot: hash_number(fields, "slot"),
+            signature: text("signature")?,
+            signer: text("signer").unwrap_or_else(|| "未知".to_string()),
+            fee_payer: text("fee_payer"),
+            curve_account: text("curve_account"),
+            virtual_token_reserves: hash_number(fields, "virtual_token_reserves"),
+            virtual_sol_reserves: hash_number(fields, "virtual_sol_reserves"),
+            price: hash_number(fields, "price"),
+            creator_vault: None,
+            transaction_type,
+        })
+    }
+}
+
+impl CurveReserves {
+    /// 解析 `bonding_curve_summary` 生成的绑定曲线账户文本（可带CREATOR和TIME行）
+    pub fn parse(text: &str) -> Option<Self> {
+        if field(text, "ACCOUNT TYPE")? != "BondingCurve" {
+            return None;
+        }
+        let number = |label: &str| field(text, label)?.parse::<u64>().ok();
+        Some(Self {
+            curve_account: field(text, "PUBKEY")?.to_string(),
+            virtual_token_reserves: number("VIRTUAL TOKEN RESERVES")?,
+            virtual_sol_reserves: number("VIRTUAL SOL RESERVES")?,
+            real_token_reserves: number("REAL TOKEN RESERVES")?,
+            real_sol_reserves: number("REAL SOL RESERVES")?,
+            token_total_supply: number("TOKEN TOTAL SUPPLY")?,
+            complete: field(text, "COMPLETE")?.parse().ok()?,
+            creator: field(text, "CREATOR").map(str::to_string),
+        })
+    }
+}
+
+/// 按键名约定读取Redis缓存并解析为结构体
+pub struct CacheReader {
+    client: redis::Client,
+    keys: RedisKeys,
+}
+
+impl CacheReader {
+    pub fn new(redis_url: &str, keys: RedisKeys) -> anyhow::Result<Self> {
+        Ok(Self { client: redis::Client::open(redis_url)?, keys })
+    }
+
+    async fn get(&self, key: &str) -> anyhow::Result<Option<String>> {
+        let mut connection = self.client.get_multiplexed_tokio_connection().await?;
+        Ok(connection.get(key).await?)
+    }
+
+    /// 读取缓存的买卖交易（文本或哈希格式），交易不存在（或已过期）时返回None
+    pub async fn get_trade(&self, signature: &str) -> anyhow::Result<Option<TradeRecord>> {
+        let record = match self.get(&self.keys.trade(signature)).await? {
+            Some(text) => TradeRecord::parse(&text),
+            None => {
+                let mut connection = self.client.get_multiplexed_tokio_connection().await?;
+                let fields: HashMap<String, String> = connection.hgetall(self.keys.trade_hash(signature)).await?;
+                if fields.is_empty() {
+                    return Ok(None);
+                }
+                TradeRecord::from_hash(&fields)
+            }
+        };
+        record
+            .map(Some)
+            .ok_or_else(|| anyhow::anyhow!("无法解析交易({})的缓存数据", signature))
+    }
+
+    /// 读取代币最近的买卖交易（按槽位从新到旧），最多 `limit` 笔，跳过索引中已过期的交易
+    pub async fn get_recent_trades(&self, mint: &str, limit: usize) -> anyhow::Result<Vec<TradeRecord>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let mut connection = self.client.get_multiplexed_tokio_connection().await?;
+        let signatures: Vec<String> = connection.zrevrange(self.keys.mint_trades(mint), 0, limit as isize - 1).await?;
+        let mut trades = Vec::with_capacity(signatures.len());
+        for signature in signatures {
+            if let Some(trade) = self.get_trade(&signature).await? {
+                trades.push(trade);
+            }
+        }
+        Ok(trades)
+    }
+
+    /// 读取代币绑定曲线账户的最新储备，账户数据不存在（或已过期）时返回None
+    pub async fn get_latest_reserves(&self, mint: &str) -> anyhow::Result<Option<CurveReserves>> {
+        let curve_account = crate::calculate_curve_account_from_mint(mint)
+            .ok_or_else(|| anyhow::anyhow!("无法为Mint({})计算曲线账户", mint))?;
+        let Some(text) = self.get(&self.keys.account(&curve_account)).await? else {
+            return Ok(None);
+        };
+        CurveReserves::parse(&text)
+            .map(Some)
+            .ok_or_else(|| anyhow::anyhow!("无法解析曲线账户({})的缓存数据", curve_account))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redis_key_prefix_namespaces_every_key() {
+        let legacy = RedisKeys::new(None);
+        assert_eq!(legacy, RedisKeys::new(Some("  ")));
+        assert_eq!(legacy.trade("sig"), "sig");
+        assert_eq!(legacy.trade_hash("sig"), "tx:sig");
+        assert_eq!(legacy.account("curve"), "curve");
+        assert_eq!(legacy.candles(60, "mint"), "candles:60s:mint");
+        assert_eq!(legacy.curve_to_mint(), "pump:curve2mint");
+
+        let keys = RedisKeys::new(Some("bot1:"));
+        assert_eq!(keys.prefix(), "bot1");
+        assert_eq!(keys.trade("sig"), "bot1:trade:sig");
+        assert_eq!(keys.trade_hash("sig"), "bot1:tx:sig");
+        assert_eq!(keys.account("curve"), "bot1:acct:curve");
+        assert_eq!(keys.mint_trades("mint"), "bot1:trades:mint");
+        assert_eq!(keys.graduation("mint"), "bot1:graduation:mint");
+        assert_eq!(keys.candles(60, "mint"), "bot1:candles:60s:mint");
+        assert_eq!(keys.vault_to_creator(), "bot1:vault2creator");
+        assert_eq!(keys.mint_to_creator(), "bot1:mint2creator");
+    }
+}
@@ -0,0 +1,146 @@
+use dashmap::DashMap;
+use log::debug;
+use serde::Serialize;
+
+/// 单根K线（OHLCV），价格单位为SOL
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Candle {
+    pub start: i64,          // 时间段开始时间（Unix秒）
+    pub interval_secs: u64,  // 时间段长度（秒）
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume_sol: f64,     // 成交额（SOL）
+    pub volume_token: u64,   // 成交量（代币最小单位）
+    pub trades: u64,         // 成交笔数
+}
+
+impl Candle {
+    fn open_at(start: i64, interval_secs: u64, price: f64) -> Self {
+        Self {
+            start,
+            interval_secs,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume_sol: 0.0,
+            volume_token: 0,
+            trades: 0,
+        }
+    }
+
+    fn apply(&mut self, price: f64, volume_sol: f64, volume_token: u64) {
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.close = price;
+        self.volume_sol += volume_sol;
+        self.volume_token = self.volume_token.saturating_add(volume_token);
+        self.trades += 1;
+    }
+}
+
+/// 按mint聚合成交价格的K线，内存中只保留每个代币当前未收盘的一根
+///
+/// 成交落入新的时间段时收盘上一根；长时间没有成交的代币由 `close_stale` 定期收盘。
+/// 没有成交的时间段不生成K线，由使用方按上一根的收盘价补齐。
+pub struct CandleAggregator {
+    interval_secs: u64,
+    current: DashMap<String, Candle>, // mint -> 当前K线
+}
+
+impl CandleAggregator {
+    pub fn new(interval_secs: u64) -> Self {
+        Self {
+            interval_secs: interval_secs.max(1),
+            current: DashMap::new(),
+        }
+    }
+
+    pub fn interval_secs(&self) -> u64 {
+        self.interval_secs
+    }
+
+    fn bucket_start(&self, timestamp_millis: i64) -> i64 {
+        let secs = timestamp_millis.div_euclid(1000);
+        secs - secs.rem_euclid(self.interval_secs as i64)
+    }
+
+    /// 记录一笔成交，进入新的时间段时返回上一根已收盘的K线
+    ///
+    /// 并行解码可能导致成交乱序到达，早于当前K线时间段的成交不再修改已收盘的K线。
+    pub fn record(&self, mint: &str, price: f64, volume_sol: f64, volume_token: u64, timestamp_millis: i64) -> Option<Candle> {
+        let start = self.bucket_start(timestamp_millis);
+        let mut candle = self
+            .current
+            .entry(mint.to_string())
+            .or_insert_with(|| Candle::open_at(start, self.interval_secs, price));
+        if start < candle.start {
+            debug!("[K线] 代币({})的成交早于当前K线时间段，忽略", mint);
+            return None;
+        }
+        let closed = (start > candle.start)
+            .then(|| std::mem::replace(&mut *candle, Candle::open_at(start, self.interval_secs, price)));
+        candle.apply(price, volume_sol, volume_token);
+        closed
+    }
+
+    /// 收盘所有时间段已经结束的K线（期间没有新成交的代币），返回 (mint, K线) 列表
+    pub fn close_stale(&self, now_millis: i64) -> Vec<(String, Candle)> {
+        let now_start = self.bucket_start(now_millis);
+        let mut closed = Vec::new();
+        self.current.retain(|mint, candle| {
+            if candle.start < now_start {
+                closed.push((mint.clone(), candle.clone()));
+                false
+            } else {
+                true
+            }
+        });
+        closed
+    }
+
+    /// 代币当前的K线（尚未收盘）
+    pub fn current(&self, mint: &str) -> Option<Candle> {
+        self.current.get(mint).map(|candle| candle.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn candle_rolls_over_into_new_bucket() {
+        let candles = CandleAggregator::new(5);
+        assert_eq!(candles.record("mint", 1.0, 0.1, 10, 10_000), None);
+        assert_eq!(candles.record("mint", 3.0, 0.2, 20, 11_500), None);
+        assert_eq!(candles.record("mint", 2.0, 0.3, 30, 14_999), None);
+
+        // 进入下一个5秒周期时收盘上一根K线
+        let closed = candles.record("mint", 4.0, 0.4, 40, 15_000).unwrap();
+        assert_eq!((closed.start, closed.open, closed.high, closed.low, closed.close), (10, 1.0, 3.0, 1.0, 2.0));
+        assert_eq!((closed.volume_token, closed.trades), (60, 3));
+        assert!((closed.volume_sol - 0.6).abs() < 1e-9);
+
+        // 乱序到达的旧成交不修改当前K线
+        assert_eq!(candles.record("mint", 100.0, 1.0, 1, 12_000), None);
+        let current = candles.current("mint").unwrap();
+        assert_eq!((current.start, current.open, current.high, current.trades), (15, 4.0, 4.0, 1));
+    }
+
+    #[test]
+    fn stale_candles_close_without_new_trades() {
+        let candles = CandleAggregator::new(60);
+        candles.record("quiet", 1.0, 0.1, 10, 0);
+        candles.record("busy", 2.0, 0.1, 10, 61_000);
+
+        let closed = candles.close_stale(90_000);
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].0, "quiet");
+        assert_eq!(closed[0].1.start, 0);
+        assert!(candles.current("quiet").is_none());
+        assert!(candles.current("busy").is_some());
+    }
+}
@@ -0,0 +1,270 @@
+use crate::json_case::JsonCase;
+use glob::glob;
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    fs,
+    io::Write,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::mpsc;
+
+const CPI_LOG_QUEUE_SIZE: usize = 4096; // 待写入的CPI日志队列容量
+const DEFAULT_NDJSON_FILE_BYTES: u64 = 64 * 1024 * 1024; // 未配置目录总大小上限时单个NDJSON文件的滚动大小
+
+/// CPI日志的写入方式
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum CpiLogMode {
+    /// 每笔交易一个JSON文件
+    #[default]
+    #[serde(rename = "per-file")]
+    PerFile,
+    /// 每笔交易一行紧凑JSON，追加到按天或按大小滚动的文件
+    #[serde(rename = "ndjson")]
+    Ndjson,
+}
+
+/// 正在追加的NDJSON文件
+struct RollingFile {
+    path: PathBuf,
+    date: String,
+    size: u64,
+    file: fs::File,
+}
+
+/// 已写入的CPI日志文件
+struct StoredFile {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+/// CPI日志JSON文件存储
+///
+/// 在内存中按写入顺序记录已知文件，每次写入后按文件数量、目录总大小和文件存活时间清理最旧的文件，
+/// 只在打开时扫描一次目录，避免每笔交易都重新glob并排序整个目录。
+/// NDJSON模式下同样的限制作用于滚动后的文件，正在追加的文件不会被清理。
+pub struct CpiLogStore {
+    dir: PathBuf,
+    max_files: usize,
+    max_total_bytes: Option<u64>,
+    max_age: Option<Duration>,
+    files: Mutex<(VecDeque<StoredFile>, u64)>, // (按时间从旧到新的文件, 总字节数)
+    current: Mutex<Option<RollingFile>>,       // NDJSON模式下正在追加的文件
+}
+
+impl CpiLogStore {
+    /// 打开日志目录（不存在时创建），并加载目录中已有的JSON文件
+    pub fn open(
+        dir: &str,
+        max_files: usize,
+        max_total_bytes: Option<u64>,
+        max_age: Option<Duration>,
+    ) -> anyhow::Result<Self> {
+        let dir = PathBuf::from(dir);
+        if !dir.exists() {
+            fs::create_dir_all(&dir)?;
+            info!("创建CPI日志JSON目录: {:?}", dir);
+        }
+
+        let mut paths = Vec::new();
+        for extension in ["json", "ndjson"] {
+            paths.extend(glob(&format!("{}/*.{}", dir.display(), extension))?.filter_map(Result::ok));
+        }
+        let mut existing: Vec<StoredFile> = paths
+            .into_iter()
+            .filter_map(|path| {
+                let metadata = fs::metadata(&path).ok()?;
+                Some(StoredFile {
+                    path,
+                    size: metadata.len(),
+                    modified: metadata.modified().ok()?,
+                })
+            })
+            .collect();
+        existing.sort_by_key(|file| file.modified);
+        let total_bytes = existing.iter().map(|file| file.size).sum();
+        debug!("CPI日志目录已有 {} 个文件, 共 {} 字节", existing.len(), total_bytes);
+
+        let store = Self {
+            dir,
+            max_files,
+            max_total_bytes,
+            max_age,
+            files: Mutex::new((existing.into(), total_bytes)),
+            current: Mutex::new(None),
+        };
+        store.prune();
+        Ok(store)
+    }
+
+    /// 写入一个JSON文件，并清理超出限制的旧文件
+    pub fn write(&self, file_name: &str, content: &str) -> anyhow::Result<PathBuf> {
+        let path = self.dir.join(file_name);
+        fs::write(&path, content)?;
+
+        {
+            let mut files = self.files.lock().expect("cpi log store lock poisoned");
+            let size = content.len() as u64;
+            files.0.push_back(StoredFile {
+                path: path.clone(),
+                size,
+                modified: SystemTime::now(),
+            });
+            files.1 += size;
+        }
+        self.prune();
+
+        Ok(path)
+    }
+
+    /// 追加一行到当前NDJSON文件，日期变化或达到单文件大小上限时切换到新文件，并清理超出限制的旧文件
+    pub fn append_line(&self, line: &str) -> anyhow::Result<PathBuf> {
+        let path = {
+            let mut current = self.current.lock().expect("cpi log store lock poisoned");
+            let date = chrono::Utc::now().format("%Y%m%d").to_string();
+            let line_bytes = line.len() as u64 + 1;
+            let needs_roll = current.as_ref().is_none_or(|file| {
+                file.date != date || (file.size > 0 && file.size + line_bytes > self.ndjson_file_bytes())
+            });
+            if needs_roll {
+                let millis = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+                let path = self.dir.join(format!("cpi_{}_{}.ndjson", date, millis));
+                let file = fs::OpenOptions::new().create(true).append(true).open(&path)?;
+                debug!("切换CPI日志NDJSON文件: {:?}", path);
+                self.files.lock().expect("cpi log store lock poisoned").0.push_back(StoredFile {
+                    path: path.clone(),
+                    size: 0,
+                    modified: SystemTime::now(),
+                });
+                *current = Some(RollingFile { path, date, size: 0, file });
+            }
+
+            let Some(rolling) = current.as_mut() else {
+                return Err(anyhow::anyhow!("没有可写入的NDJSON文件"));
+            };
+            rolling.file.write_all(line.as_bytes())?;
+            rolling.file.write_all(b"\n")?;
+            rolling.size += line_bytes;
+
+            let mut files = self.files.lock().expect("cpi log store lock poisoned");
+            if let Some(stored) = files.0.iter_mut().rev().find(|file| file.path == rolling.path) {
+                stored.size += line_bytes;
+                stored.modified = SystemTime::now();
+            }
+            files.1 += line_bytes;
+            rolling.path.clone()
+        };
+        self.prune();
+
+        Ok(path)
+    }
+
+    /// 单个NDJSON文件的滚动大小：配置了目录总大小上限时平均分给每个文件，否则使用默认值
+    fn ndjson_file_bytes(&self) -> u64 {
+        match self.max_total_bytes {
+            Some(max_total) if self.max_files > 0 => (max_total / self.max_files as u64).max(1),
+            Some(max_total) => max_total.max(1),
+            None => DEFAULT_NDJSON_FILE_BYTES,
+        }
+    }
+
+    /// 按文件数量、总大小和存活时间删除最旧的文件
+    fn prune(&self) {
+        // 先取出正在追加的文件路径，避免与append_line以相反顺序持有两把锁
+        let current_path = self
+            .current
+            .lock()
+            .expect("cpi log store lock poisoned")
+            .as_ref()
+            .map(|file| file.path.clone());
+        let mut files = self.files.lock().expect("cpi log store lock poisoned");
+        let now = SystemTime::now();
+        loop {
+            let (queue, total_bytes) = &mut *files;
+            let Some(oldest) = queue.front() else {
+                break;
+            };
+            let over_count = self.max_files > 0 && queue.len() > self.max_files;
+            let over_size = self.max_total_bytes.is_some_and(|max| *total_bytes > max);
+            let expired = self.max_age.is_some_and(|max_age| {
+                now.duration_since(oldest.modified).is_ok_and(|age| age > max_age)
+            });
+            if !(over_count || over_size || expired) || current_path.as_deref() == Some(oldest.path.as_path()) {
+                break;
+            }
+
+            let Some(oldest) = queue.pop_front() else {
+                break;
+            };
+            *total_bytes = total_bytes.saturating_sub(oldest.size);
+            match fs::remove_file(&oldest.path) {
+                Ok(()) => debug!("删除旧的CPI日志文件: {:?}", oldest.path),
+                Err(e) => warn!("删除旧的CPI日志文件失败 {:?}: {}", oldest.path, e),
+            }
+        }
+    }
+}
+
+/// CPI日志后台写入器
+///
+/// 交易处理只把日志放入有界队列，由独立的阻塞任务负责序列化、写文件和清理旧文件，
+/// 磁盘延迟不会阻塞数据流的消费；队列已满时丢弃日志并输出警告。
+pub struct CpiLogWriter<T> {
+    tx: mpsc::Sender<(String, T)>, // (文件名前缀, 日志内容)
+}
+
+impl<T> Clone for CpiLogWriter<T> {
+    fn clone(&self) -> Self {
+        Self { tx: self.tx.clone() }
+    }
+}
+
+impl<T: Serialize + Send + 'static> CpiLogWriter<T> {
+    /// 创建写入器并启动后台写入任务（需在tokio运行时中调用）
+    pub fn spawn(store: Arc<CpiLogStore>, pretty: bool, mode: CpiLogMode, json_case: JsonCase) -> Self {
+        let (tx, mut rx) = mpsc::channel::<(String, T)>(CPI_LOG_QUEUE_SIZE);
+        tokio::task::spawn_blocking(move || {
+            while let Some((prefix, content)) = rx.blocking_recv() {
+                let result = match mode {
+                    CpiLogMode::PerFile => save(&store, &prefix, &content, pretty, json_case),
+                    CpiLogMode::Ndjson => serde_json::to_value(&content)
+                        .and_then(|value| serde_json::to_string(&json_case.apply(value)))
+                        .map_err(anyhow::Error::from)
+                        .and_then(|line| store.append_line(&line)),
+                };
+                match result {
+                    Ok(path) if mode == CpiLogMode::PerFile => info!("保存CPI日志到JSON文件: {:?}", path),
+                    Ok(_) => {}
+                    Err(e) => error!("保存CPI日志失败 ({}): {}", prefix, e),
+                }
+            }
+        });
+        Self { tx }
+    }
+
+    /// 将日志放入写入队列；单文件模式下文件名为 `{prefix}_{毫秒时间戳}.json`
+    pub fn write(&self, prefix: String, content: T) {
+        if let Err(e) = self.tx.try_send((prefix, content)) {
+            let prefix = match e {
+                mpsc::error::TrySendError::Full((prefix, _)) | mpsc::error::TrySendError::Closed((prefix, _)) => prefix,
+            };
+            warn!("CPI日志写入队列已满或已关闭，丢弃日志: {}", prefix);
+        }
+    }
+}
+
+fn save<T: Serialize>(store: &CpiLogStore, prefix: &str, content: &T, pretty: bool, json_case: JsonCase) -> anyhow::Result<PathBuf> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    let file_name = format!("{}_{}.json", prefix, timestamp);
+    let content = json_case.apply(serde_json::to_value(content)?);
+    let json_content = if pretty {
+        serde_json::to_string_pretty(&content)?
+    } else {
+        serde_json::to_string(&content)?
+    };
+    store.write(&file_name, &json_content)
+}
@@ -0,0 +1,138 @@
+use log::{debug, info, warn};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+/// mint(或金库地址) -> creator 映射表
+///
+/// 启动时从 `creator_map_path` 指定的 JSON/TOML 文件加载，文件不存在时回退到内置映射；
+/// 可通过 `spawn_hot_reload` 按修改时间轮询文件，长期运行时无需重启即可生效。
+pub struct CreatorMap {
+    path: Option<PathBuf>,
+    entries: RwLock<Arc<HashMap<String, String>>>,
+    modified: RwLock<Option<SystemTime>>,
+}
+
+impl CreatorMap {
+    /// 从配置的文件路径加载映射表，未配置或文件不存在时使用内置映射
+    pub fn load(path: Option<&str>) -> anyhow::Result<Self> {
+        let map = Self {
+            path: path.map(PathBuf::from),
+            entries: RwLock::new(Arc::new(builtin_creator_map())),
+            modified: RwLock::new(None),
+        };
+
+        match &map.path {
+            Some(p) if p.exists() => {
+                map.reload()?;
+            }
+            Some(p) => {
+                warn!("[Creator] 映射文件({:?})不存在，使用内置映射", p);
+            }
+            None => {
+                debug!("[Creator] 未配置 creator_map_path，使用内置映射");
+            }
+        }
+
+        Ok(map)
+    }
+
+    /// 查找mint(或金库地址)对应的创建者
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.snapshot().get(key).cloned()
+    }
+
+    /// 获取当前映射表快照
+    pub fn snapshot(&self) -> Arc<HashMap<String, String>> {
+        Arc::clone(&self.entries.read().expect("creator map lock poisoned"))
+    }
+
+    /// 当文件修改时间发生变化时重新加载，返回是否发生了重新加载
+    pub fn reload_if_changed(&self) -> anyhow::Result<bool> {
+        let Some(path) = &self.path else {
+            return Ok(false);
+        };
+        let Ok(metadata) = fs::metadata(path) else {
+            return Ok(false);
+        };
+        let modified = metadata.modified()?;
+        if *self.modified.read().expect("creator map lock poisoned") == Some(modified) {
+            return Ok(false);
+        }
+        self.reload()?;
+        Ok(true)
+    }
+
+    /// 启动后台任务，每隔 `interval` 检查一次映射文件是否被修改
+    pub fn spawn_hot_reload(self: Arc<Self>, interval: Duration) {
+        if self.path.is_none() {
+            return;
+        }
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match self.reload_if_changed() {
+                    Ok(true) => info!("[Creator] 映射文件已重新加载，共 {} 条", self.snapshot().len()),
+                    Ok(false) => {}
+                    Err(e) => warn!("[Creator] 重新加载映射文件失败，继续使用旧映射: {}", e),
+                }
+            }
+        });
+    }
+
+    fn reload(&self) -> anyhow::Result<()> {
+        let path = self.path.as_ref().expect("reload requires a path");
+        let modified = fs::metadata(path)?.modified()?;
+        let entries = parse_creator_map_file(path)?;
+        info!("[Creator] 从 {:?} 加载了 {} 条创建者映射", path, entries.len());
+        *self.entries.write().expect("creator map lock poisoned") = Arc::new(entries);
+        *self.modified.write().expect("creator map lock poisoned") = Some(modified);
+        Ok(())
+    }
+}
+
+/// 解析 `{ "mint": "creator" }` 格式的映射文件，根据扩展名选择TOML或JSON
+fn parse_creator_map_file(path: &Path) -> anyhow::Result<HashMap<String, String>> {
+    let content = fs::read_to_string(path)?;
+    let is_toml = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"));
+    if is_toml {
+        Ok(toml::from_str(&content)?)
+    } else {
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
+/// 内置映射（未配置映射文件时的后备数据）
+fn builtin_creator_map() -> HashMap<String, String> {
+    [
+        // 示例数据，请替换为实际数据
+        ("DCLjJRAP4PineCmCabTKRrTVsSaggkmfgBj8AMPapump", "T5SWiQQCACjAMSjTnHEbRjFzxqQyd5xoLvHqFPRqRLw"),
+        ("4qMyinhBRrePr82BjoKheaXocfTXChBMk3TWifHypump", "2yodq5YqMk5owNYhUWjh9gNkwRxaQBYDAcJdaGC7B7vG"),
+        ("7kJzws2KnTV73d16ZuifeFmSyupxYkp7CPYenV3Apump", "J9MBJJrqxsqBSXMk46PT5XJj9qXBzj6kcGbECdmDSQoV"),
+        ("FqF6Ac1j71qjTxjg9mJag3zrmmnxVtXJQTxZjSPdpump", "F5RYi7FMPefkc7okJNh21HgKmFVtJYyGBm1xxvriDVYZ"),
+        // 修正amUfFDR5KxiFKpgibmPAPRwhaB9jrPcKWsBVJMhpump的创建者地址
+        ("amUfFDR5KxiFKpgibmPAPRwhaB9jrPcKWsBVJMhpump", "Hju3K6uRadH7AkynqHGCZgD1W63WNa47h6DuNpTk3xsG"),
+        ("A5JqPPSTf3Rc4W9R9CYLRhRowMLZLquweJgR6iDepump", "Eou3bQd3VYUzXxcLBqihFP5J5qK3W3f8Lq5CsX3EY8Yk"),
+        // 添加新的对应关系
+        ("GFVtnX25mEtpjEXc47X1AKfcd9tdPdds9FdMQoJ1pump", "HNjUCzKFHAqZVvf3mFe89X35aQdNwqKptkwViNNgUzKf"),
+        ("7v1cnL3KtzbHYar9anc8eQGV9NYDMPgYwb526ShUpump", "BYNj1SpM6PxMUVu5hLYVdJxiP5Qv8fQ5eeqZQ213APGj"),
+        ("F7ZDfpnBX13Uy5gK8J4mQLvMpDqa1zhajdUtfvwgpump", "BM2SfEe3rjG48RtNqLHk1KVJqb2EXfz6CuD6epn3U5Ku"),
+        ("85578kyWUYj7kU4GeSKZ8RYoQuhxdxiVc5CXL52spump", "ChcyLqAMCm25LGFhgP9RXAd54oCbKZ1DdDmwkh4dpQsM"),
+        // 特殊账户映射
+        ("54Pgg7FuLuP13dRQoFPTH4FdZHi141bQDzVwukt6m8Tk", "ChcyLqAMCm25LGFhgP9RXAd54oCbKZ1DdDmwkh4dpQsM"), // 这个rent实际是creator_vault
+        // 金库地址映射到创建者
+        ("7hTckgnGnLQR6sdH7YkqFTAA7VwTfYFaZ6EhEsU3saCX", "HNjUCzKFHAqZVvf3mFe89X35aQdNwqKptkwViNNgUzKf"),
+        ("HxmpdosPST3HoZwMg8uV8hg9EoYpisyCQQAP8HAqnMQK", "BM2SfEe3rjG48RtNqLHk1KVJqb2EXfz6CuD6epn3U5Ku"),
+    ]
+    .iter()
+    .map(|(mint, creator)| (mint.to_string(), creator.to_string()))
+    .collect()
+}
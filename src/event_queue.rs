@@ -0,0 +1,85 @@
+use crate::metrics::{Metrics, METRICS};
+use log::{debug, info, warn};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+const EVENT_QUEUE_SIZE: usize = 10_000; // 待发布的事件队列容量
+const CONNECT_TIMEOUT_SECS: u64 = 5; // 连接NATS的超时（秒）
+pub const DEFAULT_NATS_SUBJECT_PREFIX: &str = "pump"; // 默认主题前缀
+
+/// 事件类型，对应发布的NATS主题 `{前缀}.{类型}`
+#[derive(Debug, Clone, Copy)]
+pub enum EventKind {
+    Buy,
+    Sell,
+    Create,
+    Account,
+}
+
+impl EventKind {
+    fn subject_suffix(self) -> &'static str {
+        match self {
+            Self::Buy => "buy",
+            Self::Sell => "sell",
+            Self::Create => "create",
+            Self::Account => "account",
+        }
+    }
+}
+
+/// NATS事件发布器
+///
+/// 交易和账户事件只放入有界队列，由后台任务发布到 `{前缀}.buy`、`{前缀}.sell`、`{前缀}.create`、`{前缀}.account`。
+/// 连接断开时客户端在后台自动重连，期间的消息由客户端缓冲；队列已满时丢弃消息并计入发布失败数，
+/// 不会阻塞数据流的处理。
+#[derive(Clone)]
+pub struct EventPublisher {
+    tx: mpsc::Sender<(EventKind, String)>,
+}
+
+impl EventPublisher {
+    /// 创建发布器并启动后台连接和发布任务（需在tokio运行时中调用）
+    pub fn spawn(url: String, subject_prefix: String) -> Self {
+        let (tx, rx) = mpsc::channel(EVENT_QUEUE_SIZE);
+        tokio::spawn(run_publisher(url, subject_prefix, rx));
+        Self { tx }
+    }
+
+    /// 将事件（JSON）放入发布队列
+    pub fn publish(&self, kind: EventKind, payload: String) {
+        if self.tx.try_send((kind, payload)).is_err() {
+            Metrics::inc(&METRICS.event_publish_failures);
+            debug!("[NATS] 发布队列已满或已关闭，丢弃一条 {:?} 事件", kind);
+        }
+    }
+}
+
+async fn run_publisher(url: String, subject_prefix: String, mut rx: mpsc::Receiver<(EventKind, String)>) {
+    // 首次连接失败时也在后台重试，不影响启动
+    let options = async_nats::ConnectOptions::new()
+        .retry_on_initial_connect()
+        .connection_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS))
+        .event_callback(|event| async move {
+            match event {
+                async_nats::Event::Connected => info!("[NATS] 已连接"),
+                async_nats::Event::Disconnected => warn!("[NATS] 连接断开，正在重连"),
+                other => debug!("[NATS] {}", other),
+            }
+        });
+    let client = match options.connect(url.as_str()).await {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("[NATS] 无法连接到 {}，停止发布事件: {}", url, e);
+            return;
+        }
+    };
+
+    while let Some((kind, payload)) = rx.recv().await {
+        let subject = format!("{}.{}", subject_prefix, kind.subject_suffix());
+        if let Err(e) = client.publish(subject, payload.into()).await {
+            Metrics::inc(&METRICS.event_publish_failures);
+            debug!("[NATS] 发布 {:?} 事件失败: {}", kind, e);
+        }
+    }
+    let _ = client.flush().await;
+}
@@ -0,0 +1,264 @@
+//! 监控事件的输出目标
+//!
+//! `EventHandler` 完成过滤和分析（小额交易、夹子、成交量、毕业等）后，把每个事件依次交给配置启用的输出目标。
+//! 新增输出方式只需实现 `EventSink` 并在 `build_sinks` 中按配置加入，不需要修改事件处理逻辑。
+
+use crate::{
+    account_event_json,
+    cpi_log_store::CpiLogWriter,
+    event_queue::{EventKind, EventPublisher},
+    extract_mint_address_from_account_data,
+    json_case::JsonCase,
+    log_file_writer::LogFileWriter,
+    log_targets,
+    metrics::{Metrics, METRICS},
+    ws_server::TradeBroadcaster,
+    AdminAction, AdminEvent, CpiLogFile, DecodedAccount, EventLogLevels, Features, GlobalState, MonitorEvent, SignerFilter, TradeEvent, TransactionCache,
+};
+use chrono::Utc;
+use log::{debug, info};
+use std::sync::Arc;
+
+/// 监控事件的输出目标
+pub(crate) trait EventSink: Send {
+    fn handle(&self, event: &MonitorEvent);
+}
+
+// 买卖和创建交易的种类与日志target
+fn trade_kind(event: &MonitorEvent) -> Option<(&TradeEvent, EventKind, &'static str)> {
+    match event {
+        MonitorEvent::Buy(trade) => Some((trade, EventKind::Buy, log_targets::BUY)),
+        MonitorEvent::Sell(trade) => Some((trade, EventKind::Sell, log_targets::SELL)),
+        MonitorEvent::Create(trade) => Some((trade, EventKind::Create, log_targets::CREATE)),
+        _ => None,
+    }
+}
+
+// 交易的显示文本：结构化日志（单行JSON）或默认的多行文本
+fn display_message(trade: &TradeEvent, structured: bool, json_case: JsonCase) -> &str {
+    let json = trade.json(json_case);
+    if structured && !json.is_empty() {
+        json
+    } else {
+        &trade.log_message
+    }
+}
+
+/// 按 trade/unmatched_trade/account 日志级别输出到日志
+pub(crate) struct LogSink {
+    levels: EventLogLevels,
+    structured: bool,
+    json_case: JsonCase,
+    signer_filter: Arc<SignerFilter>,
+}
+
+impl EventSink for LogSink {
+    fn handle(&self, event: &MonitorEvent) {
+        if let Some((trade, _, log_target)) = trade_kind(event) {
+            // 按监控和签名者过滤结果以 trade_log_level 或 unmatched_trade_log_level 输出
+            let level = if trade.is_monitored && self.signer_filter.allows(&trade.entry.signer) {
+                self.levels.trade
+            } else {
+                self.levels.unmatched_trade
+            };
+            if let Some(level) = level {
+                log::log!(target: log_target, level, "{}", display_message(trade, self.structured, self.json_case));
+            }
+            return;
+        }
+        match event {
+            MonitorEvent::Instruction(event) => {
+                let status_marker = if event.failed { "[FAILED] " } else { "" };
+                let level = if event.is_monitored { self.levels.trade } else { self.levels.unmatched_trade };
+                match serde_json::to_value(&event.instruction) {
+                    Ok(value) => {
+                        if let Some(level) = level {
+                            let data = self.json_case.apply(value);
+                            log::log!(level, "{}[{}] 指令: {}, 签名: {}, 数据: {}", status_marker, event.program, event.instruction.name, event.signature, data);
+                        }
+                    }
+                    Err(e) => {
+                        debug!("[{}] 无法序列化指令为JSON: {}", event.program, e);
+                    }
+                }
+            }
+            // 经典Token指令附带解码后的参数，Token-2022扩展指令只有名称
+            MonitorEvent::TokenInstruction(event) => match event.instruction.as_ref().map(serde_json::to_value) {
+                Some(Ok(value)) => debug!(target: log_targets::TOKEN, "{}, 数据: {}", event.log_message, self.json_case.apply(value)),
+                _ => debug!(target: log_targets::TOKEN, "{}", event.log_message),
+            },
+            // 按 account_log_level 输出账户信息（默认debug）
+            MonitorEvent::AccountUpdate(event) => {
+                if let Some(level) = self.levels.account {
+                    log::log!(target: log_targets::ACCOUNT, level, "{}", event.summary);
+                }
+            }
+            MonitorEvent::Admin(event) => {
+                info!(target: log_targets::ADMIN, "{} {}", event.label(), self.json_case.apply(event.to_json()));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 追加带本地时间前缀的日志行到 `log_file_path`，只记录涉及监听地址且签名者未被过滤的交易
+pub(crate) struct FileSink {
+    writer: LogFileWriter,
+    structured: bool,
+    json_case: JsonCase,
+    signer_filter: Arc<SignerFilter>,
+}
+
+impl FileSink {
+    fn write_line(&self, message: &str) {
+        self.writer.write_line(format!("[{}] {}", crate::format_time(Utc::now()), message));
+    }
+}
+
+impl EventSink for FileSink {
+    fn handle(&self, event: &MonitorEvent) {
+        match event {
+            MonitorEvent::Buy(trade) | MonitorEvent::Sell(trade) | MonitorEvent::Create(trade) => {
+                if trade.is_monitored && self.signer_filter.allows(&trade.entry.signer) {
+                    self.write_line(display_message(trade, self.structured, self.json_case));
+                }
+            }
+            MonitorEvent::TokenInstruction(event) => self.write_line(&event.log_message),
+            MonitorEvent::AccountUpdate(event) => self.write_line(&event.summary),
+            MonitorEvent::Admin(event) => {
+                self.write_line(&format!("{} {}", event.label(), self.json_case.apply(event.to_json())));
+            }
+            MonitorEvent::Instruction(_) => {}
+        }
+    }
+}
+
+/// 写入交易缓存（内存和Redis）：买卖交易、账户数据、协议参数（Global账户和SetParams指令）和曲线储备
+pub(crate) struct RedisSink {
+    cache: Arc<TransactionCache>,
+}
+
+impl EventSink for RedisSink {
+    fn handle(&self, event: &MonitorEvent) {
+        match event {
+            // 缓存包含创作者金库信息的完整交易数据
+            MonitorEvent::Buy(trade) => self.cache.cache_buy_transaction(&trade.entry, trade.enhanced_data.clone()),
+            MonitorEvent::Sell(trade) => self.cache.cache_sell_transaction(&trade.entry, trade.enhanced_data.clone(), trade.slot),
+            MonitorEvent::AccountUpdate(event) => {
+                // 缓存协议参数，供计算费用和检测费用接收者变化使用
+                if let DecodedAccount::Global(global, creator_fee_basis_points) = &event.account {
+                    self.cache.cache_global_state(GlobalState::from_account(global, *creator_fee_basis_points, event.slot));
+                }
+                self.cache.cache_account_data(&event.pubkey, event.summary.clone());
+                // 直接使用解码后的绑定曲线储备，不再从账户文本中解析
+                if let DecodedAccount::BondingCurve(bonding_curve, _) = &event.account {
+                    if let Some(mint) = extract_mint_address_from_account_data(&event.summary, Some(&self.cache)) {
+                        self.cache.cache_reserves(&mint, bonding_curve.virtual_token_reserves, bonding_curve.virtual_sol_reserves);
+                    }
+                }
+            }
+            // SetParams成功后立即更新协议参数，不必等待Global账户推送，费用计算使用新费率
+            MonitorEvent::Admin(AdminEvent { action: AdminAction::SetParams(params), authority, slot, failed: false, .. }) => {
+                let previous = self.cache.get_global_state();
+                self.cache.cache_global_state(GlobalState::with_params(previous.as_ref(), authority, params, *slot));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 保存交易的CPI日志JSON文件，由后台任务写入；配置了按代币限流时超出配额的交易不写入，只计入指标
+pub(crate) struct CpiJsonSink {
+    writer: CpiLogWriter<CpiLogFile>,
+    cache: Option<Arc<TransactionCache>>,
+}
+
+impl EventSink for CpiJsonSink {
+    fn handle(&self, event: &MonitorEvent) {
+        let Some((trade, _, _)) = trade_kind(event) else {
+            return;
+        };
+        if self.cache.as_ref().is_none_or(|cache| cache.allow_cpi_log(&trade.entry.mint)) {
+            let short_sig: String = trade.entry.signature.chars().take(8).collect();
+            self.writer.write(short_sig, CpiLogFile { entry: trade.entry.clone(), raw: trade.raw.clone() });
+        } else {
+            Metrics::inc(&METRICS.cpi_logs_rate_limited);
+            debug!("[CPI日志] 代币 {} 超出每分钟写入配额，跳过交易 {}", trade.entry.mint, trade.entry.signature);
+        }
+    }
+}
+
+/// 推送交易和管理指令JSON给WebSocket和NDJSON客户端
+pub(crate) struct WebSocketSink {
+    broadcaster: TradeBroadcaster,
+    json_case: JsonCase,
+}
+
+impl EventSink for WebSocketSink {
+    fn handle(&self, event: &MonitorEvent) {
+        if let Some((trade, _, _)) = trade_kind(event) {
+            self.broadcaster.publish(trade.json(self.json_case).to_string());
+        } else if let MonitorEvent::Admin(event) = event {
+            self.broadcaster.publish(self.json_case.apply(event.to_json()).to_string());
+        }
+    }
+}
+
+/// 发布交易和账户事件到NATS
+pub(crate) struct NatsSink {
+    publisher: EventPublisher,
+    json_case: JsonCase,
+}
+
+impl EventSink for NatsSink {
+    fn handle(&self, event: &MonitorEvent) {
+        if let Some((trade, kind, _)) = trade_kind(event) {
+            self.publisher.publish(kind, trade.json(self.json_case).to_string());
+        } else if let MonitorEvent::AccountUpdate(event) = event {
+            self.publisher.publish(EventKind::Account, self.json_case.apply(account_event_json(event)).to_string());
+        }
+    }
+}
+
+/// 事件处理可用的输出
+pub(crate) struct SinkOutputs {
+    pub cache: Option<Arc<TransactionCache>>,
+    pub trade_broadcaster: Option<TradeBroadcaster>,
+    pub cpi_log_writer: Option<CpiLogWriter<CpiLogFile>>,
+    pub event_publisher: Option<EventPublisher>,
+    pub signer_filter: Arc<SignerFilter>,
+}
+
+/// 按配置创建输出目标，顺序与原先的处理顺序一致：缓存、CPI日志、推送、日志、日志文件
+pub(crate) fn build_sinks(features: &Features, outputs: SinkOutputs) -> anyhow::Result<Vec<Box<dyn EventSink>>> {
+    let json_case = features.json_case.unwrap_or_default();
+    let mut sinks: Vec<Box<dyn EventSink>> = Vec::new();
+    if let Some(cache) = &outputs.cache {
+        sinks.push(Box::new(RedisSink { cache: cache.clone() }));
+    }
+    if let Some(writer) = outputs.cpi_log_writer {
+        sinks.push(Box::new(CpiJsonSink { writer, cache: outputs.cache.clone() }));
+    }
+    if let Some(broadcaster) = outputs.trade_broadcaster {
+        sinks.push(Box::new(WebSocketSink { broadcaster, json_case }));
+    }
+    if let Some(publisher) = outputs.event_publisher {
+        sinks.push(Box::new(NatsSink { publisher, json_case }));
+    }
+    sinks.push(Box::new(LogSink {
+        levels: features.event_log_levels()?,
+        structured: features.structured_logs,
+        json_case,
+        signer_filter: outputs.signer_filter.clone(),
+    }));
+    // 打开日志文件并启动后台写入任务（如果启用）
+    if features.log_to_file {
+        sinks.push(Box::new(FileSink {
+            writer: LogFileWriter::open(&features.log_file_path)?,
+            structured: features.structured_logs,
+            json_case,
+            signer_filter: outputs.signer_filter,
+        }));
+    }
+    Ok(sinks)
+}
@@ -0,0 +1,142 @@
+use crate::grpc_tls::GrpcTlsConfig;
+use log::{info, warn};
+use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+/// gRPC端点配置，兼容单个地址和地址列表两种写法
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum GrpcEndpoints {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl GrpcEndpoints {
+    pub fn to_vec(&self) -> Vec<String> {
+        match self {
+            Self::One(endpoint) => vec![endpoint.clone()],
+            Self::Many(endpoints) => endpoints.clone(),
+        }
+    }
+}
+
+/// 可故障转移的gRPC端点列表，记录当前使用的端点；所有端点使用同一组TLS设置和x-token
+pub struct EndpointPool {
+    endpoints: Vec<String>,
+    active: AtomicUsize,
+    tls: GrpcTlsConfig,
+    x_token: Option<String>,
+}
+
+impl EndpointPool {
+    pub fn new(endpoints: Vec<String>, tls: GrpcTlsConfig, x_token: Option<String>) -> anyhow::Result<Self> {
+        if endpoints.is_empty() {
+            return Err(anyhow::anyhow!("未配置 grpc_endpoint"));
+        }
+        Ok(Self {
+            endpoints,
+            active: AtomicUsize::new(0),
+            tls,
+            x_token,
+        })
+    }
+
+    pub fn tls(&self) -> &GrpcTlsConfig {
+        &self.tls
+    }
+
+    pub fn x_token(&self) -> Option<&str> {
+        self.x_token.as_deref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    /// 当前使用的端点 (序号, 地址)
+    pub fn active(&self) -> (usize, &str) {
+        let index = self.active.load(Ordering::SeqCst);
+        (index, &self.endpoints[index])
+    }
+
+    /// 序号为from的端点不可用时切换到下一个端点（轮询）；其他任务已经切换过时不重复切换
+    pub fn failover(&self, from: usize) {
+        let next = (from + 1) % self.endpoints.len();
+        if next != from
+            && self
+                .active
+                .compare_exchange(from, next, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+        {
+            warn!("[gRPC] 端点 {} 不可用，切换到 {}", self.endpoints[from], self.endpoints[next]);
+        }
+    }
+
+    /// 当前使用序号为from的备用端点时切回主端点（第一个端点），下次连接时先尝试主端点
+    pub fn restore_primary(&self, from: usize) {
+        if from != 0 && self.active.compare_exchange(from, 0, Ordering::SeqCst, Ordering::SeqCst).is_ok() {
+            info!("[gRPC] 尝试切回主端点 {}", self.endpoints[0]);
+        }
+    }
+}
+
+/// 一个监控任务的数据流进度，跨重连保留，用于决定重连后的起始槽位以及是否切换端点
+#[derive(Debug, Default)]
+pub struct StreamProgress {
+    last_slot: AtomicU64,
+    failed: AtomicBool,
+}
+
+impl StreamProgress {
+    /// 记录收到的更新所在的槽位
+    pub fn observe_slot(&self, slot: u64) {
+        self.last_slot.fetch_max(slot, Ordering::Relaxed);
+    }
+
+    /// 重连时的起始槽位：未设置 from_slot 时只接收实时数据；已经收到过更新时从最后的槽位继续，
+    /// 而不是重新回放整段历史（该槽位中已处理的交易由签名去重跳过）
+    pub fn resume_from(&self, from_slot: Option<u64>) -> Option<u64> {
+        let last_slot = self.last_slot.load(Ordering::Relaxed);
+        from_slot.map(|slot| slot.max(last_slot))
+    }
+
+    /// 记录数据流因错误断开（订阅失败或收到错误状态），而不是被服务端正常关闭
+    pub fn mark_failed(&self) {
+        self.failed.store(true, Ordering::Relaxed);
+    }
+
+    /// 取出并清除错误标记
+    pub fn take_failed(&self) -> bool {
+        self.failed.swap(false, Ordering::Relaxed)
+    }
+}
+
+/// 日志中显示的x-token：只保留前4个字符
+pub fn mask_token(token: &str) -> String {
+    let prefix: String = token.chars().take(4).collect();
+    if token.chars().count() <= 8 {
+        "****".to_string()
+    } else {
+        format!("{}****", prefix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoint_pool_fails_over_on_errors_and_restores_primary() {
+        let pool = EndpointPool::new(vec!["http://a".to_string(), "http://b".to_string()], GrpcTlsConfig::default(), None).unwrap();
+        pool.failover(0);
+        assert_eq!(pool.active(), (1, "http://b"));
+        pool.restore_primary(1);
+        assert_eq!(pool.active(), (0, "http://a"));
+
+        let progress = StreamProgress::default();
+        assert!(!progress.take_failed());
+        progress.mark_failed();
+        assert!(progress.take_failed());
+        assert!(!progress.take_failed());
+    }
+}
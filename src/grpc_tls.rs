@@ -0,0 +1,202 @@
+use hyper_util::rt::TokioIo;
+use log::warn;
+use serde::Deserialize;
+use std::{
+    fs,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::net::TcpStream;
+use tokio_rustls::{
+    client::TlsStream,
+    rustls::{
+        self,
+        client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        crypto::{ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider},
+        pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime},
+        DigitallySignedStruct, SignatureScheme,
+    },
+    TlsConnector,
+};
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity, Uri};
+
+/// gRPC端点的TLS设置（配置中的 `[grpc_tls]`），未配置时使用系统根证书校验服务端
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct GrpcTlsConfig {
+    pub ca_cert_path: Option<String>,     // 自定义CA证书（PEM），与系统根证书一起用于校验服务端
+    pub client_cert_path: Option<String>, // 双向TLS的客户端证书（PEM），需同时设置 client_key_path
+    pub client_key_path: Option<String>,  // 客户端证书的私钥（PEM）
+    pub domain_name: Option<String>,      // 校验服务端证书时使用的域名（默认取端点地址中的主机名）
+    #[serde(default)]
+    pub insecure_skip_verify: bool,       // 不校验服务端证书，仅用于开发环境
+}
+
+impl GrpcTlsConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.client_cert_path.is_some() != self.client_key_path.is_some() {
+            return Err(anyhow::anyhow!("grpc_tls.client_cert_path 和 grpc_tls.client_key_path 必须同时设置"));
+        }
+        Ok(())
+    }
+
+    /// 按配置生成tonic的TLS设置：系统根证书加上自定义CA，配置了客户端证书时启用双向TLS
+    pub fn client_tls_config(&self) -> anyhow::Result<ClientTlsConfig> {
+        self.validate()?;
+        let mut tls = ClientTlsConfig::new().with_native_roots();
+        if let Some(path) = &self.ca_cert_path {
+            tls = tls.ca_certificate(Certificate::from_pem(read_pem(path, "CA证书")?));
+        }
+        if let (Some(cert_path), Some(key_path)) = (&self.client_cert_path, &self.client_key_path) {
+            tls = tls.identity(Identity::from_pem(read_pem(cert_path, "客户端证书")?, read_pem(key_path, "客户端私钥")?));
+        }
+        if let Some(domain_name) = &self.domain_name {
+            tls = tls.domain_name(domain_name.clone());
+        }
+        Ok(tls)
+    }
+
+    /// 建立到端点的gRPC通道，连接和请求都使用 `timeout` 超时；http端点不使用TLS
+    pub async fn connect(&self, endpoint: String, timeout: Duration) -> anyhow::Result<Channel> {
+        let uri: Uri = endpoint.parse()?;
+        if uri.scheme_str() != Some("https") {
+            return Ok(Endpoint::from(uri).connect_timeout(timeout).timeout(timeout).connect().await?);
+        }
+        if !self.insecure_skip_verify {
+            let endpoint = Endpoint::from(uri).connect_timeout(timeout).timeout(timeout);
+            return Ok(endpoint.tls_config(self.client_tls_config()?)?.connect().await?);
+        }
+
+        warn!("[gRPC] 已开启 grpc_tls.insecure_skip_verify，不校验 {} 的服务端证书，连接可被中间人窃听或篡改，切勿在生产环境使用", uri);
+        let connector = InsecureTlsConnector {
+            tls: TlsConnector::from(Arc::new(self.insecure_rustls_config()?)),
+            domain_name: self.domain_name.clone(),
+        };
+        // tonic对https地址只接受它自己的TLS设置，这里以http地址交给自定义连接器，请求中仍使用原地址
+        let mut parts = uri.clone().into_parts();
+        parts.scheme = Some("http".parse()?);
+        let endpoint = Endpoint::from(Uri::from_parts(parts)?)
+            .origin(uri)
+            .connect_timeout(timeout)
+            .timeout(timeout);
+        Ok(endpoint.connect_with_connector(connector).await?)
+    }
+
+    fn insecure_rustls_config(&self) -> anyhow::Result<rustls::ClientConfig> {
+        self.validate()?;
+        let provider = Arc::new(ring::default_provider());
+        let builder = rustls::ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(SkipServerVerification(provider)));
+        let mut config = match (&self.client_cert_path, &self.client_key_path) {
+            (Some(cert_path), Some(key_path)) => {
+                let certs = rustls_pemfile::certs(&mut read_pem(cert_path, "客户端证书")?.as_slice())
+                    .collect::<Result<Vec<CertificateDer<'static>>, _>>()?;
+                let key: PrivateKeyDer<'static> = rustls_pemfile::private_key(&mut read_pem(key_path, "客户端私钥")?.as_slice())?
+                    .ok_or_else(|| anyhow::anyhow!("{} 中没有私钥", key_path))?;
+                builder.with_client_auth_cert(certs, key)?
+            }
+            _ => builder.with_no_client_auth(),
+        };
+        config.alpn_protocols = vec![b"h2".to_vec()];
+        Ok(config)
+    }
+}
+
+fn read_pem(path: &str, what: &str) -> anyhow::Result<Vec<u8>> {
+    fs::read(path).map_err(|e| anyhow::anyhow!("无法读取{} {}: {}", what, path, e))
+}
+
+// 接受任何服务端证书，但仍校验握手签名
+#[derive(Debug)]
+struct SkipServerVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+// 不校验服务端证书的TLS连接器
+#[derive(Clone)]
+struct InsecureTlsConnector {
+    tls: TlsConnector,
+    domain_name: Option<String>,
+}
+
+impl tower_service::Service<Uri> for InsecureTlsConnector {
+    type Response = TokioIo<TlsStream<TcpStream>>;
+    type Error = anyhow::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let tls = self.tls.clone();
+        let domain_name = self.domain_name.clone();
+        Box::pin(async move {
+            let host = uri.host().ok_or_else(|| anyhow::anyhow!("gRPC端点地址中没有主机名: {}", uri))?.to_string();
+            let port = uri.port_u16().unwrap_or(443);
+            let stream = TcpStream::connect((host.as_str(), port)).await?;
+            stream.set_nodelay(true)?;
+            let server_name = ServerName::try_from(domain_name.unwrap_or(host))?;
+            Ok(TokioIo::new(tls.connect(server_name, stream).await?))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grpc_tls_requires_cert_and_key_together() {
+        let base = r#"
+            ca_cert_path = "/nonexistent/ca.pem"
+            client_cert_path = "certs/client.pem"
+        "#;
+        let config: GrpcTlsConfig = toml::from_str(base).unwrap();
+        let message = config.validate().unwrap_err().to_string();
+        assert!(message.contains("client_key_path"));
+
+        let config: GrpcTlsConfig = toml::from_str(&format!("{}client_key_path = \"certs/client.key\"\n", base)).unwrap();
+        config.validate().unwrap();
+        assert!(!config.insecure_skip_verify);
+        let message = config.client_tls_config().unwrap_err().to_string();
+        assert!(message.contains("/nonexistent/ca.pem"));
+    }
+}
@@ -0,0 +1,31 @@
+use crate::metrics::METRICS;
+use axum::{http::StatusCode, routing::get, Json, Router};
+use log::info;
+use serde_json::json;
+
+/// 启动健康检查HTTP服务
+///
+/// `GET /healthz`: 最近 `stale_secs` 秒内收到过gRPC消息时返回200，否则返回503，
+/// 供Kubernetes/systemd在数据流静默卡住（TCP连接仍在）时重启进程。
+pub async fn serve(port: u16, stale_secs: u64) -> anyhow::Result<()> {
+    let app = Router::new().route("/healthz", get(move || async move { healthz(stale_secs) }));
+
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await?;
+    info!("[Health] 健康检查服务已启动: http://0.0.0.0:{}/healthz", port);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+fn healthz(stale_secs: u64) -> (StatusCode, Json<serde_json::Value>) {
+    let since_last = METRICS.secs_since_last_message();
+    let healthy = since_last.is_some_and(|secs| secs <= stale_secs);
+    let status = if healthy { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (
+        status,
+        Json(json!({
+            "status": if healthy { "ok" } else { "stale" },
+            "seconds_since_last_message": since_last,
+            "stale_after_secs": stale_secs,
+        })),
+    )
+}
@@ -0,0 +1,249 @@
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+/// IDL中的参数或结构体字段
+#[derive(Deserialize, Clone, Debug)]
+pub struct IdlField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: IdlType,
+}
+
+/// IDL类型：基础类型名称（如 `u64`、`publicKey`）或 option/vec/array/defined 组合类型
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum IdlType {
+    Primitive(String),
+    Option { option: Box<IdlType> },
+    Vec { vec: Box<IdlType> },
+    Array { array: (Box<IdlType>, usize) },
+    Defined { defined: IdlDefined },
+}
+
+/// 自定义类型的引用，旧版IDL为名称字符串，新版为 `{ "name": ... }`
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum IdlDefined {
+    Name(String),
+    Named { name: String },
+}
+
+impl IdlDefined {
+    fn name(&self) -> &str {
+        match self {
+            Self::Name(name) | Self::Named { name } => name,
+        }
+    }
+}
+
+/// IDL `types` 中的自定义类型
+#[derive(Deserialize, Clone, Debug)]
+pub struct IdlTypeDef {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: IdlTypeDefBody,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct IdlTypeDefBody {
+    pub kind: String, // "struct" 或 "enum"
+    #[serde(default)]
+    pub fields: Vec<IdlField>,
+    #[serde(default)]
+    pub variants: Vec<IdlEnumVariant>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct IdlEnumVariant {
+    pub name: String,
+    #[serde(default)]
+    pub fields: Option<Value>, // 带字段的变体暂不支持解码
+}
+
+/// 按IDL字段定义解码Borsh编码的参数，返回以蛇形字段名为键的JSON对象
+pub fn decode_fields(fields: &[IdlField], types: &[IdlTypeDef], data: &mut &[u8]) -> anyhow::Result<Value> {
+    let mut object = Map::new();
+    for field in fields {
+        let value = decode_type(&field.ty, types, data)
+            .map_err(|e| anyhow::anyhow!("解码参数 {} 失败: {}", field.name, e))?;
+        object.insert(crate::instruction_account_mapper::to_snake_case(&field.name), value);
+    }
+    Ok(Value::Object(object))
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> anyhow::Result<&'a [u8]> {
+    if data.len() < len {
+        return Err(anyhow::anyhow!("数据不足: 需要 {} 字节, 剩余 {} 字节", len, data.len()));
+    }
+    let (head, tail) = data.split_at(len);
+    *data = tail;
+    Ok(head)
+}
+
+fn take_array<const N: usize>(data: &mut &[u8]) -> anyhow::Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    bytes.copy_from_slice(take(data, N)?);
+    Ok(bytes)
+}
+
+fn take_len(data: &mut &[u8]) -> anyhow::Result<usize> {
+    Ok(u32::from_le_bytes(take_array(data)?) as usize)
+}
+
+fn decode_type(ty: &IdlType, types: &[IdlTypeDef], data: &mut &[u8]) -> anyhow::Result<Value> {
+    match ty {
+        IdlType::Primitive(name) => decode_primitive(name, data),
+        IdlType::Option { option } => match take(data, 1)?[0] {
+            0 => Ok(Value::Null),
+            _ => decode_type(option, types, data),
+        },
+        IdlType::Vec { vec } => {
+            let len = take_len(data)?;
+            (0..len).map(|_| decode_type(vec, types, data)).collect::<anyhow::Result<Vec<_>>>().map(Value::Array)
+        }
+        IdlType::Array { array: (item, len) } => {
+            (0..*len).map(|_| decode_type(item, types, data)).collect::<anyhow::Result<Vec<_>>>().map(Value::Array)
+        }
+        IdlType::Defined { defined } => {
+            let name = defined.name();
+            let def = types
+                .iter()
+                .find(|def| def.name == name)
+                .ok_or_else(|| anyhow::anyhow!("IDL中没有类型 {}", name))?;
+            match def.ty.kind.as_str() {
+                "struct" => decode_fields(&def.ty.fields, types, data),
+                "enum" => {
+                    let index = take(data, 1)?[0] as usize;
+                    let variant = def
+                        .ty
+                        .variants
+                        .get(index)
+                        .ok_or_else(|| anyhow::anyhow!("类型 {} 没有第 {} 个变体", name, index))?;
+                    if variant.fields.is_some() {
+                        return Err(anyhow::anyhow!("暂不支持带字段的枚举变体 {}::{}", name, variant.name));
+                    }
+                    Ok(json!(variant.name))
+                }
+                kind => Err(anyhow::anyhow!("不支持的类型种类 {} ({})", kind, name)),
+            }
+        }
+    }
+}
+
+fn decode_primitive(name: &str, data: &mut &[u8]) -> anyhow::Result<Value> {
+    Ok(match name {
+        "bool" => json!(take(data, 1)?[0] != 0),
+        "u8" => json!(take(data, 1)?[0]),
+        "i8" => json!(take(data, 1)?[0] as i8),
+        "u16" => json!(u16::from_le_bytes(take_array(data)?)),
+        "i16" => json!(i16::from_le_bytes(take_array(data)?)),
+        "u32" => json!(u32::from_le_bytes(take_array(data)?)),
+        "i32" => json!(i32::from_le_bytes(take_array(data)?)),
+        "u64" => json!(u64::from_le_bytes(take_array(data)?)),
+        "i64" => json!(i64::from_le_bytes(take_array(data)?)),
+        "f32" => json!(f32::from_le_bytes(take_array(data)?)),
+        "f64" => json!(f64::from_le_bytes(take_array(data)?)),
+        // 128位整数超出JSON数字的精度，以字符串输出
+        "u128" => json!(u128::from_le_bytes(take_array(data)?).to_string()),
+        "i128" => json!(i128::from_le_bytes(take_array(data)?).to_string()),
+        "string" => {
+            let len = take_len(data)?;
+            json!(std::str::from_utf8(take(data, len)?)?)
+        }
+        "bytes" => {
+            let len = take_len(data)?;
+            json!(take(data, len)?.iter().map(|b| format!("{:02x}", b)).collect::<String>())
+        }
+        "publicKey" | "pubkey" => json!(bs58::encode(take(data, 32)?).into_string()),
+        other => return Err(anyhow::anyhow!("不支持的类型 {}", other)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(value: Value) -> Vec<IdlField> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn types() -> Vec<IdlTypeDef> {
+        serde_json::from_value(json!([
+            { "name": "Side", "type": { "kind": "enum", "variants": [{ "name": "Buy" }, { "name": "Sell" }] } },
+            { "name": "Tagged", "type": { "kind": "enum", "variants": [{ "name": "Amount", "fields": ["u64"] }] } },
+            { "name": "Point", "type": { "kind": "struct", "fields": [{ "name": "x", "type": "u8" }, { "name": "y", "type": "u8" }] } },
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn option_vec_and_array_decode() {
+        let fields = fields(json!([
+            { "name": "maybeAmount", "type": { "option": "u64" } },
+            { "name": "missing", "type": { "option": "u64" } },
+            { "name": "points", "type": { "vec": { "defined": { "name": "Point" } } } },
+            { "name": "pair", "type": { "array": ["u16", 2] } },
+        ]));
+        let mut data = vec![1];
+        data.extend_from_slice(&7u64.to_le_bytes());
+        data.push(0);
+        data.extend_from_slice(&2u32.to_le_bytes());
+        data.extend_from_slice(&[1, 2, 3, 4]);
+        data.extend_from_slice(&5u16.to_le_bytes());
+        data.extend_from_slice(&6u16.to_le_bytes());
+
+        let mut input = data.as_slice();
+        let decoded = decode_fields(&fields, &types(), &mut input).unwrap();
+        assert!(input.is_empty());
+        assert_eq!(
+            decoded,
+            json!({
+                "maybe_amount": 7,
+                "missing": null,
+                "points": [{ "x": 1, "y": 2 }, { "x": 3, "y": 4 }],
+                "pair": [5, 6],
+            })
+        );
+    }
+
+    #[test]
+    fn defined_enum_decodes_variant_name() {
+        let side = fields(json!([{ "name": "side", "type": { "defined": "Side" } }]));
+        assert_eq!(decode_fields(&side, &types(), &mut [1u8].as_slice()).unwrap(), json!({ "side": "Sell" }));
+        assert!(decode_fields(&side, &types(), &mut [2u8].as_slice()).is_err());
+
+        // 带字段的变体和未知类型都返回错误
+        let tagged = fields(json!([{ "name": "tagged", "type": { "defined": "Tagged" } }]));
+        assert!(decode_fields(&tagged, &types(), &mut [0u8; 9].as_slice()).is_err());
+        let unknown = fields(json!([{ "name": "other", "type": { "defined": "Other" } }]));
+        assert!(decode_fields(&unknown, &types(), &mut [0u8].as_slice()).is_err());
+    }
+
+    #[test]
+    fn short_buffer_reports_field_name() {
+        let fields = fields(json!([
+            { "name": "amount", "type": "u64" },
+            { "name": "name", "type": "string" },
+        ]));
+        let mut data = 1u64.to_le_bytes().to_vec();
+        data.extend_from_slice(&10u32.to_le_bytes());
+        data.extend_from_slice(b"short");
+        let message = decode_fields(&fields, &[], &mut data.as_slice()).unwrap_err().to_string();
+        assert!(message.contains("name") && message.contains("数据不足"), "{}", message);
+        assert!(decode_fields(&fields, &[], &mut [0u8; 4].as_slice()).is_err());
+    }
+
+    #[test]
+    fn u128_decodes_as_string() {
+        let fields = fields(json!([
+            { "name": "big", "type": "u128" },
+            { "name": "negative", "type": "i128" },
+        ]));
+        let mut data = u128::MAX.to_le_bytes().to_vec();
+        data.extend_from_slice(&(-5i128).to_le_bytes());
+        assert_eq!(
+            decode_fields(&fields, &[], &mut data.as_slice()).unwrap(),
+            json!({ "big": u128::MAX.to_string(), "negative": "-5" })
+        );
+    }
+}
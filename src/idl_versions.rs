@@ -0,0 +1,165 @@
+//! 按交易槽位和指令布局选择程序的IDL
+//!
+//! PumpFun升级时会调整指令的账户列表，用旧IDL映射新交易（或反过来）会把账户名称错位。
+//! 同一程序可以在 `idl_path` 之外配置多个 `idl_versions`，解码每条指令时：
+//! 1. 只考虑 `from_slot`（包含）到 `until_slot`（不包含）范围覆盖交易槽位的版本，未设置的一端不限制；
+//!    `idl_path` 中的默认IDL始终参与选择，排在所有版本之后；
+//! 2. 其中优先使用该指令在IDL中定义的账户数与交易中实际账户数相同的版本（布局信号）；
+//! 3. 都不相同时使用第一个定义了该指令的版本，都没有定义时使用第一个版本。
+
+use crate::instruction_account_mapper::Idl;
+use serde::Deserialize;
+use std::fs;
+
+/// 配置中的一个IDL版本
+#[derive(Debug, Deserialize, Clone)]
+pub struct IdlVersionConfig {
+    pub name: Option<String>,     // 版本名称，用于日志（默认使用文件路径）
+    pub idl_path: String,
+    pub from_slot: Option<u64>,   // 从该槽位开始使用（包含）
+    pub until_slot: Option<u64>,  // 到该槽位为止（不包含）
+}
+
+impl IdlVersionConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let (Some(from_slot), Some(until_slot)) = (self.from_slot, self.until_slot) {
+            if from_slot >= until_slot {
+                return Err(anyhow::anyhow!(
+                    "IDL版本 {} 的 from_slot ({}) 必须小于 until_slot ({})",
+                    self.idl_path, from_slot, until_slot
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 已加载的IDL版本
+#[derive(Clone)]
+pub struct IdlVersion {
+    pub name: String,
+    from_slot: Option<u64>,
+    until_slot: Option<u64>,
+    idl: Idl,
+}
+
+impl IdlVersion {
+    pub fn new(name: String, from_slot: Option<u64>, until_slot: Option<u64>, idl: Idl) -> Self {
+        Self { name, from_slot, until_slot, idl }
+    }
+
+    fn covers(&self, slot: u64) -> bool {
+        self.from_slot.is_none_or(|from_slot| slot >= from_slot) && self.until_slot.is_none_or(|until_slot| slot < until_slot)
+    }
+}
+
+/// 程序的默认IDL和按槽位区分的IDL版本
+#[derive(Clone, Default)]
+pub struct IdlSet {
+    default: Option<Idl>,
+    versions: Vec<IdlVersion>,
+}
+
+impl IdlSet {
+    pub fn new(default: Option<Idl>, versions: Vec<IdlVersion>) -> Self {
+        Self { default, versions }
+    }
+
+    /// 加载默认IDL和所有版本的IDL文件
+    pub fn load(default_path: Option<&str>, versions: &[IdlVersionConfig]) -> anyhow::Result<Self> {
+        let default = default_path.map(read_idl).transpose()?;
+        let versions = versions
+            .iter()
+            .map(|version| {
+                version.validate()?;
+                Ok(IdlVersion::new(
+                    version.name.clone().unwrap_or_else(|| version.idl_path.clone()),
+                    version.from_slot,
+                    version.until_slot,
+                    read_idl(&version.idl_path)?,
+                ))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self::new(default, versions))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.default.is_none() && self.versions.is_empty()
+    }
+
+    pub fn versions(&self) -> &[IdlVersion] {
+        &self.versions
+    }
+
+    // 槽位范围覆盖交易的版本，最后是默认IDL
+    fn candidates(&self, slot: u64) -> impl Iterator<Item = &Idl> {
+        self.versions
+            .iter()
+            .filter(move |version| version.covers(slot))
+            .map(|version| &version.idl)
+            .chain(self.default.as_ref())
+    }
+
+    /// 按指令数据的判别符查找指令名称
+    pub fn instruction_name_for_data(&self, slot: u64, data: &[u8]) -> Option<&str> {
+        self.candidates(slot).find_map(|idl| idl.instruction_name_for_data(data))
+    }
+
+    /// 选择映射该指令账户使用的IDL，规则见模块说明
+    pub fn select(&self, slot: u64, instruction: &str, account_count: usize) -> Option<&Idl> {
+        let mut defines_instruction = None;
+        for idl in self.candidates(slot) {
+            match idl.instruction_account_count(instruction) {
+                Some(count) if count == account_count => return Some(idl),
+                Some(_) if defines_instruction.is_none() => defines_instruction = Some(idl),
+                _ => {}
+            }
+        }
+        defines_instruction.or_else(|| self.candidates(slot).next())
+    }
+}
+
+fn read_idl(path: &str) -> anyhow::Result<Idl> {
+    let content = fs::read_to_string(path).map_err(|e| anyhow::anyhow!("无法读取IDL文件 {}: {}", path, e))?;
+    serde_json::from_str(&content).map_err(|e| anyhow::anyhow!("{} 不是有效的IDL: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pump_interface::instructions::BUY_IX_DISCM;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn idl_versions_select_by_slot_and_account_count() {
+        let idl = |accounts: &[&str]| -> Idl {
+            let accounts: Vec<Value> = accounts.iter().map(|name| json!({ "name": name })).collect();
+            serde_json::from_value(json!({ "instructions": [{ "name": "buy", "accounts": accounts }] })).unwrap()
+        };
+        let old = idl(&["global", "mint", "user"]);
+        let new = idl(&["global", "mint", "user", "creatorVault"]);
+        let idls = IdlSet::new(
+            Some(idl(&["global", "mint"])),
+            vec![
+                IdlVersion::new("v1".to_string(), None, Some(100), old),
+                IdlVersion::new("v2".to_string(), Some(100), None, new),
+            ],
+        );
+        let account_count = |slot: u64, accounts: usize| idls.select(slot, "buy", accounts).and_then(|idl| idl.instruction_account_count("buy"));
+
+        // 槽位决定候选版本，账户数相同的版本优先（包括默认IDL）
+        assert_eq!(account_count(50, 3), Some(3));
+        assert_eq!(account_count(150, 4), Some(4));
+        assert_eq!(account_count(150, 2), Some(2));
+        // 没有账户数相同的版本时使用槽位范围内的第一个版本
+        assert_eq!(account_count(50, 7), Some(3));
+        assert_eq!(account_count(100, 7), Some(4));
+        assert!(idls.select(150, "sell", 3).is_some());
+
+        let data = [BUY_IX_DISCM.as_slice(), &[0u8; 16]].concat();
+        assert_eq!(idls.instruction_name_for_data(150, &data), Some("buy"));
+
+        let config: IdlVersionConfig = toml::from_str("idl_path = \"idls/pump.json\"\nfrom_slot = 10\nuntil_slot = 10").unwrap();
+        assert!(config.validate().is_err());
+    }
+}
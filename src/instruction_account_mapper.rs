@@ -1,12 +1,20 @@
-use crate::serialization::serialize_pubkey;
+use crate::{
+    idl_args::{self, IdlField, IdlTypeDef},
+    serialization::serialize_pubkey,
+};
 use serde::{Deserialize, Serialize};
-use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use solana_program::{hash::hashv, pubkey::Pubkey};
 use solana_sdk::instruction::AccountMeta;
 
 #[derive(Deserialize, Clone)]
 struct IdlInstruction {
     name: String,
     accounts: Vec<IdlAccount>,
+    // 可选的剩余账户名称（按顺序对应IDL账户之后追加的账户），读写和签名标志取自交易
+    #[serde(rename = "remainingAccounts", alias = "remaining_accounts", default)]
+    remaining_accounts: Vec<IdlAccount>,
+    #[serde(default)]
+    args: Vec<IdlField>,
 }
 
 #[derive(Deserialize, Clone)]
@@ -25,6 +33,58 @@ struct IdlAccount {
 #[derive(Deserialize, Clone)]
 pub struct Idl {
     instructions: Vec<IdlInstruction>,
+    #[serde(default)]
+    types: Vec<IdlTypeDef>,
+}
+
+impl Idl {
+    /// 根据指令数据的前8字节（Anchor判别符）查找指令名称
+    pub fn instruction_name_for_data(&self, data: &[u8]) -> Option<&str> {
+        let discriminator = data.get(..8)?;
+        self.instructions
+            .iter()
+            .find(|ix| anchor_discriminator(&ix.name) == discriminator)
+            .map(|ix| ix.name.as_str())
+    }
+
+    /// 指令在IDL中定义的账户数（不含剩余账户），IDL中没有该指令时返回None
+    pub fn instruction_account_count(&self, instruction_name: &str) -> Option<usize> {
+        self.find_instruction(instruction_name).ok().map(|instruction| instruction.accounts.len())
+    }
+
+    /// 按IDL中的参数定义解码指令参数（不含8字节判别符），返回 `{ 参数名: 值 }`
+    pub fn decode_args(&self, instruction_name: &str, args_data: &[u8]) -> anyhow::Result<serde_json::Value> {
+        let instruction = self
+            .find_instruction(instruction_name)
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        let mut data = args_data;
+        idl_args::decode_fields(&instruction.args, &self.types, &mut data)
+    }
+}
+
+/// Anchor指令判别符: sha256("global:" + snake_case(name)) 的前8字节
+fn anchor_discriminator(name: &str) -> [u8; 8] {
+    let preimage = format!("global:{}", to_snake_case(name));
+    let hash = hashv(&[preimage.as_bytes()]);
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash.to_bytes()[..8]);
+    discriminator
+}
+
+/// IDL中的指令名为驼峰格式（如 `setParams`），Anchor计算判别符时使用蛇形格式
+pub(crate) fn to_snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
 }
 
 #[derive(Debug, Serialize)]
@@ -36,50 +96,203 @@ pub struct AccountMetadata {
     pub name: String,
 }
 
+/// 按IDL映射指令账户失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccountMappingError {
+    /// IDL中没有该指令
+    UnknownInstruction(String),
+    /// 指令中的账户少于IDL定义的账户数
+    TruncatedAccounts {
+        instruction: String,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl std::fmt::Display for AccountMappingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownInstruction(name) => write!(f, "IDL中没有指令 {}", name),
+            Self::TruncatedAccounts { instruction, expected, actual } => {
+                write!(f, "指令 {} 的账户数 {} 少于IDL定义的 {}", instruction, actual, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AccountMappingError {}
+
 pub trait InstructionAccountMapper<'info> {
-    fn map_accounts<'me>(
+    /// 按IDL映射账户，IDL中没有该指令或账户数不足时返回错误
+    fn map_accounts(
         &self,
         accounts: &[AccountMeta],
         instruction_name: &str,
-    ) -> Result<Vec<AccountMetadata>, ProgramError>;
-}
+    ) -> Result<Vec<AccountMetadata>, AccountMappingError>;
 
-impl<'info> InstructionAccountMapper<'info> for Idl {
-    fn map_accounts<'me>(
+    /// 按IDL映射账户，失败时不丢弃指令：IDL中没有该指令时使用生成的名称（`account_0`, `account_1`, …），
+    /// 账户数不足时按IDL名称映射实际存在的账户；同时返回映射中遇到的问题
+    fn map_accounts_lenient(
         &self,
         accounts: &[AccountMeta],
         instruction_name: &str,
-    ) -> Result<Vec<AccountMetadata>, ProgramError> {
-        let instruction = self
-            .instructions
+    ) -> (Vec<AccountMetadata>, Option<AccountMappingError>);
+}
+
+impl Idl {
+    fn find_instruction(&self, instruction_name: &str) -> Result<&IdlInstruction, AccountMappingError> {
+        self.instructions
             .iter()
             .find(|ix| ix.name == instruction_name)
-            .ok_or(ProgramError::InvalidArgument)?;
+            .ok_or_else(|| AccountMappingError::UnknownInstruction(instruction_name.to_string()))
+    }
+}
 
-        let mut account_metadata: Vec<AccountMetadata> = accounts
-            .iter()
-            .take(instruction.accounts.len())
-            .enumerate()
-            .map(|(i, account)| {
-                let account_info = &instruction.accounts[i];
-                AccountMetadata {
-                    pubkey: account.pubkey,
-                    is_writable: if account_info.is_mut { true } else { account_info.writable },
-                    is_signer: if account_info.is_signer { true } else { account_info.signer },
-                    name: account_info.name.clone(),
-                }
-            })
-            .collect();
-
-        for (i, account) in accounts.iter().enumerate().skip(instruction.accounts.len()) {
-            account_metadata.push(AccountMetadata {
-                pubkey: account.pubkey,
-                is_writable: account.is_writable,
-                is_signer: account.is_signer,
-                name: format!("Remaining accounts {}", i - instruction.accounts.len() + 1),
+impl<'info> InstructionAccountMapper<'info> for Idl {
+    fn map_accounts(
+        &self,
+        accounts: &[AccountMeta],
+        instruction_name: &str,
+    ) -> Result<Vec<AccountMetadata>, AccountMappingError> {
+        let instruction = self.find_instruction(instruction_name)?;
+        if accounts.len() < instruction.accounts.len() {
+            return Err(AccountMappingError::TruncatedAccounts {
+                instruction: instruction_name.to_string(),
+                expected: instruction.accounts.len(),
+                actual: accounts.len(),
             });
         }
+        Ok(map_with_instruction(instruction, accounts))
+    }
+
+    fn map_accounts_lenient(
+        &self,
+        accounts: &[AccountMeta],
+        instruction_name: &str,
+    ) -> (Vec<AccountMetadata>, Option<AccountMappingError>) {
+        match self.map_accounts(accounts, instruction_name) {
+            Ok(mapped) => (mapped, None),
+            Err(error @ AccountMappingError::UnknownInstruction(_)) => (generic_accounts(accounts), Some(error)),
+            Err(error @ AccountMappingError::TruncatedAccounts { .. }) => {
+                let mapped = self
+                    .find_instruction(instruction_name)
+                    .map(|instruction| map_with_instruction(instruction, accounts))
+                    .unwrap_or_else(|_| generic_accounts(accounts));
+                (mapped, Some(error))
+            }
+        }
+    }
+}
+
+/// 按IDL中的账户名称映射；超出IDL定义的账户优先使用 `remainingAccounts` 中的名称，没有时作为编号的剩余账户
+fn map_with_instruction(instruction: &IdlInstruction, accounts: &[AccountMeta]) -> Vec<AccountMetadata> {
+    let mut account_metadata: Vec<AccountMetadata> = accounts
+        .iter()
+        .zip(&instruction.accounts)
+        .map(|(account, account_info)| AccountMetadata {
+            pubkey: account.pubkey,
+            is_writable: if account_info.is_mut { true } else { account_info.writable },
+            is_signer: if account_info.is_signer { true } else { account_info.signer },
+            name: account_info.name.clone(),
+        })
+        .collect();
+
+    for (i, account) in accounts.iter().enumerate().skip(instruction.accounts.len()) {
+        let remaining_index = i - instruction.accounts.len();
+        let name = match instruction.remaining_accounts.get(remaining_index) {
+            Some(hint) => hint.name.clone(),
+            None => format!("Remaining accounts {}", remaining_index + 1),
+        };
+        account_metadata.push(AccountMetadata {
+            pubkey: account.pubkey,
+            is_writable: account.is_writable,
+            is_signer: account.is_signer,
+            name,
+        });
+    }
+    account_metadata
+}
+
+/// 没有IDL定义时按位置生成账户名称，读写和签名标志取自交易
+fn generic_accounts(accounts: &[AccountMeta]) -> Vec<AccountMetadata> {
+    accounts
+        .iter()
+        .enumerate()
+        .map(|(i, account)| AccountMetadata {
+            pubkey: account.pubkey,
+            is_writable: account.is_writable,
+            is_signer: account.is_signer,
+            name: format!("account_{}", i),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn lenient_account_mapping_keeps_unmatched_instructions() {
+        let idl: Idl = serde_json::from_value(json!({
+            "instructions": [
+                { "name": "buy", "accounts": [{ "name": "global" }, { "name": "mint" }, { "name": "user", "isSigner": true }] }
+            ]
+        }))
+        .unwrap();
+        let metas = vec![AccountMeta::new_readonly(Pubkey::new_unique(), false), AccountMeta::new(Pubkey::new_unique(), true)];
+
+        let (accounts, error) = idl.map_accounts_lenient(&metas, "sell");
+        assert_eq!(error, Some(AccountMappingError::UnknownInstruction("sell".to_string())));
+        assert_eq!(accounts.iter().map(|a| a.name.as_str()).collect::<Vec<_>>(), ["account_0", "account_1"]);
+        assert!(accounts[1].is_signer);
+
+        let (accounts, error) = idl.map_accounts_lenient(&metas, "buy");
+        assert!(matches!(error, Some(AccountMappingError::TruncatedAccounts { expected: 3, actual: 2, .. })));
+        assert_eq!(accounts.iter().map(|a| a.name.as_str()).collect::<Vec<_>>(), ["global", "mint"]);
+
+        // 严格映射在账户不足时返回错误而不是越界，没有账户时宽松映射返回空列表
+        assert!(matches!(idl.map_accounts(&metas, "buy"), Err(AccountMappingError::TruncatedAccounts { expected: 3, actual: 2, .. })));
+        let (accounts, error) = idl.map_accounts_lenient(&[], "buy");
+        assert!(accounts.is_empty());
+        assert!(matches!(error, Some(AccountMappingError::TruncatedAccounts { expected: 3, actual: 0, .. })));
+    }
+
+    #[test]
+    fn remaining_accounts_use_idl_names() {
+        let idl: Idl = serde_json::from_value(json!({
+            "instructions": [
+                { "name": "sell", "accounts": [{ "name": "global" }], "remainingAccounts": [{ "name": "feeConfig" }] }
+            ]
+        }))
+        .unwrap();
+        let metas: Vec<AccountMeta> = (0..3).map(|_| AccountMeta::new_readonly(Pubkey::new_unique(), false)).collect();
+        let accounts = idl.map_accounts(&metas, "sell").unwrap();
+        assert_eq!(
+            accounts.iter().map(|a| a.name.as_str()).collect::<Vec<_>>(),
+            ["global", "feeConfig", "Remaining accounts 2"]
+        );
+    }
+
+    #[test]
+    fn idl_args_decode_set_params() {
+        let idl: Idl = serde_json::from_str(&std::fs::read_to_string("idls/pump.json").unwrap()).unwrap();
+        let fee_recipient = Pubkey::new_unique();
+        let mut data = fee_recipient.to_bytes().to_vec();
+        for value in [1u64, 2, 3, 4, 95] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        let args = idl.decode_args("setParams", &data).unwrap();
+        assert_eq!(args["fee_recipient"], json!(fee_recipient.to_string()));
+        assert_eq!(args["initial_virtual_token_reserves"], json!(1));
+        assert_eq!(args["fee_basis_points"], json!(95));
 
-        Ok(account_metadata)
+        let mut create = Vec::new();
+        for text in ["Name", "SYM", "https://uri"] {
+            create.extend_from_slice(&(text.len() as u32).to_le_bytes());
+            create.extend_from_slice(text.as_bytes());
+        }
+        assert_eq!(idl.decode_args("create", &create).unwrap(), json!({ "name": "Name", "symbol": "SYM", "uri": "https://uri" }));
+        assert!(idl.decode_args("create", &create[..6]).is_err());
     }
-} 
\ No newline at end of file
+}
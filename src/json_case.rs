@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// 输出JSON（CPI日志、推送、事件发布和结构化日志）的字段命名方式
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum JsonCase {
+    /// 保持原样：交易字段为snake_case，IDL中的账户名为camelCase
+    #[default]
+    #[serde(rename = "mixed")]
+    Mixed,
+    /// 所有字段名转换为snake_case
+    #[serde(rename = "snake_case")]
+    Snake,
+    /// 所有字段名转换为camelCase
+    #[serde(rename = "camelCase")]
+    Camel,
+}
+
+impl JsonCase {
+    /// 递归转换对象的字段名；作为键的Solana地址保持不变
+    pub fn apply(self, value: Value) -> Value {
+        match (self, value) {
+            (Self::Mixed, value) => value,
+            (_, Value::Object(object)) => Value::Object(
+                object
+                    .into_iter()
+                    .map(|(key, value)| (self.rename(&key), self.apply(value)))
+                    .collect::<Map<String, Value>>(),
+            ),
+            (_, Value::Array(items)) => Value::Array(items.into_iter().map(|item| self.apply(item)).collect()),
+            (_, value) => value,
+        }
+    }
+
+    /// 序列化为单行JSON并转换字段名，序列化失败时返回空字符串
+    pub fn to_json<T: Serialize>(self, value: &T) -> String {
+        let result = match self {
+            Self::Mixed => serde_json::to_string(value),
+            _ => serde_json::to_value(value).and_then(|value| serde_json::to_string(&self.apply(value))),
+        };
+        result.unwrap_or_default()
+    }
+
+    fn rename(self, key: &str) -> String {
+        if is_address(key) {
+            return key.to_string();
+        }
+        match self {
+            Self::Mixed => key.to_string(),
+            Self::Snake => to_snake_case(key),
+            Self::Camel => to_camel_case(key),
+        }
+    }
+}
+
+// 以地址为键的对象（如按账户分组的数据）不能改名
+fn is_address(key: &str) -> bool {
+    (32..=44).contains(&key.len()) && bs58::decode(key).into_vec().is_ok_and(|bytes| bytes.len() == 32)
+}
+
+// associatedTokenProgram -> associated_token_program，连续的大写字母视为一个单词
+fn to_snake_case(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    let mut result = String::with_capacity(key.len() + 4);
+    for (index, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() && index > 0 {
+            let previous = chars[index - 1];
+            let next_is_lower = chars.get(index + 1).is_some_and(char::is_ascii_lowercase);
+            if previous.is_ascii_lowercase() || previous.is_ascii_digit() || (previous.is_ascii_uppercase() && next_is_lower) {
+                result.push('_');
+            }
+        }
+        result.push(c.to_ascii_lowercase());
+    }
+    result
+}
+
+// virtual_sol_reserves -> virtualSolReserves，已是camelCase的名称保持不变
+fn to_camel_case(key: &str) -> String {
+    let mut result = String::with_capacity(key.len());
+    for (index, part) in key.split('_').filter(|part| !part.is_empty()).enumerate() {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            if index == 0 {
+                result.push(first.to_ascii_lowercase());
+            } else {
+                result.push(first.to_ascii_uppercase());
+            }
+            result.push_str(chars.as_str());
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn json_case_renames_keys_but_keeps_addresses() {
+        let value = json!({
+            "token_amount": 1,
+            "accounts": [{ "associatedTokenProgram": "x", "creator_vault": "y" }],
+            "by_account": { "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P": { "tokenIDLPath": 2 } },
+        });
+        assert_eq!(JsonCase::Mixed.apply(value.clone()), value);
+        assert_eq!(
+            JsonCase::Snake.apply(value.clone()),
+            json!({
+                "token_amount": 1,
+                "accounts": [{ "associated_token_program": "x", "creator_vault": "y" }],
+                "by_account": { "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P": { "token_idl_path": 2 } },
+            })
+        );
+        assert_eq!(
+            JsonCase::Camel.apply(value),
+            json!({
+                "tokenAmount": 1,
+                "accounts": [{ "associatedTokenProgram": "x", "creatorVault": "y" }],
+                "byAccount": { "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P": { "tokenIDLPath": 2 } },
+            })
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use std::fmt;
+
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+/// 以lamports为单位的SOL金额，链上数据和指令参数都使用该单位；显示时换算为SOL
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Lamports(pub u64);
+
+impl Lamports {
+    /// 换算为SOL
+    pub fn to_sol(self) -> f64 {
+        self.0 as f64 / LAMPORTS_PER_SOL as f64
+    }
+}
+
+impl From<u64> for Lamports {
+    fn from(lamports: u64) -> Self {
+        Self(lamports)
+    }
+}
+
+/// 格式化为 `0.5 SOL`
+impl fmt::Display for Lamports {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} SOL", self.to_sol())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lamports_convert_and_display_as_sol() {
+        assert_eq!(Lamports(1_500_000_000).to_sol(), 1.5);
+        assert_eq!(Lamports(5_000).to_string(), "0.000005 SOL");
+        assert_eq!(Lamports::from(0).to_string(), "0 SOL");
+    }
+}
@@ -0,0 +1,91 @@
+use log::{error, warn};
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    sync::mpsc::{self, RecvTimeoutError, SyncSender, TrySendError},
+    time::{Duration, Instant},
+};
+
+const LOG_FILE_QUEUE_SIZE: usize = 8192; // 待写入的日志行队列容量
+const LOG_FILE_BUFFER_BYTES: usize = 64 * 1024; // 写文件的缓冲区大小
+const LOG_FILE_FLUSH_INTERVAL: Duration = Duration::from_secs(1); // 缓冲区中的日志最迟多久写入磁盘
+
+/// 交易日志文件后台写入器
+///
+/// 事件处理只把格式化好的日志行放入有界队列，由独立的阻塞任务通过 `BufWriter` 批量追加到文件，
+/// 每秒（或队列关闭时）刷新一次缓冲区，磁盘延迟不会阻塞数据流的消费；队列已满时丢弃日志行并输出警告。
+#[derive(Clone)]
+pub struct LogFileWriter {
+    tx: SyncSender<String>,
+}
+
+impl LogFileWriter {
+    /// 以追加方式打开日志文件并启动后台写入任务（需在tokio运行时中调用）
+    pub fn open(path: &str) -> anyhow::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let (tx, rx) = mpsc::sync_channel(LOG_FILE_QUEUE_SIZE);
+        let path = path.to_string();
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = run_writer(BufWriter::with_capacity(LOG_FILE_BUFFER_BYTES, file), rx) {
+                error!("写入日志文件失败 ({}): {}", path, e);
+            }
+        });
+        Ok(Self { tx })
+    }
+
+    /// 将一行日志放入写入队列
+    pub fn write_line(&self, line: String) {
+        match self.tx.try_send(line) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => warn!("日志文件写入队列已满，丢弃一行日志"),
+            Err(TrySendError::Disconnected(_)) => warn!("日志文件写入任务已停止，丢弃一行日志"),
+        }
+    }
+}
+
+// 写入队列中的日志行，超过刷新间隔时刷新缓冲区；所有发送端关闭后写完剩余日志并退出
+fn run_writer(mut writer: BufWriter<File>, rx: mpsc::Receiver<String>) -> std::io::Result<()> {
+    let mut last_flush = Instant::now();
+    loop {
+        match rx.recv_timeout(LOG_FILE_FLUSH_INTERVAL) {
+            Ok(line) => writeln!(writer, "{}", line)?,
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        if last_flush.elapsed() >= LOG_FILE_FLUSH_INTERVAL {
+            if !writer.buffer().is_empty() {
+                writer.flush()?;
+            }
+            last_flush = Instant::now();
+        }
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+
+    #[tokio::test]
+    async fn log_file_writer_appends_lines_and_flushes_on_close() {
+        let path = env::temp_dir().join(format!("copy-bot-log-{}.log", std::process::id()));
+        fs::write(&path, "existing\n").unwrap();
+        let writer = LogFileWriter::open(path.to_str().unwrap()).unwrap();
+        writer.write_line("first".to_string());
+        writer.clone().write_line("second".to_string());
+        drop(writer);
+
+        // 所有发送端关闭后后台任务写完剩余日志并退出
+        let mut content = String::new();
+        for _ in 0..50 {
+            content = fs::read_to_string(&path).unwrap();
+            if content.ends_with("second\n") {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        fs::remove_file(&path).unwrap();
+        assert_eq!(content, "existing\nfirst\nsecond\n");
+    }
+}
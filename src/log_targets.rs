@@ -0,0 +1,67 @@
+//! 按事件类型区分的日志target
+//!
+//! 可通过 `RUST_LOG` 单独控制每类日志，例如 `RUST_LOG=info,pump::account=off,pump::redis=warn`。
+//! 未使用这些target的日志仍使用默认的模块target（`copy_bot`）。
+
+use log::{Level, LevelFilter};
+use std::str::FromStr;
+
+pub const BUY: &str = "pump::buy"; // 买入交易
+pub const SELL: &str = "pump::sell"; // 卖出交易
+pub const CREATE: &str = "pump::create"; // 代币创建
+pub const ACCOUNT: &str = "pump::account"; // 账户更新（绑定曲线、Global、代币账户）
+pub const TOKEN: &str = "pump::token"; // Token程序指令
+pub const REDIS: &str = "pump::redis"; // Redis读写
+pub const GRADUATION: &str = "pump::graduation"; // 代币毕业
+pub const SANDWICH: &str = "pump::sandwich"; // 疑似夹子交易
+pub const VOLUME: &str = "pump::volume"; // 成交量突增
+pub const ADMIN: &str = "pump::admin"; // 管理指令（Withdraw、SetParams）
+
+/// 各类事件日志的输出级别（来自 `[features]` 中的 `*_log_level`），None表示不输出
+///
+/// 与 `RUST_LOG` 配合使用：事件先按这里的级别输出，再由 `RUST_LOG` 按target过滤。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EventLogLevels {
+    pub account: Option<Level>,         // 账户更新（默认debug）
+    pub trade: Option<Level>,           // 涉及监听地址的买卖和指令（默认info）
+    pub unmatched_trade: Option<Level>, // 其他买卖和指令（默认debug）
+    pub graduation: Option<Level>,      // 代币毕业（默认info）
+}
+
+impl Default for EventLogLevels {
+    fn default() -> Self {
+        Self {
+            account: Some(Level::Debug),
+            trade: Some(Level::Info),
+            unmatched_trade: Some(Level::Debug),
+            graduation: Some(Level::Info),
+        }
+    }
+}
+
+impl EventLogLevels {
+    /// 解析各类事件的日志级别（不区分大小写），未配置的使用默认级别
+    pub fn parse(
+        account: Option<&str>,
+        trade: Option<&str>,
+        unmatched_trade: Option<&str>,
+        graduation: Option<&str>,
+    ) -> anyhow::Result<Self> {
+        let defaults = Self::default();
+        Ok(Self {
+            account: parse_level("account_log_level", account, defaults.account)?,
+            trade: parse_level("trade_log_level", trade, defaults.trade)?,
+            unmatched_trade: parse_level("unmatched_trade_log_level", unmatched_trade, defaults.unmatched_trade)?,
+            graduation: parse_level("graduation_log_level", graduation, defaults.graduation)?,
+        })
+    }
+}
+
+fn parse_level(name: &str, value: Option<&str>, default: Option<Level>) -> anyhow::Result<Option<Level>> {
+    match value {
+        None => Ok(default),
+        Some(value) => LevelFilter::from_str(value.trim()).map(|filter| filter.to_level()).map_err(|_| {
+            anyhow::anyhow!("无效的 {} 配置: {}（可选值: off, error, warn, info, debug, trace）", name, value)
+        }),
+    }
+}
@@ -1,37 +1,97 @@
+mod account_rpc;
+mod api;
+mod cache_reader;
+mod candles;
+mod cpi_log_store;
+mod creator_map;
+mod event_queue;
+mod event_sink;
+mod grpc_endpoints;
+mod grpc_tls;
+mod health;
+mod idl_args;
+mod idl_versions;
+mod json_case;
+mod lamports;
 mod instruction_account_mapper;
+mod log_file_writer;
+mod log_targets;
+mod metrics;
+mod mint_rate_limit;
+mod ndjson_sink;
+mod price_alerts;
+mod pump_events;
+mod redis_writer;
+mod sandwich;
 mod serialization;
 mod token_serializable;
+mod update_file;
+mod volume;
+mod ws_server;
 
 #[allow(unused_imports)]
 use {
+    account_rpc::{AccountRpc, DEFAULT_RPC_MAX_REQUESTS_PER_SEC, DEFAULT_RPC_WAIT_TIMEOUT_MS},
+    cache_reader::{RedisKeys, RedisTradeFormat},
+    candles::{Candle, CandleAggregator},
+    mint_rate_limit::MintRateLimiter,
     clap::Parser as ClapParser,
-    futures::{sink::SinkExt, stream::StreamExt},
-    instruction_account_mapper::{AccountMetadata, Idl, InstructionAccountMapper},
-    log::{error, info, debug, warn},
+    cpi_log_store::{CpiLogMode, CpiLogStore, CpiLogWriter},
+    creator_map::CreatorMap,
+    event_queue::{EventKind, EventPublisher, DEFAULT_NATS_SUBJECT_PREFIX},
+    event_sink::{build_sinks, EventSink, SinkOutputs},
+    grpc_endpoints::{mask_token, EndpointPool, GrpcEndpoints, StreamProgress},
+    grpc_tls::GrpcTlsConfig,
+    futures::{sink::SinkExt, stream::{Stream, StreamExt}},
+    json_case::JsonCase,
+    lamports::Lamports,
+    idl_versions::{IdlSet, IdlVersion, IdlVersionConfig},
+    instruction_account_mapper::{AccountMappingError, AccountMetadata, Idl, InstructionAccountMapper},
+    metrics::{Metrics, METRICS},
+    price_alerts::{PriceAlertConfig, PriceAlerts},
+    pump_events::{PumpTradeEvent, TradeEvents},
+    redis_writer::RedisWriter,
+    sandwich::{RecentTrade, SandwichDetector, SandwichSuspect},
+    volume::{MintVolume, VolumeTracker},
+    log::{error, info, debug, trace, warn},
+    log_file_writer::LogFileWriter,
+    log_targets::EventLogLevels,
     serde::Deserialize,
     serde::{Serialize},
     serde_json::Value,
-    std::{collections::HashMap, env, fs, path::PathBuf, str::FromStr, sync::Arc, time::{Duration, SystemTime, UNIX_EPOCH}, io::Write},
+    std::{collections::{HashMap, HashSet}, env, fs, path::PathBuf, str::FromStr, sync::{Arc, OnceLock}, time::{Duration, SystemTime, UNIX_EPOCH}, io::Write},
     tokio::time::interval,
-    tonic::transport::channel::ClientTlsConfig,
-    yellowstone_grpc_client::{GeyserGrpcClient, Interceptor},
+    tonic::metadata::AsciiMetadataValue,
+    tonic_health::pb::health_client::HealthClient,
+    yellowstone_grpc_client::{GeyserGrpcClient, Interceptor, InterceptorXToken},
     yellowstone_grpc_proto::{
         geyser::SubscribeRequestFilterTransactions,
         geyser::SubscribeRequestFilterAccounts,
+        geyser::{
+            subscribe_request_filter_accounts_filter::Filter as AccountsFilter,
+            subscribe_request_filter_accounts_filter_memcmp::Data as MemcmpData,
+            SubscribeRequestFilterAccountsFilter, SubscribeRequestFilterAccountsFilterMemcmp,
+        },
         prelude::{
-            subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest, SubscribeRequestPing,
+            subscribe_update::UpdateOneof, CommitmentLevel, CompiledInstruction, InnerInstructions, Message, MessageHeader, SubscribeRequest, SubscribeUpdateTransactionInfo, TransactionStatusMeta,
+            SubscribeRequestPing, SubscribeRequestAccountsDataSlice, SubscribeUpdate, geyser_client::GeyserClient,
         },
     },
-    pump_interface::instructions::PumpProgramIx,
+    pump_interface::instructions::{PumpProgramIx, SetParamsIxArgs, BUY_IX_DISCM, CREATE_IX_DISCM, INITIALIZE_IX_DISCM, SELL_IX_DISCM, SET_PARAMS_IX_DISCM, WITHDRAW_IX_DISCM},
     pump_interface::accounts::{BondingCurve, BondingCurveAccount, Global, GlobalAccount, BONDING_CURVE_ACCOUNT_DISCM, GLOBAL_ACCOUNT_DISCM},
     solana_sdk::{pubkey::Pubkey, instruction::AccountMeta},
-    chrono::{TimeZone, Utc, FixedOffset, DateTime},
+    chrono::{Utc, FixedOffset, DateTime},
     spl_token::instruction::TokenInstruction,
-    token_serializable::convert_to_serializable,
+    solana_program::program_pack::Pack,
+    token_serializable::{convert_to_serializable, SerializableTokenInstruction},
+    update_file::UpdateCapture,
+    ws_server::TradeBroadcaster,
     dashmap::DashMap,
     serde_json::json,
     redis::AsyncCommands,
     glob::glob,
+    lru::LruCache,
+    std::num::NonZeroUsize,
 };
 
 type TxnFilterMap = HashMap<String, SubscribeRequestFilterTransactions>;
@@ -40,9 +100,95 @@ type AccountFilterMap = HashMap<String, SubscribeRequestFilterAccounts>;
 // 定义常量
 const PUMP_PROGRAM_ID: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
 const TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
-const CACHE_CLEANUP_INTERVAL_SECS: u64 = 600; // 缓存清理间隔（秒）
-const MAX_CACHE_AGE_SECS: u64 = 15; // 内存缓存最大有效期（秒）
-const REDIS_CACHE_AGE_SECS: u64 = 600; // Redis缓存最大有效期（10分钟）
+const TOKEN_2022_PROGRAM_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+const ASSOCIATED_TOKEN_PROGRAM_ID: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
+const RENT_SYSVAR_ID: &str = "SysvarRent111111111111111111111111111111111";
+const DEFAULT_CACHE_CLEANUP_INTERVAL_SECS: u64 = 600; // 默认缓存清理间隔（秒）
+const DEFAULT_MAX_CACHE_AGE_SECS: u64 = 15; // 默认内存缓存最大有效期（秒）
+const DEFAULT_REDIS_CACHE_AGE_SECS: u64 = 600; // 默认Redis缓存最大有效期（10分钟）
+const SOL_DECIMALS: u8 = 9; // SOL精度
+const DEFAULT_TOKEN_DECIMALS: u8 = 6; // 未知代币的默认精度（pump代币为6）
+const DEFAULT_CREATOR_MAP_RELOAD_SECS: u64 = 30; // 创建者映射文件热加载检查间隔（秒）
+const DEFAULT_SEEN_SIGNATURE_CAPACITY: usize = 50_000; // 默认记录的最近交易签名数量（用于去重）
+const TOKEN_ACCOUNT_OWNER_CAPACITY: usize = 100_000; // 记录所有者的最近代币账户数量
+const MINT_DECIMALS_CAPACITY: usize = 100_000; // 记录精度的最近代币数量
+const TRADE_SLOT_CAPACITY: usize = 100_000; // 记录最近交易槽位的代币数量
+const CURVE_COMPLETE_CAPACITY: usize = 100_000; // 记录complete标志的最近绑定曲线数量
+const RESERVE_MOVE_IDLE_SECS: u64 = 3600; // 曲线超过该时间（秒）没有储备更新时丢弃其RESERVE_MOVE基准
+const LEARNED_MAPPING_CAPACITY: usize = 100_000; // 每种学到的映射最多保留的条目数（内存和Redis哈希）
+const DEFAULT_MINT_TRADE_INDEX_LEN: usize = 100; // 默认每个代币在Redis中索引的最近交易数量
+const DEFAULT_CACHE_MAX_ENTRIES: usize = 100_000; // 默认买入、卖出和账户缓存各自最多保留的条目数
+const DEFAULT_VOLUME_WINDOW_SECS: u64 = 60; // 只配置 volume_spike_sol 时成交量统计的默认窗口（秒）
+const MONITOR_EVENT_CHANNEL_CAPACITY: usize = 1024; // 解码事件通道容量，处理方跟不上时解码暂停
+const DEFAULT_TRANSACTION_QUEUE_CAPACITY: usize = 1024; // 默认待解码交易队列容量，队列满时暂停读取数据流
+const TRANSACTION_QUEUE_LAG_PCT: usize = 80; // 待解码交易队列占用超过该百分比时报告处理落后
+const TRANSACTION_QUEUE_LAG_LOG_SECS: u64 = 10; // 处理落后日志的最短间隔（秒）
+const DEFAULT_FEE_BASIS_POINTS: u64 = 100; // 未观察到Global账户时使用的协议费率（1%）
+const DEFAULT_CREATOR_FEE_BASIS_POINTS: u64 = 100; // 未观察到Global账户时使用的创作者费率（1%）
+const DEFAULT_INITIAL_REAL_TOKEN_RESERVES: u64 = 793_100_000_000_000; // 未观察到Global账户时使用的初始真实代币储备（计算流通量）
+const DEFAULT_FROM_SLOT_MAX_LAG: u64 = 3000; // 默认最多回放落后当前槽位的槽位数（端点通常只保留最近几千个槽位）
+const RECONNECT_DELAY_SECS: u64 = 2; // 数据流断开后重连前的等待时间（秒）
+const PRIMARY_ENDPOINT_RETRY_SECS: u64 = 300; // 使用备用gRPC端点时，每隔该时间重新尝试主端点（秒）
+const SHUTDOWN_TIMEOUT_SECS: u64 = 5; // 退出时等待监控任务和Redis写入完成的最长时间（秒）
+const DEFAULT_HEALTH_STALE_SECS: u64 = 30; // 超过该时间未收到gRPC消息时健康检查返回503（秒）
+const DEFAULT_TIMEZONE_OFFSET_HOURS: i32 = 8; // 日志时间默认使用东八区（北京时间）
+const DEFAULT_MONITOR_NAME: &str = "default"; // 未配置 [[monitors]] 时单个监控配置的名称
+const CANDLE_REDIS_RETENTION_SECS: u64 = 86400; // Redis中保留的K线时长（秒）
+const ACCOUNT_FALLBACK_TIMEOUT: Duration = Duration::from_secs(2); // 内存中没有账户数据时回退查询Redis的超时
+
+// 链上程序ID等常量，启动时根据配置设置一次
+static CONSTANTS: std::sync::OnceLock<Constants> = std::sync::OnceLock::new();
+
+// 日志时间使用的时区，启动时根据配置设置一次
+static TIMEZONE_OFFSET: std::sync::OnceLock<FixedOffset> = std::sync::OnceLock::new();
+
+// 缓存保留时间配置
+//
+// 内存中的交易和账户数据只在清理任务运行时按 max_age 删除，读取时不检查是否过期，
+// 因此实际保留时间在 max_age 到 max_age + cleanup_interval 之间：清理刚运行过时，
+// 超过 max_age 未更新的绑定曲线账户数据已被删除。买卖处理通过 get_account_data_or_fetch 查找曲线数据，
+// 内存中没有时回退到Redis（保留 redis_ttl_secs）和可选的RPC查询，见该方法。
+#[derive(Debug, Clone, Copy)]
+struct CacheRetention {
+    max_age: Duration,          // 内存缓存最大有效期
+    redis_ttl_secs: u64,        // 写入Redis的缓存过期时间（秒）
+    cleanup_interval: Duration, // 清理任务运行间隔
+    mint_trade_index_len: usize, // Redis中每个代币索引的最近交易数量（0表示不索引）
+    max_entries: usize,         // 买入、卖出和账户缓存各自最多保留的条目数（0表示不限制）
+}
+
+impl CacheRetention {
+    fn from_features(features: &Features) -> Self {
+        Self {
+            max_age: Duration::from_secs(features.cache_max_age_secs.unwrap_or(DEFAULT_MAX_CACHE_AGE_SECS)),
+            redis_ttl_secs: features.redis_cache_ttl_secs.unwrap_or(DEFAULT_REDIS_CACHE_AGE_SECS),
+            cleanup_interval: Duration::from_secs(
+                features.cache_cleanup_interval_secs.unwrap_or(DEFAULT_CACHE_CLEANUP_INTERVAL_SECS).max(1),
+            ),
+            mint_trade_index_len: features.mint_trade_index_len.unwrap_or(DEFAULT_MINT_TRADE_INDEX_LEN),
+            max_entries: features.cache_max_entries.unwrap_or(DEFAULT_CACHE_MAX_ENTRIES),
+        }
+    }
+}
+
+// 条目数超过上限时按时间戳删除最旧的条目；一次删除到上限的90%，摊销每次淘汰时扫描整个缓存的开销
+fn evict_oldest(map: &DashMap<String, CacheItem>, max_entries: usize) -> usize {
+    if max_entries == 0 || map.len() <= max_entries {
+        return 0;
+    }
+    let target = max_entries - max_entries / 10;
+    let mut timestamps: Vec<SystemTime> = map.iter().map(|entry| entry.timestamp).collect();
+    let excess = timestamps.len().saturating_sub(target);
+    if excess == 0 {
+        return 0;
+    }
+    let cutoff = *timestamps.select_nth_unstable(excess - 1).1;
+    let before = map.len();
+    map.retain(|_, item| item.timestamp > cutoff);
+    let removed = before.saturating_sub(map.len());
+    Metrics::add(&METRICS.cache_size_evictions, removed as u64);
+    removed
+}
 
 // 定义缓存项结构
 #[derive(Debug, Clone)]
@@ -51,6 +197,162 @@ struct CacheItem {
     timestamp: SystemTime,
 }
 
+// 曲线储备快照
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+struct ReserveSnapshot {
+    virtual_token_reserves: u64,
+    virtual_sol_reserves: u64,
+    // 曲线关联代币账户中的实际代币余额（需订阅该代币账户）
+    token_account_balance: Option<u64>,
+}
+
+// RESERVE_MOVE的比较基准，last_seen为最近一次观察到该曲线储备的时间
+#[derive(Debug, Clone, Copy)]
+struct ReserveMoveBaseline {
+    virtual_token_reserves: u64,
+    virtual_sol_reserves: u64,
+    last_seen: SystemTime,
+}
+
+// 代币账户所属的钱包和代币
+#[derive(Debug, Clone, PartialEq)]
+struct TokenAccountOwner {
+    owner: String,
+    mint: String,
+}
+
+// Global账户中的协议参数，每次Global账户更新时整体替换
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct GlobalState {
+    authority: String,
+    fee_recipient: String,
+    fee_basis_points: u64,
+    // 新版Global账户中的创作者费率，旧账户数据长度不足时为None
+    creator_fee_basis_points: Option<u64>,
+    initial_virtual_token_reserves: u64,
+    initial_virtual_sol_reserves: u64,
+    initial_real_token_reserves: u64,
+    token_total_supply: u64,
+    slot: u64, // 观察到该状态的槽位
+}
+
+impl GlobalState {
+    fn from_account(global: &Global, creator_fee_basis_points: Option<u64>, slot: u64) -> Self {
+        Self {
+            authority: global.authority.to_string(),
+            fee_recipient: global.fee_recipient.to_string(),
+            fee_basis_points: global.fee_basis_points,
+            creator_fee_basis_points,
+            initial_virtual_token_reserves: global.initial_virtual_token_reserves,
+            initial_virtual_sol_reserves: global.initial_virtual_sol_reserves,
+            initial_real_token_reserves: global.initial_real_token_reserves,
+            token_total_supply: global.token_total_supply,
+            slot,
+        }
+    }
+
+    /// 与之前的状态相比发生变化的权限和费用参数，格式为 `字段: 旧值 -> 新值`
+    fn changes_from(&self, previous: &GlobalState) -> Vec<String> {
+        let mut changes = Vec::new();
+        if self.authority != previous.authority {
+            changes.push(format!("authority: {} -> {}", previous.authority, self.authority));
+        }
+        if self.fee_recipient != previous.fee_recipient {
+            changes.push(format!("fee_recipient: {} -> {}", previous.fee_recipient, self.fee_recipient));
+        }
+        if self.fee_basis_points != previous.fee_basis_points {
+            changes.push(format!("fee_basis_points: {} -> {}", previous.fee_basis_points, self.fee_basis_points));
+        }
+        if self.creator_fee_basis_points != previous.creator_fee_basis_points {
+            changes.push(format!(
+                "creator_fee_basis_points: {:?} -> {:?}",
+                previous.creator_fee_basis_points, self.creator_fee_basis_points
+            ));
+        }
+        changes
+    }
+
+    /// 应用SetParams指令中的新参数；权限和创作者费率不在指令参数中，沿用之前观察到的值
+    fn with_params(previous: Option<&GlobalState>, authority: &str, params: &SetParamsIxArgs, slot: u64) -> Self {
+        Self {
+            authority: previous.map_or_else(|| authority.to_string(), |previous| previous.authority.clone()),
+            fee_recipient: params.fee_recipient.to_string(),
+            fee_basis_points: params.fee_basis_points,
+            creator_fee_basis_points: previous.and_then(|previous| previous.creator_fee_basis_points),
+            initial_virtual_token_reserves: params.initial_virtual_token_reserves,
+            initial_virtual_sol_reserves: params.initial_virtual_sol_reserves,
+            initial_real_token_reserves: params.initial_real_token_reserves,
+            token_total_supply: params.token_total_supply,
+            slot,
+        }
+    }
+}
+
+// pump.fun一笔买卖交易的费用（单位：lamports）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PumpFees {
+    sol_amount: u64,               // 曲线交易金额（不含费用）
+    fee_basis_points: u64,         // 协议费率
+    creator_fee_basis_points: u64, // 创作者费率
+    protocol_fee: u64,             // 协议费用
+    creator_fee: u64,              // 创作者费用
+}
+
+impl PumpFees {
+    /// 按曲线交易金额（不含费用）计算费用，与pump程序一致：
+    /// 协议费用和创作者费用分别按 ceil(sol_amount * bps / 10000) 计算，
+    /// 买入时加在交易金额之上，卖出时从交易金额中扣除。
+    fn on_sol_amount(sol_amount: u64, fee_basis_points: u64, creator_fee_basis_points: u64) -> Self {
+        Self {
+            sol_amount,
+            fee_basis_points,
+            creator_fee_basis_points,
+            protocol_fee: pump_fee(sol_amount, fee_basis_points),
+            creator_fee: pump_fee(sol_amount, creator_fee_basis_points),
+        }
+    }
+
+    /// 由含费用的买入总额（如max_sol_cost）还原交易金额后计算费用：
+    /// sol_amount = floor(total * 10000 / (10000 + 总费率))
+    fn from_buy_total(total: u64, fee_basis_points: u64, creator_fee_basis_points: u64) -> Self {
+        let total_bps = 10_000 + fee_basis_points as u128 + creator_fee_basis_points as u128;
+        let sol_amount = (total as u128 * 10_000 / total_bps) as u64;
+        Self::on_sol_amount(sol_amount, fee_basis_points, creator_fee_basis_points)
+    }
+
+    /// 由扣除费用后的卖出净额（如min_sol_output）还原交易金额后计算费用：
+    /// sol_amount = ceil(net * 10000 / (10000 - 总费率))
+    fn from_sell_net(net: u64, fee_basis_points: u64, creator_fee_basis_points: u64) -> Self {
+        let total_bps = 10_000u128.saturating_sub(fee_basis_points as u128 + creator_fee_basis_points as u128).max(1);
+        let sol_amount = (net as u128 * 10_000).div_ceil(total_bps) as u64;
+        Self::on_sol_amount(sol_amount, fee_basis_points, creator_fee_basis_points)
+    }
+}
+
+/// 指令数据的鉴别器是否属于已知的PumpFun指令（解码失败时用于区分未知指令和参数格式不一致）
+fn is_known_pump_discriminator(data: &[u8]) -> bool {
+    const KNOWN: [[u8; 8]; 6] = [INITIALIZE_IX_DISCM, SET_PARAMS_IX_DISCM, CREATE_IX_DISCM, BUY_IX_DISCM, SELL_IX_DISCM, WITHDRAW_IX_DISCM];
+    data.get(..8).is_some_and(|discriminator| KNOWN.iter().any(|known| known.as_slice() == discriminator))
+}
+
+/// pump程序的单项费用：ceil(amount * basis_points / 10000)
+fn pump_fee(amount: u64, basis_points: u64) -> u64 {
+    (amount as u128 * basis_points as u128).div_ceil(10_000) as u64
+}
+
+/// 买卖交易的费用；费率优先取Global账户中的值，未观察到Global账户时使用默认费率。
+/// 旧版Global账户没有创作者费率字段，对应的程序版本不收取创作者费用。
+fn pump_trade_fees(ix: &PumpProgramIx, global_state: Option<&GlobalState>) -> Option<PumpFees> {
+    let fee_basis_points = global_state.map_or(DEFAULT_FEE_BASIS_POINTS, |global| global.fee_basis_points);
+    let creator_fee_basis_points = global_state
+        .map_or(DEFAULT_CREATOR_FEE_BASIS_POINTS, |global| global.creator_fee_basis_points.unwrap_or(0));
+    match ix {
+        PumpProgramIx::Buy(buy_args) => Some(PumpFees::from_buy_total(buy_args.max_sol_cost, fee_basis_points, creator_fee_basis_points)),
+        PumpProgramIx::Sell(sell_args) => Some(PumpFees::from_sell_net(sell_args.min_sol_output, fee_basis_points, creator_fee_basis_points)),
+        _ => None,
+    }
+}
+
 // 定义缓存结构
 struct TransactionCache {
     // 交易缓存
@@ -61,26 +363,115 @@ struct TransactionCache {
     // 最新的账户数据，用于关联到交易中
     latest_account_data: DashMap<String, String>, // mint -> account_data
     // 账户中最新的虚拟储备信息，用于与交易对比
-    latest_reserves: DashMap<String, (u64, u64)>, // mint -> (virtual_token_reserves, virtual_sol_reserves)
+    latest_reserves: DashMap<String, ReserveSnapshot>, // mint -> 虚拟储备及曲线代币账户余额
+    // 代币精度，从Token程序指令中获取
+    mint_decimals: std::sync::Mutex<LruCache<String, u8>>, // mint -> decimals
+    // 每个mint最近一笔已缓存交易的槽位，保证并行处理时最新数据不被旧交易覆盖
+    latest_trade_slots: std::sync::Mutex<LruCache<String, u64>>, // mint -> slot
+    // 绑定曲线最近一次观察到的complete标志，用于检测毕业
+    curve_complete: std::sync::Mutex<LruCache<String, bool>>, // curve -> complete
+    // 每个mint最近一次触发RESERVE_MOVE时的虚拟储备，作为下次比较的基准
+    reserve_move_baselines: DashMap<String, ReserveMoveBaseline>, // mint -> 基准储备
+    // 从链上数据学到的反查映射，同步写入Redis哈希，重启后从Redis恢复；超出容量时淘汰最久未用的映射
+    curve_mints: std::sync::Mutex<LruCache<String, String>>,    // curve -> mint
+    vault_creators: std::sync::Mutex<LruCache<String, String>>, // creator_vault -> creator
+    mint_creators: std::sync::Mutex<LruCache<String, String>>,  // mint -> creator
+    // 最近处理过的交易签名，用于跳过重复推送的交易
+    seen_signatures: std::sync::Mutex<LruCache<String, ()>>,
+    // 代币账户 -> (所有者钱包, mint)，来自代币账户数据、初始化指令和交易的代币余额
+    token_account_owners: std::sync::Mutex<LruCache<String, TokenAccountOwner>>,
+    // 最近一次观察到的Global账户协议参数
+    global_state: std::sync::RwLock<Option<GlobalState>>,
+    // 按mint聚合的成交K线（未配置 candle_interval_secs 时不启用）
+    candles: Option<CandleAggregator>,
+    candle_redis: bool, // 收盘的K线是否写入Redis
+    redis_trade_format: RedisTradeFormat, // 买卖交易写入Redis的格式
+    redis_keys: RedisKeys, // 写入和回退读取Redis使用的键名（含 redis_key_prefix）
+    // 按mint保留最近成交，用于检测疑似夹子交易（未配置 sandwich_window_slots 时不启用）
+    sandwich: Option<SandwichDetector>,
+    // 按mint限制CPI日志文件的写入频率（未配置 cpi_log_max_per_mint_per_minute 时不限制）
+    cpi_log_limiter: Option<MintRateLimiter>,
+    // 按mint统计滑动窗口内的成交量（未配置 volume_window_secs/volume_spike_sol 时不启用）
+    volume: Option<VolumeTracker>,
+    // 内存和Redis缓存的保留时间
+    retention: CacheRetention,
+    // Redis后台批量写入器
+    redis_writer: RedisWriter,
+    // 内存中没有账户数据时回退查询Redis使用的连接（首次使用时建立，出错后重建）
     redis_client: Arc<redis::Client>,
+    redis_read_connection: std::sync::Mutex<Option<redis::aio::MultiplexedConnection>>,
+    // 内存和Redis中都没有账户数据时按需查询的RPC（未配置 rpc_url 时不查询）
+    account_rpc: Option<Arc<AccountRpc>>,
 }
 
 impl TransactionCache {
-    fn new(redis_client: Arc<redis::Client>) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        redis_client: Arc<redis::Client>,
+        seen_signature_capacity: usize,
+        candle_interval_secs: Option<u64>,
+        candle_redis: bool,
+        redis_trade_format: RedisTradeFormat,
+        redis_keys: RedisKeys,
+        sandwich_window_slots: Option<u64>,
+        cpi_log_max_per_mint_per_minute: Option<u32>,
+        volume_tracker: Option<VolumeTracker>,
+        retention: CacheRetention,
+        account_rpc: Option<Arc<AccountRpc>>,
+    ) -> Self {
+        let seen_signature_capacity = NonZeroUsize::new(seen_signature_capacity)
+            .unwrap_or(NonZeroUsize::MIN);
         Self {
             buy_transactions: DashMap::new(),
             sell_transactions: DashMap::new(),
             account_data: DashMap::new(),
             latest_account_data: DashMap::new(),
             latest_reserves: DashMap::new(),
+            mint_decimals: std::sync::Mutex::new(LruCache::new(
+                NonZeroUsize::new(MINT_DECIMALS_CAPACITY).expect("non-zero capacity"),
+            )),
+            latest_trade_slots: std::sync::Mutex::new(LruCache::new(
+                NonZeroUsize::new(TRADE_SLOT_CAPACITY).expect("non-zero capacity"),
+            )),
+            curve_complete: std::sync::Mutex::new(LruCache::new(
+                NonZeroUsize::new(CURVE_COMPLETE_CAPACITY).expect("non-zero capacity"),
+            )),
+            reserve_move_baselines: DashMap::new(),
+            curve_mints: std::sync::Mutex::new(LruCache::new(
+                NonZeroUsize::new(LEARNED_MAPPING_CAPACITY).expect("non-zero capacity"),
+            )),
+            vault_creators: std::sync::Mutex::new(LruCache::new(
+                NonZeroUsize::new(LEARNED_MAPPING_CAPACITY).expect("non-zero capacity"),
+            )),
+            mint_creators: std::sync::Mutex::new(LruCache::new(
+                NonZeroUsize::new(LEARNED_MAPPING_CAPACITY).expect("non-zero capacity"),
+            )),
+            seen_signatures: std::sync::Mutex::new(LruCache::new(seen_signature_capacity)),
+            token_account_owners: std::sync::Mutex::new(LruCache::new(
+                NonZeroUsize::new(TOKEN_ACCOUNT_OWNER_CAPACITY).expect("non-zero capacity"),
+            )),
+            global_state: std::sync::RwLock::new(None),
+            candles: candle_interval_secs.map(CandleAggregator::new),
+            candle_redis,
+            redis_trade_format,
+            redis_keys,
+            sandwich: sandwich_window_slots.map(SandwichDetector::new),
+            cpi_log_limiter: cpi_log_max_per_mint_per_minute.map(MintRateLimiter::new),
+            volume: volume_tracker,
+            retention,
+            redis_writer: RedisWriter::spawn(Arc::clone(&redis_client)),
             redis_client,
+            redis_read_connection: std::sync::Mutex::new(None),
+            account_rpc,
         }
     }
 
     // 缓存买入交易
-    fn cache_buy_transaction(&self, signature: &str, data: String, mint: Option<&str>) {
+    fn cache_buy_transaction(&self, entry: &CpiLogEntry, data: String) {
+        let signature = entry.signature.as_str();
+        let mint = Some(entry.mint.as_str());
         // 首先记录函数调用信息
-        info!("[缓存] 缓存买入交易 - 签名: {}, Mint: {:?}", signature, mint);
+        info!(target: log_targets::BUY, "[缓存] 缓存买入交易 - 签名: {}, Mint: {:?}", signature, mint);
         
         let mut enhanced_data = data.clone();
         
@@ -92,7 +483,7 @@ impl TransactionCache {
             
             // 计算并添加绑定曲线账户信息
             if let Some(curve_account) = calculate_curve_account_from_mint(mint_address) {
-                info!("[关联] Buy交易({})关联到曲线账户({})", signature, curve_account);
+                info!(target: log_targets::BUY, "[关联] Buy交易({})关联到曲线账户({})", signature, curve_account);
                 enhanced_data.push_str("\n\n关联曲线账户:\n");
                 enhanced_data.push_str(&curve_account);
                 
@@ -103,30 +494,30 @@ impl TransactionCache {
                     
                     // 提取并添加虚拟储备信息
                     if let Some((vt, vs)) = extract_reserves_from_account_data(&curve_data) {
-                        info!("[储备] Buy交易({})的虚拟储备 - 代币: {}, SOL: {}", signature, vt, vs);
+                        info!(target: log_targets::BUY, "[储备] Buy交易({})的虚拟储备 - 代币: {}, SOL: {}", signature, vt, vs);
                         enhanced_data.push_str(&format!("\n\n虚拟储备信息:\n虚拟代币储备: {}\n虚拟SOL储备: {}", vt, vs));
                         
                         // 计算并添加价格信息
-                        let price = calculate_price(vt, vs);
-                        info!("[价格] Buy交易({})的代币价格: {} SOL", signature, price);
+                        let price = calculate_price(vt, vs, self.token_decimals(mint_address));
+                        info!(target: log_targets::BUY, "[价格] Buy交易({})的代币价格: {} SOL", signature, price);
                         enhanced_data.push_str(&format!("\n\n价格信息:\n当前价格: {} SOL", price));
                     } else {
-                        warn!("[储备] 无法从曲线账户({})提取虚拟储备信息", curve_account);
+                        warn!(target: log_targets::BUY, "[储备] 无法从曲线账户({})提取虚拟储备信息", curve_account);
                     }
                     
                     // 查找并添加创作者金库地址
                     if let Some(creator_vault) = extract_creator_vault_from_log(data.as_str()) {
                         // 检查是否已包含金库地址信息
                         if !enhanced_data.contains("创作者金库地址:") {
-                            info!("[金库] Buy交易({})的创作者金库地址: {}", signature, creator_vault);
+                            info!(target: log_targets::BUY, "[金库] Buy交易({})的创作者金库地址: {}", signature, creator_vault);
                             enhanced_data.push_str(&format!("\n\n创作者金库地址:\n{}", creator_vault));
                         }
                     }
                 } else {
-                    warn!("[缓存] 未找到曲线账户({})的数据", curve_account);
+                    warn!(target: log_targets::BUY, "[缓存] 未找到曲线账户({})的数据", curve_account);
                 }
             } else {
-                warn!("[关联] 无法为Mint({})计算曲线账户", mint_address);
+                warn!(target: log_targets::BUY, "[关联] 无法为Mint({})计算曲线账户", mint_address);
             }
         }
         
@@ -135,37 +526,21 @@ impl TransactionCache {
             timestamp: SystemTime::now(),
         };
         self.buy_transactions.insert(signature.to_string(), cache_item);
+        evict_oldest(&self.buy_transactions, self.retention.max_entries);
 
-        let client_clone = Arc::clone(&self.redis_client);
-        let key = signature.to_string(); // 直接使用签名作为键，不添加前缀
-        let enhanced_data_clone = enhanced_data.clone(); // 克隆数据
-        tokio::spawn(async move {
-            let mut con = match client_clone.get_multiplexed_tokio_connection().await {
-                Ok(c) => c,
-                Err(e) => {
-                    error!("[Redis] 获取连接失败 (sig: {}): {}", key, e);
-                    return;
-                }
-            };
-            if let Err(e) = con.set::<_, _, ()>(&key, &enhanced_data_clone).await {
-                error!("[Redis] 缓存交易失败 (sig: {}): {}", key, e);
-            } else {
-                debug!("[Redis] 成功缓存交易 (sig: {})", key);
-                if let Err(e) = con.expire::<_, ()>(&key, REDIS_CACHE_AGE_SECS as i64).await {
-                    error!("[Redis] 设置交易过期时间失败 (sig: {}): {}", key, e);
-                }
-            }
-        });
+        self.write_trade(entry, enhanced_data);
     }
 
     // 缓存卖出交易
-    fn cache_sell_transaction(&self, signature: &str, data: String, mint: Option<&str>) {
+    fn cache_sell_transaction(&self, entry: &CpiLogEntry, data: String, slot: u64) {
+        let signature = entry.signature.as_str();
+        let mint = Some(entry.mint.as_str());
         // 先提取交易信息中是否已包含创作者金库地址
         let mut enhanced_data = data.clone();
         if let Some(creator_vault) = extract_creator_vault_from_log(data.as_str()) {
             // 检查是否已包含金库地址信息
             if !enhanced_data.contains("创作者金库地址:") {
-                info!("[金库] Sell交易({})的创作者金库地址: {}", signature, creator_vault);
+                info!(target: log_targets::SELL, "[金库] Sell交易({})的创作者金库地址: {}", signature, creator_vault);
                 enhanced_data.push_str(&format!("\n\n创作者金库地址:\n{}", creator_vault));
             }
         } else {
@@ -179,7 +554,7 @@ impl TransactionCache {
                             let pubkey = line[pubkey_start+1..].trim();
                             // 检查是否已包含金库地址信息
                             if !enhanced_data.contains("创作者金库地址:") {
-                                info!("[金库] Sell交易({})从associatedTokenProgram识别创作者金库地址: {}", signature, pubkey);
+                                info!(target: log_targets::SELL, "[金库] Sell交易({})从associatedTokenProgram识别创作者金库地址: {}", signature, pubkey);
                                 enhanced_data.push_str(&format!("\n\n创作者金库地址:\n{}", pubkey));
                             }
                         }
@@ -194,16 +569,16 @@ impl TransactionCache {
             timestamp: SystemTime::now(),
         };
 
-        // 如果提供了mint参数，更新最新的mint数据
+        // 如果提供了mint参数，更新最新的mint数据（交易并行处理，较早槽位的交易不覆盖较新的数据）
         if let Some(mint_address) = mint {
-            if !mint_address.is_empty() {
+            if !mint_address.is_empty() && self.advance_trade_slot(mint_address, slot) {
                 // 记录该mint最新的卖出交易数据
                 self.latest_account_data.insert(mint_address.to_string(), cache_item.data.clone());
-                info!("[关联] Sell交易({})关联到代币({})", signature, mint_address);
+                info!(target: log_targets::SELL, "[关联] Sell交易({})关联到代币({})", signature, mint_address);
 
                 // 尝试获取曲线账户
                 if let Some(curve) = calculate_curve_account_from_mint(mint_address) {
-                    info!("[关联] Sell交易({})关联到曲线账户({})", signature, curve);
+                    info!(target: log_targets::SELL, "[关联] Sell交易({})关联到曲线账户({})", signature, curve);
                     
                     // 添加曲线账户信息到enhanced_data
                     enhanced_data.push_str("\n\n关联曲线账户:\n");
@@ -217,15 +592,15 @@ impl TransactionCache {
                         
                         if let Some((vt, vs)) = extract_reserves_from_account_data(&reserves_data) {
                             // 记录该mint最新的储备信息
-                            self.latest_reserves.insert(mint_address.to_string(), (vt, vs));
-                            info!("[储备] Sell交易({})的虚拟储备 - 代币: {}, SOL: {}", signature, vt, vs);
+                            self.cache_reserves(mint_address, vt, vs);
+                            info!(target: log_targets::SELL, "[储备] Sell交易({})的虚拟储备 - 代币: {}, SOL: {}", signature, vt, vs);
                             
                             // 添加虚拟储备信息到enhanced_data
                             enhanced_data.push_str(&format!("\n\n虚拟储备信息:\n虚拟代币储备: {}\n虚拟SOL储备: {}", vt, vs));
                             
                             // 计算价格
-                            let price = calculate_price(vt, vs);
-                            info!("[价格] Sell交易({})的代币价格: {} SOL", signature, price);
+                            let price = calculate_price(vt, vs, self.token_decimals(mint_address));
+                            info!(target: log_targets::SELL, "[价格] Sell交易({})的代币价格: {} SOL", signature, price);
                             
                             // 添加价格信息到enhanced_data
                             enhanced_data.push_str(&format!("\n\n价格信息:\n当前价格: {} SOL", price));
@@ -240,18 +615,29 @@ impl TransactionCache {
             data: enhanced_data.clone(),
             timestamp: SystemTime::now(),
         });
+        evict_oldest(&self.sell_transactions, self.retention.max_entries);
         
-        // 尝试存储到Redis
-        if let Ok(mut conn) = self.redis_client.get_connection() {
-            let key = signature.to_string(); // 直接使用签名作为键，不添加前缀
-            if let Err(e) = redis::cmd("SET").arg(&key).arg(&enhanced_data).query::<()>(&mut conn) {
-                error!("[Redis] 存储交易失败 (sig: {}): {}", key, e);
-            } else {
-                debug!("[Redis] 成功缓存交易 (sig: {})", key);
-                // 设置过期时间
-                if let Err(e) = redis::cmd("EXPIRE").arg(&key).arg(REDIS_CACHE_AGE_SECS).query::<()>(&mut conn) {
-                    error!("[Redis] 设置交易过期时间失败 (sig: {}): {}", key, e);
-                }
+        self.write_trade(entry, enhanced_data);
+    }
+
+    // 将买卖交易写入Redis：文本格式以签名为键（配置了前缀时为 {prefix}:trade:{signature}），哈希格式写入 tx:{signature}；
+    // 同时把签名按槽位加入代币的最近交易索引 trades:{mint}（mint未知时不索引），键名见 RedisKeys
+    fn write_trade(&self, entry: &CpiLogEntry, enhanced_data: String) {
+        if self.retention.mint_trade_index_len > 0 && !entry.mint.is_empty() {
+            self.redis_writer.zadd_capped(
+                self.redis_keys.mint_trades(&entry.mint),
+                entry.slot,
+                entry.signature.clone(),
+                self.retention.mint_trade_index_len,
+                self.retention.redis_ttl_secs,
+            );
+        }
+        match self.redis_trade_format {
+            RedisTradeFormat::Text => {
+                self.redis_writer.write(self.redis_keys.trade(&entry.signature), enhanced_data, self.retention.redis_ttl_secs);
+            }
+            RedisTradeFormat::Hash => {
+                self.redis_writer.write_hash(self.redis_keys.trade_hash(&entry.signature), entry.hash_fields(), self.retention.redis_ttl_secs);
             }
         }
     }
@@ -263,39 +649,15 @@ impl TransactionCache {
             timestamp: SystemTime::now(),
         };
         self.account_data.insert(pubkey.to_string(), cache_item);
+        evict_oldest(&self.account_data, self.retention.max_entries);
 
         // 尝试提取mint地址
-        if let Some(mint) = extract_mint_address_from_account_data(&data) {
+        if let Some(mint) = extract_mint_address_from_account_data(&data, Some(self)) {
             debug!("[关联] 从账户数据中提取到mint地址: {}, 账户: {}", mint, pubkey);
-            self.latest_account_data.insert(mint.clone(), data.clone());
-            
-            // 尝试提取虚拟储备信息
-            if let Some((virtual_token_reserves, virtual_sol_reserves)) = extract_reserves_from_account_data(&data) {
-                debug!("[储备] 提取到虚拟储备 - Mint: {}, VT: {}, VS: {}", 
-                    mint, virtual_token_reserves, virtual_sol_reserves);
-                self.latest_reserves.insert(mint, (virtual_token_reserves, virtual_sol_reserves));
-            }
+            self.latest_account_data.insert(mint, data.clone());
         }
 
-        let client_clone = Arc::clone(&self.redis_client);
-        let key = pubkey.to_string();
-        tokio::spawn(async move {
-            let mut con = match client_clone.get_multiplexed_tokio_connection().await {
-                Ok(c) => c,
-                Err(e) => {
-                    error!("[Redis] 获取连接失败 (account - key: {}): {}", key, e);
-                    return;
-                }
-            };
-            if let Err(e) = con.set::<_, _, ()>(&key, &data).await {
-                error!("[Redis] 缓存账户数据失败 (key: {}): {}", key, e);
-            } else {
-                debug!("[Redis] 成功缓存账户数据 (key: {})", key);
-                if let Err(e) = con.expire::<_, ()>(&key, REDIS_CACHE_AGE_SECS as i64).await {
-                    error!("[Redis] 设置账户数据过期时间失败 (key: {}): {}", key, e);
-                }
-            }
-        });
+        self.redis_writer.write(self.redis_keys.account(pubkey), data, self.retention.redis_ttl_secs);
     }
 
     // 获取最新的账户数据（按mint地址）
@@ -303,11 +665,322 @@ impl TransactionCache {
         self.latest_account_data.get(mint).map(|data| data.clone())
     }
     
+    // 虚拟SOL储备相对上次触发时的变化超过阈值（百分比）时更新基准并返回旧储备，首次观察只记录基准
+    fn check_reserve_move(&self, mint: &str, virtual_token_reserves: u64, virtual_sol_reserves: u64, threshold_pct: f64) -> Option<(u64, u64)> {
+        let now = SystemTime::now();
+        let mut baseline = self.reserve_move_baselines
+            .entry(mint.to_string())
+            .or_insert(ReserveMoveBaseline { virtual_token_reserves, virtual_sol_reserves, last_seen: now });
+        baseline.last_seen = now;
+        let old = (baseline.virtual_token_reserves, baseline.virtual_sol_reserves);
+        if old.1 > 0 {
+            let change_pct = (virtual_sol_reserves as f64 - old.1 as f64).abs() / old.1 as f64 * 100.0;
+            if change_pct < threshold_pct {
+                return None;
+            }
+        }
+        baseline.virtual_token_reserves = virtual_token_reserves;
+        baseline.virtual_sol_reserves = virtual_sol_reserves;
+        Some(old).filter(|(_, old_sol)| *old_sol > 0)
+    }
+
+    // 曲线毕业后不再需要RESERVE_MOVE基准
+    fn clear_reserve_move(&self, mint: &str) {
+        self.reserve_move_baselines.remove(mint);
+    }
+
     // 获取最新的虚拟储备数据（按mint地址）
-    fn get_latest_reserves(&self, mint: &str) -> Option<(u64, u64)> {
+    fn get_latest_reserves(&self, mint: &str) -> Option<ReserveSnapshot> {
         self.latest_reserves.get(mint).map(|reserves| *reserves)
     }
 
+    // 记录解码得到的虚拟储备，保留已记录的代币账户余额
+    fn cache_reserves(&self, mint: &str, virtual_token_reserves: u64, virtual_sol_reserves: u64) {
+        debug!(target: log_targets::ACCOUNT, "[储备] 虚拟储备 - Mint: {}, VT: {}, VS: {}", mint, virtual_token_reserves, virtual_sol_reserves);
+        let mut entry = self.latest_reserves.entry(mint.to_string()).or_default();
+        entry.virtual_token_reserves = virtual_token_reserves;
+        entry.virtual_sol_reserves = virtual_sol_reserves;
+    }
+
+    // 更新曲线关联代币账户的实际余额
+    fn update_token_account_balance(&self, mint: &str, amount: u64) {
+        let mut entry = self.latest_reserves.entry(mint.to_string()).or_default();
+        entry.token_account_balance = Some(amount);
+        if entry.virtual_token_reserves > 0 {
+            debug!(target: log_targets::ACCOUNT, "[储备] 代币({})曲线账户余额: {}, 虚拟代币储备: {}",
+                mint, amount, entry.virtual_token_reserves);
+        }
+    }
+
+    // 记录该mint最近一笔交易的槽位，槽位早于已记录的槽位时返回false
+    fn advance_trade_slot(&self, mint: &str, slot: u64) -> bool {
+        let mut slots = self.latest_trade_slots.lock().expect("trade slots lock poisoned");
+        match slots.get_mut(mint) {
+            Some(latest) if slot < *latest => false,
+            Some(latest) => {
+                *latest = slot;
+                true
+            }
+            None => {
+                slots.put(mint.to_string(), slot);
+                true
+            }
+        }
+    }
+
+    // 记录交易签名，已处理过时返回false
+    fn mark_signature_seen(&self, signature: &str) -> bool {
+        self.seen_signatures
+            .lock()
+            .expect("seen signatures lock poisoned")
+            .put(signature.to_string(), ())
+            .is_none()
+    }
+
+    // 记录代币账户的所有者和mint
+    fn remember_token_account(&self, token_account: &str, owner: &str, mint: &str) {
+        let owner = TokenAccountOwner { owner: owner.to_string(), mint: mint.to_string() };
+        let mut owners = self.token_account_owners.lock().expect("token account owners lock poisoned");
+        if owners.put(token_account.to_string(), owner.clone()).as_ref() != Some(&owner) {
+            trace!("[代币账户] {} -> 所有者: {}, Mint: {}", token_account, owner.owner, owner.mint);
+        }
+    }
+
+    // 查找代币账户的所有者和mint
+    fn get_token_account_owner(&self, token_account: &str) -> Option<TokenAccountOwner> {
+        self.token_account_owners
+            .lock()
+            .expect("token account owners lock poisoned")
+            .get(token_account)
+            .cloned()
+    }
+
+    // 查找持有该代币的代币账户所属的钱包，代币账户记录的mint不同时返回None
+    fn token_account_wallet(&self, token_account: &str, mint: &str) -> Option<String> {
+        self.get_token_account_owner(token_account)
+            .filter(|owner| owner.mint == mint)
+            .map(|owner| owner.owner)
+    }
+
+    // 记录交易代币余额中的代币账户所有者（交易前后的余额都包含所有者和mint）
+    fn remember_token_balance_owners(&self, meta: &TransactionStatusMeta, account_keys: &AccountKeys) {
+        for balance in meta.pre_token_balances.iter().chain(&meta.post_token_balances) {
+            if balance.owner.is_empty() || balance.mint.is_empty() {
+                continue;
+            }
+            if let Some(key) = account_keys.get(balance.account_index as usize) {
+                self.remember_token_account(&bs58::encode(key).into_string(), &balance.owner, &balance.mint);
+            }
+        }
+    }
+
+    // 记录绑定曲线的complete标志，只有从false变为true时返回true（首次观察到时不算毕业）
+    fn update_curve_complete(&self, curve: &str, complete: bool) -> bool {
+        let mut curves = self.curve_complete.lock().expect("curve complete lock poisoned");
+        match curves.put(curve.to_string(), complete) {
+            Some(previous) => !previous && complete,
+            None => false,
+        }
+    }
+
+    // 记录学到的映射，新学到的映射写入Redis哈希，返回是否为新映射；
+    // 被LRU淘汰的映射同时从Redis哈希删除，哈希大小不超过内存中的容量
+    fn remember_mapping(&self, mappings: &std::sync::Mutex<LruCache<String, String>>, hash: String, key: &str, value: &str) -> bool {
+        let mut mappings = mappings.lock().expect("learned mappings lock poisoned");
+        if mappings.get(key).map(String::as_str) == Some(value) {
+            return false;
+        }
+        if let Some((evicted, _)) = mappings.push(key.to_string(), value.to_string()).filter(|(evicted, _)| evicted != key) {
+            self.redis_writer.hdel(hash.clone(), evicted);
+        }
+        self.redis_writer.hset(hash, key.to_string(), value.to_string());
+        true
+    }
+
+    // 将Redis哈希中的映射恢复到内存，超出容量被淘汰的映射从哈希删除
+    fn restore_mappings(&self, mappings: &std::sync::Mutex<LruCache<String, String>>, hash: String, entries: HashMap<String, String>) {
+        let mut mappings = mappings.lock().expect("learned mappings lock poisoned");
+        for (key, value) in entries {
+            if let Some((evicted, _)) = mappings.push(key.clone(), value).filter(|(evicted, _)| *evicted != key) {
+                self.redis_writer.hdel(hash.clone(), evicted);
+            }
+        }
+    }
+
+    // 记录曲线账户对应的mint，新学到的映射写入Redis哈希
+    fn remember_curve_mint(&self, curve: &str, mint: &str) {
+        if self.remember_mapping(&self.curve_mints, self.redis_keys.curve_to_mint(), curve, mint) {
+            debug!("[映射] 曲线账户({}) -> Mint({})", curve, mint);
+        }
+    }
+
+    // 查找曲线账户对应的mint
+    fn get_curve_mint(&self, curve: &str) -> Option<String> {
+        self.curve_mints.lock().expect("learned mappings lock poisoned").get(curve).cloned()
+    }
+
+    // 记录创作者金库对应的创建者，新学到的映射写入Redis哈希
+    fn remember_vault_creator(&self, vault: &str, creator: &str) {
+        if self.remember_mapping(&self.vault_creators, self.redis_keys.vault_to_creator(), vault, creator) {
+            debug!("[映射] 创作者金库({}) -> 创建者({})", vault, creator);
+        }
+    }
+
+    // 查找创作者金库对应的创建者
+    fn get_vault_creator(&self, vault: &str) -> Option<String> {
+        self.vault_creators.lock().expect("learned mappings lock poisoned").get(vault).cloned()
+    }
+
+    // 记录代币对应的创建者（来自Create指令），新学到的映射写入Redis哈希
+    fn remember_mint_creator(&self, mint: &str, creator: &str) {
+        if self.remember_mapping(&self.mint_creators, self.redis_keys.mint_to_creator(), mint, creator) {
+            debug!("[映射] Mint({}) -> 创建者({})", mint, creator);
+        }
+    }
+
+    // 查找代币对应的创建者
+    fn get_mint_creator(&self, mint: &str) -> Option<String> {
+        self.mint_creators.lock().expect("learned mappings lock poisoned").get(mint).cloned()
+    }
+
+    // 启动时从Redis哈希恢复之前学到的映射，返回 (曲线映射数, 金库映射数, 代币创建者映射数)
+    async fn load_learned_mappings(&self, redis_client: &redis::Client) -> anyhow::Result<(usize, usize, usize)> {
+        let mut con = redis_client.get_multiplexed_tokio_connection().await?;
+        let curve_mints: HashMap<String, String> = con.hgetall(self.redis_keys.curve_to_mint()).await?;
+        let vault_creators: HashMap<String, String> = con.hgetall(self.redis_keys.vault_to_creator()).await?;
+        let mint_creators: HashMap<String, String> = con.hgetall(self.redis_keys.mint_to_creator()).await?;
+        let counts = (curve_mints.len(), vault_creators.len(), mint_creators.len());
+        self.restore_mappings(&self.curve_mints, self.redis_keys.curve_to_mint(), curve_mints);
+        self.restore_mappings(&self.vault_creators, self.redis_keys.vault_to_creator(), vault_creators);
+        self.restore_mappings(&self.mint_creators, self.redis_keys.mint_to_creator(), mint_creators);
+        Ok(counts)
+    }
+
+    // 用成交价格更新K线（未启用K线或交易失败时跳过）；交易中没有价格时使用账户监控得到的最新储备计算
+    fn record_candle_trade(&self, entry: &CpiLogEntry) {
+        let Some(candles) = &self.candles else {
+            return;
+        };
+        if entry.failed {
+            return;
+        }
+        let price = entry.price.or_else(|| {
+            self.get_latest_reserves(&entry.mint)
+                .filter(|reserves| reserves.virtual_token_reserves > 0)
+                .map(|reserves| calculate_price(reserves.virtual_token_reserves, reserves.virtual_sol_reserves, self.token_decimals(&entry.mint)))
+        });
+        let Some(price) = price else {
+            return;
+        };
+        let volume_sol = entry.actual_sol_cost.unwrap_or(entry.sol_amount);
+        let timestamp = entry.timestamp.unwrap_or_else(|| Utc::now().timestamp_millis());
+        if let Some(closed) = candles.record(&entry.mint, price, volume_sol, entry.token_amount, timestamp) {
+            self.store_closed_candle(&entry.mint, &closed);
+        }
+    }
+
+    // 该mint是否还可以写入CPI日志文件（未配置限流时总是可以）
+    fn allow_cpi_log(&self, mint: &str) -> bool {
+        self.cpi_log_limiter.as_ref().is_none_or(|limiter| limiter.try_acquire(mint))
+    }
+
+    // 记录成交量，窗口成交额越过 volume_spike_sol 时返回当前统计（未启用统计或交易失败时跳过）
+    fn record_trade_volume(&self, entry: &CpiLogEntry) -> Option<MintVolume> {
+        let volume = self.volume.as_ref()?;
+        if entry.failed || entry.mint == "未知" {
+            return None;
+        }
+        let timestamp = entry.timestamp.unwrap_or_else(|| Utc::now().timestamp_millis());
+        volume.record(&entry.mint, timestamp, entry.transaction_type == "Buy", entry.traded_sol())
+    }
+
+    // 获取代币当前窗口内的成交统计
+    fn get_mint_volume(&self, mint: &str) -> Option<MintVolume> {
+        self.volume.as_ref()?.volume(mint, Utc::now().timestamp_millis())
+    }
+
+    // 记录成交并检查是否构成疑似夹子交易（未启用检测、交易失败或缺少签名者时跳过）
+    fn record_sandwich_trade(&self, entry: &CpiLogEntry, slot: u64) -> Option<SandwichSuspect> {
+        let sandwich = self.sandwich.as_ref()?;
+        if entry.failed || entry.signer == "未知" || entry.mint == "未知" {
+            return None;
+        }
+        sandwich.record(&entry.mint, RecentTrade {
+            signer: entry.signer.clone(),
+            is_buy: entry.transaction_type == "Buy",
+            slot,
+            signature: entry.signature.clone(),
+        })
+    }
+
+    // 收盘长时间没有成交的代币的K线
+    fn close_stale_candles(&self) {
+        let Some(candles) = &self.candles else {
+            return;
+        };
+        for (mint, candle) in candles.close_stale(Utc::now().timestamp_millis()) {
+            self.store_closed_candle(&mint, &candle);
+        }
+    }
+
+    // 将收盘的K线写入Redis有序集合（需开启 candle_redis）
+    fn store_closed_candle(&self, mint: &str, candle: &Candle) {
+        debug!("[K线] 代币({})收盘: {:?}", mint, candle);
+        if !self.candle_redis {
+            return;
+        }
+        if let Ok(member) = serde_json::to_string(candle) {
+            let key = self.redis_keys.candles(candle.interval_secs, mint);
+            self.redis_writer.zadd(key, candle.start, member, CANDLE_REDIS_RETENTION_SECS);
+        }
+    }
+
+    // 获取代币当前未收盘的K线
+    fn get_current_candle(&self, mint: &str) -> Option<Candle> {
+        self.candles.as_ref().and_then(|candles| candles.current(mint))
+    }
+
+    // 记录代币毕业事件到Redis
+    fn cache_graduation(&self, mint: &str, data: String) {
+        self.redis_writer.write(self.redis_keys.graduation(mint), data, self.retention.redis_ttl_secs);
+    }
+
+    // 缓存Global账户的协议参数，权限或费用参数变化时输出警告
+    fn cache_global_state(&self, state: GlobalState) {
+        debug!(target: log_targets::ACCOUNT, "[Global] 更新协议参数: {:?}", state);
+        let mut current = self.global_state.write().expect("global state lock poisoned");
+        if let Some(previous) = current.as_ref() {
+            let changes = state.changes_from(previous);
+            if !changes.is_empty() {
+                warn!(target: log_targets::ACCOUNT, "[Global] 协议参数变化（槽位 {}）: {}", state.slot, changes.join(", "));
+            }
+        }
+        *current = Some(state);
+    }
+
+    // 获取最近一次观察到的Global账户协议参数
+    fn get_global_state(&self) -> Option<GlobalState> {
+        self.global_state.read().expect("global state lock poisoned").clone()
+    }
+
+    // 缓存代币精度
+    fn cache_mint_decimals(&self, mint: &str, decimals: u8) {
+        let mut mint_decimals = self.mint_decimals.lock().expect("mint decimals lock poisoned");
+        if mint_decimals.put(mint.to_string(), decimals).is_none() {
+            debug!("[精度] 记录代币({})精度: {}", mint, decimals);
+        }
+    }
+
+    // 获取代币精度（按mint地址）
+    fn get_mint_decimals(&self, mint: &str) -> Option<u8> {
+        self.mint_decimals.lock().expect("mint decimals lock poisoned").get(mint).copied()
+    }
+
+    // 获取用于价格计算的代币精度，未知时使用默认精度
+    fn token_decimals(&self, mint: &str) -> u8 {
+        self.get_mint_decimals(mint).unwrap_or(DEFAULT_TOKEN_DECIMALS)
+    }
+
     // 获取买入交易
     fn get_buy_transaction(&self, signature: &str) -> Option<String> {
         self.buy_transactions.get(signature).map(|item| item.data.clone())
@@ -318,13 +991,87 @@ impl TransactionCache {
         self.sell_transactions.get(signature).map(|item| item.data.clone())
     }
 
-    // 获取账户数据
+    // 获取账户数据（不检查是否过期；清理任务运行后超过 max_age 未更新的账户会查不到，见 CacheRetention）
     fn get_account_data(&self, pubkey: &str) -> Option<String> {
         self.account_data.get(pubkey).map(|item| item.data.clone())
     }
 
-    // 清理过期缓存
-    fn cleanup(&self, max_age: Duration) {
+    // 获取账户数据，内存中没有（已被清理）时依次回退到Redis和RPC，查到后放回内存缓存
+    //
+    // 内部通过 block_on 同步等待异步查询，只能在阻塞任务（spawn_blocking）中调用。
+    fn get_account_data_or_fetch(&self, pubkey: &str) -> Option<String> {
+        if let Some(data) = self.get_account_data(pubkey) {
+            return Some(data);
+        }
+        let handle = tokio::runtime::Handle::try_current().ok()?;
+
+        if self.redis_writer.is_available() {
+            match handle.block_on(tokio::time::timeout(ACCOUNT_FALLBACK_TIMEOUT, self.get_redis_string(&self.redis_keys.account(pubkey)))) {
+                Ok(Ok(Some(data))) => {
+                    debug!(target: log_targets::REDIS, "[缓存] 内存中没有账户({})数据，从Redis读取", pubkey);
+                    self.account_data.insert(pubkey.to_string(), CacheItem { data: data.clone(), timestamp: SystemTime::now() });
+                    evict_oldest(&self.account_data, self.retention.max_entries);
+                    return Some(data);
+                }
+                Ok(Ok(None)) => {}
+                Ok(Err(e)) => {
+                    debug!(target: log_targets::REDIS, "[缓存] 从Redis读取账户({})数据失败: {}", pubkey, e);
+                    *self.redis_read_connection.lock().expect("redis read connection lock poisoned") = None;
+                }
+                Err(_) => debug!(target: log_targets::REDIS, "[缓存] 从Redis读取账户({})数据超时", pubkey),
+            }
+        }
+
+        let bytes = self.fetch_account_from_rpc(&handle, pubkey)?;
+        let Ok(DecodedAccount::BondingCurve(bc, onchain_creator)) = decode_account_data(&bytes) else {
+            debug!("[RPC] 账户({})不是绑定曲线账户", pubkey);
+            return None;
+        };
+        let creator = onchain_creator.map(|c| c.to_string()).unwrap_or_else(|| "未知".to_string());
+        let mut data = bonding_curve_summary(pubkey, &bc);
+        data.push_str(&format!("CREATOR: {}\n", creator));
+        data.push_str(&format!("TIME: {}\n", format_time(Utc::now())));
+        info!("[RPC] 缓存中没有曲线账户({})数据，已从RPC读取", pubkey);
+        self.cache_account_data(pubkey, data.clone());
+        Some(data)
+    }
+
+    // 通过RPC查询账户原始数据：受限流时直接放弃，最多等待 wait_timeout，超时的查询在后台完成后留给下次查找
+    fn fetch_account_from_rpc(&self, handle: &tokio::runtime::Handle, pubkey: &str) -> Option<Vec<u8>> {
+        let rpc = self.account_rpc.as_ref()?;
+        if let Some(bytes) = rpc.take_result(pubkey) {
+            return Some(bytes);
+        }
+        if !rpc.try_begin(pubkey) {
+            return None;
+        }
+        let task = handle.spawn(Arc::clone(rpc).fetch(pubkey.to_string()));
+        match handle.block_on(tokio::time::timeout(rpc.wait_timeout(), task)) {
+            Ok(_) => rpc.take_result(pubkey),
+            Err(_) => {
+                debug!("[RPC] 查询账户({})超过 {:?}，结果留待下次使用", pubkey, rpc.wait_timeout());
+                None
+            }
+        }
+    }
+
+    // 从Redis读取字符串值，复用已建立的连接
+    async fn get_redis_string(&self, key: &str) -> redis::RedisResult<Option<String>> {
+        let existing = self.redis_read_connection.lock().expect("redis read connection lock poisoned").clone();
+        let mut con = match existing {
+            Some(con) => con,
+            None => {
+                let con = self.redis_client.get_multiplexed_tokio_connection().await?;
+                *self.redis_read_connection.lock().expect("redis read connection lock poisoned") = Some(con.clone());
+                con
+            }
+        };
+        con.get(key).await
+    }
+
+    // 清理超过 retention.max_age 未更新的缓存
+    fn cleanup(&self) {
+        let max_age = self.retention.max_age;
         let now = SystemTime::now();
         let mut buy_removed = 0;
         let mut sell_removed = 0;
@@ -363,21 +1110,66 @@ impl TransactionCache {
             }
         });
 
-        if buy_removed > 0 || sell_removed > 0 || account_removed > 0 {
-            debug!("缓存清理: 移除 {} 个买入交易, {} 个卖出交易, {} 个账户数据", 
-                buy_removed, sell_removed, account_removed);
+        // 清理长时间没有储备更新的RESERVE_MOVE基准
+        let reserve_idle = Duration::from_secs(RESERVE_MOVE_IDLE_SECS);
+        self.reserve_move_baselines.retain(|_, baseline| {
+            now.duration_since(baseline.last_seen).map_or(true, |idle| idle <= reserve_idle)
+        });
+
+        // 清理超出夹子检测窗口的成交记录
+        let sandwich_removed = self.sandwich.as_ref().map_or(0, SandwichDetector::prune);
+        // 清理已补满的CPI日志限流令牌桶
+        if let Some(limiter) = &self.cpi_log_limiter {
+            limiter.prune();
+        }
+        // 清理窗口内已没有成交的代币
+        if let Some(volume) = &self.volume {
+            volume.prune(Utc::now().timestamp_millis());
+        }
+
+        if buy_removed > 0 || sell_removed > 0 || account_removed > 0 || sandwich_removed > 0 {
+            debug!("缓存清理: 移除 {} 个买入交易, {} 个卖出交易, {} 个账户数据, {} 个代币的夹子检测记录", 
+                buy_removed, sell_removed, account_removed, sandwich_removed);
         }
     }
 
+    // 等待Redis写入队列清空（退出前调用）
+    async fn flush_redis(&self, timeout: Duration) -> bool {
+        self.redis_writer.drain(timeout).await
+    }
+
     // 获取缓存统计信息
-    fn get_stats(&self) -> (usize, usize, usize, usize, usize) {
-        (
-            self.buy_transactions.len(),
-            self.sell_transactions.len(),
-            self.account_data.len(),
-            self.latest_account_data.len(),
-            self.latest_reserves.len(),
-        )
+    fn get_stats(&self) -> CacheStats {
+        CacheStats {
+            buy_transactions: self.buy_transactions.len(),
+            sell_transactions: self.sell_transactions.len(),
+            account_data: self.account_data.len(),
+            latest_account_data: self.latest_account_data.len(),
+            latest_reserves: self.latest_reserves.len(),
+        }
+    }
+}
+
+/// 内存缓存当前的条目数（占用量，不是累计处理数）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+struct CacheStats {
+    buy_transactions: usize,
+    sell_transactions: usize,
+    account_data: usize,
+    latest_account_data: usize,
+    latest_reserves: usize,
+}
+
+impl CacheStats {
+    // 各缓存在 pump_cache_size 指标中的标签和条目数
+    fn by_label(&self) -> [(&'static str, usize); 5] {
+        [
+            ("buy", self.buy_transactions),
+            ("sell", self.sell_transactions),
+            ("account", self.account_data),
+            ("latest_account", self.latest_account_data),
+            ("latest_reserves", self.latest_reserves),
+        ]
     }
 }
 
@@ -393,34 +1185,572 @@ struct Features {
     cpi_log_json: bool,               // 是否将CPI日志保存为JSON文件
     cpi_log_json_dir: String,         // CPI日志JSON文件保存目录
     cpi_log_json_max_files: usize,    // 保存的最大文件数量
+    #[serde(default)]
+    cpi_log_json_max_total_bytes: Option<u64>, // CPI日志目录总大小上限（字节）
+    #[serde(default)]
+    cpi_log_json_max_age_secs: Option<u64>,    // CPI日志文件最长保留时间（秒）
+    #[serde(default)]
+    cpi_log_json_pretty: Option<bool>,         // CPI日志JSON是否格式化输出（默认true，false时输出紧凑JSON）
+    #[serde(default)]
+    cpi_log_mode: Option<CpiLogMode>,          // CPI日志写入方式: "per-file"（默认）或 "ndjson"
+    #[serde(default)]
+    cpi_log_max_per_mint_per_minute: Option<u32>, // 每个代币每分钟最多写入的CPI日志数（需启用缓存，不设置则不限制）
+    #[serde(default)]
+    seen_signature_capacity: Option<usize>,    // 用于去重的最近交易签名数量（默认50000）
+    #[serde(default)]
+    metrics_port: Option<u16>,        // Prometheus指标服务端口（不设置则不启动）
+    #[serde(default)]
+    structured_logs: bool,            // 买卖日志输出为单行JSON（默认多行文本）
+    #[serde(default)]
+    json_case: Option<JsonCase>,      // 输出JSON的字段命名: "mixed"（默认，保持原样）、"snake_case" 或 "camelCase"
+    #[serde(default)]
+    api_port: Option<u16>,            // 缓存查询HTTP服务端口（不设置则不启动，需启用缓存）
+    #[serde(default)]
+    ws_port: Option<u16>,             // WebSocket交易推送服务端口（不设置则不启动）
+    #[serde(default)]
+    ndjson_tcp_port: Option<u16>,     // NDJSON交易流TCP端口（不设置则不启动）
+    #[serde(default)]
+    ndjson_unix_socket: Option<String>, // NDJSON交易流Unix域套接字路径（不设置则不启动）
+    #[serde(default)]
+    transaction_workers: Option<usize>, // 并行处理交易的工作任务数（默认CPU核数）
+    #[serde(default)]
+    transaction_queue_capacity: Option<usize>, // 数据流与工作任务之间待解码交易队列的容量（默认1024）
+    #[serde(default)]
+    reserve_move_threshold_pct: Option<f64>, // 虚拟SOL储备变化超过该百分比时输出RESERVE_MOVE事件（不设置则不检测）
+    #[serde(default)]
+    sandwich_window_slots: Option<u64>, // 在该槽位窗口内检测疑似夹子交易并输出SANDWICH_SUSPECT事件（需启用缓存，不设置则不检测）
+    #[serde(default)]
+    volume_window_secs: Option<u64>,  // 按该秒数的滑动窗口统计每个代币的买卖笔数和SOL成交额（需启用缓存，只配置 volume_spike_sol 时默认60秒）
+    #[serde(default)]
+    volume_spike_sol: Option<f64>,    // 窗口成交额越过该SOL数量时输出VOLUME_SPIKE事件（不设置则只统计不报告）
+    #[serde(default)]
+    account_data_slice: bool,         // 账户订阅只请求解码需要的数据前缀，减少带宽（默认请求完整数据）
+    #[serde(default)]
+    bonding_curve_account_filter: bool, // 账户订阅只接收绑定曲线账户（按鉴别器过滤），Global账户单独按地址订阅
+    #[serde(default)]
+    account_data_size: Option<u64>,   // 账户订阅只接收数据长度等于该值的程序账户（不设置则不过滤）
+    #[serde(default)]
+    health_port: Option<u16>,         // 健康检查服务端口（提供 /healthz，不设置则不启动）
+    #[serde(default)]
+    health_stale_secs: Option<u64>,   // 超过该秒数未收到gRPC消息时 /healthz 返回503（默认30）
+    #[serde(default)]
+    heartbeat_secs: Option<u64>,      // 连续该秒数没有输出事件时输出一行心跳日志（不设置则不输出）
+    #[serde(default)]
+    capture_path: Option<String>,     // 将原始交易更新录制到该路径（不设置则不录制，capture 子命令优先）
+    #[serde(default)]
+    capture_max_file_bytes: Option<u64>, // 单个录制文件的滚动大小（字节，默认256MB）
+    #[serde(default)]
+    candle_interval_secs: Option<u64>, // 按该周期（秒）聚合每个代币的成交K线（需启用缓存，不设置则不聚合）
+    #[serde(default)]
+    candle_redis: bool,               // 收盘的K线是否写入Redis有序集合 candles:{周期}s:{mint}
+    #[serde(default)]
+    redis_trade_format: Option<RedisTradeFormat>, // 买卖交易写入Redis的格式: "text"（默认）或 "hash"
+    #[serde(default)]
+    redis_key_prefix: Option<String>, // 写入Redis的所有键的前缀，多个实例共用Redis时用于区分（不设置则不加前缀）
+    #[serde(default)]
+    mint_trade_index_len: Option<usize>, // Redis中每个代币索引的最近交易数量 trades:{mint}（默认100，0表示不索引）
+    #[serde(default)]
+    cache_max_age_secs: Option<u64>,  // 内存缓存最大有效期（秒，默认15），清理时删除超过该时间未更新的条目
+    #[serde(default)]
+    redis_cache_ttl_secs: Option<u64>, // 写入Redis的交易/账户缓存过期时间（秒，默认600）
+    #[serde(default)]
+    cache_cleanup_interval_secs: Option<u64>, // 内存缓存清理间隔（秒，默认600）
+    #[serde(default)]
+    cache_max_entries: Option<usize>, // 买入、卖出和账户缓存各自最多保留的条目数（默认100000，0表示不限制），超出时淘汰最旧的条目
+    #[serde(default)]
+    all_program_transactions: bool,   // 监听地址为空时处理监控程序的全部交易（全市场模式，数据量远大于按地址监听）
+    #[serde(default)]
+    nats_url: Option<String>,         // 将交易和账户事件发布到该NATS服务器（不设置则不发布）
+    #[serde(default)]
+    nats_subject_prefix: Option<String>, // NATS主题前缀（默认pump），事件发布到 {前缀}.buy/sell/create/account
+    #[serde(default)]
+    account_log_level: Option<String>, // 账户更新的日志级别（默认debug，off为不输出）
+    #[serde(default)]
+    trade_log_level: Option<String>,  // 涉及监听地址的买卖和指令的日志级别（默认info）
+    #[serde(default)]
+    unmatched_trade_log_level: Option<String>, // 其他买卖和指令的日志级别（默认debug）
+    #[serde(default)]
+    graduation_log_level: Option<String>, // 代币毕业的日志级别（默认info）
+    #[serde(default)]
+    min_sol_amount: Option<f64>,      // 低于该SOL金额的买卖视为小额交易（默认0，保留全部交易）
+    #[serde(default)]
+    dust_trade_action: Option<DustTradeAction>, // 小额交易的处理方式: "debug"（默认）或 "skip"
+}
+
+/// 低于 min_sol_amount 的小额买卖交易的处理方式，两种方式都不写入缓存、Redis、文件和推送
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+enum DustTradeAction {
+    /// 只以debug级别输出交易日志
+    #[default]
+    #[serde(rename = "debug")]
+    Debug,
+    /// 完全跳过，只计入过滤数量
+    #[serde(rename = "skip")]
+    Skip,
+}
+
+impl Features {
+    /// 配置文件中没有 `[features]` 时使用的特性集
+    fn fallback() -> Self {
+        Self {
+            basic_transaction_monitoring: true,
+            advanced_event_detection: true,
+            token_transaction_monitoring: true,
+            account_monitoring: true,
+            log_to_file: false,
+            log_file_path: "".to_string(),
+            enable_cache: true,
+            cpi_log_json: false,
+            cpi_log_json_dir: "logs/cpi_json".to_string(),
+            cpi_log_json_max_files: 30,
+            cpi_log_json_max_total_bytes: None,
+            cpi_log_json_max_age_secs: None,
+            cpi_log_json_pretty: None,
+            cpi_log_mode: None,
+            cpi_log_max_per_mint_per_minute: None,
+            seen_signature_capacity: None,
+            metrics_port: None,
+            structured_logs: false,
+            json_case: None,
+            api_port: None,
+            ws_port: None,
+            ndjson_tcp_port: None,
+            ndjson_unix_socket: None,
+            transaction_workers: None,
+            transaction_queue_capacity: None,
+            reserve_move_threshold_pct: None,
+            sandwich_window_slots: None,
+            volume_window_secs: None,
+            volume_spike_sol: None,
+            account_data_slice: false,
+            bonding_curve_account_filter: false,
+            account_data_size: None,
+            health_port: None,
+            health_stale_secs: None,
+            heartbeat_secs: None,
+            capture_path: None,
+            capture_max_file_bytes: None,
+            candle_interval_secs: None,
+            candle_redis: false,
+            redis_trade_format: None,
+            redis_key_prefix: None,
+            mint_trade_index_len: None,
+            cache_max_age_secs: None,
+            redis_cache_ttl_secs: None,
+            cache_cleanup_interval_secs: None,
+            cache_max_entries: None,
+            all_program_transactions: false,
+            nats_url: None,
+            nats_subject_prefix: None,
+            account_log_level: None,
+            trade_log_level: None,
+            unmatched_trade_log_level: None,
+            graduation_log_level: None,
+            min_sol_amount: None,
+            dust_trade_action: None,
+        }
+    }
+
+    /// 买卖交易的SOL金额是否低于 min_sol_amount（优先使用实际金额，无法确定时使用限价）
+    fn is_dust_trade(&self, entry: &CpiLogEntry) -> bool {
+        entry.traded_sol() < self.min_sol_amount.unwrap_or(0.0)
+    }
+
+    /// 解析各类事件的日志级别配置
+    fn event_log_levels(&self) -> anyhow::Result<EventLogLevels> {
+        EventLogLevels::parse(
+            self.account_log_level.as_deref(),
+            self.trade_log_level.as_deref(),
+            self.unmatched_trade_log_level.as_deref(),
+            self.graduation_log_level.as_deref(),
+        )
+    }
 }
 
 #[derive(Debug, Deserialize)]
 struct Config {
-    grpc_endpoint: String,
-    monitored_addresses: Vec<String>,
+    #[serde(alias = "grpc_endpoints")]
+    grpc_endpoint: GrpcEndpoints,            // gRPC端点，可配置为列表，不可用时按顺序切换
+    grpc_tls: Option<GrpcTlsConfig>,         // gRPC端点的自定义CA/客户端证书（不设置则使用系统根证书）
+    grpc_x_token: Option<String>,            // 付费gRPC服务商的认证token，作为 x-token 请求头随每个请求发送
+    #[serde(default)]
+    monitored_addresses: Vec<String>,        // 监听的地址，涉及这些地址的交易以info级别输出（开启 all_program_transactions 时可为空，否则必须配置）
     pump_program_id: Option<String>,
     pump_idl_path: Option<String>,
+    #[serde(default)]
+    pump_idl_versions: Vec<IdlVersionConfig>, // 按槽位范围区分的PumpFun IDL版本，选择规则见 idl_versions.rs
     token_idl_path: Option<String>,
     features: Option<Features>,
     redis_url: String,
+    rpc_url: Option<String>,                 // Solana RPC地址，缓存和Redis中都没有曲线账户数据时按需查询（不设置则不查询）
+    rpc_max_requests_per_sec: Option<u32>,   // 按需RPC查询每秒最多次数（默认10），超出时放弃查询
+    rpc_wait_timeout_ms: Option<u64>,        // 解码交易时等待RPC查询结果的时间（毫秒，默认500），超时后结果留给下次查找
+    creator_map_path: Option<String>,        // mint->creator 映射文件路径（JSON或TOML）
+    creator_map_reload_secs: Option<u64>,    // 映射文件热加载检查间隔（秒）
+    programs: Option<Vec<ProgramConfig>>,    // 需要监控的程序列表（未配置时只监控PumpFun）
+    commitment: Option<String>,              // 订阅确认级别: processed/confirmed/finalized（默认processed）
+    #[serde(default)]
+    watched_mints: Vec<String>,              // 只处理这些代币的买卖交易（为空时处理全部）
+    #[serde(default)]
+    signer_allowlist: Vec<String>,           // 只有这些签名者的买卖以info级别输出并写入日志文件（为空时不限制）
+    #[serde(default)]
+    signer_denylist: Vec<String>,            // 这些签名者的买卖降为debug级别输出（如机器人/MEV）
+    #[serde(default)]
+    include_failed: bool,                    // 是否同时订阅失败的交易（日志中标记FAILED）
+    from_slot: Option<u64>,                  // 从指定槽位开始回放（不设置则只接收实时数据）
+    from_slot_max_lag: Option<u64>,          // from_slot 最多落后当前槽位的槽位数（默认3000），更早的槽位改为从保留范围的起点回放
+    timezone_offset_hours: Option<i32>,      // 日志时间的UTC偏移小时数（默认8，即北京时间）
+    #[serde(default)]
+    constants: Constants,                    // 可覆盖的程序ID等常量（用于mock环境或Token-2022）
+    #[serde(default)]
+    alerts: Vec<PriceAlertConfig>,           // 价格提醒，账户监控更新储备后检查（需启用账户监控）
+    #[serde(default)]
+    monitors: Vec<MonitorProfileConfig>,     // 多个独立的监控配置（共享gRPC端点和Redis），配置后替代顶层的监听地址、过滤列表和价格提醒
 }
 
-impl Config {
-    fn load(path: PathBuf) -> anyhow::Result<Self> {
-        let content = fs::read_to_string(path)?;
-        Ok(toml::from_str(&content)?)
-    }
+/// `[[monitors]]` 中的单个监控配置，未配置features时使用顶层的 `[features]`
+#[derive(Debug, Deserialize, Clone)]
+struct MonitorProfileConfig {
+    name: String,
+    #[serde(default)]
+    monitored_addresses: Vec<String>,
+    #[serde(default)]
+    watched_mints: Vec<String>,
+    #[serde(default)]
+    signer_allowlist: Vec<String>,
+    #[serde(default)]
+    signer_denylist: Vec<String>,
+    #[serde(default)]
+    alerts: Vec<PriceAlertConfig>,
+    features: Option<Features>,
+}
 
-    fn load_pump_idl(&self) -> anyhow::Result<Option<Idl>> {
-        if let Some(idl_path) = &self.pump_idl_path {
-            let content = fs::read_to_string(idl_path)?;
-            Ok(Some(serde_json::from_str(&content)?))
-        } else {
-            Ok(None)
-        }
-    }
-    
+/// 解析后的监控配置，每个配置独立订阅并使用各自的缓存、日志输出和推送
+#[derive(Debug, Clone)]
+struct MonitorProfile {
+    name: String,
+    monitored_addresses: Vec<String>,
+    watched_mints: Vec<String>,
+    signer_allowlist: Vec<String>,
+    signer_denylist: Vec<String>,
+    alerts: Vec<PriceAlertConfig>,
+    features: Features,
+}
+
+/// 链上程序ID等常量，默认值为主网地址
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+struct Constants {
+    #[serde(skip)]
+    pump_program_id: String,        // 由顶层 pump_program_id 设置，用于计算曲线和金库PDA
+    token_program_ids: Vec<String>, // Token监控识别的Token程序（可加入Token-2022）
+    rent_sysvar_id: String,         // 租金系统变量，用于区分rent账户和被误标为rent的creator_vault
+    #[serde(skip)]
+    pump_program: Pubkey,           // 启动时解析的 pump_program_id，处理交易时不再重复解析
+    #[serde(skip)]
+    token_programs: Vec<Pubkey>,    // 启动时解析的 token_program_ids
+}
+
+impl Default for Constants {
+    fn default() -> Self {
+        let token_program_ids = vec![TOKEN_PROGRAM_ID.to_string(), TOKEN_2022_PROGRAM_ID.to_string()];
+        Self {
+            pump_program_id: PUMP_PROGRAM_ID.to_string(),
+            token_programs: token_program_ids.iter().filter_map(|id| Pubkey::from_str(id).ok()).collect(),
+            token_program_ids,
+            rent_sysvar_id: RENT_SYSVAR_ID.to_string(),
+            pump_program: Pubkey::from_str(PUMP_PROGRAM_ID).unwrap_or_default(),
+        }
+    }
+}
+
+impl Constants {
+    /// 根据配置生成常量并检查地址格式
+    fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let mut constants = config.constants.clone();
+        if let Some(pump_program_id) = &config.pump_program_id {
+            constants.pump_program_id = pump_program_id.clone();
+        }
+        let parse = |id: &String| Pubkey::from_str(id).map_err(|e| anyhow::anyhow!("无效的程序ID {}: {}", id, e));
+        constants.pump_program = parse(&constants.pump_program_id)?;
+        constants.token_programs = constants.token_program_ids.iter().map(parse).collect::<anyhow::Result<_>>()?;
+        parse(&constants.rent_sysvar_id)?;
+        Ok(constants)
+    }
+
+    fn pump_program_id(&self) -> Pubkey {
+        self.pump_program
+    }
+
+    /// 判断指令的程序ID是否为配置的Token程序之一
+    fn is_token_program(&self, program_id: &[u8]) -> bool {
+        self.token_programs.iter().any(|id| id.as_ref() == program_id)
+    }
+}
+
+/// 当前使用的常量，未设置时使用默认值
+fn constants() -> &'static Constants {
+    CONSTANTS.get_or_init(Constants::default)
+}
+
+/// 按签名者过滤买卖日志：拒绝列表优先，允许列表非空时只输出列表中的签名者
+#[derive(Debug, Default)]
+struct SignerFilter {
+    allowlist: HashSet<String>,
+    denylist: HashSet<String>,
+}
+
+impl SignerFilter {
+    fn new(allowlist: &[String], denylist: &[String]) -> Self {
+        Self {
+            allowlist: allowlist.iter().cloned().collect(),
+            denylist: denylist.iter().cloned().collect(),
+        }
+    }
+
+    /// 该签名者的买卖是否以info级别输出
+    fn allows(&self, signer: &str) -> bool {
+        !self.denylist.contains(signer) && (self.allowlist.is_empty() || self.allowlist.contains(signer))
+    }
+}
+
+/// 单个监控程序的配置
+#[derive(Debug, Deserialize, Clone)]
+struct ProgramConfig {
+    name: Option<String>,
+    program_id: String,
+    idl_path: Option<String>,
+    #[serde(default)]
+    idl_versions: Vec<IdlVersionConfig>, // 按槽位范围区分的IDL版本
+    decoder: Option<ProgramDecoder>,
+}
+
+/// 程序指令解码方式
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum ProgramDecoder {
+    Pump, // 使用pump_interface解析（包含买卖事件处理）
+    Idl,  // 使用IDL通用解析（按判别符匹配指令并映射账户）
+}
+
+/// 已加载IDL的监控程序
+#[derive(Clone)]
+struct MonitoredProgram {
+    name: String,
+    program_id: Pubkey,
+    idls: IdlSet,
+    decoder: ProgramDecoder,
+}
+
+impl Config {
+    fn load(path: PathBuf, overrides: &FeatureOverrides) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let mut config: Self = toml::from_str(&content)?;
+        config.apply_feature_overrides(overrides);
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// 把命令行的功能开关应用到顶层 [features] 和单独配置了features的监控配置
+    fn apply_feature_overrides(&mut self, overrides: &FeatureOverrides) {
+        let mut top_level = self.features.clone().unwrap_or_else(Features::fallback);
+        let changes = overrides.apply(&mut top_level);
+        if changes.is_empty() {
+            return;
+        }
+        info!("命令行覆盖了功能开关: {}", changes.join(", "));
+        self.features = Some(top_level);
+        for features in self.monitors.iter_mut().filter_map(|profile| profile.features.as_mut()) {
+            overrides.apply(features);
+        }
+    }
+
+    /// 检查监听地址和 pump_program_id 是否为有效的公钥，一次列出所有无效的条目
+    fn validate(&self) -> anyhow::Result<()> {
+        let mut invalid: Vec<String> = self.monitored_addresses.iter()
+            .enumerate()
+            .filter_map(|(index, address)| {
+                Pubkey::from_str(address).err().map(|e| format!("monitored_addresses[{}] = \"{}\": {}", index, address, e))
+            })
+            .collect();
+        for (profile_index, profile) in self.monitors.iter().enumerate() {
+            invalid.extend(profile.monitored_addresses.iter().enumerate().filter_map(|(index, address)| {
+                Pubkey::from_str(address).err().map(|e| {
+                    format!("monitors[{}].monitored_addresses[{}] = \"{}\": {}", profile_index, index, address, e)
+                })
+            }));
+        }
+        if let Some(pump_program_id) = &self.pump_program_id {
+            if let Err(e) = Pubkey::from_str(pump_program_id) {
+                invalid.push(format!("pump_program_id = \"{}\": {}", pump_program_id, e));
+            }
+        }
+
+        if !invalid.is_empty() {
+            return Err(anyhow::anyhow!("配置中有 {} 个无效的地址:\n  {}", invalid.len(), invalid.join("\n  ")));
+        }
+        if let Some(grpc_tls) = &self.grpc_tls {
+            grpc_tls.validate()?;
+        }
+        for version in self.pump_idl_versions.iter().chain(self.programs.iter().flatten().flat_map(|program| &program.idl_versions)) {
+            version.validate()?;
+        }
+        if let Some(token) = &self.grpc_x_token {
+            if token.trim().is_empty() || AsciiMetadataValue::try_from(token.as_str()).is_err() {
+                return Err(anyhow::anyhow!("grpc_x_token ({}) 为空或包含不能放入请求头的字符", mask_token(token)));
+            }
+        }
+        self.validate_monitor_outputs()
+    }
+
+    /// 检查监控配置的名称不重复，且各配置不会写入同一个日志文件、CPI日志目录或Redis键空间，服务端口互不冲突
+    fn validate_monitor_outputs(&self) -> anyhow::Result<()> {
+        let mut names = HashSet::new();
+        let mut log_files = HashMap::new();
+        let mut cpi_log_dirs = HashMap::new();
+        let mut redis_prefixes = HashMap::new();
+        let mut ports = HashMap::new();
+        let mut unix_sockets = HashMap::new();
+        // 指标和健康检查服务只读取顶层 [features]
+        let top_level = self.features.clone().unwrap_or_else(Features::fallback);
+        for (field, port) in [("metrics_port", top_level.metrics_port), ("health_port", top_level.health_port)] {
+            let Some(port) = port else {
+                continue;
+            };
+            let owner = format!("features.{}", field);
+            if let Some(other) = ports.insert(port, owner.clone()) {
+                return Err(anyhow::anyhow!("{} 和 {} 使用同一个端口: {}", other, owner, port));
+            }
+        }
+        for profile in self.monitor_profiles() {
+            if profile.name.trim().is_empty() {
+                return Err(anyhow::anyhow!("monitors 中的监控配置必须设置 name"));
+            }
+            if !names.insert(profile.name.clone()) {
+                return Err(anyhow::anyhow!("监控配置名称重复: {}", profile.name));
+            }
+            let features = &profile.features;
+            if features.log_to_file {
+                if let Some(other) = log_files.insert(features.log_file_path.clone(), profile.name.clone()) {
+                    return Err(anyhow::anyhow!(
+                        "监控配置 {} 和 {} 使用同一个日志文件: {}", other, profile.name, features.log_file_path
+                    ));
+                }
+            }
+            if features.cpi_log_json {
+                if let Some(other) = cpi_log_dirs.insert(features.cpi_log_json_dir.clone(), profile.name.clone()) {
+                    return Err(anyhow::anyhow!(
+                        "监控配置 {} 和 {} 使用同一个CPI日志目录: {}", other, profile.name, features.cpi_log_json_dir
+                    ));
+                }
+            }
+            // 各监控配置的缓存独立淘汰学到的映射，共用同一组Redis键时会互相删除对方的数据
+            if features.enable_cache {
+                let prefix = RedisKeys::new(features.redis_key_prefix.as_deref()).prefix().to_string();
+                if let Some(other) = redis_prefixes.insert(prefix.clone(), profile.name.clone()) {
+                    return Err(anyhow::anyhow!(
+                        "监控配置 {} 和 {} 都启用了缓存且使用同一个Redis键前缀: \"{}\"，请为其中一个设置不同的 redis_key_prefix",
+                        other, profile.name, prefix
+                    ));
+                }
+            }
+            // HTTP、WebSocket和NDJSON服务各自监听端口，同一个端口只能由一个服务使用
+            let services = [("api_port", features.api_port), ("ws_port", features.ws_port), ("ndjson_tcp_port", features.ndjson_tcp_port)];
+            for (field, port) in services {
+                let Some(port) = port else {
+                    continue;
+                };
+                let owner = format!("{}.{}", profile.name, field);
+                if let Some(other) = ports.insert(port, owner.clone()) {
+                    return Err(anyhow::anyhow!("{} 和 {} 使用同一个端口: {}", other, owner, port));
+                }
+            }
+            if let Some(path) = &features.ndjson_unix_socket {
+                if let Some(other) = unix_sockets.insert(path.clone(), profile.name.clone()) {
+                    return Err(anyhow::anyhow!(
+                        "监控配置 {} 和 {} 使用同一个NDJSON套接字: {}", other, profile.name, path
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 需要启动的监控配置；未配置 `[[monitors]]` 时，顶层的监听地址、过滤列表、价格提醒和features组成名为 "default" 的单个配置
+    fn monitor_profiles(&self) -> Vec<MonitorProfile> {
+        let features = self.features.clone().unwrap_or_else(Features::fallback);
+        if self.monitors.is_empty() {
+            return vec![MonitorProfile {
+                name: DEFAULT_MONITOR_NAME.to_string(),
+                monitored_addresses: self.monitored_addresses.clone(),
+                watched_mints: self.watched_mints.clone(),
+                signer_allowlist: self.signer_allowlist.clone(),
+                signer_denylist: self.signer_denylist.clone(),
+                alerts: self.alerts.clone(),
+                features,
+            }];
+        }
+        self.monitors
+            .iter()
+            .map(|profile| MonitorProfile {
+                name: profile.name.clone(),
+                monitored_addresses: profile.monitored_addresses.clone(),
+                watched_mints: profile.watched_mints.clone(),
+                signer_allowlist: profile.signer_allowlist.clone(),
+                signer_denylist: profile.signer_denylist.clone(),
+                alerts: profile.alerts.clone(),
+                features: profile.features.clone().unwrap_or_else(|| features.clone()),
+            })
+            .collect()
+    }
+
+    /// 解析订阅确认级别（不区分大小写），未配置时默认为processed
+    fn commitment_level(&self) -> anyhow::Result<CommitmentLevel> {
+        match self.commitment.as_deref().map(str::to_ascii_lowercase).as_deref() {
+            None | Some("processed") => Ok(CommitmentLevel::Processed),
+            Some("confirmed") => Ok(CommitmentLevel::Confirmed),
+            Some("finalized") => Ok(CommitmentLevel::Finalized),
+            Some(other) => Err(anyhow::anyhow!(
+                "无效的 commitment 配置: {}（可选值: processed, confirmed, finalized）",
+                other
+            )),
+        }
+    }
+
+    /// 加载需要监控的程序列表；未配置 `[[programs]]` 时使用 pump_program_id/pump_idl_path
+    fn load_programs(&self) -> anyhow::Result<Vec<MonitoredProgram>> {
+        let configs = match &self.programs {
+            Some(programs) if !programs.is_empty() => programs.clone(),
+            _ => vec![ProgramConfig {
+                name: Some("PumpFun".to_string()),
+                program_id: self.pump_program_id.clone().unwrap_or_else(|| PUMP_PROGRAM_ID.to_string()),
+                idl_path: self.pump_idl_path.clone(),
+                idl_versions: self.pump_idl_versions.clone(),
+                decoder: Some(ProgramDecoder::Pump),
+            }],
+        };
+
+        configs
+            .into_iter()
+            .map(|program| {
+                let program_id = Pubkey::from_str(&program.program_id)
+                    .map_err(|e| anyhow::anyhow!("无效的程序ID {}: {}", program.program_id, e))?;
+                let idls = IdlSet::load(program.idl_path.as_deref(), &program.idl_versions)?;
+                let decoder = program.decoder.unwrap_or(if program.program_id == PUMP_PROGRAM_ID {
+                    ProgramDecoder::Pump
+                } else {
+                    ProgramDecoder::Idl
+                });
+                Ok(MonitoredProgram {
+                    name: program.name.unwrap_or_else(|| program.program_id.clone()),
+                    program_id,
+                    idls,
+                    decoder,
+                })
+            })
+            .collect()
+    }
+    
     fn load_token_idl(&self) -> anyhow::Result<Option<Idl>> {
         if let Some(idl_path) = &self.token_idl_path {
             let content = fs::read_to_string(idl_path)?;
@@ -434,35 +1764,199 @@ impl Config {
 #[derive(Debug, Clone, ClapParser)]
 #[clap(author, version, about = "Solana 交易监控工具")]
 struct Args {
-    #[clap(short, long, help = "配置文件路径", default_value = "config.toml")]
+    #[clap(short, long, global = true, help = "配置文件路径", default_value = "config.toml")]
     config: PathBuf,
+    #[clap(subcommand)]
+    command: Option<Command>,
+    #[clap(flatten)]
+    feature_overrides: FeatureOverrides,
+}
+
+/// 命令行上的功能开关，覆盖配置文件中（包括每个监控配置的）对应的值，用于临时调试而不修改配置文件
+#[derive(Debug, Clone, Default, clap::Args)]
+struct FeatureOverrides {
+    #[clap(long, global = true, conflicts_with = "no_account_monitoring", help = "开启账户监控")]
+    enable_account_monitoring: bool,
+    #[clap(long, global = true, help = "关闭账户监控")]
+    no_account_monitoring: bool,
+    #[clap(long, global = true, conflicts_with = "no_token_monitoring", help = "开启Token程序交易监控")]
+    enable_token_monitoring: bool,
+    #[clap(long, global = true, help = "关闭Token程序交易监控")]
+    no_token_monitoring: bool,
+    #[clap(long, global = true, conflicts_with = "no_cache", help = "开启交易和账户缓存")]
+    enable_cache: bool,
+    #[clap(long, global = true, help = "关闭交易和账户缓存")]
+    no_cache: bool,
+    #[clap(long, global = true, conflicts_with = "no_cpi_json", help = "开启CPI日志JSON文件")]
+    enable_cpi_json: bool,
+    #[clap(long, global = true, help = "关闭CPI日志JSON文件")]
+    no_cpi_json: bool,
+    #[clap(long, global = true, conflicts_with = "no_structured_logs", help = "交易日志输出为单行JSON")]
+    structured_logs: bool,
+    #[clap(long, global = true, help = "交易日志输出为多行文本")]
+    no_structured_logs: bool,
+    #[clap(long, global = true, value_name = "PATH", conflicts_with = "no_log_file", help = "把交易日志写入指定文件（开启 log_to_file）")]
+    log_file: Option<String>,
+    #[clap(long, global = true, help = "不写交易日志文件")]
+    no_log_file: bool,
+}
+
+// 一对开关参数：开启优先，都未指定时为None
+fn switch_override(enable: bool, disable: bool) -> Option<bool> {
+    if enable {
+        Some(true)
+    } else if disable {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+impl FeatureOverrides {
+    /// 按命令行修改功能开关，返回修改的项（`字段=值`），没有指定任何参数时为空
+    fn apply(&self, features: &mut Features) -> Vec<String> {
+        let mut changes = Vec::new();
+        let switches = [
+            (switch_override(self.enable_account_monitoring, self.no_account_monitoring), &mut features.account_monitoring, "account_monitoring"),
+            (switch_override(self.enable_token_monitoring, self.no_token_monitoring), &mut features.token_transaction_monitoring, "token_transaction_monitoring"),
+            (switch_override(self.enable_cache, self.no_cache), &mut features.enable_cache, "enable_cache"),
+            (switch_override(self.enable_cpi_json, self.no_cpi_json), &mut features.cpi_log_json, "cpi_log_json"),
+            (switch_override(self.structured_logs, self.no_structured_logs), &mut features.structured_logs, "structured_logs"),
+            (switch_override(self.log_file.is_some(), self.no_log_file), &mut features.log_to_file, "log_to_file"),
+        ];
+        for (value, field, name) in switches {
+            if let Some(value) = value {
+                *field = value;
+                changes.push(format!("{}={}", name, value));
+            }
+        }
+        if let Some(path) = &self.log_file {
+            features.log_file_path = path.clone();
+            changes.push(format!("log_file_path={}", path));
+        }
+        changes
+    }
+}
+
+/// 运行模式，未指定子命令时等同于 `monitor`
+#[derive(Debug, Clone, clap::Subcommand)]
+enum Command {
+    /// 订阅gRPC端点并监控交易和账户（默认）
+    Monitor(MonitorArgs),
+    /// 只检查配置文件（地址、IDL、Redis、gRPC端点）并输出结果，不启动监控
+    CheckConfig,
+    /// 从录制文件回放交易更新（不连接gRPC端点），处理完毕后退出
+    Replay {
+        #[clap(help = "capture 录制的文件")]
+        path: PathBuf,
+    },
+    /// 启动监控，并将收到的原始交易更新录制到文件（覆盖配置文件中的 capture_path）
+    Capture {
+        #[clap(help = "录制文件路径，实际文件名附加创建时的毫秒时间戳")]
+        path: PathBuf,
+        #[clap(flatten)]
+        monitor: MonitorArgs,
+    },
+    /// 从Redis读取缓存数据，以JSON输出后退出
+    #[clap(subcommand)]
+    Query(QueryCommand),
+}
+
+#[derive(Debug, Clone, Default, clap::Args)]
+struct MonitorArgs {
+    #[clap(long, help = "从指定槽位开始回放历史数据（覆盖配置文件中的 from_slot）")]
+    from_slot: Option<u64>,
+}
+
+#[derive(Debug, Clone, clap::Subcommand)]
+enum QueryCommand {
+    /// 指定签名的缓存交易
+    Trade { signature: String },
+    /// 指定Mint的绑定曲线最新储备
+    Reserves { mint: String },
+    /// 指定Mint最近的买卖交易（按槽位从新到旧）
+    MintTrades {
+        mint: String,
+        #[clap(long, help = "最多输出的交易数量", default_value_t = 20)]
+        limit: usize,
+    },
 }
 
 impl Args {
-    async fn connect(&self, endpoint: String) -> anyhow::Result<GeyserGrpcClient<impl Interceptor>> {
-        GeyserGrpcClient::build_from_shared(endpoint)?
-            .connect_timeout(Duration::from_secs(10))
-            .timeout(Duration::from_secs(10))
-            .tls_config(ClientTlsConfig::new().with_native_roots())?
-            .max_decoding_message_size(1024 * 1024 * 1024)
-            .connect()
-            .await
-            .map_err(Into::into)
-    }
-
-    fn get_txn_updates(&self, addresses: Vec<String>, program_id: &str) -> anyhow::Result<SubscribeRequest> {
+    /// 监控模式的参数（monitor、capture 或未指定子命令）
+    fn monitor_args(&self) -> MonitorArgs {
+        match &self.command {
+            Some(Command::Monitor(monitor)) | Some(Command::Capture { monitor, .. }) => monitor.clone(),
+            _ => MonitorArgs::default(),
+        }
+    }
+
+    fn replay_path(&self) -> Option<PathBuf> {
+        match &self.command {
+            Some(Command::Replay { path }) => Some(path.clone()),
+            _ => None,
+        }
+    }
+
+    fn capture_path(&self) -> Option<PathBuf> {
+        match &self.command {
+            Some(Command::Capture { path, .. }) => Some(path.clone()),
+            _ => None,
+        }
+    }
+
+    async fn connect(&self, endpoint: String, tls: &GrpcTlsConfig, x_token: Option<&str>) -> anyhow::Result<GeyserGrpcClient<InterceptorXToken>> {
+        let x_token = x_token
+            .map(|token| AsciiMetadataValue::try_from(token).map_err(|_| anyhow::anyhow!("grpc_x_token 包含不能放入请求头的字符")))
+            .transpose()?;
+        let channel = tls.connect(endpoint, Duration::from_secs(10)).await?;
+        let interceptor = InterceptorXToken { x_token, x_request_snapshot: false };
+        Ok(GeyserGrpcClient::new(
+            HealthClient::with_interceptor(channel.clone(), interceptor.clone()),
+            GeyserClient::with_interceptor(channel, interceptor).max_decoding_message_size(1024 * 1024 * 1024),
+        ))
+    }
+
+    /// 从当前端点开始依次尝试连接，失败时切换到下一个端点，返回 (客户端, 端点序号)
+    async fn connect_any(&self, pool: &EndpointPool) -> anyhow::Result<(GeyserGrpcClient<impl Interceptor>, usize)> {
+        for _ in 0..pool.len() {
+            let (index, endpoint) = pool.active();
+            match self.connect(endpoint.to_string(), pool.tls(), pool.x_token()).await {
+                Ok(client) => {
+                    info!("[gRPC] 已连接到端点: {}", endpoint);
+                    return Ok((client, index));
+                }
+                Err(e) => {
+                    warn!("[gRPC] 连接端点 {} 失败: {}", endpoint, e);
+                    pool.failover(index);
+                }
+            }
+        }
+        Err(anyhow::anyhow!("所有gRPC端点都无法连接"))
+    }
+
+    fn get_txn_updates(&self, profile: &MonitorProfile, program_ids: &[String], commitment: CommitmentLevel, include_failed: bool, from_slot: Option<u64>) -> anyhow::Result<SubscribeRequest> {
         let mut transactions: TxnFilterMap = HashMap::new();
         
-        // 构建监听地址列表，包含用户地址和程序ID
-        let mut all_accounts = addresses.clone();
-        all_accounts.push(program_id.to_string());
+        // 按地址监听时只订阅涉及这些地址的交易；不包含程序ID，否则会收到监控程序的全部交易。
+        // 只有开启 all_program_transactions 时才订阅监控程序的全部交易
+        let account_include = if !profile.monitored_addresses.is_empty() {
+            profile.monitored_addresses.clone()
+        } else if profile.features.all_program_transactions {
+            program_ids.to_vec()
+        } else {
+            return Err(anyhow::anyhow!(
+                "[{}] 未配置监听地址，也未开启 all_program_transactions，不订阅监控程序的全部交易",
+                profile.name
+            ));
+        };
 
         transactions.insert(
             "client".to_owned(),
             SubscribeRequestFilterTransactions {
                 vote: Some(false),
-                failed: Some(false),
-                account_include: all_accounts,
+                failed: if include_failed { None } else { Some(false) },
+                account_include,
                 account_exclude: vec![],
                 account_required: vec![],
                 signature: None,
@@ -477,23 +1971,78 @@ impl Args {
             blocks: HashMap::default(),
             blocks_meta: HashMap::default(),
             entry: HashMap::default(),
-            commitment: Some(CommitmentLevel::Processed as i32),
+            commitment: Some(commitment as i32),
             accounts_data_slice: Vec::default(),
             ping: None,
-            from_slot: None,
+            from_slot,
         })
     }
     
-    fn get_account_updates(&self, program_id: &str) -> anyhow::Result<SubscribeRequest> {
+    fn get_account_updates(&self, program_id: &str, token_accounts: Vec<String>, commitment: CommitmentLevel, from_slot: Option<u64>, features: &Features) -> anyhow::Result<SubscribeRequest> {
         let mut accounts: AccountFilterMap = HashMap::new();
         
+        // 数据切片对订阅中的所有账户生效，长度取所有需要解码的字段的最大结束位置，避免截断
+        let accounts_data_slice = if features.account_data_slice {
+            let length = account_data_slice_len(!token_accounts.is_empty());
+            info!("账户订阅只请求前 {} 字节数据", length);
+            vec![SubscribeRequestAccountsDataSlice { offset: 0, length: length as u64 }]
+        } else {
+            Vec::default()
+        };
+        
+        // 曲线关联代币账户和Mint账户属于Token程序，需要按地址单独订阅
+        if !token_accounts.is_empty() {
+            accounts.insert(
+                "bondingCurveTokenAccounts".to_owned(),
+                SubscribeRequestFilterAccounts {
+                    account: token_accounts,
+                    owner: vec![],
+                    nonempty_txn_signature: None,
+                    filters: vec![],
+                },
+            );
+        }
+        
+        // 程序账户的服务端过滤条件（同时配置时需全部满足），不配置时接收该程序的全部账户
+        let mut filters = Vec::new();
+        if features.bonding_curve_account_filter {
+            filters.push(SubscribeRequestFilterAccountsFilter {
+                filter: Some(AccountsFilter::Memcmp(SubscribeRequestFilterAccountsFilterMemcmp {
+                    offset: 0,
+                    data: Some(MemcmpData::Bytes(BONDING_CURVE_ACCOUNT_DISCM.to_vec())),
+                })),
+            });
+        }
+        if let Some(data_size) = features.account_data_size {
+            filters.push(SubscribeRequestFilterAccountsFilter {
+                filter: Some(AccountsFilter::Datasize(data_size)),
+            });
+        }
+        if !filters.is_empty() {
+            // 过滤后不再收到Global账户，按地址单独订阅以保持协议参数更新
+            let program_pubkey = Pubkey::from_str(program_id)
+                .map_err(|e| anyhow::anyhow!("无效的程序ID {}: {}", program_id, e))?;
+            let (global_account, _) = Pubkey::find_program_address(&[b"global"], &program_pubkey);
+            info!("账户订阅只接收符合条件的程序账户 (绑定曲线鉴别器: {}, 数据长度: {:?})，Global账户({})单独订阅",
+                features.bonding_curve_account_filter, features.account_data_size, global_account);
+            accounts.insert(
+                "globalAccount".to_owned(),
+                SubscribeRequestFilterAccounts {
+                    account: vec![global_account.to_string()],
+                    owner: vec![],
+                    nonempty_txn_signature: None,
+                    filters: vec![],
+                },
+            );
+        }
+        
         accounts.insert(
             "accountData".to_owned(),
             SubscribeRequestFilterAccounts {
                 account: vec![],
                 owner: vec![program_id.to_string()],
                 nonempty_txn_signature: None,
-                filters: vec![],
+                filters,
             },
         );
         
@@ -505,10 +2054,10 @@ impl Args {
             blocks: HashMap::default(),
             blocks_meta: HashMap::default(),
             entry: HashMap::default(),
-            commitment: Some(CommitmentLevel::Processed as i32),
-            accounts_data_slice: Vec::default(),
+            commitment: Some(commitment as i32),
+            accounts_data_slice,
             ping: None,
-            from_slot: None,
+            from_slot,
         })
     }
 }
@@ -523,7 +2072,7 @@ fn to_camel_case(name: &str) -> String {
 }
 
 /// Extracts the instruction name and converts it to camel case.
-fn get_instruction_name_with_typename(instruction: &TokenInstruction) -> String {
+fn get_instruction_name_with_typename(instruction: &impl std::fmt::Debug) -> String {
     let debug_string = format!("{:?}", instruction);
     if let Some(first_brace) = debug_string.find(" {") {
         let name = &debug_string[..first_brace]; // Extract name before `{`
@@ -535,8 +2084,10 @@ fn get_instruction_name_with_typename(instruction: &TokenInstruction) -> String
 
 #[derive(Debug)]
 pub enum DecodedAccount {
-    BondingCurve(BondingCurve),
-    Global(Global),
+    /// 绑定曲线账户，第二个字段为新版布局中存储的creator（旧账户没有该字段时为None）
+    BondingCurve(BondingCurve, Option<Pubkey>),
+    /// 全局配置账户，第二个字段为新版布局中存储的创作者费率（旧账户没有该字段时为None）
+    Global(Global, Option<u64>),
 }
 
 #[derive(Debug)]
@@ -555,883 +2106,2769 @@ pub struct DecodedInstruction {
     pub parent_program_id: Option<Pubkey>,
 }
 
-/// 使用虚拟储备数据计算价格
-fn calculate_price(vt: u64, vs: u64) -> f64 {
-    if vt == 0 {
-        return 0.0; // 避免除以零
+/// 交易中的全部账户，按Solana规范顺序排列：
+/// 静态账户、地址查找表加载的可写账户、地址查找表加载的只读账户（v0交易）
+#[derive(Default)]
+struct AccountKeys {
+    keys: Vec<Vec<u8>>,
+    header: Option<MessageHeader>,
+    num_static: usize,
+    num_loaded_writable: usize,
+}
+
+impl AccountKeys {
+    fn new(message: &Message, meta: Option<&TransactionStatusMeta>) -> Self {
+        let mut keys = message.account_keys.clone();
+        let num_static = keys.len();
+        let mut num_loaded_writable = 0;
+        if let Some(meta) = meta {
+            num_loaded_writable = meta.loaded_writable_addresses.len();
+            keys.extend(meta.loaded_writable_addresses.iter().cloned());
+            keys.extend(meta.loaded_readonly_addresses.iter().cloned());
+        }
+        Self {
+            keys,
+            header: message.header,
+            num_static,
+            num_loaded_writable,
+        }
+    }
+
+    fn get(&self, index: usize) -> Option<&Vec<u8>> {
+        self.keys.get(index)
+    }
+
+    fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &Vec<u8>> {
+        self.keys.iter()
+    }
+
+    /// 手续费支付者，即消息的第一个账户（中继/代付交易中可能与指令中的user不同）
+    fn fee_payer(&self) -> Option<String> {
+        self.keys.first().map(|key| bs58::encode(key).into_string())
+    }
+
+    /// 按Base58地址查找账户索引
+    fn position(&self, address: &str) -> Option<usize> {
+        let pubkey = Pubkey::from_str(address).ok()?;
+        self.keys.iter().position(|key| key.as_slice() == pubkey.as_ref())
+    }
+
+    /// 账户的 (是否签名者, 是否可写)；查找表加载的账户不会是签名者
+    fn access(&self, index: usize) -> (bool, bool) {
+        if index < self.num_static {
+            self.header.as_ref().map_or((false, false), |h| account_access(index, h, self.num_static))
+        } else {
+            (false, index < self.num_static + self.num_loaded_writable)
+        }
     }
-    // 价格公式: vs/vt （SOL储备/代币储备）
-    // SOL精度为9，代币精度为6，需要考虑精度差异
-    // 转换为SOL单位并应用精度调整：(vs / 10^9) / (vt / 10^6) = vs / vt * 10^-3
-    (vs as f64) / (vt as f64) * 0.001
 }
 
-/// 用于序列化到JSON的CPI日志数据结构
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct CpiLogEntry {
-    transaction_type: String,           // Buy 或 Sell
-    mint: String,                       // 代币Mint地址
-    token_amount: u64,                  // 代币数量
-    sol_amount: f64,                    // SOL数量（买入时为成本，卖出时为输出）
-    time: String,                       // 交易时间（ISO 8601格式）
-    signature: String,                  // 交易签名
-    signer: String,                     // 签名者地址
-    price: Option<f64>,                 // 计算出的代币价格
-    virtual_token_reserves: Option<u64>, // 虚拟代币储备
-    virtual_sol_reserves: Option<u64>,   // 虚拟SOL储备
-    real_token_reserves: Option<u64>,    // 真实代币储备
-    real_sol_reserves: Option<u64>,      // 真实SOL储备
-    curve_account: Option<String>,      // 关联的绑定曲线账户
-    creator: Option<String>,            // 创作者地址
-    creator_fee_basis_points: Option<u64>, // 创作者费用点数
-    creator_fee: Option<u64>,           // 创作者费用
-    fee_recipient: Option<String>,      // 费用接收者
-    fee_basis_points: Option<u64>,      // 费用基点
-    fee_amount: Option<u64>,            // 费用金额
-    actual_sol_cost: Option<f64>,       // 实际SOL花费（用于Buy交易）
-    timestamp: Option<i64>,             // 时间戳
+/// 交易前后各账户的SOL余额（顺序与AccountKeys一致）、交易手续费以及本交易新建的代币账户
+#[derive(Default)]
+struct BalanceChanges {
+    pre_balances: Vec<u64>,
+    post_balances: Vec<u64>,
+    fee: u64,
+    created_token_accounts: Vec<usize>, // 交易后有代币余额、交易前没有的代币账户索引（如买入时创建的ATA）
 }
 
-/// 辅助函数，保存CPI日志到JSON文件
-fn save_cpi_log_to_json(entry: CpiLogEntry, dir_path: &str, max_files: usize) -> anyhow::Result<()> {
-    // 确保目录存在
-    let dir = std::path::Path::new(dir_path);
-    if !dir.exists() {
-        fs::create_dir_all(dir)?;
-        info!("创建CPI日志JSON目录: {:?}", dir);
+impl BalanceChanges {
+    fn new(meta: Option<&TransactionStatusMeta>) -> Self {
+        meta.map_or_else(Self::default, |meta| Self {
+            pre_balances: meta.pre_balances.clone(),
+            post_balances: meta.post_balances.clone(),
+            fee: meta.fee,
+            created_token_accounts: meta.post_token_balances.iter()
+                .map(|balance| balance.account_index)
+                .filter(|index| !meta.pre_token_balances.iter().any(|balance| balance.account_index == *index))
+                .map(|index| index as usize)
+                .collect(),
+        })
     }
 
-    // 创建文件名，使用交易签名和时间戳
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .expect("时间错误")
-        .as_millis();
-    
-    let short_sig = if entry.signature.len() > 8 {
-        &entry.signature[0..8]
-    } else {
-        &entry.signature
-    };
-    
-    let filename = format!("{}/{}_{}.json", dir_path, short_sig, timestamp);
-
-    // 序列化并写入文件
-    let json_content = serde_json::to_string_pretty(&entry)?;
-    fs::write(&filename, json_content)?;
-    info!("保存CPI日志到JSON文件: {}", filename);
-
-    // 如果超过最大文件数，删除最旧的文件
-    if max_files > 0 {
-        // 获取所有JSON文件并按修改时间排序
-        let pattern = format!("{}/*.json", dir_path);
-        let mut files: Vec<_> = glob(&pattern)
-            .expect("读取文件列表失败")
-            .filter_map(Result::ok)
-            .collect();
+    /// 账户在交易中实际花费的lamports，不含交易手续费（由索引0的手续费支付者承担）。
+    /// 包含同一交易中的其他支出（如创建代币账户的租金），余额未减少或索引无效时返回None
+    fn spent(&self, index: usize) -> Option<u64> {
+        let pre = *self.pre_balances.get(index)?;
+        let post = *self.post_balances.get(index)?;
+        let spent = pre.checked_sub(post)?;
+        Some(if index == 0 { spent.saturating_sub(self.fee) } else { spent })
+    }
 
-        // 如果文件数量超过限制
-        if files.len() > max_files {
-            // 按修改时间排序（最旧的在前面）
-            files.sort_by(|a, b| {
-                let time_a = fs::metadata(a).unwrap().modified().unwrap();
-                let time_b = fs::metadata(b).unwrap().modified().unwrap();
-                time_a.cmp(&time_b)
-            });
+    /// 买入实际花费的lamports：在 `spent` 的基础上扣除本交易新建代币账户的租金
+    fn spent_on_trade(&self, index: usize) -> Option<u64> {
+        let rent: u64 = self.created_token_accounts.iter()
+            .filter_map(|account| self.post_balances.get(*account))
+            .sum();
+        Some(self.spent(index)?.saturating_sub(rent))
+    }
 
-            // 删除多余的（最旧的）文件
-            let files_to_remove = files.len() - max_files;
-            for i in 0..files_to_remove {
-                if let Err(e) = fs::remove_file(&files[i]) {
-                    warn!("删除旧的CPI日志文件失败 {:?}: {}", files[i], e);
-                } else {
-                    debug!("删除旧的CPI日志文件: {:?}", files[i]);
+    /// 账户收到的lamports（不含交易手续费），余额减少或索引越界时返回None
+    fn received(&self, index: usize) -> Option<u64> {
+        let pre = *self.pre_balances.get(index)?;
+        let post = *self.post_balances.get(index)?;
+        let received = post.checked_sub(pre)?;
+        Some(if index == 0 { received.saturating_add(self.fee) } else { received })
+    }
+}
+
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000; // 每个签名的基础手续费
+const DEFAULT_INSTRUCTION_COMPUTE_UNITS: u32 = 200_000; // 未设置计算单元上限时每条指令的默认上限
+const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000; // 交易的计算单元上限
+
+/// 交易的计算预算设置（ComputeBudget程序指令，只在顶层生效）和估算的手续费
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PriorityFee {
+    compute_unit_limit: u32, // 请求的计算单元上限，未设置时为运行时默认值
+    compute_unit_price: u64, // 每计算单元的价格（micro-lamports），未设置时为0
+    priority_fee: u64,       // 优先费 = ceil(上限 * 价格 / 1_000_000)（lamports）
+    total_fee: u64,          // 基础费（每个签名5000 lamports）加优先费（lamports）
+}
+
+impl PriorityFee {
+    fn from_message(message: &Message, account_keys: &AccountKeys) -> Self {
+        let compute_budget_program = solana_sdk::compute_budget::id();
+        let mut requested_limit = None;
+        let mut compute_unit_price = 0;
+        let mut other_instructions = 0u32;
+        for instruction in &message.instructions {
+            let is_compute_budget = account_keys
+                .get(instruction.program_id_index as usize)
+                .is_some_and(|program_id| program_id.as_slice() == compute_budget_program.as_ref());
+            if !is_compute_budget {
+                other_instructions += 1;
+                continue;
+            }
+            // 指令数据为1字节标签加小端参数: 2 = SetComputeUnitLimit(u32), 3 = SetComputeUnitPrice(u64)
+            match instruction.data.split_first() {
+                Some((2, args)) => {
+                    if let Ok(bytes) = args.try_into() {
+                        requested_limit = Some(u32::from_le_bytes(bytes));
+                    }
+                }
+                Some((3, args)) => {
+                    if let Ok(bytes) = args.try_into() {
+                        compute_unit_price = u64::from_le_bytes(bytes);
+                    }
                 }
+                _ => {}
             }
         }
+        let compute_unit_limit = requested_limit
+            .unwrap_or_else(|| other_instructions.saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNITS))
+            .min(MAX_COMPUTE_UNIT_LIMIT);
+        let priority_fee = (compute_unit_limit as u128 * compute_unit_price as u128).div_ceil(1_000_000) as u64;
+        let signatures = message.header.as_ref().map_or(1, |header| header.num_required_signatures.max(1));
+        Self {
+            compute_unit_limit,
+            compute_unit_price,
+            priority_fee,
+            total_fee: LAMPORTS_PER_SIGNATURE * signatures as u64 + priority_fee,
+        }
     }
 
-    Ok(())
+    // 买卖日志中的手续费行，没有设置优先费时省略
+    fn log_line(&self) -> String {
+        if self.compute_unit_price == 0 {
+            return String::new();
+        }
+        format!(
+            "PRIORITY FEE: {} (CU LIMIT: {}, CU PRICE: {} micro-lamports, TOTAL FEE: {})\n",
+            Lamports(self.priority_fee),
+            self.compute_unit_limit,
+            self.compute_unit_price,
+            Lamports(self.total_fee)
+        )
+    }
+}
+
+// 买卖日志中的滑点行，无法计算时省略
+fn format_slippage_line(slippage: Option<f64>) -> String {
+    slippage.map(|pct| format!("SLIPPAGE: {:.2}%\n", pct)).unwrap_or_default()
 }
 
-/// 保存原始CPI日志数据到JSON文件
-fn save_raw_cpi_log_to_json(log_data: Value, dir_path: &str, max_files: usize) -> anyhow::Result<()> {
-    // 确保目录存在
-    let dir = std::path::Path::new(dir_path);
-    if !dir.exists() {
-        fs::create_dir_all(dir)?;
-        info!("创建CPI日志JSON目录: {:?}", dir);
+/// 实际成交相对限价的滑点余量（百分比），正数表示成交优于限价
+///
+/// 买入为 (max_sol_cost - 实际花费) / max_sol_cost，卖出为 (实际收到 - min_sol_output) / min_sol_output；
+/// 限价为0（不限滑点）时无法计算，返回None。
+fn slippage_pct(actual: u64, limit: u64, is_buy: bool) -> Option<f64> {
+    if limit == 0 {
+        return None;
     }
+    let headroom = if is_buy {
+        limit as f64 - actual as f64
+    } else {
+        actual as f64 - limit as f64
+    };
+    Some(headroom / limit as f64 * 100.0)
+}
 
-    // 创建文件名，使用交易签名和时间戳
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .expect("时间错误")
-        .as_millis();
-    
-    let signature = log_data["signature"].as_str().unwrap_or("unknown");
-    let short_sig = if signature.len() > 8 {
-        &signature[0..8]
+/// 成交对价格的影响：(成交后价格 - 成交前价格) / 成交前价格，正数表示价格上涨
+///
+/// 储备为（虚拟代币, 虚拟SOL），价格换算中的精度调整前后相同，相除时抵消；任一侧价格无法计算时返回None。
+fn price_impact(before: (u64, u64), after: (u64, u64)) -> Option<f64> {
+    let price = |(token, sol): (u64, u64)| (token > 0).then(|| sol as f64 / token as f64);
+    let before = price(before).filter(|price| *price > 0.0)?;
+    Some((price(after)? - before) / before)
+}
+
+/// 单笔买卖的价格影响：使用TradeEvent中成交后的储备并倒推成交前的储备；
+/// 没有TradeEvent时成交后的储备无法确定，不计算价格影响
+fn trade_price_impact(event: &PumpTradeEvent) -> Option<f64> {
+    price_impact(event.reserves_before()?, (event.virtual_token_reserves, event.virtual_sol_reserves))
+}
+
+/// 根据编译后指令的账户索引构建AccountMeta列表
+///
+/// 账户地址不是32字节时返回None，由调用方跳过该指令，避免输出错误的全零地址
+fn build_account_metas(instruction: &CompiledInstruction, account_keys: &AccountKeys) -> Option<Vec<AccountMeta>> {
+    instruction.accounts.iter()
+        .filter(|&&acc_idx| {
+            // 确保索引在数组范围内
+            (acc_idx as usize) < account_keys.len()
+        })
+        .map(|&acc_idx| {
+            let pubkey = Pubkey::try_from(account_keys.keys[acc_idx as usize].as_slice()).ok()?;
+            
+            // 根据消息头和账户顺序推导签名者/可写标记
+            let (is_signer, is_writable) = account_keys.access(acc_idx as usize);
+            
+            Some(AccountMeta {
+                pubkey,
+                is_signer,
+                is_writable,
+            })
+        })
+        .collect()
+}
+
+/// 按Solana账户排列规则推导账户的 (是否签名者, 是否可写)
+///
+/// 账户按以下顺序排列：可写签名者、只读签名者、可写非签名者、只读非签名者，
+/// 消息头记录了签名者数量以及两类只读账户的数量。
+fn account_access(index: usize, header: &MessageHeader, num_account_keys: usize) -> (bool, bool) {
+    let num_signers = header.num_required_signatures as usize;
+    if index < num_signers {
+        let num_writable_signers = num_signers.saturating_sub(header.num_readonly_signed_accounts as usize);
+        (true, index < num_writable_signers)
     } else {
-        signature
+        let num_writable_keys = num_account_keys.saturating_sub(header.num_readonly_unsigned_accounts as usize);
+        (false, index < num_writable_keys)
+    }
+}
+
+/// 使用程序配置的IDL通用解码指令（按IDL解析指令名称、账户和参数，参数无法解码时以原始十六进制输出）
+fn decode_idl_instruction(
+    program: &MonitoredProgram,
+    instruction: &CompiledInstruction,
+    account_keys: &AccountKeys,
+    parent_program_id: Option<Pubkey>,
+    slot: u64,
+) -> Option<DecodedInstruction> {
+    let name = program.idls.instruction_name_for_data(slot, &instruction.data)?.to_string();
+    let idl = program.idls.select(slot, &name, instruction.accounts.len())?;
+    let Some(account_metas) = build_account_metas(instruction, account_keys) else {
+        log::debug!("[{}] 指令({})包含无效的账户地址，跳过", program.name, name);
+        return None;
     };
-    
-    let filename = format!("{}/{}_{}.json", dir_path, short_sig, timestamp);
-
-    // 序列化并写入文件，使用pretty格式确保易读性
-    let json_content = serde_json::to_string_pretty(&log_data)?;
-    fs::write(&filename, json_content)?;
-    info!("保存原始CPI日志到JSON文件: {}", filename);
-
-    // 如果超过最大文件数，删除最旧的文件
-    if max_files > 0 {
-        // 获取所有JSON文件并按修改时间排序
-        let pattern = format!("{}/*.json", dir_path);
-        let mut files: Vec<_> = glob(&pattern)
-            .expect("读取文件列表失败")
-            .filter_map(Result::ok)
-            .collect();
+    let (accounts, mapping_error) = idl.map_accounts_lenient(&account_metas, &name);
+    if let Some(e) = mapping_error {
+        log::debug!("[{}] 映射账户不完整: {}", program.name, e);
+    }
+    // 按IDL中的参数定义解码，失败时以原始十六进制输出
+    let args = idl.decode_args(&name, &instruction.data[8..]).unwrap_or_else(|e| {
+        log::debug!("[{}] 按IDL解码指令({})参数失败: {}", program.name, name, e);
+        let args_hex: String = instruction.data[8..].iter().map(|b| format!("{:02x}", b)).collect();
+        json!({ "raw_args": args_hex })
+    });
+    Some(DecodedInstruction {
+        data: json!({ &name: args }),
+        name,
+        accounts,
+        program_id: program.program_id,
+        parent_program_id,
+    })
+}
 
-        // 如果文件数量超过限制
-        if files.len() > max_files {
-            // 按修改时间排序（最旧的在前面）
-            files.sort_by(|a, b| {
-                let time_a = fs::metadata(a).unwrap().modified().unwrap();
-                let time_b = fs::metadata(b).unwrap().modified().unwrap();
-                time_a.cmp(&time_b)
-            });
+/// 按执行顺序展开顶层指令和内部(CPI)指令
+///
+/// 内部指令转换为 `CompiledInstruction`，并附带其所属顶层指令的程序ID，
+/// 顶层指令的父程序ID为 `None`。
+fn flatten_instructions(
+    message: &Message,
+    account_keys: &AccountKeys,
+    inner_instructions: &[InnerInstructions],
+) -> Vec<(CompiledInstruction, Option<Pubkey>)> {
+    let program_id_at = |index: u32| {
+        account_keys
+            .get(index as usize)
+            .and_then(|key| Pubkey::try_from(key.as_slice()).ok())
+    };
 
-            // 删除多余的（最旧的）文件
-            let files_to_remove = files.len() - max_files;
-            for i in 0..files_to_remove {
-                if let Err(e) = fs::remove_file(&files[i]) {
-                    warn!("删除旧的CPI日志文件失败 {:?}: {}", files[i], e);
-                } else {
-                    debug!("删除旧的CPI日志文件: {:?}", files[i]);
-                }
-            }
+    let mut all = Vec::with_capacity(message.instructions.len());
+    for (index, instruction) in message.instructions.iter().enumerate() {
+        all.push((instruction.clone(), None));
+        let parent_program_id = program_id_at(instruction.program_id_index);
+        for inner in inner_instructions.iter().filter(|inner| inner.index as usize == index) {
+            all.extend(inner.instructions.iter().map(|ix| {
+                (
+                    CompiledInstruction {
+                        program_id_index: ix.program_id_index,
+                        accounts: ix.accounts.clone(),
+                        data: ix.data.clone(),
+                    },
+                    parent_program_id,
+                )
+            }));
         }
     }
+    all
+}
 
+/// 设置日志时间使用的UTC偏移（小时）
+fn set_timezone_offset(hours: i32) -> anyhow::Result<()> {
+    let offset = FixedOffset::east_opt(hours * 3600)
+        .ok_or_else(|| anyhow::anyhow!("无效的 timezone_offset_hours: {}", hours))?;
+    let _ = TIMEZONE_OFFSET.set(offset);
     Ok(())
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    env::set_var(
-        env_logger::DEFAULT_FILTER_ENV,
-        env::var_os(env_logger::DEFAULT_FILTER_ENV).unwrap_or_else(|| "error".into()),
-    );
-    env_logger::init();
+/// 配置的日志时区，未设置时为北京时间
+fn timezone_offset() -> FixedOffset {
+    TIMEZONE_OFFSET.get().copied().unwrap_or_else(|| {
+        FixedOffset::east_opt(DEFAULT_TIMEZONE_OFFSET_HOURS * 3600).expect("valid default offset")
+    })
+}
+
+/// 按配置的时区格式化时间（ISO 8601，带时区偏移，如 2024-01-01T08:00:00.000+08:00）
+fn format_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&timezone_offset()).format("%Y-%m-%dT%H:%M:%S%.3f%:z").to_string()
+}
+
+/// 使用虚拟储备数据计算价格
+fn calculate_price(vt: u64, vs: u64, token_decimals: u8) -> f64 {
+    if vt == 0 {
+        return 0.0; // 避免除以零
+    }
+    // 价格公式: vs/vt （SOL储备/代币储备）
+    // SOL精度为9，代币精度为token_decimals，需要考虑精度差异
+    // 转换为SOL单位并应用精度调整：(vs / 10^9) / (vt / 10^d) = vs / vt * 10^(d-9)
+    (vs as f64) / (vt as f64) * 10f64.powi(token_decimals as i32 - SOL_DECIMALS as i32)
+}
+
+/// 代币按当前价格估算的市值和完全稀释估值（SOL）
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MarketValue {
+    market_cap_sol: Option<f64>, // 价格 × 流通量（已从曲线售出的代币），真实代币储备未知时为空
+    fdv_sol: f64,                // 价格 × 总供应量
+}
+
+impl MarketValue {
+    /// 流通量 = 初始真实代币储备（优先取Global账户）- 当前真实代币储备。
+    /// 供应量先按精度换算为代币数量再以f64与价格相乘，大供应量不会溢出；价格或总供应量未知时返回None
+    fn estimate(
+        price: Option<f64>,
+        token_total_supply: Option<u64>,
+        real_token_reserves: Option<u64>,
+        global_state: Option<&GlobalState>,
+        token_decimals: u8,
+    ) -> Option<Self> {
+        let price = price.filter(|price| price.is_finite() && *price > 0.0)?;
+        let token_total_supply = token_total_supply.filter(|supply| *supply > 0)?;
+        let initial_real_token_reserves =
+            global_state.map_or(DEFAULT_INITIAL_REAL_TOKEN_RESERVES, |global| global.initial_real_token_reserves);
+        let to_tokens = |amount: u64| amount as f64 / 10f64.powi(i32::from(token_decimals));
+        Some(Self {
+            market_cap_sol: real_token_reserves
+                .map(|reserves| to_tokens(initial_real_token_reserves.saturating_sub(reserves)) * price),
+            fdv_sol: to_tokens(token_total_supply) * price,
+        })
+    }
+}
+
+// 买卖和账户日志中的市值行，无法估算时省略
+fn format_market_value_line(value: Option<&MarketValue>) -> String {
+    let Some(value) = value else {
+        return String::new();
+    };
+    let market_cap = value.market_cap_sol.map(|cap| format!("MARKET CAP: {:.2} SOL\n", cap)).unwrap_or_default();
+    format!("{}FDV: {:.2} SOL\n", market_cap, value.fdv_sol)
+}
+
+/// 从SPL Mint账户数据中解析代币精度
+fn decode_mint_decimals(data: &[u8]) -> Option<u8> {
+    spl_token::state::Mint::unpack(data).ok().map(|mint| mint.decimals)
+}
+
+/// 从携带精度的Token指令中提取 (mint账户在指令账户列表中的位置, 精度)
+/// 初始化代币账户的指令中的 (代币账户, 所有者, mint)，`key` 按指令中的账户位置返回地址
+fn token_account_from_instruction(ix: &TokenInstruction, key: impl Fn(usize) -> Option<String>) -> Option<(String, String, String)> {
+    let owner = match ix {
+        TokenInstruction::InitializeAccount => key(2)?,
+        TokenInstruction::InitializeAccount2 { owner } | TokenInstruction::InitializeAccount3 { owner } => owner.to_string(),
+        _ => return None,
+    };
+    Some((key(0)?, owner, key(1)?))
+}
+
+fn mint_decimals_from_token_instruction(ix: &TokenInstruction) -> Option<(usize, u8)> {
+    match ix {
+        TokenInstruction::InitializeMint { decimals, .. }
+        | TokenInstruction::InitializeMint2 { decimals, .. }
+        | TokenInstruction::MintToChecked { decimals, .. } => Some((0, *decimals)),
+        TokenInstruction::TransferChecked { decimals, .. }
+        | TokenInstruction::ApproveChecked { decimals, .. }
+        | TokenInstruction::BurnChecked { decimals, .. } => Some((1, *decimals)),
+        _ => None,
+    }
+}
+
+/// 事件在链上的位置，按 (slot, transaction_index, instruction_index) 可得到区块内确定的顺序
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+struct EventOrder {
+    slot: u64,
+    transaction_index: u64,  // 交易在区块中的序号
+    instruction_index: u32,  // 指令在交易展开后的指令列表中的序号
+}
+
+/// 用于序列化到JSON的CPI日志数据结构
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct CpiLogEntry {
+    transaction_type: String,           // Buy、Sell 或 Create
+    mint: String,                       // 代币Mint地址
+    token_amount: u64,                  // 代币数量
+    #[serde(default)]
+    token_amount_ui: Option<f64>,       // 按代币精度换算后的代币数量（精度未知时为空）
+    sol_amount: f64,                    // SOL数量（买入时为成本，卖出时为输出）
+    time: String,                       // 交易时间（ISO 8601格式）
+    signature: String,                  // 交易签名
+    #[serde(default)]
+    slot: u64,                          // 交易所在槽位
+    #[serde(default)]
+    transaction_index: u64,             // 交易在区块中的序号
+    #[serde(default)]
+    instruction_index: u32,             // 指令在交易中的序号（顶层和内部指令按执行顺序展开后计数）
+    signer: String,                     // 签名者地址（指令中的user账户）
+    #[serde(default)]
+    fee_payer: Option<String>,          // 手续费支付者（交易的第一个账户），中继/代付交易中可能与signer不同
+    price: Option<f64>,                 // 计算出的代币价格
+    virtual_token_reserves: Option<u64>, // 虚拟代币储备
+    virtual_sol_reserves: Option<u64>,   // 虚拟SOL储备
+    real_token_reserves: Option<u64>,    // 真实代币储备
+    real_sol_reserves: Option<u64>,      // 真实SOL储备
+    curve_account: Option<String>,      // 关联的绑定曲线账户
+    creator: Option<String>,            // 创作者地址
+    creator_fee_basis_points: Option<u64>, // 创作者费用点数
+    creator_fee: Option<u64>,           // 创作者费用
+    fee_recipient: Option<String>,      // 费用接收者
+    fee_basis_points: Option<u64>,      // 费用基点
+    fee_amount: Option<u64>,            // 费用金额
+    actual_sol_cost: Option<f64>,       // 实际SOL花费（用于Buy交易）
+    #[serde(default)]
+    actual_sol_output: Option<f64>,     // 实际收到的SOL（用于Sell交易）
+    #[serde(default)]
+    slippage_pct: Option<f64>,          // 相对限价的滑点余量百分比，正数表示优于限价（实际金额无法确定时为空）
+    #[serde(default)]
+    price_impact: Option<f64>,          // 本笔成交对价格的影响比例 (成交后价格 - 成交前价格) / 成交前价格（没有TradeEvent时为空）
+    #[serde(default)]
+    market_cap_sol: Option<f64>,        // 估算市值：价格 × 流通量（SOL）
+    #[serde(default)]
+    fdv_sol: Option<f64>,               // 估算完全稀释估值：价格 × 总供应量（SOL）
+    #[serde(default)]
+    compute_unit_limit: Option<u32>,    // 请求的计算单元上限
+    #[serde(default)]
+    compute_unit_price: Option<u64>,    // 每计算单元的价格（micro-lamports）
+    #[serde(default)]
+    priority_fee: Option<u64>,          // 估算的优先费（lamports）
+    #[serde(default)]
+    total_fee: Option<u64>,             // 估算的总手续费：基础费加优先费（lamports）
+    timestamp: Option<i64>,             // 时间戳
+    #[serde(default)]
+    failed: bool,                       // 交易是否执行失败
+    #[serde(default)]
+    trade_event: bool,                  // 成交金额、储备和费用是否取自程序发出的TradeEvent（否则由余额变化和指令参数推算）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    metadata: Option<TokenMetadata>,    // 新代币的名称、符号和URI（仅用于Create）
+}
+
+/// Create指令中的代币元数据
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct TokenMetadata {
+    name: String,
+    symbol: String,
+    uri: String,
+}
+
+impl CpiLogEntry {
+    /// 以字段名和值的形式展开（空值不写入），用于写入Redis哈希
+    fn hash_fields(&self) -> Vec<(String, String)> {
+        let Ok(Value::Object(object)) = serde_json::to_value(self) else {
+            return Vec::new();
+        };
+        object
+            .into_iter()
+            .filter_map(|(field, value)| match value {
+                Value::Null => None,
+                Value::String(text) => Some((field, text)),
+                other => Some((field, other.to_string())),
+            })
+            .collect()
+    }
+
+    /// 构建买卖交易的日志条目，只填充交易本身和曲线账户相关的字段
+    #[allow(clippy::too_many_arguments)]
+    fn for_trade(
+        transaction_type: &str,
+        mint: &str,
+        token_amount: u64,
+        sol_amount: f64,
+        time: &str,
+        signature: &str,
+        order: EventOrder,
+        signer: &str,
+        price: Option<f64>,
+        virtual_token_reserves: Option<u64>,
+        virtual_sol_reserves: Option<u64>,
+        curve_account: Option<String>,
+        creator: Option<String>,
+    ) -> Self {
+        Self {
+            transaction_type: transaction_type.to_string(),
+            mint: mint.to_string(),
+            token_amount,
+            token_amount_ui: None,
+            sol_amount,
+            time: time.to_string(),
+            signature: signature.to_string(),
+            slot: order.slot,
+            transaction_index: order.transaction_index,
+            instruction_index: order.instruction_index,
+            signer: signer.to_string(),
+            fee_payer: None,
+            price,
+            virtual_token_reserves,
+            virtual_sol_reserves,
+            real_token_reserves: None,
+            real_sol_reserves: None,
+            curve_account,
+            creator,
+            creator_fee_basis_points: None,
+            creator_fee: None,
+            fee_recipient: None,
+            fee_basis_points: None,
+            fee_amount: None,
+            actual_sol_cost: None,
+            actual_sol_output: None,
+            slippage_pct: None,
+            price_impact: None,
+            market_cap_sol: None,
+            fdv_sol: None,
+            compute_unit_limit: None,
+            compute_unit_price: None,
+            priority_fee: None,
+            total_fee: None,
+            timestamp: None,
+            failed: false,
+            trade_event: false,
+            metadata: None,
+        }
+    }
+
+    /// 成交的SOL金额，优先使用实际花费或收到的金额，无法确定时使用限价
+    fn traded_sol(&self) -> f64 {
+        self.actual_sol_cost.or(self.actual_sol_output).unwrap_or(self.sol_amount)
+    }
+
+    /// 填充估算的市值和完全稀释估值，无法估算时保持为空
+    fn apply_market_value(&mut self, value: Option<&MarketValue>) {
+        self.market_cap_sol = value.and_then(|value| value.market_cap_sol);
+        self.fdv_sol = value.map(|value| value.fdv_sol);
+    }
+
+    /// 填充交易的计算预算设置和估算的手续费
+    fn apply_priority_fee(&mut self, fee: &PriorityFee) {
+        self.compute_unit_limit = Some(fee.compute_unit_limit);
+        self.compute_unit_price = Some(fee.compute_unit_price);
+        self.priority_fee = Some(fee.priority_fee);
+        self.total_fee = Some(fee.total_fee);
+    }
+
+    /// 填充协议费用、创作者费用以及Global账户中的费用接收者
+    fn apply_fees(&mut self, fees: &PumpFees, global_state: Option<&GlobalState>) {
+        self.fee_recipient = global_state.map(|global| global.fee_recipient.clone());
+        self.fee_basis_points = Some(fees.fee_basis_points);
+        self.fee_amount = Some(fees.protocol_fee);
+        self.creator_fee_basis_points = Some(fees.creator_fee_basis_points);
+        self.creator_fee = Some(fees.creator_fee);
+    }
+
+    /// 用TradeEvent中的实际费用覆盖按限价估算的费用（旧版本事件没有费用字段时保留估算值）
+    fn apply_trade_event(&mut self, event: &PumpTradeEvent) {
+        self.trade_event = true;
+        if let (Some(recipient), Some(basis_points), Some(fee)) = (&event.fee_recipient, event.fee_basis_points, event.fee) {
+            self.fee_recipient = Some(recipient.clone());
+            self.fee_basis_points = Some(basis_points);
+            self.fee_amount = Some(fee);
+        }
+        if let (Some(basis_points), Some(fee)) = (event.creator_fee_basis_points, event.creator_fee) {
+            self.creator_fee_basis_points = Some(basis_points);
+            self.creator_fee = Some(fee);
+        }
+    }
+}
+
+/// 写入JSON文件的CPI日志：类型化的交易条目，附带指令解析出的原始数据
+#[derive(Serialize)]
+struct CpiLogFile {
+    #[serde(flatten)]
+    entry: CpiLogEntry,
+    raw: Value,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    env::set_var(
+        env_logger::DEFAULT_FILTER_ENV,
+        env::var_os(env_logger::DEFAULT_FILTER_ENV).unwrap_or_else(|| "error".into()),
+    );
+    env_logger::init();
 
     let args = Args::parse();
-    let config = Config::load(args.config.clone())?;
+    if let Some(Command::CheckConfig) = args.command {
+        let ok = check_config(&args).await;
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+    let config = Config::load(args.config.clone(), &args.feature_overrides)?;
+    set_timezone_offset(config.timezone_offset_hours.unwrap_or(DEFAULT_TIMEZONE_OFFSET_HOURS))?;
+    let _ = CONSTANTS.set(Constants::from_config(&config)?);
+    if let Some(Command::Query(query)) = &args.command {
+        return query_cache(query, &config).await;
+    }
+    // 顶层 [features] 用于进程级的指标和健康检查服务，也是未单独配置features的监控配置的默认值
     let features = config.features.clone().unwrap_or_else(|| {
-        warn!("配置文件中未找到 'features' 部分，将使用默认特性集。");
-        Features {
-            basic_transaction_monitoring: true,
-            advanced_event_detection: true,
-            token_transaction_monitoring: true,
-            account_monitoring: true,
-            log_to_file: false,
-            log_file_path: "".to_string(),
-            enable_cache: true,
-            cpi_log_json: false,
-            cpi_log_json_dir: "logs/cpi_json".to_string(),
-            cpi_log_json_max_files: 30,
+        if config.monitors.is_empty() {
+            warn!("配置文件中未找到 'features' 部分，将使用默认特性集。");
         }
+        Features::fallback()
     });
     
     let redis_client = Arc::new(redis::Client::open(config.redis_url.as_str()).map_err(|e| {
-        error!("[Redis] 连接 Redis 失败 ({}): {}", config.redis_url, e);
+        error!(target: log_targets::REDIS, "[Redis] 连接 Redis 失败 ({}): {}", config.redis_url, e);
         anyhow::anyhow!("[Redis] 连接 Redis 失败: {}", e)
     })?);
-    info!("[Redis] 已连接到: {}", config.redis_url);
+    info!(target: log_targets::REDIS, "[Redis] 已连接到: {}", config.redis_url);
     
-    let pump_idl = config.load_pump_idl()?;
+    let programs = Arc::new(config.load_programs()?);
+    let commitment = config.commitment_level()?;
     let token_idl = config.load_token_idl()?;
     
+    // 账户监控仍然只针对PumpFun的BondingCurve账户
     let program_id = config.pump_program_id.as_deref().unwrap_or(PUMP_PROGRAM_ID);
+
+    // 加载mint->creator映射，并按配置的间隔检查文件变化
+    let creator_map = Arc::new(CreatorMap::load(config.creator_map_path.as_deref())?);
+    Arc::clone(&creator_map).spawn_hot_reload(Duration::from_secs(
+        config.creator_map_reload_secs.unwrap_or(DEFAULT_CREATOR_MAP_RELOAD_SECS),
+    ));
     
     // 输出配置信息
-    info!("正在监听地址: {:?}", config.monitored_addresses);
-    info!("PumpFun 程序 ID: {}", program_id);
-    info!("功能配置:");
-    info!("  - 基本交易监控: {}", features.basic_transaction_monitoring);
-    info!("  - 高级事件检测: {}", features.advanced_event_detection);
-    info!("  - Token交易监控: {}", features.token_transaction_monitoring);
-    log::debug!("  - 账户监控: {}", features.account_monitoring);
-    info!("  - 记录到文件: {}", features.log_to_file);
-    info!("  - 启用缓存: {}", features.enable_cache);
-    info!("  - CPI日志JSON: {}", features.cpi_log_json);
-    if features.cpi_log_json {
-        info!("  - CPI日志JSON目录: {}", features.cpi_log_json_dir);
-        info!("  - 最大文件数: {}", features.cpi_log_json_max_files);
+    for program in programs.iter() {
+        info!("监控程序: {} ({}), 解码方式: {:?}", program.name, program.program_id, program.decoder);
+    }
+    info!("确认级别: {:?}", commitment);
+    if let Some(port) = features.metrics_port {
+        info!("指标服务端口: {}", port);
+    }
+    if let Some(port) = features.health_port {
+        info!("健康检查服务端口: {}", port);
+    }
+    if let Some(secs) = features.heartbeat_secs {
+        info!("心跳日志间隔: {} 秒", secs);
+    }
+    
+    for program in programs.iter().filter(|p| !p.idls.is_empty()) {
+        log::debug!("已加载 {} IDL 文件", program.name);
+        for version in program.idls.versions() {
+            info!("[{}] IDL版本: {}", program.name, version.name);
+        }
+    }
+    
+    if token_idl.is_some() {
+        log::debug!("已加载 Token IDL 文件");
+    }
+    
+    // 按需查询曲线账户的RPC客户端，由所有监控配置的缓存共享
+    let account_rpc = config
+        .rpc_url
+        .clone()
+        .map(|url| {
+            AccountRpc::new(
+                url,
+                config.rpc_max_requests_per_sec.unwrap_or(DEFAULT_RPC_MAX_REQUESTS_PER_SEC),
+                Duration::from_millis(config.rpc_wait_timeout_ms.unwrap_or(DEFAULT_RPC_WAIT_TIMEOUT_MS)),
+            )
+            .map(Arc::new)
+        })
+        .transpose()?;
+    if let Some(rpc) = &account_rpc {
+        info!(
+            "[RPC] 缓存中没有曲线账户数据时从 {} 查询（每秒最多 {} 次，最多等待 {:?}）",
+            rpc.url(), rpc.max_requests_per_sec(), rpc.wait_timeout()
+        );
+    }
+
+    if !config.monitors.is_empty() {
+        if !config.monitored_addresses.is_empty()
+            || !config.watched_mints.is_empty()
+            || !config.signer_allowlist.is_empty()
+            || !config.signer_denylist.is_empty()
+            || !config.alerts.is_empty()
+        {
+            warn!("已配置 [[monitors]]，顶层的 monitored_addresses、watched_mints、signer_allowlist/signer_denylist 和 alerts 不再生效");
+        }
+        info!("共 {} 个监控配置，共享gRPC端点和Redis", config.monitors.len());
+    }
+    let mut monitors = Vec::new();
+    for profile in config.monitor_profiles() {
+        monitors.push(MonitorRuntime::start(profile, &redis_client, account_rpc.clone()).await?);
+    }
+    
+    // 启动Prometheus指标服务（如果配置了端口），缓存统计使用第一个启用缓存的监控配置
+    if let Some(port) = features.metrics_port {
+        let cache_clone = monitors.iter().find_map(|monitor| monitor.cache.clone());
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve(port, cache_clone).await {
+                error!("[Metrics] 指标服务错误: {}", e);
+            }
+        });
     }
     
-    if pump_idl.is_some() {
-        log::debug!("已加载 PumpFun IDL 文件");
+    // 启动健康检查服务（如果配置了端口）
+    if let Some(port) = features.health_port {
+        let stale_secs = features.health_stale_secs.unwrap_or(DEFAULT_HEALTH_STALE_SECS);
+        tokio::spawn(async move {
+            if let Err(e) = health::serve(port, stale_secs).await {
+                error!("[Health] 健康检查服务错误: {}", e);
+            }
+        });
+    }
+
+    // 空闲时定期输出心跳日志（如果配置了间隔），缓存统计与指标服务使用同一个监控配置
+    if let Some(secs) = features.heartbeat_secs.filter(|secs| *secs > 0) {
+        let cache_clone = monitors.iter().find_map(|monitor| monitor.cache.clone());
+        tokio::spawn(metrics::heartbeat(Duration::from_secs(secs), cache_clone));
+    }
+
+    let program_ids: Vec<String> = programs.iter().map(|p| p.program_id.to_string()).collect();
+
+    // 回放模式：不连接gRPC端点，录制文件中的交易更新按原顺序处理完毕后退出（多个监控配置依次回放同一文件）
+    if let Some(replay_path) = args.replay_path() {
+        if !replay_path.is_file() {
+            return Err(anyhow::anyhow!("录制文件不存在: {:?}", replay_path));
+        }
+        for monitor in monitors {
+            let profile = &monitor.profile;
+            if profile.features.account_monitoring {
+                info!("[{}] 回放模式只处理交易更新，不启动账户监控", profile.name);
+            }
+            // 单个工作任务按顺序解码，保证每次回放的输出一致
+            let mut replay_features = profile.features.clone();
+            replay_features.transaction_workers = Some(1);
+            let request_txn = args.get_txn_updates(profile, &program_ids, commitment, config.include_failed, None)?;
+            let (_shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+            geyser_subscribe(
+                TransactionSource::<InterceptorXToken>::Replay(replay_path.clone()),
+                request_txn,
+                Arc::clone(&programs),
+                token_idl.clone(),
+                &replay_features,
+                monitor.cache.clone(),
+                Arc::clone(&creator_map),
+                monitor.watched_mints,
+                monitor.trade_broadcaster,
+                monitor.cpi_log_writer,
+                monitor.signer_filter,
+                monitor.event_publisher,
+                None,
+                Arc::default(),
+                shutdown_rx,
+            ).await?;
+            flush_cache(monitor.cache.as_deref()).await;
+        }
+        info!("回放完成");
+        return Ok(());
+    }
+
+    // 录制原始交易更新（命令行参数优先于配置文件），只录制第一个监控配置的订阅
+    let capture_path = args
+        .capture_path()
+        .or_else(|| monitors[0].profile.features.capture_path.as_ref().map(PathBuf::from));
+    let capture = capture_path
+        .map(|path| {
+            UpdateCapture::spawn(&path, monitors[0].profile.features.capture_max_file_bytes.unwrap_or(update_file::DEFAULT_CAPTURE_FILE_BYTES))
+        })
+        .transpose()?;
+
+    let endpoints = Arc::new(EndpointPool::new(config.grpc_endpoint.to_vec(), config.grpc_tls.clone().unwrap_or_default(), config.grpc_x_token.clone())?);
+    if let Some(token) = endpoints.x_token() {
+        info!("[gRPC] 使用x-token认证: {}", mask_token(token));
+    }
+
+    // 回放起始槽位：命令行参数优先于配置文件
+    let mut from_slot = args.monitor_args().from_slot.or(config.from_slot);
+    if let Some(slot) = from_slot {
+        let (mut client, _) = args.connect_any(&endpoints).await?;
+        let current_slot = client.get_slot(Some(commitment)).await
+            .map_err(|e| anyhow::anyhow!("获取端点当前槽位失败，无法校验 from_slot: {}", e))?
+            .slot;
+        let slot = clamp_from_slot(slot, current_slot, config.from_slot_max_lag.unwrap_or(DEFAULT_FROM_SLOT_MAX_LAG))?;
+        info!("将从槽位 {} 开始回放（落后当前槽位 {} 个）", slot, current_slot - slot);
+        from_slot = Some(slot);
+    }
+    
+    info!("已连接到 gRPC 端点，开始监控...");
+
+    // 退出信号，收到SIGINT/SIGTERM后通知各监控任务停止
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let mut monitor_tasks = Vec::new();
+    let mut caches = Vec::new();
+
+    // 每个监控配置的两个监控模式同时启动，分别在不同的任务中运行
+    for (index, monitor) in monitors.into_iter().enumerate() {
+        let MonitorRuntime { profile, cache, cpi_log_writer, trade_broadcaster, event_publisher, watched_mints, signer_filter } = monitor;
+        let name = profile.name.clone();
+        let features = profile.features.clone();
+        caches.extend(cache.clone());
+
+        if features.basic_transaction_monitoring {
+            info!("[{}] 启用交易监控模式", name);
+            let mut request_txn = args.get_txn_updates(&profile, &program_ids, commitment, config.include_failed, from_slot)?;
+            let progress = Arc::new(StreamProgress::default());
+            let name = name.clone();
+            let programs_clone = Arc::clone(&programs);
+            let trade_broadcaster_clone = trade_broadcaster.clone();
+            let event_publisher_clone = event_publisher.clone();
+            let capture = capture.clone().filter(|_| index == 0);
+            let token_idl_clone = token_idl.clone();
+            let features_clone = features.clone();
+            let cache_clone = cache.clone();
+            let creator_map_clone = Arc::clone(&creator_map);
+            let mut shutdown_rx_clone = shutdown_rx.clone();
+            let endpoints_clone = Arc::clone(&endpoints);
+            let args_clone = args.clone();
+            
+            // 数据流断开时切换到下一个端点重连，直到收到退出信号
+            monitor_tasks.push(tokio::spawn(async move {
+                loop {
+                    request_txn.from_slot = progress.resume_from(from_slot);
+                    match args_clone.connect_any(&endpoints_clone).await {
+                        Ok((client_txn, connected)) => {
+                            let result = run_subscription(
+                                |stop| geyser_subscribe(
+                                    TransactionSource::Grpc(Box::new(client_txn)),
+                                    request_txn.clone(), 
+                                    Arc::clone(&programs_clone), 
+                                    token_idl_clone.clone(), 
+                                    &features_clone, 
+                                    cache_clone.clone(),
+                                    Arc::clone(&creator_map_clone),
+                                    Arc::clone(&watched_mints),
+                                    trade_broadcaster_clone.clone(),
+                                    cpi_log_writer.clone(),
+                                    Arc::clone(&signer_filter),
+                                    event_publisher_clone.clone(),
+                                    capture.clone(),
+                                    Arc::clone(&progress),
+                                    stop
+                                ),
+                                &endpoints_clone,
+                                connected,
+                                &mut shutdown_rx_clone,
+                            ).await;
+                            if let Err(e) = &result {
+                                error!("[{}] 交易监控错误: {}", name, e);
+                            }
+                            if *shutdown_rx_clone.borrow() {
+                                break;
+                            }
+                            // 数据流因错误断开时切换到下一个端点，被服务端正常关闭时重连当前端点
+                            let failed = progress.take_failed();
+                            if result.is_err() || failed {
+                                endpoints_clone.failover(connected);
+                            }
+                        }
+                        Err(e) => error!("[{}] 交易监控错误: {}", name, e),
+                    }
+                    warn!("[交易监控][{}] 数据流已断开，{}秒后重连", name, RECONNECT_DELAY_SECS);
+                    tokio::select! {
+                        _ = tokio::time::sleep(Duration::from_secs(RECONNECT_DELAY_SECS)) => {}
+                        _ = shutdown_rx_clone.changed() => break,
+                    }
+                }
+            }));
+        }
+        
+        if !profile.alerts.is_empty() && !features.account_monitoring {
+            warn!("[{}] 已配置价格提醒，但未启用账户监控，价格提醒不会生效", name);
+        }
+
+        if features.account_monitoring {
+            log::debug!("[{}] 启用账户监控模式", name);
+            // 订阅关注代币的曲线关联代币账户（获取实际代币余额）和Mint账户（获取代币精度）
+            let token_accounts: Vec<String> = profile.watched_mints.iter()
+                .filter_map(|mint| calculate_associated_bonding_curve_from_mint(mint))
+                .chain(profile.watched_mints.iter().cloned())
+                .collect();
+            if token_accounts.is_empty() {
+                log::debug!("[{}] 未配置 watched_mints，不订阅曲线关联代币账户和Mint账户", name);
+            }
+            let mut request_acct = args.get_account_updates(program_id, token_accounts, commitment, from_slot, &features)?;
+            let progress = Arc::new(StreamProgress::default());
+            let price_alerts = if profile.alerts.is_empty() {
+                None
+            } else {
+                Some(Arc::new(PriceAlerts::new(profile.alerts.clone())?)).filter(|alerts| !alerts.is_empty())
+            };
+            let features_clone = features.clone();
+            let cache_clone = cache.clone();
+            let creator_map_clone = Arc::clone(&creator_map);
+            let mut shutdown_rx_clone = shutdown_rx.clone();
+            let endpoints_clone = Arc::clone(&endpoints);
+            let args_clone = args.clone();
+            
+            // 数据流断开时切换到下一个端点重连，直到收到退出信号
+            monitor_tasks.push(tokio::spawn(async move {
+                loop {
+                    request_acct.from_slot = progress.resume_from(from_slot);
+                    match args_clone.connect_any(&endpoints_clone).await {
+                        Ok((client_acct, connected)) => {
+                            let result = run_subscription(
+                                |stop| geyser_subscribe_accounts(
+                                    client_acct, 
+                                    request_acct.clone(), 
+                                    &features_clone, 
+                                    cache_clone.clone(),
+                                    Arc::clone(&creator_map_clone),
+                                    trade_broadcaster.clone(),
+                                    price_alerts.clone(),
+                                    event_publisher.clone(),
+                                    Arc::clone(&progress),
+                                    stop
+                                ),
+                                &endpoints_clone,
+                                connected,
+                                &mut shutdown_rx_clone,
+                            ).await;
+                            if let Err(e) = &result {
+                                error!("[{}] 账户监控错误: {}", name, e);
+                            }
+                            if *shutdown_rx_clone.borrow() {
+                                break;
+                            }
+                            // 数据流因错误断开时切换到下一个端点，被服务端正常关闭时重连当前端点
+                            let failed = progress.take_failed();
+                            if result.is_err() || failed {
+                                endpoints_clone.failover(connected);
+                            }
+                        }
+                        Err(e) => error!("[{}] 账户监控错误: {}", name, e),
+                    }
+                    warn!("[账户监控][{}] 数据流已断开，{}秒后重连", name, RECONNECT_DELAY_SECS);
+                    tokio::select! {
+                        _ = tokio::time::sleep(Duration::from_secs(RECONNECT_DELAY_SECS)) => {}
+                        _ = shutdown_rx_clone.changed() => break,
+                    }
+                }
+            }));
+        }
+    }
+    
+    // 运行直到收到退出信号
+    wait_for_shutdown_signal().await;
+    info!("收到退出信号，正在停止监控...");
+    let _ = shutdown_tx.send(true);
+
+    let shutdown_timeout = Duration::from_secs(SHUTDOWN_TIMEOUT_SECS);
+    for task in monitor_tasks {
+        if tokio::time::timeout(shutdown_timeout, task).await.is_err() {
+            warn!("监控任务未能在 {} 秒内停止", SHUTDOWN_TIMEOUT_SECS);
+        }
+    }
+
+    for cache in &caches {
+        flush_cache(Some(cache)).await;
+    }
+
+    info!("已退出");
+    Ok(())
+}
+
+/// 单个监控配置运行所需的组件：各配置的缓存、CPI日志、推送和过滤相互独立，只共享gRPC端点和Redis
+struct MonitorRuntime {
+    profile: MonitorProfile,
+    cache: Option<Arc<TransactionCache>>,
+    cpi_log_writer: Option<CpiLogWriter<CpiLogFile>>,
+    trade_broadcaster: Option<TradeBroadcaster>,
+    event_publisher: Option<EventPublisher>,
+    watched_mints: Arc<HashSet<String>>,
+    signer_filter: Arc<SignerFilter>,
+}
+
+impl MonitorRuntime {
+    /// 按监控配置创建日志目录、CPI日志写入、缓存和推送服务，并输出配置信息
+    async fn start(
+        profile: MonitorProfile,
+        redis_client: &Arc<redis::Client>,
+        account_rpc: Option<Arc<AccountRpc>>,
+    ) -> anyhow::Result<Self> {
+        let name = profile.name.as_str();
+        let features = &profile.features;
+        let event_log_levels = features.event_log_levels()?;
+        if event_log_levels != EventLogLevels::default() {
+            info!("[{}] 事件日志级别: {:?}", name, event_log_levels);
+        }
+
+        // 输出配置信息
+        if profile.monitored_addresses.is_empty() && features.all_program_transactions {
+            warn!("[{}] 未配置监听地址，已开启 all_program_transactions：处理监控程序的全部交易（全市场），数据量以及CPU、Redis和日志负载远高于按地址监听", name);
+        } else if profile.monitored_addresses.is_empty() {
+            return Err(anyhow::anyhow!(
+                "[{}] 未配置监听地址：请配置 monitored_addresses，或在 [features] 中开启 all_program_transactions 处理全部交易", name
+            ));
+        } else {
+            info!("[{}] 正在监听地址: {:?}", name, profile.monitored_addresses);
+        }
+        if !profile.watched_mints.is_empty() {
+            info!("[{}] 只处理以下代币的买卖交易: {:?}", name, profile.watched_mints);
+        }
+        if !profile.signer_allowlist.is_empty() {
+            info!("[{}] 只输出以下签名者的买卖: {:?}", name, profile.signer_allowlist);
+        }
+        if !profile.signer_denylist.is_empty() {
+            info!("[{}] 以下签名者的买卖降为debug输出: {:?}", name, profile.signer_denylist);
+        }
+        info!("[{}] 功能配置:", name);
+        info!("  - 基本交易监控: {}", features.basic_transaction_monitoring);
+        info!("  - 高级事件检测: {}", features.advanced_event_detection);
+        info!("  - Token交易监控: {}", features.token_transaction_monitoring);
+        log::debug!("  - 账户监控: {}", features.account_monitoring);
+        info!("  - 记录到文件: {}", features.log_to_file);
+        info!("  - 启用缓存: {}", features.enable_cache);
+        info!("  - CPI日志JSON: {}", features.cpi_log_json);
+        if let Some(port) = features.api_port {
+            info!("  - 缓存查询服务端口: {}", port);
+        }
+        if let Some(port) = features.ws_port {
+            info!("  - WebSocket推送端口: {}", port);
+        }
+        if let Some(port) = features.ndjson_tcp_port {
+            info!("  - NDJSON交易流端口: {}", port);
+        }
+        if let Some(path) = &features.ndjson_unix_socket {
+            info!("  - NDJSON交易流套接字: {}", path);
+        }
+        if features.cpi_log_json {
+            info!("  - CPI日志JSON目录: {}", features.cpi_log_json_dir);
+            info!("  - 最大文件数: {}", features.cpi_log_json_max_files);
+            if let Some(max_bytes) = features.cpi_log_json_max_total_bytes {
+                info!("  - 最大总大小: {} 字节", max_bytes);
+            }
+            if let Some(max_age) = features.cpi_log_json_max_age_secs {
+                info!("  - 最长保留时间: {} 秒", max_age);
+            }
+        }
+
+        // 创建日志文件目录（如果启用了记录到文件）
+        if features.log_to_file {
+            let log_dir = std::path::Path::new(&features.log_file_path).parent()
+                .expect("无法获取日志文件目录");
+            if !log_dir.exists() {
+                fs::create_dir_all(log_dir)?;
+                info!("[{}] 创建日志目录: {:?}", name, log_dir);
+            }
+        }
+
+        // 打开CPI日志JSON目录并启动后台写入任务（如果启用）
+        let cpi_log_writer = if features.cpi_log_json && !features.cpi_log_json_dir.is_empty() {
+            let store = Arc::new(CpiLogStore::open(
+                &features.cpi_log_json_dir,
+                features.cpi_log_json_max_files,
+                features.cpi_log_json_max_total_bytes,
+                features.cpi_log_json_max_age_secs.map(Duration::from_secs),
+            )?);
+            Some(CpiLogWriter::spawn(
+                store,
+                features.cpi_log_json_pretty.unwrap_or(true),
+                features.cpi_log_mode.unwrap_or_default(),
+                features.json_case.unwrap_or_default(),
+            ))
+        } else {
+            None
+        };
+
+        // 创建缓存并启动清理任务
+        let cache = if features.enable_cache {
+            let cache = Arc::new(TransactionCache::new(
+                Arc::clone(redis_client),
+                features.seen_signature_capacity.unwrap_or(DEFAULT_SEEN_SIGNATURE_CAPACITY),
+                features.candle_interval_secs,
+                features.candle_redis,
+                features.redis_trade_format.unwrap_or_default(),
+                RedisKeys::new(features.redis_key_prefix.as_deref()),
+                features.sandwich_window_slots,
+                features.cpi_log_max_per_mint_per_minute.filter(|_| features.cpi_log_json),
+                features
+                    .volume_window_secs
+                    .or(features.volume_spike_sol.map(|_| DEFAULT_VOLUME_WINDOW_SECS))
+                    .map(|window_secs| VolumeTracker::new(window_secs, features.volume_spike_sol)),
+                CacheRetention::from_features(features),
+                account_rpc,
+            ));
+            match cache.load_learned_mappings(redis_client).await {
+                Ok((curves, vaults, mints)) => info!(
+                    "[映射] 从Redis恢复了 {} 条曲线->Mint映射, {} 条金库->创建者映射, {} 条Mint->创建者映射",
+                    curves, vaults, mints
+                ),
+                Err(e) => warn!("[映射] 从Redis恢复映射失败，将重新学习: {}", e),
+            }
+            let cache_clone = Arc::clone(&cache);
+            
+            // 启动缓存清理任务
+            tokio::spawn(async move {
+                let mut interval = interval(cache_clone.retention.cleanup_interval);
+                loop {
+                    interval.tick().await;
+                    cache_clone.cleanup();
+                    
+                    // 每10次清理（约100秒）输出一次统计信息
+                    let stats = cache_clone.get_stats();
+                    debug!("缓存统计: {} 个买入交易, {} 个卖出交易, {} 个账户数据, {} 个最新账户数据, {} 个最新储备数据",
+                        stats.buy_transactions, stats.sell_transactions, stats.account_data, stats.latest_account_data, stats.latest_reserves);
+                }
+            });
+
+            if !cache.redis_keys.prefix().is_empty() {
+                info!("[Redis] 写入的键使用前缀: {}:", cache.redis_keys.prefix());
+            }
+            if let Some(window_slots) = cache.sandwich.as_ref().map(SandwichDetector::window_slots) {
+                info!("[夹子检测] 检测 {} 个槽位内的疑似夹子交易", window_slots);
+            }
+            if let Some(per_minute) = cache.cpi_log_limiter.as_ref().map(MintRateLimiter::per_minute) {
+                info!("[CPI日志] 每个代币每分钟最多写入 {} 条CPI日志", per_minute);
+            }
+            if let Some(volume) = &cache.volume {
+                info!("[成交量] 统计每个代币最近 {} 秒的成交, 突增阈值: {:?} SOL", volume.window_secs(), volume.spike_threshold_sol());
+            }
+
+            // 每个K线周期检查一次，收盘期间没有新成交的代币的K线
+            if let Some(interval_secs) = cache.candles.as_ref().map(CandleAggregator::interval_secs) {
+                info!("[K线] 按 {} 秒周期聚合成交价格, 写入Redis: {}", interval_secs, features.candle_redis);
+                let cache_clone = Arc::clone(&cache);
+                tokio::spawn(async move {
+                    let mut ticker = interval(Duration::from_secs(interval_secs));
+                    loop {
+                        ticker.tick().await;
+                        cache_clone.close_stale_candles();
+                    }
+                });
+            }
+            
+            Some(cache)
+        } else {
+            None
+        };
+
+        // 启动缓存查询HTTP服务（如果配置了端口）
+        if let Some(port) = features.api_port {
+            match cache.clone() {
+                Some(cache_clone) => {
+                    tokio::spawn(async move {
+                        if let Err(e) = api::serve(port, cache_clone).await {
+                            error!("[API] 缓存查询服务错误: {}", e);
+                        }
+                    });
+                }
+                None => warn!("[API] 未启用缓存(enable_cache = false)，不启动缓存查询服务"),
+            }
+        }
+        
+        // 交易推送（WebSocket或NDJSON任一启用时创建广播）
+        let trade_broadcaster = (features.ws_port.is_some()
+            || features.ndjson_tcp_port.is_some()
+            || features.ndjson_unix_socket.is_some())
+        .then(TradeBroadcaster::new);
+        if let Some(broadcaster) = &trade_broadcaster {
+            // 启动WebSocket交易推送服务（如果配置了端口）
+            if let Some(port) = features.ws_port {
+                let broadcaster_clone = broadcaster.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = ws_server::serve(port, broadcaster_clone).await {
+                        error!("[WebSocket] 交易推送服务错误: {}", e);
+                    }
+                });
+            }
+            // 启动NDJSON交易流（TCP和/或Unix域套接字）
+            if let Some(port) = features.ndjson_tcp_port {
+                let broadcaster_clone = broadcaster.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = ndjson_sink::serve_tcp(port, broadcaster_clone).await {
+                        error!("[NDJSON] TCP交易流错误: {}", e);
+                    }
+                });
+            }
+            if let Some(path) = features.ndjson_unix_socket.clone() {
+                let broadcaster_clone = broadcaster.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = ndjson_sink::serve_unix(path, broadcaster_clone).await {
+                        error!("[NDJSON] Unix套接字交易流错误: {}", e);
+                    }
+                });
+            }
+        }
+        
+        // 交易和账户事件发布到NATS（如果配置了服务器地址）
+        let event_publisher = features.nats_url.clone().map(|url| {
+            let subject_prefix = features.nats_subject_prefix.clone().unwrap_or_else(|| DEFAULT_NATS_SUBJECT_PREFIX.to_string());
+            info!("[NATS] 事件将发布到 {} (主题: {}.buy/sell/create/account)", url, subject_prefix);
+            EventPublisher::spawn(url, subject_prefix)
+        });
+
+        let watched_mints = Arc::new(profile.watched_mints.iter().cloned().collect::<HashSet<String>>());
+        let signer_filter = Arc::new(SignerFilter::new(&profile.signer_allowlist, &profile.signer_denylist));
+
+        Ok(Self {
+            profile,
+            cache,
+            cpi_log_writer,
+            trade_broadcaster,
+            event_publisher,
+            watched_mints,
+            signer_filter,
+        })
+    }
+}
+
+/// 退出前写完Redis队列中的缓存数据并输出最终统计
+async fn flush_cache(cache: Option<&TransactionCache>) {
+    let Some(cache) = cache else {
+        return;
+    };
+    if !cache.flush_redis(Duration::from_secs(SHUTDOWN_TIMEOUT_SECS)).await {
+        warn!(target: log_targets::REDIS, "[Redis] 退出前未能写完所有缓存数据");
+    }
+    let stats = cache.get_stats();
+    info!("最终缓存统计: {} 个买入交易, {} 个卖出交易, {} 个账户数据, {} 个最新账户数据, {} 个最新储备数据",
+        stats.buy_transactions, stats.sell_transactions, stats.account_data, stats.latest_account_data, stats.latest_reserves);
+}
+
+// 按 query 子命令从Redis读取缓存的交易或储备并输出JSON
+async fn query_cache(query: &QueryCommand, config: &Config) -> anyhow::Result<()> {
+    let features = config.features.clone().unwrap_or_else(Features::fallback);
+    let reader = cache_reader::CacheReader::new(&config.redis_url, RedisKeys::new(features.redis_key_prefix.as_deref()))?;
+    let result = match query {
+        QueryCommand::Trade { signature } => serde_json::to_value(reader.get_trade(signature).await?)?,
+        QueryCommand::Reserves { mint } => serde_json::to_value(reader.get_latest_reserves(mint).await?)?,
+        QueryCommand::MintTrades { mint, limit } => {
+            let trades = reader.get_recent_trades(mint, *limit).await?;
+            if trades.is_empty() { Value::Null } else { serde_json::to_value(trades)? }
+        }
+    };
+    if result.is_null() {
+        eprintln!("Redis中没有对应的缓存数据（可能已过期）");
+        std::process::exit(1);
+    }
+    println!("{}", serde_json::to_string_pretty(&result)?);
+    Ok(())
+}
+
+/// 预检配置文件：逐项验证并打印结果，全部通过时返回true
+async fn check_config(args: &Args) -> bool {
+    fn report(ok: &mut bool, item: &str, result: anyhow::Result<String>) {
+        match result {
+            Ok(detail) => println!("[通过] {}: {}", item, detail),
+            Err(e) => {
+                *ok = false;
+                println!("[失败] {}: {}", item, e);
+            }
+        }
+    }
+
+    fn check_idl(path: &str) -> anyhow::Result<String> {
+        let content = fs::read_to_string(path).map_err(|e| anyhow::anyhow!("无法读取 {}: {}", path, e))?;
+        serde_json::from_str::<Idl>(&content).map_err(|e| anyhow::anyhow!("{} 不是有效的IDL: {}", path, e))?;
+        Ok(path.to_string())
+    }
+
+    println!("检查配置文件: {:?}", args.config);
+    let config = match Config::load(args.config.clone(), &args.feature_overrides) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("[失败] 配置文件: {}", e);
+            println!("检查未通过");
+            return false;
+        }
+    };
+    let mut ok = true;
+    report(&mut ok, "配置文件", Ok("解析成功".to_string()));
+
+    let profiles = config.monitor_profiles();
+    if !config.monitors.is_empty() {
+        let names: Vec<&str> = profiles.iter().map(|profile| profile.name.as_str()).collect();
+        report(&mut ok, "监控配置", Ok(names.join(", ")));
+    }
+    for profile in &profiles {
+        for address in &profile.monitored_addresses {
+            report(
+                &mut ok,
+                "监控地址",
+                Pubkey::from_str(address)
+                    .map(|_| address.clone())
+                    .map_err(|e| anyhow::anyhow!("{} 不是有效的地址: {}", address, e)),
+            );
+        }
+        for mint in &profile.watched_mints {
+            report(
+                &mut ok,
+                "关注代币",
+                Pubkey::from_str(mint)
+                    .map(|_| mint.clone())
+                    .map_err(|e| anyhow::anyhow!("{} 不是有效的地址: {}", mint, e)),
+            );
+        }
+    }
+
+    let mut idl_paths: Vec<&str> = config.pump_idl_path.iter().chain(config.token_idl_path.iter()).map(String::as_str).collect();
+    idl_paths.extend(config.pump_idl_versions.iter().map(|version| version.idl_path.as_str()));
+    for program in config.programs.iter().flatten() {
+        report(
+            &mut ok,
+            "程序ID",
+            Pubkey::from_str(&program.program_id)
+                .map(|_| program.program_id.clone())
+                .map_err(|e| anyhow::anyhow!("{} 不是有效的程序ID: {}", program.program_id, e)),
+        );
+        idl_paths.extend(program.idl_path.as_deref());
+        idl_paths.extend(program.idl_versions.iter().map(|version| version.idl_path.as_str()));
+    }
+    for path in idl_paths {
+        report(&mut ok, "IDL文件", check_idl(path));
+    }
+
+    report(&mut ok, "确认级别", config.commitment_level().map(|level| format!("{:?}", level)));
+    for profile in &profiles {
+        report(&mut ok, "事件日志级别", profile.features.event_log_levels().map(|levels| format!("{:?}", levels)));
+    }
+    report(
+        &mut ok,
+        "程序ID常量",
+        Constants::from_config(&config).map(|constants| format!("Token程序: {:?}", constants.token_program_ids)),
+    );
+    report(
+        &mut ok,
+        "创作者映射",
+        CreatorMap::load(config.creator_map_path.as_deref()).map(|_| {
+            config.creator_map_path.clone().unwrap_or_else(|| "使用内置映射".to_string())
+        }),
+    );
+
+    let redis_result = match redis::Client::open(config.redis_url.as_str()) {
+        Ok(client) => match tokio::time::timeout(Duration::from_secs(5), client.get_multiplexed_tokio_connection()).await {
+            Ok(Ok(_)) => Ok(format!("{} 连接成功", config.redis_url)),
+            Ok(Err(e)) => Err(anyhow::anyhow!("{} 连接失败: {}", config.redis_url, e)),
+            Err(_) => Err(anyhow::anyhow!("{} 连接超时", config.redis_url)),
+        },
+        Err(e) => Err(anyhow::anyhow!("{} 不是有效的Redis URL: {}", config.redis_url, e)),
+    };
+    report(&mut ok, "Redis", redis_result);
+
+    let endpoints = config.grpc_endpoint.to_vec();
+    let grpc_tls = config.grpc_tls.clone().unwrap_or_default();
+    if let Some(token) = &config.grpc_x_token {
+        report(&mut ok, "gRPC认证", Ok(format!("x-token {}", mask_token(token))));
+    }
+    if endpoints.is_empty() {
+        report(&mut ok, "gRPC端点", Err(anyhow::anyhow!("未配置 grpc_endpoint")));
+    }
+    for endpoint in endpoints {
+        let grpc_result = match args.connect(endpoint.clone(), &grpc_tls, config.grpc_x_token.as_deref()).await {
+            Ok(mut client) => client
+                .get_version()
+                .await
+                .map(|response| format!("{} 可访问 (版本: {})", endpoint, response.version))
+                .map_err(|e| anyhow::anyhow!("{} 请求失败: {}", endpoint, e)),
+            Err(e) => Err(anyhow::anyhow!("{} 连接失败: {}", endpoint, e)),
+        };
+        report(&mut ok, "gRPC端点", grpc_result);
+    }
+
+    println!("{}", if ok { "检查通过" } else { "检查未通过" });
+    ok
+}
+
+/// 订阅请求中监听的地址（不含监控程序ID，全市场模式下为空）
+fn monitored_addresses(request: &SubscribeRequest, programs: &[MonitoredProgram]) -> Vec<String> {
+    let Some(txn_filter) = request.transactions.get("client") else {
+        return vec![];
+    };
+    txn_filter.account_include.iter()
+        .filter(|addr| !programs.iter().any(|p| p.program_id.to_string() == **addr))
+        .cloned()
+        .collect()
+}
+
+/// 交易的账户中是否包含监听的地址
+fn involves_monitored_address(account_keys: &AccountKeys, monitored_addresses: &[String]) -> bool {
+    account_keys.iter().any(|key| monitored_addresses.contains(&bs58::encode(key).into_string()))
+}
+
+/// 校验回放起始槽位：不能大于端点当前槽位；落后超过 max_lag 个槽位时已不在端点保留范围内，
+/// 改为从保留范围的起点回放
+fn clamp_from_slot(slot: u64, current_slot: u64, max_lag: u64) -> anyhow::Result<u64> {
+    if slot > current_slot {
+        return Err(anyhow::anyhow!("from_slot {} 大于端点当前槽位 {}", slot, current_slot));
+    }
+    let oldest_slot = current_slot.saturating_sub(max_lag);
+    if slot < oldest_slot {
+        warn!("from_slot {} 落后当前槽位 {} 超过 {} 个槽位，改为从槽位 {} 开始回放", slot, current_slot, max_lag, oldest_slot);
+        return Ok(oldest_slot);
+    }
+    Ok(slot)
+}
+
+/// 等待SIGINT（Ctrl-C）或SIGTERM
+async fn wait_for_shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = sigterm.recv() => {}
+                }
+                return;
+            }
+            Err(e) => warn!("无法注册SIGTERM处理器: {}", e),
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// 监控管道解码出的事件，由调用方决定如何处理（写缓存、日志、推送等）
+enum MonitorEvent {
+    Buy(TradeEvent),
+    Sell(TradeEvent),
+    /// PumpFun新代币创建，与买卖交易使用相同的输出
+    Create(TradeEvent),
+    /// 通过IDL解码的其他监控程序指令
+    Instruction(InstructionEvent),
+    TokenInstruction(TokenInstructionEvent),
+    AccountUpdate(AccountUpdateEvent),
+    /// PumpFun管理指令（Withdraw、SetParams）
+    Admin(AdminEvent),
+}
+
+/// PumpFun买卖交易
+struct TradeEvent {
+    entry: CpiLogEntry,    // 类型化的交易条目
+    raw: Value,            // 指令解析出的原始数据（账户、参数、费用等）
+    log_message: String,   // 多行文本格式的交易日志
+    enhanced_data: String, // 附加创作者金库地址后的交易数据，用于缓存
+    slot: u64,             // 交易所在槽位
+    is_monitored: bool,    // 是否涉及监听的地址
+    json: OnceLock<String>, // 按json_case序列化的交易条目，由第一个需要的输出生成
+}
+
+impl TradeEvent {
+    /// 单行JSON格式的交易条目，所有输出共用一次序列化结果（同一事件处理器的json_case不变）
+    fn json(&self, json_case: JsonCase) -> &str {
+        self.json.get_or_init(|| json_case.to_json(&self.entry))
+    }
+}
+
+/// 通过IDL解码的指令
+struct InstructionEvent {
+    program: String, // 监控程序名称
+    signature: String,
+    instruction: DecodedInstruction,
+    failed: bool,       // 交易是否执行失败
+    is_monitored: bool, // 是否涉及监听的地址
+}
+
+/// PumpFun管理指令
+struct AdminEvent {
+    action: AdminAction,
+    authority: String, // 发起指令的管理账户（签名者）
+    signature: String,
+    slot: u64,
+    time: String,
+    failed: bool, // 交易是否执行失败
+}
+
+enum AdminAction {
+    /// 代币完成绑定曲线后，迁移账户提取曲线中的流动性
+    Withdraw { mint: String, bonding_curve: Option<String> },
+    /// 修改协议费率、费用接收者和新代币的初始储备
+    SetParams(SetParamsIxArgs),
+}
+
+impl AdminEvent {
+    /// 日志和输出中的事件名称
+    fn label(&self) -> &'static str {
+        match self.action {
+            AdminAction::Withdraw { .. } => "WITHDRAW",
+            AdminAction::SetParams(_) => "PARAMS_CHANGE",
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let mut value = match &self.action {
+            AdminAction::Withdraw { mint, bonding_curve } => json!({
+                "type": "withdraw",
+                "mint": mint,
+                "bonding_curve": bonding_curve,
+            }),
+            AdminAction::SetParams(params) => json!({
+                "type": "params_change",
+                "fee_recipient": params.fee_recipient.to_string(),
+                "fee_basis_points": params.fee_basis_points,
+                "initial_virtual_token_reserves": params.initial_virtual_token_reserves,
+                "initial_virtual_sol_reserves": params.initial_virtual_sol_reserves,
+                "initial_real_token_reserves": params.initial_real_token_reserves,
+                "token_total_supply": params.token_total_supply,
+            }),
+        };
+        value["authority"] = json!(self.authority);
+        value["signature"] = json!(self.signature);
+        value["slot"] = json!(self.slot);
+        value["time"] = json!(self.time);
+        value["failed"] = json!(self.failed);
+        value
+    }
+}
+
+/// 涉及监听地址的Token程序指令
+struct TokenInstructionEvent {
+    instruction: Option<SerializableTokenInstruction>, // Token-2022扩展指令只记录名称，为None
+    log_message: String,
+}
+
+/// 解码后的PumpFun账户更新
+struct AccountUpdateEvent {
+    pubkey: String,
+    slot: u64,
+    account: DecodedAccount,
+    summary: String, // 账户信息文本，用于缓存和日志
+}
+
+/// 把事件通道包装为Stream
+fn receiver_stream(rx: tokio::sync::mpsc::Receiver<MonitorEvent>) -> impl Stream<Item = MonitorEvent> {
+    futures::stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|event| (event, rx)) })
+}
+
+/// 交易解码，由工作任务并行执行；缓存只用于读取储备、精度等补充信息
+struct TransactionProcessor {
+    programs: Arc<Vec<MonitoredProgram>>,
+    features: Features,
+    cache: Option<Arc<TransactionCache>>,
+    creator_map: Arc<CreatorMap>,
+    watched_mints: Arc<HashSet<String>>,
+}
+
+impl TransactionProcessor {
+    /// 解码单笔交易中的所有指令（含内部指令），返回解码出的事件
+    #[allow(clippy::too_many_lines)]
+    fn process(
+        &self,
+        txn: SubscribeUpdateTransactionInfo,
+        slot: u64,
+        signature: String,
+        account_keys: AccountKeys,
+        is_monitored_address_involved: bool,
+    ) -> Vec<MonitorEvent> {
+        let Self {
+            programs,
+            features,
+            cache,
+            creator_map,
+            watched_mints,
+        } = self;
+        let mut events = Vec::new();
+
+        // 失败的交易（仅在 include_failed 开启时会收到）在日志中标记
+        let is_failed = txn.meta.as_ref().is_some_and(|meta| meta.err.is_some());
+        let status_marker = if is_failed { "[FAILED] " } else { "" };
+
+        // 交易在区块中的序号，与槽位、指令序号一起确定事件顺序
+        let transaction_index = txn.index;
+
+        // 签名者的余额变化，用于计算买入的实际SOL花费
+        let balance_changes = BalanceChanges::new(txn.meta.as_ref());
+        // 程序发出的TradeEvent，提供买卖的实际成交金额、成交后储备和费用
+        let mut trade_events = TradeEvents::from_meta(txn.meta.as_ref());
+        // 先记录代币余额中的代币账户所有者，同一交易中的买卖即可据此找到钱包
+        if let (Some(cache_ref), Some(meta)) = (cache, txn.meta.as_ref()) {
+            cache_ref.remember_token_balance_owners(meta, &account_keys);
+        }
+
+        // 内部(CPI)指令，用于捕获通过聚合器/路由程序发起的交易
+        let inner_instructions = txn.meta
+            .map(|meta| meta.inner_instructions)
+            .unwrap_or_default();
+
+        // 处理 PumpFun 交易
+        if let Some(raw_transaction) = txn.transaction {
+            if let Some(raw_message) = raw_transaction.message {
+                // 遍历顶层指令和内部指令，内部指令附带外层指令的程序ID
+                let all_instructions = flatten_instructions(&raw_message, &account_keys, &inner_instructions);
+                // 交易的计算单元上限和价格，用于估算买卖支付的优先费
+                let priority_fee = PriorityFee::from_message(&raw_message, &account_keys);
+                let fee_payer = account_keys.fee_payer().unwrap_or_else(|| "未知".to_string());
+                for (instruction_index, (instruction, parent_program_id)) in all_instructions.iter().enumerate() {
+                    let parent_program_id = *parent_program_id;
+                    let order = EventOrder {
+                        slot,
+                        transaction_index,
+                        instruction_index: instruction_index as u32,
+                    };
+                    // 获取程序 ID
+                    let program_id_index = instruction.program_id_index as usize;
+                    if let Some(program_id_bytes) = account_keys.get(program_id_index) {
+
+                        // 检查是否是配置的监控程序，并按程序选择解码器
+                        if let Some(program) = programs.iter().find(|p| p.program_id.as_ref() == program_id_bytes.as_slice()) {
+                            match program.decoder {
+                                ProgramDecoder::Idl => {
+                                    if let Some(decoded_instruction) = decode_idl_instruction(program, instruction, &account_keys, parent_program_id, slot) {
+                                        events.push(MonitorEvent::Instruction(InstructionEvent {
+                                            program: program.name.clone(),
+                                            signature: signature.clone(),
+                                            instruction: decoded_instruction,
+                                            failed: is_failed,
+                                            is_monitored: is_monitored_address_involved,
+                                        }));
+                                    }
+                                }
+                                // 尝试解析PumpFun指令
+                                ProgramDecoder::Pump => match PumpProgramIx::deserialize(&instruction.data) {
+                                    Ok(decoded_ix) => {
+                                        let now = Utc::now();
+                                        let formatted_time = format_time(now);
+
+                                        // 根据是否涉及监控地址以及功能开关选择分析方式
+                                        let _advanced_analysis = features.advanced_event_detection;
+
+                                        // 使用官方高效处理方式，创建DecodedInstruction
+                                        // 按交易槽位和指令的账户数选择IDL版本
+                                        if let Some(idl) = program.idls.select(slot, &decoded_ix.name(), instruction.accounts.len()) {
+                                            // 创建AccountMeta列表
+                                            let Some(account_metas) = build_account_metas(instruction, &account_keys) else {
+                                                log::debug!("指令包含无效的账户地址，跳过, 签名: {}", signature);
+                                                continue;
+                                            };
+
+                                            // 使用InstructionAccountMapper映射账户，IDL与指令不匹配时使用生成的账户名称，仍然记录该指令
+                                            let (mapped_accounts, mapping_error) = idl.map_accounts_lenient(&account_metas, &decoded_ix.name());
+                                            if let Some(e) = mapping_error {
+                                                log::debug!("映射账户不完整, 签名: {}: {}", signature, e);
+                                            }
+                                            {
+                                                let decoded_instruction = DecodedInstruction {
+                                                    name: decoded_ix.name(),
+                                                    accounts: mapped_accounts,
+                                                    data: match decoded_ix {
+                                                        PumpProgramIx::Buy(ref buy_args) => {
+                                                            // 手动创建Buy指令的JSON对象
+                                                            json!({
+                                                                "buy": {
+                                                                    "amount": buy_args.amount,
+                                                                    "max_sol_cost": buy_args.max_sol_cost
+                                                                }
+                                                            })
+                                                        },
+                                                        PumpProgramIx::Sell(ref sell_args) => {
+                                                            // 手动创建Sell指令的JSON对象
+                                                            json!({
+                                                                "sell": {
+                                                                    "amount": sell_args.amount,
+                                                                    "min_sol_output": sell_args.min_sol_output
+                                                                }
+                                                            })
+                                                        },
+                                                        PumpProgramIx::Create(ref create_args) => {
+                                                            json!({
+                                                                "create": {
+                                                                    "name": create_args.name,
+                                                                    "symbol": create_args.symbol,
+                                                                    "uri": create_args.uri
+                                                                }
+                                                            })
+                                                        },
+                                                        _ => {
+                                                            // 其他指令按IDL中的参数定义解码，失败时只提供名称
+                                                            let args = idl.decode_args(&decoded_ix.name(), instruction.data.get(8..).unwrap_or_default())
+                                                                .unwrap_or_else(|e| {
+                                                                    log::debug!("按IDL解码指令({})参数失败: {}", decoded_ix.name(), e);
+                                                                    json!({})
+                                                                });
+                                                            json!({ decoded_ix.name(): args })
+                                                        }
+                                                    },
+                                                    program_id: program.program_id,
+                                                    parent_program_id,
+                                                };
+
+                                                // 从映射后的账户中提取mint和签名者地址
+                                                let accounts = &decoded_instruction.accounts;
+                                                let mint_address = accounts.iter()
+                                                    .find(|account| account.name == "mint")
+                                                    .map_or_else(|| "未知".to_string(), |account| account.pubkey.to_string());
+                                                // 签名标志取自交易（映射后的账户按IDL声明，user总是标记为签名者）
+                                                let signer_address = accounts.iter()
+                                                    .zip(&account_metas)
+                                                    .find(|(account, meta)| account.name == "user" && meta.is_signer)
+                                                    .map(|(account, _)| account.pubkey.to_string())
+                                                    .or_else(|| {
+                                                        // 指令中的user不是签名者（如由路由程序代为调用）时，通过用户的关联代币账户找到所属钱包
+                                                        let token_account = accounts.iter().find(|account| account.name == "associatedUser")?;
+                                                        let wallet = cache.as_ref()?.token_account_wallet(&token_account.pubkey.to_string(), &mint_address)?;
+                                                        log::debug!("通过代币账户({})找到钱包: {}, 签名: {}", token_account.pubkey, wallet, signature);
+                                                        Some(wallet)
+                                                    })
+                                                    .unwrap_or_else(|| "未知".to_string());
+                                                // TradeEvent中的user是指令的user账户，由路由程序代为调用时与签名者不同
+                                                let instruction_user = accounts.iter()
+                                                    .find(|account| account.name == "user")
+                                                    .map_or_else(|| signer_address.clone(), |account| account.pubkey.to_string());
+
+                                                // 配置了关注的代币列表时，跳过其他代币的买卖交易
+                                                let is_trade = matches!(decoded_ix, PumpProgramIx::Buy(_) | PumpProgramIx::Sell(_));
+                                                if is_trade && !watched_mints.is_empty() && !watched_mints.contains(&mint_address) {
+                                                    log::debug!("跳过未关注代币({})的交易, 签名: {}", mint_address, signature);
+                                                    continue;
+                                                }
+
+                                                // 按指令类型处理
+                                                match decoded_ix {
+                                                    PumpProgramIx::Buy(ref buy_args) => {
+                                                        Metrics::inc(&METRICS.buys);
+                                                        // 实际花费优先取TradeEvent（失败的交易没有实际成交），没有事件时取签名者的余额变化（扣除新建代币账户的租金），
+                                                        // 都无法确定时不输出实际花费，也不计算滑点
+                                                        let trade_event = (!is_failed)
+                                                            .then(|| trade_events.take(&mint_address, true, &instruction_user))
+                                                            .flatten();
+                                                        let measured_sol_cost = trade_event.as_ref().map(PumpTradeEvent::sol_cost).or_else(|| {
+                                                            account_keys
+                                                                .position(&signer_address)
+                                                                .filter(|_| !is_failed)
+                                                                .and_then(|index| balance_changes.spent_on_trade(index))
+                                                        });
+                                                        let token_amount = trade_event.as_ref().map_or(buy_args.amount, |event| event.token_amount);
+                                                        let slippage = measured_sol_cost
+                                                            .and_then(|cost| slippage_pct(cost, buy_args.max_sol_cost, true));
+                                                        let log_message = format!(
+                                                            "{}TYPE: Buy\nMINT: {}\nTOKEN AMOUNT: {}\n{}MAX SOL COST: {}\n{}TIME: {}\nSLOT: {}\nSIGNATURE: {}\n签名者地址: {}\n手续费支付者: {}",
+                                                            status_marker,
+                                                            mint_address,
+                                                            token_amount,
+                                                            measured_sol_cost
+                                                                .map(|cost| format!("SOL COST: {}\n", Lamports(cost)))
+                                                                .unwrap_or_default(),
+                                                            Lamports(buy_args.max_sol_cost),
+                                                            format_slippage_line(slippage) + &priority_fee.log_line(),
+                                                            formatted_time,
+                                                            slot,
+                                                            signature,
+                                                            signer_address,
+                                                            fee_payer
+                                                        );
+
+                                                        // 初始化增强版日志信息
+                                                        let mut enhanced_data = log_message.clone();
+
+                                                        // 如果启用缓存，将Buy交易缓存起来
+                                                        // 注意: 由于下面会更新包含creator_vault的交易信息，所以这里不再缓存
+                                                        // 移除以下缓存代码以避免重复调用
+                                                        // if let Some(cache_ref) = &cache {
+                                                        //    cache_ref.cache_buy_transaction(&signature, log_message.clone(), Some(&mint_address));
+                                                        // }
+
+                                                        // 处理买入交易的虚拟储备、价格和缓存
+                                                        // 计算曲线账户
+                                                        let curve_account = calculate_curve_account_from_mint(&mint_address);
+                                                        if let (Some(cache_ref), Some(curve_account_str)) = (&cache, &curve_account) {
+                                                            cache_ref.remember_curve_mint(curve_account_str, &mint_address);
+                                                        }
+
+                                                        // 获取虚拟储备信息
+                                                        let mut virtual_token_reserves = None;
+                                                        let mut virtual_sol_reserves = None;
+                                                        let mut price = None;
+                                                        let mut creator = None;
+                                                        let mut real_reserves = None;
+                                                        let mut token_total_supply = None;
+
+                                                        // 如果有曲线账户，尝试获取曲线账户数据和储备信息
+                                                        if let Some(ref curve_account_str) = curve_account {
+                                                            if let Some(cache_ref) = &cache {
+                                                                // TradeEvent已包含成交后的储备和创建者，此时只读内存缓存，不阻塞等待Redis/RPC查询
+                                                                let curve_data = if trade_event.is_some() {
+                                                                    cache_ref.get_account_data(curve_account_str)
+                                                                } else {
+                                                                    cache_ref.get_account_data_or_fetch(curve_account_str)
+                                                                };
+                                                                if let Some(curve_data) = curve_data {
+                                                                    if let Some((vt, vs)) = extract_reserves_from_account_data(&curve_data) {
+                                                                        virtual_token_reserves = Some(vt);
+                                                                        virtual_sol_reserves = Some(vs);
+                                                                        price = Some(calculate_price(vt, vs, cache_ref.token_decimals(&mint_address)));
+                                                                    }
+
+                                                                    // 尝试获取代币创建者信息
+                                                                    creator = extract_creator_from_account_data(&curve_data, creator_map, Some(cache_ref));
+                                                                    real_reserves = extract_real_reserves_from_account_data(&curve_data);
+                                                                    token_total_supply = extract_token_total_supply_from_account_data(&curve_data);
+                                                                }
+                                                            }
+                                                        }
+                                                        // TradeEvent中是本笔成交后的储备，比缓存中的账户数据更准确
+                                                        if let Some(event) = &trade_event {
+                                                            virtual_token_reserves = Some(event.virtual_token_reserves);
+                                                            virtual_sol_reserves = Some(event.virtual_sol_reserves);
+                                                            price = Some(calculate_price(
+                                                                event.virtual_token_reserves,
+                                                                event.virtual_sol_reserves,
+                                                                cache.as_ref().map_or(DEFAULT_TOKEN_DECIMALS, |cache_ref| cache_ref.token_decimals(&mint_address)),
+                                                            ));
+                                                            if let (Some(token), Some(sol)) = (event.real_token_reserves, event.real_sol_reserves) {
+                                                                real_reserves = Some((token, sol));
+                                                            }
+                                                            if event.creator.is_some() {
+                                                                creator = event.creator.clone();
+                                                            }
+                                                        }
+                                                        // 曲线账户数据中没有创建者时，使用从Create指令学到的映射
+                                                        if creator.is_none() {
+                                                            creator = cache.as_ref().and_then(|cache_ref| cache_ref.get_mint_creator(&mint_address));
+                                                        }
+
+                                                        // 提取原始交易数据以获取金库地址，无论是否启用CPI日志
+                                                        let global_state = cache.as_ref().and_then(|cache_ref| cache_ref.get_global_state());
+                                                        let raw_log_data = extract_raw_cpi_log_data(
+                                                            &decoded_ix,
+                                                            &signature,
+                                                            order,
+                                                            accounts,
+                                                            &mint_address,
+                                                            &signer_address,
+                                                            &formatted_time,
+                                                            &curve_account,
+                                                            virtual_token_reserves,
+                                                            virtual_sol_reserves,
+                                                            creator_map,
+                                                            cache.as_deref(),
+                                                            global_state.as_ref()
+                                                        );
+
+                                                        // 提取金库地址并更新日志信息 - 这步是关键，无论是否保存CPI日志都需要
+                                                        if let Some(creator_vault) = raw_log_data.get("creator_vault").and_then(|v| v.as_str()) {
+                                                            // 检查是否已包含金库地址信息
+                                                            if !enhanced_data.contains("创作者金库地址:") {
+                                                                enhanced_data.push_str(&format!("\n\n创作者金库地址:\n{}", creator_vault));
+                                                                info!(target: log_targets::BUY, "[金库] Buy交易({})的创作者金库地址: {}", signature, creator_vault);
+                                                            }
+                                                        } else {
+                                                            // 如果从raw_log_data中未找到，尝试从原始日志中提取
+                                                            if let Some(cv) = extract_creator_vault_from_log(log_message.as_str()) {
+                                                                // 检查是否已包含金库地址信息
+                                                                if !enhanced_data.contains("创作者金库地址:") {
+                                                                    enhanced_data.push_str(&format!("\n\n创作者金库地址:\n{}", cv));
+                                                                    info!(target: log_targets::BUY, "[金库] Buy交易({})的创作者金库地址: {}", signature, cv);
+                                                                }
+                                                            }
+                                                        }
+
+                                                        // 交易日志条目，用于结构化日志和WebSocket推送
+                                                        let mut trade_entry = CpiLogEntry::for_trade(
+                                                            "Buy",
+                                                            &mint_address,
+                                                            token_amount,
+                                                            Lamports(buy_args.max_sol_cost).to_sol(),
+                                                            &formatted_time,
+                                                            &signature,
+                                                            order,
+                                                            &signer_address,
+                                                            price,
+                                                            virtual_token_reserves,
+                                                            virtual_sol_reserves,
+                                                            curve_account.clone(),
+                                                            creator.clone(),
+                                                        );
+                                                        trade_entry.failed = is_failed;
+                                                        trade_entry.fee_payer = Some(fee_payer.clone());
+                                                        trade_entry.apply_priority_fee(&priority_fee);
+                                                        trade_entry.token_amount_ui = cache.as_ref()
+                                                            .and_then(|cache_ref| cache_ref.get_mint_decimals(&mint_address))
+                                                            .map(|decimals| token_amount as f64 / 10f64.powi(i32::from(decimals)));
+                                                        trade_entry.timestamp = Some(now.timestamp_millis());
+                                                        trade_entry.real_token_reserves = real_reserves.map(|(token, _)| token);
+                                                        trade_entry.real_sol_reserves = real_reserves.map(|(_, sol)| sol);
+                                                        trade_entry.actual_sol_cost = measured_sol_cost.map(|cost| Lamports(cost).to_sol());
+                                                        trade_entry.slippage_pct = slippage;
+                                                        if !is_failed {
+                                                            trade_entry.price_impact = trade_event.as_ref().and_then(trade_price_impact);
+                                                        }
+                                                        if let Some(fees) = pump_trade_fees(&decoded_ix, global_state.as_ref()) {
+                                                            trade_entry.apply_fees(&fees, global_state.as_ref());
+                                                        }
+                                                        if let Some(event) = &trade_event {
+                                                            trade_entry.apply_trade_event(event);
+                                                        }
+                                                        let market_value = MarketValue::estimate(
+                                                            price,
+                                                            token_total_supply,
+                                                            trade_entry.real_token_reserves,
+                                                            global_state.as_ref(),
+                                                            cache.as_ref().map_or(DEFAULT_TOKEN_DECIMALS, |cache_ref| cache_ref.token_decimals(&mint_address)),
+                                                        );
+                                                        trade_entry.apply_market_value(market_value.as_ref());
+                                                        let log_message = log_message + &format_market_value_line(market_value.as_ref());
+
+                                                        events.push(MonitorEvent::Buy(TradeEvent {
+                                                            entry: trade_entry,
+                                                            raw: raw_log_data,
+                                                            log_message,
+                                                            enhanced_data,
+                                                            slot,
+                                                            is_monitored: is_monitored_address_involved,
+                                                            json: OnceLock::new(),
+                                                        }));
+                                                    },
+                                                    PumpProgramIx::Sell(ref sell_args) => {
+                                                        Metrics::inc(&METRICS.sells);
+                                                        // 实际收到的SOL优先取TradeEvent，没有事件时取签名者的余额变化，失败或都无法确定时不计算滑点
+                                                        let trade_event = (!is_failed)
+                                                            .then(|| trade_events.take(&mint_address, false, &instruction_user))
+                                                            .flatten();
+                                                        let actual_sol_output = trade_event.as_ref().map(PumpTradeEvent::sol_output).or_else(|| {
+                                                            account_keys
+                                                                .position(&signer_address)
+                                                                .filter(|_| !is_failed)
+                                                                .and_then(|index| balance_changes.received(index))
+                                                        });
+                                                        let token_amount = trade_event.as_ref().map_or(sell_args.amount, |event| event.token_amount);
+                                                        let slippage = actual_sol_output
+                                                            .and_then(|output| slippage_pct(output, sell_args.min_sol_output, false));
+                                                        let log_message = format!(
+                                                            "{}TYPE: Sell\nMINT: {}\nTOKEN AMOUNT: {}\nMIN SOL OUTPUT: {}\n{}{}TIME: {}\nSLOT: {}\nSIGNATURE: {}\n签名者地址: {}\n手续费支付者: {}",
+                                                            status_marker,
+                                                            mint_address,
+                                                            token_amount,
+                                                            Lamports(sell_args.min_sol_output),
+                                                            actual_sol_output
+                                                                .map(|output| format!("SOL OUTPUT: {}\n", Lamports(output)))
+                                                                .unwrap_or_default(),
+                                                            format_slippage_line(slippage) + &priority_fee.log_line(),
+                                                            formatted_time,
+                                                            slot,
+                                                            signature,
+                                                            signer_address,
+                                                            fee_payer
+                                                        );
+
+                                                        // 初始化增强版日志信息
+                                                        let mut enhanced_data = log_message.clone();
+
+                                                        // 如果启用缓存，将Sell交易缓存起来
+                                                        // 注意: 由于下面会更新包含creator_vault的交易信息，所以这里不再缓存
+                                                        // 移除以下缓存代码以避免重复调用
+                                                        // if let Some(cache_ref) = &cache {
+                                                        //    cache_ref.cache_sell_transaction(&signature, log_message.clone(), Some(&mint_address));
+                                                        // }
+
+                                                        // 处理卖出交易的虚拟储备、价格和缓存
+                                                        // 计算曲线账户
+                                                        let curve_account = calculate_curve_account_from_mint(&mint_address);
+                                                        if let (Some(cache_ref), Some(curve_account_str)) = (&cache, &curve_account) {
+                                                            cache_ref.remember_curve_mint(curve_account_str, &mint_address);
+                                                        }
+
+                                                        // 获取虚拟储备信息
+                                                        let mut virtual_token_reserves = None;
+                                                        let mut virtual_sol_reserves = None;
+                                                        let mut price = None;
+                                                        let mut creator = None;
+                                                        let mut real_reserves = None;
+                                                        let mut token_total_supply = None;
+
+                                                        // 如果有曲线账户，尝试获取曲线账户数据和储备信息
+                                                        if let Some(ref curve_account_str) = curve_account {
+                                                            if let Some(cache_ref) = &cache {
+                                                                // TradeEvent已包含成交后的储备和创建者，此时只读内存缓存，不阻塞等待Redis/RPC查询
+                                                                let curve_data = if trade_event.is_some() {
+                                                                    cache_ref.get_account_data(curve_account_str)
+                                                                } else {
+                                                                    cache_ref.get_account_data_or_fetch(curve_account_str)
+                                                                };
+                                                                if let Some(curve_data) = curve_data {
+                                                                    if let Some((vt, vs)) = extract_reserves_from_account_data(&curve_data) {
+                                                                        virtual_token_reserves = Some(vt);
+                                                                        virtual_sol_reserves = Some(vs);
+                                                                        price = Some(calculate_price(vt, vs, cache_ref.token_decimals(&mint_address)));
+                                                                    }
+
+                                                                    // 尝试获取代币创建者信息
+                                                                    creator = extract_creator_from_account_data(&curve_data, creator_map, Some(cache_ref));
+                                                                    real_reserves = extract_real_reserves_from_account_data(&curve_data);
+                                                                    token_total_supply = extract_token_total_supply_from_account_data(&curve_data);
+                                                                }
+                                                            }
+                                                        }
+                                                        // TradeEvent中是本笔成交后的储备，比缓存中的账户数据更准确
+                                                        if let Some(event) = &trade_event {
+                                                            virtual_token_reserves = Some(event.virtual_token_reserves);
+                                                            virtual_sol_reserves = Some(event.virtual_sol_reserves);
+                                                            price = Some(calculate_price(
+                                                                event.virtual_token_reserves,
+                                                                event.virtual_sol_reserves,
+                                                                cache.as_ref().map_or(DEFAULT_TOKEN_DECIMALS, |cache_ref| cache_ref.token_decimals(&mint_address)),
+                                                            ));
+                                                            if let (Some(token), Some(sol)) = (event.real_token_reserves, event.real_sol_reserves) {
+                                                                real_reserves = Some((token, sol));
+                                                            }
+                                                            if event.creator.is_some() {
+                                                                creator = event.creator.clone();
+                                                            }
+                                                        }
+                                                        // 曲线账户数据中没有创建者时，使用从Create指令学到的映射
+                                                        if creator.is_none() {
+                                                            creator = cache.as_ref().and_then(|cache_ref| cache_ref.get_mint_creator(&mint_address));
+                                                        }
+
+                                                        // 提取原始交易数据以获取金库地址，无论是否启用CPI日志
+                                                        let global_state = cache.as_ref().and_then(|cache_ref| cache_ref.get_global_state());
+                                                        let raw_log_data = extract_raw_cpi_log_data(
+                                                            &decoded_ix,
+                                                            &signature,
+                                                            order,
+                                                            accounts,
+                                                            &mint_address,
+                                                            &signer_address,
+                                                            &formatted_time,
+                                                            &curve_account,
+                                                            virtual_token_reserves,
+                                                            virtual_sol_reserves,
+                                                            creator_map,
+                                                            cache.as_deref(),
+                                                            global_state.as_ref()
+                                                        );
+
+                                                        // 提取金库地址并更新日志信息 - 这步是关键，无论是否保存CPI日志都需要
+                                                        if let Some(creator_vault) = raw_log_data.get("creator_vault").and_then(|v| v.as_str()) {
+                                                            // 检查是否已包含金库地址信息
+                                                            if !enhanced_data.contains("创作者金库地址:") {
+                                                                enhanced_data.push_str(&format!("\n\n创作者金库地址:\n{}", creator_vault));
+                                                                info!(target: log_targets::SELL, "[金库] Sell交易({})的创作者金库地址: {}", signature, creator_vault);
+                                                            }
+                                                        } else {
+                                                            // 如果从raw_log_data中未找到，尝试从原始日志中提取
+                                                            if let Some(cv) = extract_creator_vault_from_log(log_message.as_str()) {
+                                                                // 检查是否已包含金库地址信息
+                                                                if !enhanced_data.contains("创作者金库地址:") {
+                                                                    enhanced_data.push_str(&format!("\n\n创作者金库地址:\n{}", cv));
+                                                                    info!(target: log_targets::SELL, "[金库] Sell交易({})的创作者金库地址: {}", signature, cv);
+                                                                }
+                                                            }
+                                                        }
+
+                                                        // 交易日志条目，用于结构化日志和WebSocket推送
+                                                        let mut trade_entry = CpiLogEntry::for_trade(
+                                                            "Sell",
+                                                            &mint_address,
+                                                            token_amount,
+                                                            Lamports(sell_args.min_sol_output).to_sol(),
+                                                            &formatted_time,
+                                                            &signature,
+                                                            order,
+                                                            &signer_address,
+                                                            price,
+                                                            virtual_token_reserves,
+                                                            virtual_sol_reserves,
+                                                            curve_account.clone(),
+                                                            creator.clone(),
+                                                        );
+                                                        trade_entry.failed = is_failed;
+                                                        trade_entry.fee_payer = Some(fee_payer.clone());
+                                                        trade_entry.apply_priority_fee(&priority_fee);
+                                                        trade_entry.token_amount_ui = cache.as_ref()
+                                                            .and_then(|cache_ref| cache_ref.get_mint_decimals(&mint_address))
+                                                            .map(|decimals| token_amount as f64 / 10f64.powi(i32::from(decimals)));
+                                                        trade_entry.timestamp = Some(now.timestamp_millis());
+                                                        trade_entry.real_token_reserves = real_reserves.map(|(token, _)| token);
+                                                        trade_entry.real_sol_reserves = real_reserves.map(|(_, sol)| sol);
+                                                        trade_entry.actual_sol_output = actual_sol_output.map(|output| Lamports(output).to_sol());
+                                                        trade_entry.slippage_pct = slippage;
+                                                        if !is_failed {
+                                                            trade_entry.price_impact = trade_event.as_ref().and_then(trade_price_impact);
+                                                        }
+                                                        if let Some(fees) = pump_trade_fees(&decoded_ix, global_state.as_ref()) {
+                                                            trade_entry.apply_fees(&fees, global_state.as_ref());
+                                                        }
+                                                        if let Some(event) = &trade_event {
+                                                            trade_entry.apply_trade_event(event);
+                                                        }
+                                                        let market_value = MarketValue::estimate(
+                                                            price,
+                                                            token_total_supply,
+                                                            trade_entry.real_token_reserves,
+                                                            global_state.as_ref(),
+                                                            cache.as_ref().map_or(DEFAULT_TOKEN_DECIMALS, |cache_ref| cache_ref.token_decimals(&mint_address)),
+                                                        );
+                                                        trade_entry.apply_market_value(market_value.as_ref());
+                                                        let log_message = log_message + &format_market_value_line(market_value.as_ref());
+
+                                                        events.push(MonitorEvent::Sell(TradeEvent {
+                                                            entry: trade_entry,
+                                                            raw: raw_log_data,
+                                                            log_message,
+                                                            enhanced_data,
+                                                            slot,
+                                                            is_monitored: is_monitored_address_involved,
+                                                            json: OnceLock::new(),
+                                                        }));
+                                                    },
+                                                    PumpProgramIx::Create(ref create_args) => {
+                                                        // 新代币创建：签名者即创建者，绑定曲线账户直接取自指令账户
+                                                        let curve_account = accounts.iter()
+                                                            .find(|account| account.name == "bondingCurve")
+                                                            .map(|account| account.pubkey.to_string())
+                                                            .or_else(|| calculate_curve_account_from_mint(&mint_address));
+                                                        let creator_vault = Pubkey::from_str(&signer_address).ok()
+                                                            .and_then(|creator| calculate_creator_vault_from_creator(&creator));
+
+                                                        // 预先记录反查映射，之后该代币的买卖和账户更新无需等待曲线账户数据即可找到mint和创建者
+                                                        if let (Some(cache_ref), false) = (&cache, is_failed) {
+                                                            if let Some(ref curve_account_str) = curve_account {
+                                                                cache_ref.remember_curve_mint(curve_account_str, &mint_address);
+                                                            }
+                                                            if signer_address != "未知" {
+                                                                cache_ref.remember_mint_creator(&mint_address, &signer_address);
+                                                                if let Some(ref vault) = creator_vault {
+                                                                    cache_ref.remember_vault_creator(vault, &signer_address);
+                                                                }
+                                                            }
+                                                        }
+
+                                                        let log_message = format!(
+                                                            "{}NEW TOKEN\nMINT: {}\nNAME: {}\nSYMBOL: {}\nURI: {}\nCREATOR: {}\nBONDING CURVE: {}\nTIME: {}\nSLOT: {}\nSIGNATURE: {}",
+                                                            status_marker,
+                                                            mint_address,
+                                                            create_args.name,
+                                                            create_args.symbol,
+                                                            create_args.uri,
+                                                            signer_address,
+                                                            curve_account.as_deref().unwrap_or("未知"),
+                                                            formatted_time,
+                                                            slot,
+                                                            signature
+                                                        );
+
+                                                        let mut trade_entry = CpiLogEntry::for_trade(
+                                                            "Create",
+                                                            &mint_address,
+                                                            0,
+                                                            0.0,
+                                                            &formatted_time,
+                                                            &signature,
+                                                            order,
+                                                            &signer_address,
+                                                            None,
+                                                            None,
+                                                            None,
+                                                            curve_account.clone(),
+                                                            Some(signer_address.clone()),
+                                                        );
+                                                        trade_entry.failed = is_failed;
+                                                        trade_entry.fee_payer = Some(fee_payer.clone());
+                                                        trade_entry.apply_priority_fee(&priority_fee);
+                                                        trade_entry.timestamp = Some(now.timestamp_millis());
+                                                        trade_entry.metadata = Some(TokenMetadata {
+                                                            name: create_args.name.clone(),
+                                                            symbol: create_args.symbol.clone(),
+                                                            uri: create_args.uri.clone(),
+                                                        });
+
+                                                        let raw_log_data = json!({
+                                                            "create": {
+                                                                "name": create_args.name,
+                                                                "symbol": create_args.symbol,
+                                                                "uri": create_args.uri
+                                                            },
+                                                            "accounts": accounts,
+                                                            "creator_vault": creator_vault,
+                                                            "slot": order.slot,
+                                                            "transaction_index": order.transaction_index,
+                                                            "instruction_index": order.instruction_index,
+                                                        });
+
+                                                        events.push(MonitorEvent::Create(TradeEvent {
+                                                            entry: trade_entry,
+                                                            raw: raw_log_data,
+                                                            enhanced_data: log_message.clone(),
+                                                            log_message,
+                                                            slot,
+                                                            // 新代币上线不限于监听地址，始终以info级别输出
+                                                            is_monitored: true,
+                                                            json: OnceLock::new(),
+                                                        }));
+                                                    },
+                                                    PumpProgramIx::Withdraw => {
+                                                        // 迁移账户在代币毕业后提取曲线中的流动性
+                                                        let bonding_curve = accounts.iter()
+                                                            .find(|account| account.name == "bondingCurve")
+                                                            .map(|account| account.pubkey.to_string());
+                                                        events.push(MonitorEvent::Admin(AdminEvent {
+                                                            action: AdminAction::Withdraw { mint: mint_address, bonding_curve },
+                                                            authority: signer_address,
+                                                            signature: signature.clone(),
+                                                            slot,
+                                                            time: formatted_time,
+                                                            failed: is_failed,
+                                                        }));
+                                                    },
+                                                    PumpProgramIx::SetParams(ref params) => {
+                                                        events.push(MonitorEvent::Admin(AdminEvent {
+                                                            action: AdminAction::SetParams(params.clone()),
+                                                            authority: signer_address,
+                                                            signature: signature.clone(),
+                                                            slot,
+                                                            time: formatted_time,
+                                                            failed: is_failed,
+                                                        }));
+                                                    },
+                                                    _ => {
+                                                        // 其他 PumpFun 指令
+                                                        log::debug!("检测到其他 PumpFun 指令: {}", decoded_ix.name());
+                                                    }
+                                                }
+                                            }
+                                        } else {
+                                            // 没有IDL文件，无法映射账户和提取mint信息
+                                            match decoded_ix {
+                                                PumpProgramIx::Buy(ref buy_args) => {
+                                                    log::debug!(target: log_targets::BUY, "Buy操作 (无mint信息): Amount: {}, MaxSolCost: {}", 
+                                                        buy_args.amount, buy_args.max_sol_cost);
+                                                },
+                                                PumpProgramIx::Sell(ref sell_args) => {
+                                                    log::debug!(target: log_targets::SELL, "Sell操作 (无mint信息): Amount: {}, MinSolOutput: {}", 
+                                                        sell_args.amount, sell_args.min_sol_output);
+                                                },
+                                                PumpProgramIx::Create(ref create_args) => {
+                                                    log::debug!(target: log_targets::CREATE, "Create操作 (无mint信息): Name: {}, Symbol: {}, Uri: {}",
+                                                        create_args.name, create_args.symbol, create_args.uri);
+                                                },
+                                                _ => {
+                                                    log::debug!("其他PumpFun指令: {}", decoded_ix.name());
+                                                }
+                                            }
+                                        }
+                                    },
+                                    Err(e) => {
+                                        let discriminator: String = instruction.data.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+                                        if is_known_pump_discriminator(&instruction.data) {
+                                            // 鉴别器匹配但参数解析失败，通常是程序升级后指令格式与解析器不一致
+                                            Metrics::inc(&METRICS.instruction_decode_failures);
+                                            warn!("[解码] PumpFun指令参数解析失败（可能是程序版本与解析器不一致）- 签名: {}, 鉴别器: {}, 数据长度: {}, 错误: {}",
+                                                signature, discriminator, instruction.data.len(), e);
+                                        } else {
+                                            // 解析器不认识的指令，属于正常情况
+                                            trace!("[解码] 未知的PumpFun指令 - 签名: {}, 鉴别器: {}, 错误: {}", signature, discriminator, e);
+                                        }
+                                    }
+                                },
+                            }
+                        }
+
+                        // 检查是否是Token程序并且Token监控已启用
+                        if features.token_transaction_monitoring && constants().is_token_program(program_id_bytes) {
+                            // 尝试解析Token指令
+                            match TokenInstruction::unpack(&instruction.data) {
+                                Ok(decoded_ix) => {
+                                    let instruction_key = |pos: usize| instruction.accounts.get(pos)
+                                        .and_then(|&acc_idx| account_keys.get(acc_idx as usize))
+                                        .map(|key| bs58::encode(key).into_string());
+                                    // 记录指令中携带的代币精度，供价格计算使用（不限于监听地址）
+                                    if let (Some(cache_ref), Some((mint_pos, decimals))) = (&cache, mint_decimals_from_token_instruction(&decoded_ix)) {
+                                        if let Some(mint) = instruction_key(mint_pos) {
+                                            cache_ref.cache_mint_decimals(&mint, decimals);
+                                        }
+                                    }
+                                    // 记录新初始化的代币账户所属的钱包，供买卖按关联代币账户找到钱包
+                                    let initialized_account = token_account_from_instruction(&decoded_ix, instruction_key);
+                                    if let (Some(cache_ref), Some((token_account, owner, mint))) = (&cache, &initialized_account) {
+                                        cache_ref.remember_token_account(token_account, owner, mint);
+                                    }
+
+                                    if !is_monitored_address_involved {
+                                        continue;
+                                    }
+
+                                    let formatted_time = format_time(Utc::now());
+
+                                    let mut log_message = format!("Token指令: {}, 时间: {}, 签名: {}", 
+                                        get_instruction_name_with_typename(&decoded_ix), 
+                                        formatted_time, 
+                                        signature);
+                                    if let Some((token_account, owner, mint)) = &initialized_account {
+                                        log_message.push_str(&format!(", 代币账户: {}, 所有者: {}, Mint: {}", token_account, owner, mint));
+                                    }
+
+                                    events.push(MonitorEvent::TokenInstruction(TokenInstructionEvent {
+                                        instruction: Some(convert_to_serializable(decoded_ix)),
+                                        log_message,
+                                    }));
+                                },
+                                Err(_) => {
+                                    // Token-2022的扩展指令与经典Token指令不兼容，只记录指令名称；其他解析失败不记录错误
+                                    if !is_monitored_address_involved {
+                                        continue;
+                                    }
+                                    if let Ok(extension_ix) = spl_token_2022::instruction::TokenInstruction::unpack(&instruction.data) {
+                                        let log_message = format!("Token-2022指令: {}, 时间: {}, 签名: {}",
+                                            get_instruction_name_with_typename(&extension_ix),
+                                            format_time(Utc::now()),
+                                            signature);
+
+                                        events.push(MonitorEvent::TokenInstruction(TokenInstructionEvent {
+                                            instruction: None,
+                                            log_message,
+                                        }));
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        events
+    }
+}
+
+/// 解码事件的默认处理：写缓存、保存CPI日志、推送交易并输出日志
+struct EventHandler {
+    features: Features,
+    cache: Option<Arc<TransactionCache>>,
+    trade_broadcaster: Option<TradeBroadcaster>, // 推送毕业、夹子和成交量突增等分析事件
+    price_alerts: Option<Arc<PriceAlerts>>,
+    log_levels: EventLogLevels,
+    sinks: Vec<Box<dyn EventSink>>, // 事件的输出目标，按配置创建
+}
+
+impl EventHandler {
+    fn new(features: &Features, outputs: SinkOutputs) -> anyhow::Result<Self> {
+        Ok(Self {
+            features: features.clone(),
+            cache: outputs.cache.clone(),
+            trade_broadcaster: outputs.trade_broadcaster.clone(),
+            price_alerts: None,
+            log_levels: features.event_log_levels()?,
+            sinks: build_sinks(features, outputs)?,
+        })
+    }
+
+    /// 过滤小额交易并更新分析状态，然后交给所有输出目标
+    fn handle(&self, event: MonitorEvent) {
+        match &event {
+            MonitorEvent::Buy(trade) | MonitorEvent::Sell(trade) if self.features.is_dust_trade(&trade.entry) => {
+                Metrics::inc(&METRICS.dust_trades_filtered);
+                if self.features.dust_trade_action.unwrap_or_default() == DustTradeAction::Debug {
+                    debug!("[小额交易] {}", trade.log_message);
+                }
+                return;
+            }
+            MonitorEvent::Buy(trade) => {
+                if let Some(cache_ref) = &self.cache {
+                    cache_ref.record_candle_trade(&trade.entry);
+                    cache_ref.record_sandwich_trade(&trade.entry, trade.slot);
+                    if let Some(volume) = cache_ref.record_trade_volume(&trade.entry) {
+                        self.report_volume_spike(&trade.entry.mint, &volume);
+                    }
+                }
+            }
+            MonitorEvent::Sell(trade) => {
+                if let Some(cache_ref) = &self.cache {
+                    cache_ref.record_candle_trade(&trade.entry);
+                    if let Some(suspect) = cache_ref.record_sandwich_trade(&trade.entry, trade.slot) {
+                        self.report_sandwich(&suspect);
+                    }
+                    if let Some(volume) = cache_ref.record_trade_volume(&trade.entry) {
+                        self.report_volume_spike(&trade.entry.mint, &volume);
+                    }
+                }
+            }
+            MonitorEvent::AccountUpdate(event) => {
+                if let DecodedAccount::BondingCurve(bonding_curve, _) = &event.account {
+                    self.detect_graduation(event, bonding_curve.complete);
+                    self.check_price_alerts(event, bonding_curve);
+                    if let Some(cache_ref) = &self.cache {
+                        if let Some(mint) = extract_mint_address_from_account_data(&event.summary, Some(cache_ref)) {
+                            self.detect_reserve_move(cache_ref, &mint, bonding_curve);
+                        }
+                    }
+                }
+            }
+            MonitorEvent::Create(_) | MonitorEvent::Instruction(_) | MonitorEvent::TokenInstruction(_) | MonitorEvent::Admin(_) => {}
+        }
+        METRICS.mark_event_emitted();
+        for sink in &self.sinks {
+            sink.handle(&event);
+        }
     }
-    
-    if token_idl.is_some() {
-        log::debug!("已加载 Token IDL 文件");
+
+    /// 绑定曲线complete标志从false变为true时记录一次毕业事件
+    fn detect_graduation(&self, event: &AccountUpdateEvent, complete: bool) {
+        let Some(cache_ref) = &self.cache else {
+            return;
+        };
+        if !cache_ref.update_curve_complete(&event.pubkey, complete) {
+            return;
+        }
+
+        let mint = extract_mint_address_from_account_data(&event.summary, Some(cache_ref)).unwrap_or_else(|| "未知".to_string());
+        if let Some(level) = self.log_levels.graduation {
+            log::log!(target: log_targets::GRADUATION, level, "GRADUATION: 代币({})已完成绑定曲线, 曲线账户: {}, 槽位: {}", mint, event.pubkey, event.slot);
+        }
+
+        let graduation = json!({
+            "type": "graduation",
+            "mint": mint,
+            "curve_account": event.pubkey,
+            "slot": event.slot,
+            "time": format_time(Utc::now()),
+        });
+        if mint != "未知" {
+            cache_ref.clear_reserve_move(&mint);
+            cache_ref.cache_graduation(&mint, graduation.to_string());
+        }
+        if let Some(broadcaster) = &self.trade_broadcaster {
+            broadcaster.publish(self.json_case().apply(graduation).to_string());
+        }
     }
-    
-    // 创建日志文件目录（如果启用了记录到文件）
-    if features.log_to_file {
-        let log_dir = std::path::Path::new(&features.log_file_path).parent()
-            .expect("无法获取日志文件目录");
-        if !log_dir.exists() {
-            fs::create_dir_all(log_dir)?;
-            info!("创建日志目录: {:?}", log_dir);
+
+    /// 输出并推送疑似夹子交易事件
+    fn report_sandwich(&self, suspect: &SandwichSuspect) {
+        let mut event = json!(suspect);
+        event["type"] = json!("sandwich_suspect");
+        event["time"] = json!(format_time(Utc::now()));
+        let event = self.json_case().apply(event);
+        info!(target: log_targets::SANDWICH, "SANDWICH_SUSPECT {}", event);
+        if let Some(broadcaster) = &self.trade_broadcaster {
+            broadcaster.publish(event.to_string());
         }
     }
-    
-    // 创建CPI日志JSON目录（如果启用）
-    if features.cpi_log_json && !features.cpi_log_json_dir.is_empty() {
-        let cpi_log_dir = std::path::Path::new(&features.cpi_log_json_dir);
-        if !cpi_log_dir.exists() {
-            fs::create_dir_all(cpi_log_dir)?;
-            info!("创建CPI日志JSON目录: {:?}", cpi_log_dir);
+
+    /// 输出并推送成交量突增事件
+    fn report_volume_spike(&self, mint: &str, volume: &MintVolume) {
+        let mut event = json!(volume);
+        event["type"] = json!("volume_spike");
+        event["mint"] = json!(mint);
+        event["threshold_sol"] = json!(self.features.volume_spike_sol);
+        event["time"] = json!(format_time(Utc::now()));
+        let event = self.json_case().apply(event);
+        info!(target: log_targets::VOLUME, "VOLUME_SPIKE {}", event);
+        if let Some(broadcaster) = &self.trade_broadcaster {
+            broadcaster.publish(event.to_string());
         }
     }
-    
-    // 创建缓存并启动清理任务
-    let cache = if features.enable_cache {
-        let cache = Arc::new(TransactionCache::new(Arc::clone(&redis_client)));
-        let cache_clone = Arc::clone(&cache);
-        
-        // 启动缓存清理任务
-        tokio::spawn(async move {
-            let mut interval = interval(Duration::from_secs(CACHE_CLEANUP_INTERVAL_SECS));
-            loop {
-                interval.tick().await;
-                cache_clone.cleanup(Duration::from_secs(MAX_CACHE_AGE_SECS));
-                
-                // 每10次清理（约100秒）输出一次统计信息
-                let (buy_count, sell_count, account_count, latest_account_count, latest_reserves_count) = cache_clone.get_stats();
-                debug!("缓存统计: {} 个买入交易, {} 个卖出交易, {} 个账户数据, {} 个最新账户数据, {} 个最新储备数据",
-                    buy_count, sell_count, account_count, latest_account_count, latest_reserves_count);
-            }
-        });
-        
-        Some(cache)
-    } else {
-        None
-    };
-    
-    let client_endpoint = config.grpc_endpoint.clone();
-    info!("已连接到 gRPC 端点，开始监控...");
 
-    // 两个监控模式同时启动，分别在不同的任务中运行
-    if features.basic_transaction_monitoring {
-        info!("启用交易监控模式");
-        let client_txn = args.connect(client_endpoint.clone()).await?;
-        let request_txn = args.get_txn_updates(config.monitored_addresses.clone(), program_id)?;
-        let pump_idl_clone = pump_idl.clone();
-        let token_idl_clone = token_idl.clone();
-        let program_id_str = program_id.to_string();
-        let features_clone = features.clone();
-        let cache_clone = cache.clone();
-        
-        tokio::spawn(async move {
-            if let Err(e) = geyser_subscribe(
-                client_txn, 
-                request_txn, 
-                pump_idl_clone, 
-                token_idl_clone, 
-                &program_id_str, 
-                &features_clone, 
-                cache_clone
-            ).await {
-                error!("交易监控错误: {}", e);
-            }
-        });
+    /// 虚拟SOL储备相对上次触发时变化超过阈值时输出RESERVE_MOVE事件
+    fn detect_reserve_move(&self, cache_ref: &TransactionCache, mint: &str, bonding_curve: &BondingCurve) {
+        let Some(threshold_pct) = self.features.reserve_move_threshold_pct else {
+            return;
+        };
+        let Some((old_token, old_sol)) = cache_ref.check_reserve_move(
+            mint,
+            bonding_curve.virtual_token_reserves,
+            bonding_curve.virtual_sol_reserves,
+            threshold_pct,
+        ) else {
+            return;
+        };
+
+        let token_decimals = cache_ref.token_decimals(mint);
+        let old_price = calculate_price(old_token, old_sol, token_decimals);
+        let new_price = calculate_price(bonding_curve.virtual_token_reserves, bonding_curve.virtual_sol_reserves, token_decimals);
+        let price_change_pct = if old_price > 0.0 { (new_price - old_price) / old_price * 100.0 } else { 0.0 };
+        let reserve_move = self.json_case().apply(json!({
+            "type": "reserve_move",
+            "mint": mint,
+            "old_virtual_token_reserves": old_token,
+            "old_virtual_sol_reserves": old_sol,
+            "new_virtual_token_reserves": bonding_curve.virtual_token_reserves,
+            "new_virtual_sol_reserves": bonding_curve.virtual_sol_reserves,
+            "old_price": old_price,
+            "new_price": new_price,
+            "price_change_pct": price_change_pct,
+            "time": format_time(Utc::now()),
+        }));
+        info!(target: log_targets::ACCOUNT, "RESERVE_MOVE {}", reserve_move);
     }
-    
-    if features.account_monitoring {
-        log::debug!("启用账户监控模式");
-        let client_acct = args.connect(client_endpoint).await?;
-        let request_acct = args.get_account_updates(program_id)?;
-        let features_clone = features.clone();
-        let cache_clone = cache.clone();
-        
-        tokio::spawn(async move {
-            if let Err(e) = geyser_subscribe_accounts(
-                client_acct, 
-                request_acct, 
-                &features_clone, 
-                cache_clone
-            ).await {
-                error!("账户监控错误: {}", e);
-            }
-        });
+
+    /// 绑定曲线储备更新后重新计算价格，检查是否越过提醒阈值
+    fn check_price_alerts(&self, event: &AccountUpdateEvent, bonding_curve: &BondingCurve) {
+        let Some(price_alerts) = &self.price_alerts else {
+            return;
+        };
+        let Some(mint) = extract_mint_address_from_account_data(&event.summary, self.cache.as_deref()) else {
+            return;
+        };
+        let token_decimals = self.cache.as_ref()
+            .map_or(DEFAULT_TOKEN_DECIMALS, |cache_ref| cache_ref.token_decimals(&mint));
+        let price = calculate_price(bonding_curve.virtual_token_reserves, bonding_curve.virtual_sol_reserves, token_decimals);
+        price_alerts.check(&mint, price, bonding_curve.virtual_token_reserves, bonding_curve.virtual_sol_reserves);
     }
-    
-    // 让主任务保持运行
-    loop {
-        tokio::time::sleep(Duration::from_secs(3600)).await;
+
+    /// 输出JSON的字段命名方式
+    fn json_case(&self) -> JsonCase {
+        self.features.json_case.unwrap_or_default()
     }
 }
 
-#[allow(clippy::too_many_lines)]
+/// 运行一次订阅，直到数据流结束或收到退出信号。连接的不是主端点时，每隔 PRIMARY_ENDPOINT_RETRY_SECS 秒
+/// 结束订阅并切回主端点，重连时先尝试主端点（仍不可用时 connect_any 会再切换到备用端点）
+async fn run_subscription<F>(
+    subscribe: impl FnOnce(tokio::sync::watch::Receiver<bool>) -> F,
+    endpoints: &EndpointPool,
+    connected: usize,
+    shutdown: &mut tokio::sync::watch::Receiver<bool>,
+) -> anyhow::Result<()>
+where
+    F: std::future::Future<Output = anyhow::Result<()>>,
+{
+    let (stop_tx, stop_rx) = tokio::sync::watch::channel(false);
+    let subscription = subscribe(stop_rx);
+    tokio::pin!(subscription);
+    let primary_retry = async {
+        if connected == 0 {
+            std::future::pending::<()>().await;
+        }
+        tokio::time::sleep(Duration::from_secs(PRIMARY_ENDPOINT_RETRY_SECS)).await;
+    };
+    tokio::select! {
+        result = &mut subscription => return result,
+        _ = shutdown.changed() => {}
+        _ = primary_retry => endpoints.restore_primary(connected),
+    }
+    let _ = stop_tx.send(true);
+    subscription.await
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn geyser_subscribe(
-    mut client: GeyserGrpcClient<impl Interceptor>,
+    source: TransactionSource<impl Interceptor + Send + 'static>,
     request: SubscribeRequest,
-    _pump_idl: Option<Idl>,
+    programs: Arc<Vec<MonitoredProgram>>,
     _token_idl: Option<Idl>,
-    program_id: &str,
     features: &Features,
     cache: Option<Arc<TransactionCache>>,
+    creator_map: Arc<CreatorMap>,
+    watched_mints: Arc<HashSet<String>>,
+    trade_broadcaster: Option<TradeBroadcaster>,
+    cpi_log_writer: Option<CpiLogWriter<CpiLogFile>>,
+    signer_filter: Arc<SignerFilter>,
+    event_publisher: Option<EventPublisher>,
+    capture: Option<UpdateCapture>,
+    progress: Arc<StreamProgress>,
+    shutdown: tokio::sync::watch::Receiver<bool>,
 ) -> anyhow::Result<()> {
-    // 在使用request前先提取监控地址
-    let monitored_addresses: Vec<String> = if let Some(txn_filter) = request.transactions.get("client") {
-        // 过滤掉程序ID本身，只保留用户要监听的地址
-        txn_filter.account_include.iter()
-            .filter(|addr| *addr != program_id)
-            .cloned()
-            .collect()
-    } else {
-        vec![]
+    let handler = EventHandler::new(features, SinkOutputs {
+        cache: cache.clone(),
+        trade_broadcaster,
+        cpi_log_writer,
+        event_publisher,
+        signer_filter,
+    })?;
+    let processor = TransactionProcessor {
+        programs,
+        features: features.clone(),
+        cache,
+        creator_map,
+        watched_mints,
     };
+
+    let mut events = std::pin::pin!(transaction_events(source, request, processor, capture, progress, shutdown));
+    while let Some(event) = events.next().await {
+        handler.handle(event);
+    }
+    Ok(())
+}
+
+/// 交易更新的来源
+enum TransactionSource<I> {
+    /// 订阅gRPC端点
+    Grpc(Box<GeyserGrpcClient<I>>),
+    /// 回放 `capture` 子命令录制的文件
+    Replay(PathBuf),
+}
+
+/// 订阅交易并返回解码后的事件流，数据流关闭或收到退出信号后结束。
+/// 解码在工作任务中并行进行，事件的处理（缓存、日志、推送）由调用方决定
+fn transaction_events(
+    source: TransactionSource<impl Interceptor + Send + 'static>,
+    request: SubscribeRequest,
+    processor: TransactionProcessor,
+    capture: Option<UpdateCapture>,
+    progress: Arc<StreamProgress>,
+    shutdown: tokio::sync::watch::Receiver<bool>,
+) -> impl Stream<Item = MonitorEvent> {
+    let (events, rx) = tokio::sync::mpsc::channel(MONITOR_EVENT_CHANNEL_CAPACITY);
+    tokio::spawn(async move {
+        let processor = Arc::new(processor);
+        let result = match source {
+            TransactionSource::Grpc(mut client) => {
+                match client.subscribe_with_request(Some(request.clone())).await {
+                    Ok((subscribe_tx, stream)) => {
+                        run_transaction_stream(subscribe_tx, stream, &request, processor, events, capture, &progress, shutdown).await
+                    }
+                    Err(e) => Err(e.into()),
+                }
+            }
+            TransactionSource::Replay(path) => match update_file::replay(&path).await {
+                Ok(stream) => {
+                    run_transaction_stream(futures::sink::drain(), stream, &request, processor, events, capture, &progress, shutdown).await
+                }
+                Err(e) => Err(anyhow::anyhow!("无法打开录制文件({:?}): {}", path, e)),
+            },
+        };
+        if let Err(e) = result {
+            error!("交易监控错误: {}", e);
+            progress.mark_failed();
+        }
+    });
+    receiver_stream(rx)
+}
+
+/// 读取交易数据流，去重后分发给工作任务解码，解码出的事件发送到通道
+#[allow(clippy::too_many_arguments)]
+async fn run_transaction_stream<S>(
+    mut subscribe_tx: S,
+    stream: impl Stream<Item = Result<SubscribeUpdate, tonic::Status>>,
+    request: &SubscribeRequest,
+    processor: Arc<TransactionProcessor>,
+    events: tokio::sync::mpsc::Sender<MonitorEvent>,
+    capture: Option<UpdateCapture>,
+    progress: &StreamProgress,
+    mut shutdown: tokio::sync::watch::Receiver<bool>,
+) -> anyhow::Result<()>
+where
+    S: futures::Sink<SubscribeRequest> + Unpin,
+    anyhow::Error: From<S::Error>,
+{
+    let monitored_addresses = monitored_addresses(request, &processor.programs);
     
     // 精简日志输出
     log::debug!("过滤后监听的地址: {:?}", monitored_addresses);
+    // 全市场模式：没有监听地址时，监控程序的全部交易都按监听地址的交易处理
+    let all_program_transactions = monitored_addresses.is_empty() && processor.features.all_program_transactions;
     
-    // 克隆 request 或使用可变引用
-    let (mut subscribe_tx, mut stream) = client.subscribe_with_request(Some(request)).await?;
+    let from_slot = request.from_slot;
+    let mut stream = std::pin::pin!(stream);
 
-    // 打开日志文件（如果启用）
-    let mut log_file = if features.log_to_file {
-        Some(
-            fs::OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(&features.log_file_path)?
-        )
-    } else {
-        None
-    };
+    // 数据流消费者只做去重和分发，交易放入有界队列由工作任务解码；队列满时暂停读取数据流
+    let workers = processor.features.transaction_workers.unwrap_or_else(default_transaction_workers).max(1);
+    let queue_capacity = processor.features.transaction_queue_capacity.unwrap_or(DEFAULT_TRANSACTION_QUEUE_CAPACITY).max(1);
+    let (work_tx, work_rx) = tokio::sync::mpsc::channel::<TransactionWork>(queue_capacity);
+    let work_rx = Arc::new(std::sync::Mutex::new(work_rx));
+    let worker_tasks: Vec<_> = (0..workers)
+        .map(|_| {
+            let work_rx = Arc::clone(&work_rx);
+            let processor = Arc::clone(&processor);
+            let events = events.clone();
+            // 在阻塞任务中解码：缓存中没有曲线数据时会同步等待Redis/RPC查询
+            tokio::task::spawn_blocking(move || loop {
+                let work = work_rx.lock().expect("transaction queue lock poisoned").blocking_recv();
+                let Some(work) = work else {
+                    break;
+                };
+                for event in processor.process(work.txn, work.slot, work.signature, work.account_keys, work.is_monitored) {
+                    if events.blocking_send(event).is_err() {
+                        return;
+                    }
+                }
+            })
+        })
+        .collect();
+    let mut queue_lag = QueueLagMonitor::new(queue_capacity);
 
-    while let Some(message) = stream.next().await {
+    loop {
+        let message = tokio::select! {
+            _ = shutdown.changed() => {
+                info!("停止交易监控");
+                break;
+            }
+            message = stream.next() => match message {
+                Some(message) => message,
+                None => break,
+            },
+        };
+        if let Ok(msg) = &message {
+            METRICS.mark_message_received();
+            if let Some(capture) = &capture {
+                capture.record(msg);
+            }
+        }
         match message {
             Ok(msg) => match msg.update_oneof {
                 Some(UpdateOneof::Transaction(update)) => {
                     if let Some(txn) = update.transaction {
+                        Metrics::inc(&METRICS.transactions_processed);
+                        METRICS.observe_slot(update.slot);
+                        progress.observe_slot(update.slot);
                         let signature = bs58::encode(&txn.signature).into_string();
                         
                         // 仅调试级别记录所有交易
                         log::debug!("收到新交易，签名: {}", signature);
                         
-                        // 检查是否和监听的地址相关
-                        let mut is_monitored_address_involved = false;
-                        
-                        // 如果有消息数据，检查账户
-                        if let Some(raw_transaction) = &txn.transaction {
-                            if let Some(raw_message) = &raw_transaction.message {
-                                // 提取交易中涉及的所有地址
-                                for account_key in &raw_message.account_keys {
-                                    let account_str = bs58::encode(account_key).into_string();
-                                    // 检查是否在监控地址列表中（排除程序ID本身）
-                                    if monitored_addresses.contains(&account_str) && account_str != program_id {
-                                        is_monitored_address_involved = true;
-                                        break;
-                                    }
-                                }
+                        // 跳过重连重放或多个过滤条件重复推送的交易
+                        if let Some(cache_ref) = &processor.cache {
+                            if !cache_ref.mark_signature_seen(&signature) {
+                                log::debug!("跳过重复交易，签名: {}", signature);
+                                continue;
                             }
                         }
+                        
+                        // 合并静态账户和地址查找表加载的账户，指令中的账户索引基于该列表
+                        let account_keys = txn.transaction.as_ref()
+                            .and_then(|raw_transaction| raw_transaction.message.as_ref())
+                            .map(|raw_message| AccountKeys::new(raw_message, txn.meta.as_ref()))
+                            .unwrap_or_default();
+                        
+                        // 检查是否和监听的地址相关
+                        let is_monitored_address_involved =
+                            all_program_transactions || involves_monitored_address(&account_keys, &monitored_addresses);
 
                         // 只有当基本交易监控开启时才处理
-                        if !features.basic_transaction_monitoring {
+                        if !processor.features.basic_transaction_monitoring {
                             continue;
                         }
 
-                        // 处理 PumpFun 交易
-                        if let Some(raw_transaction) = txn.transaction {
-                            if let Some(raw_message) = raw_transaction.message {
-                                // 遍历所有指令，不使用索引变量
-                                for instruction in raw_message.instructions.iter() {
-                                    // 获取程序 ID
-                                    let program_id_index = instruction.program_id_index as usize;
-                                    if program_id_index < raw_message.account_keys.len() {
-                                        let program_id_bytes = &raw_message.account_keys[program_id_index];
-                                        
-                                        // 检查是否是 PumpFun 程序
-                                        if let Ok(program_pubkey) = Pubkey::from_str(program_id) {
-                                            let program_bytes = program_pubkey.to_bytes().to_vec();
-                                            if program_id_bytes == &program_bytes {
-                                                // 尝试解析指令
-                                                match PumpProgramIx::deserialize(&instruction.data) {
-                                                    Ok(decoded_ix) => {
-                                                        let timestamp_millis = SystemTime::now()
-                                                            .duration_since(UNIX_EPOCH)
-                                                            .expect("Time went backwards");
-                                                        
-                                                        // 创建UTC时间
-                                                        let utc_datetime = Utc.timestamp_millis_opt(
-                                                            timestamp_millis.as_millis() as i64
-                                                        ).unwrap();
-                                                        
-                                                        // 转换为东八区（北京时间，UTC+8）
-                                                        let beijing_offset = FixedOffset::east_opt(8 * 3600).unwrap(); // 8小时 = 8 * 3600秒
-                                                        let beijing_time = utc_datetime.with_timezone(&beijing_offset);
-                                                        
-                                                        // 格式化为ISO 8601格式，显示+08:00时区信息
-                                                        let formatted_time = beijing_time.format("%Y-%m-%dT%H:%M:%S%.3f+08:00").to_string();
-                                                        
-                                                        // 根据是否涉及监控地址以及功能开关选择分析方式
-                                                        let _advanced_analysis = features.advanced_event_detection;
-                                                        
-                                                        // 使用官方高效处理方式，创建DecodedInstruction
-                                                        if let Some(ref idl) = _pump_idl {
-                                                            // 创建AccountMeta列表
-                                                            let account_metas: Vec<AccountMeta> = instruction.accounts.iter()
-                                                                .filter(|&&acc_idx| {
-                                                                    // 确保索引在数组范围内
-                                                                    (acc_idx as usize) < raw_message.account_keys.len()
-                                                                })
-                                                                .map(|&acc_idx| {
-                                                                    let pubkey = Pubkey::new_from_array(
-                                                                        raw_message.account_keys[acc_idx as usize]
-                                                                            .clone()
-                                                                            .try_into()
-                                                                            .unwrap_or_default()
-                                                                    );
-                                                                    
-                                                                    // 简化处理，仅判断是否为签名者
-                                                                    let is_signer = raw_message.header.as_ref().map_or(false, |h| {
-                                                                        (acc_idx as usize) < (h.num_required_signatures as usize)
-                                                                    });
-                                                                    
-                                                                    // 简化可写判断
-                                                                    let is_writable = true; // 默认可写，简化处理
-                                                                    
-                                                                    AccountMeta {
-                                                                        pubkey,
-                                                                        is_signer,
-                                                                        is_writable,
-                                                                    }
-                                                                })
-                                                                .collect();
-                                                            
-                                                            // 使用InstructionAccountMapper映射账户
-                                                            if let Ok(mapped_accounts) = idl.map_accounts(&account_metas, &decoded_ix.name()) {
-                                                                let decoded_instruction = DecodedInstruction {
-                                                                    name: decoded_ix.name(),
-                                                                    accounts: mapped_accounts,
-                                                                    data: match decoded_ix {
-                                                                        PumpProgramIx::Buy(ref buy_args) => {
-                                                                            // 手动创建Buy指令的JSON对象
-                                                                            json!({
-                                                                                "buy": {
-                                                                                    "amount": buy_args.amount,
-                                                                                    "max_sol_cost": buy_args.max_sol_cost
-                                                                                }
-                                                                            })
-                                                                        },
-                                                                        PumpProgramIx::Sell(ref sell_args) => {
-                                                                            // 手动创建Sell指令的JSON对象
-                                                                            json!({
-                                                                                "sell": {
-                                                                                    "amount": sell_args.amount,
-                                                                                    "min_sol_output": sell_args.min_sol_output
-                                                                                }
-                                                                            })
-                                                                        },
-                                                                        _ => {
-                                                                            // 对于其他指令，只提供名称
-                                                                            json!({ decoded_ix.name(): {} })
-                                                                        }
-                                                                    },
-                                                                    program_id: Pubkey::from_str(program_id).unwrap(),
-                                                                    parent_program_id: None,
-                                                                };
-                                                                
-                                                                // 序列化为JSON以便提取mint信息
-                                                                if let Ok(json_string) = serde_json::to_string_pretty(&decoded_instruction) {
-                                                                    let parsed_json: Value = serde_json::from_str(&json_string).unwrap_or_default();
-                                                                    
-                                                                    // 从JSON中提取需要的信息
-                                                                    let mut mint_address = "未知".to_string();
-                                                                    if let Some(accounts) = parsed_json["accounts"].as_array() {
-                                                                        if let Some(mint) = accounts.iter().find(|obj| obj["name"] == "mint") {
-                                                                            mint_address = mint["pubkey"].as_str().unwrap_or("未知").to_string();
-                                                                        }
-                                                                    }
-                                                                    
-                                                                    // 获取签名者地址
-                                                                    let mut signer_address = "未知".to_string();
-                                                                    if let Some(accounts) = parsed_json["accounts"].as_array() {
-                                                                        if let Some(user) = accounts.iter().find(|obj| obj["name"] == "user" && obj["is_signer"] == true) {
-                                                                            signer_address = user["pubkey"].as_str().unwrap_or("未知").to_string();
-                                                                        }
-                                                                    }
-                                                                    
-                                                                    // 从JSON中提取指令数据
-                                                                    match decoded_ix {
-                                                                        PumpProgramIx::Buy(ref buy_args) => {
-                                                                            let log_message = format!(
-                                                                                "TYPE: Buy\nMINT: {}\nTOKEN AMOUNT: {}\nSOL COST: {} SOL\nTIME: {}\nSIGNATURE: {}\n签名者地址: {}",
-                                                                                mint_address,
-                                                                                buy_args.amount,
-                                                                                buy_args.max_sol_cost as f64 / 1_000_000_000.0,
-                                                                                formatted_time,
-                                                                                signature,
-                                                                                signer_address
-                                                                            );
-                                                                            
-                                                                            // 初始化增强版日志信息
-                                                                            let mut enhanced_data = log_message.clone();
-                                                                            
-                                                                            // 如果启用缓存，将Buy交易缓存起来
-                                                                            // 注意: 由于下面会更新包含creator_vault的交易信息，所以这里不再缓存
-                                                                            // 移除以下缓存代码以避免重复调用
-                                                                            // if let Some(cache_ref) = &cache {
-                                                                            //    cache_ref.cache_buy_transaction(&signature, log_message.clone(), Some(&mint_address));
-                                                                            // }
-                                                                            
-                                                                            // 处理买入交易的虚拟储备、价格和缓存
-                                                                            // 计算曲线账户
-                                                                            let curve_account = calculate_curve_account_from_mint(&mint_address);
-                                                                            
-                                                                            // 获取虚拟储备信息
-                                                                            let mut virtual_token_reserves = None;
-                                                                            let mut virtual_sol_reserves = None;
-                                                                            let mut price = None;
-                                                                            let mut creator = None;
-                                                                            let mut fee_basis_points: Option<u64> = None;
-                                                                            let mut creator_fee_basis_points: Option<u64> = None;
-                                                                            
-                                                                            // 如果有曲线账户，尝试获取曲线账户数据和储备信息
-                                                                            if let Some(ref curve_account_str) = curve_account {
-                                                                                if let Some(cache_ref) = &cache {
-                                                                                    if let Some(curve_data) = cache_ref.get_account_data(curve_account_str) {
-                                                                                        if let Some((vt, vs)) = extract_reserves_from_account_data(&curve_data) {
-                                                                                            virtual_token_reserves = Some(vt);
-                                                                                            virtual_sol_reserves = Some(vs);
-                                                                                            price = Some(calculate_price(vt, vs));
-                                                                                        }
-                                                                                        
-                                                                                        // 尝试获取代币创建者信息
-                                                                                        creator = extract_creator_from_account_data(&curve_data);
-                                                                                    }
-                                                                                }
-                                                                            }
-                                                                            
-                                                                            // 提取原始交易数据以获取金库地址，无论是否启用CPI日志
-                                                                            let parsed_json: Value = if let Ok(json_string) = serde_json::to_string_pretty(&decoded_instruction) {
-                                                                                serde_json::from_str(&json_string).unwrap_or_default()
-                                                                            } else {
-                                                                                Value::Null
-                                                                            };
-                                                                            
-                                                                            // 保存原始交易数据中提取金库地址
-                                                                            let raw_log_data = extract_raw_cpi_log_data(
-                                                                                &decoded_ix,
-                                                                                &signature,
-                                                                                &parsed_json["accounts"],
-                                                                                &mint_address,
-                                                                                &signer_address,
-                                                                                &formatted_time,
-                                                                                &curve_account,
-                                                                                virtual_token_reserves,
-                                                                                virtual_sol_reserves
-                                                                            );
-                                                                            
-                                                                            // 提取金库地址并更新日志信息 - 这步是关键，无论是否保存CPI日志都需要
-                                                                            if let Some(creator_vault) = raw_log_data.get("creator_vault").and_then(|v| v.as_str()) {
-                                                                                // 检查是否已包含金库地址信息
-                                                                                if !enhanced_data.contains("创作者金库地址:") {
-                                                                                    enhanced_data.push_str(&format!("\n\n创作者金库地址:\n{}", creator_vault));
-                                                                                    info!("[金库] Buy交易({})的创作者金库地址: {}", signature, creator_vault);
-                                                                                }
-                                                                            } else {
-                                                                                // 如果从raw_log_data中未找到，尝试从原始日志中提取
-                                                                                if let Some(cv) = extract_creator_vault_from_log(log_message.as_str()) {
-                                                                                    // 检查是否已包含金库地址信息
-                                                                                    if !enhanced_data.contains("创作者金库地址:") {
-                                                                                        enhanced_data.push_str(&format!("\n\n创作者金库地址:\n{}", cv));
-                                                                                        info!("[金库] Buy交易({})的创作者金库地址: {}", signature, cv);
-                                                                                    }
-                                                                                }
-                                                                            }
-                                                                            
-                                                                            // 缓存包含创作者金库信息的完整交易数据
-                                                                            if let Some(cache_ref) = &cache {
-                                                                                cache_ref.cache_buy_transaction(&signature, enhanced_data.clone(), Some(&mint_address));
-                                                                            }
-                                                                            
-                                                                            // 保存CPI日志到JSON文件（仅当该功能启用时）
-                                                                            if features.cpi_log_json && !features.cpi_log_json_dir.is_empty() {
-                                                                                // 保存原始日志数据
-                                                                                if let Err(e) = save_raw_cpi_log_to_json(raw_log_data.clone(), &features.cpi_log_json_dir, features.cpi_log_json_max_files) {
-                                                                                    warn!("保存原始CPI日志到JSON文件失败: {}", e);
-                                                                                }
-                                                                            }
-                                                                            
-                                                                            if is_monitored_address_involved {
-                                                                                info!("{}", log_message);
-                                                                                
-                                                                                // 记录到文件
-                                                                                if features.log_to_file {
-                                                                                    if let Some(file) = &mut log_file {
-                                                                                        // 获取当前时间戳用于日志
-                                                                                        let current_time_millis = SystemTime::now()
-                                                                                            .duration_since(UNIX_EPOCH)
-                                                                                            .expect("Time went backwards");
-                                                                                        
-                                                                                        // 创建UTC时间
-                                                                                        let utc_time = Utc.timestamp_millis_opt(
-                                                                                            current_time_millis.as_millis() as i64
-                                                                                        ).unwrap();
-                                                                                        
-                                                                                        // 转换为东八区（北京时间）
-                                                                                        let beijing_offset = FixedOffset::east_opt(8 * 3600).unwrap();
-                                                                                        let beijing_time = utc_time.with_timezone(&beijing_offset);
-                                                                                        
-                                                                                        // 格式化时间
-                                                                                        let log_time = beijing_time.format("%Y-%m-%dT%H:%M:%S%.3f+08:00").to_string();
-                                                                                        
-                                                                                        let _ = writeln!(file, "[{}] {}", log_time, log_message);
-                                                                                    }
-                                                                                }
-                                                                            } else {
-                                                                                log::debug!("{}", log_message);
-                                                                            }
-                                                                        },
-                                                                        PumpProgramIx::Sell(ref sell_args) => {
-                                                                            let log_message = format!(
-                                                                                "TYPE: Sell\nMINT: {}\nTOKEN AMOUNT: {}\nMIN SOL OUTPUT: {} SOL\nTIME: {}\nSIGNATURE: {}\n签名者地址: {}",
-                                                                                mint_address,
-                                                                                sell_args.amount,
-                                                                                sell_args.min_sol_output as f64 / 1_000_000_000.0,
-                                                                                formatted_time,
-                                                                                signature,
-                                                                                signer_address
-                                                                            );
-                                                                            
-                                                                            // 初始化增强版日志信息
-                                                                            let mut enhanced_data = log_message.clone();
-                                                                            
-                                                                            // 如果启用缓存，将Sell交易缓存起来
-                                                                            // 注意: 由于下面会更新包含creator_vault的交易信息，所以这里不再缓存
-                                                                            // 移除以下缓存代码以避免重复调用
-                                                                            // if let Some(cache_ref) = &cache {
-                                                                            //    cache_ref.cache_sell_transaction(&signature, log_message.clone(), Some(&mint_address));
-                                                                            // }
-                                                                            
-                                                                            // 处理卖出交易的虚拟储备、价格和缓存
-                                                                            // 计算曲线账户
-                                                                            let curve_account = calculate_curve_account_from_mint(&mint_address);
-                                                                            
-                                                                            // 获取虚拟储备信息
-                                                                            let mut virtual_token_reserves = None;
-                                                                            let mut virtual_sol_reserves = None;
-                                                                            let mut price = None;
-                                                                            let mut creator = None;
-                                                                            let mut fee_basis_points: Option<u64> = None;
-                                                                            let mut creator_fee_basis_points: Option<u64> = None;
-                                                                            
-                                                                            // 如果有曲线账户，尝试获取曲线账户数据和储备信息
-                                                                            if let Some(ref curve_account_str) = curve_account {
-                                                                                if let Some(cache_ref) = &cache {
-                                                                                    if let Some(curve_data) = cache_ref.get_account_data(curve_account_str) {
-                                                                                        if let Some((vt, vs)) = extract_reserves_from_account_data(&curve_data) {
-                                                                                            virtual_token_reserves = Some(vt);
-                                                                                            virtual_sol_reserves = Some(vs);
-                                                                                            price = Some(calculate_price(vt, vs));
-                                                                                        }
-                                                                                        
-                                                                                        // 尝试获取代币创建者信息
-                                                                                        creator = extract_creator_from_account_data(&curve_data);
-                                                                                    }
-                                                                                }
-                                                                            }
-                                                                            
-                                                                            // 提取原始交易数据以获取金库地址，无论是否启用CPI日志
-                                                                            let parsed_json: Value = if let Ok(json_string) = serde_json::to_string_pretty(&decoded_instruction) {
-                                                                                serde_json::from_str(&json_string).unwrap_or_default()
-                                                                            } else {
-                                                                                Value::Null
-                                                                            };
-                                                                            
-                                                                            // 保存原始交易数据中提取金库地址
-                                                                            let raw_log_data = extract_raw_cpi_log_data(
-                                                                                &decoded_ix,
-                                                                                &signature,
-                                                                                &parsed_json["accounts"],
-                                                                                &mint_address,
-                                                                                &signer_address,
-                                                                                &formatted_time,
-                                                                                &curve_account,
-                                                                                virtual_token_reserves,
-                                                                                virtual_sol_reserves
-                                                                            );
-                                                                            
-                                                                            // 提取金库地址并更新日志信息 - 这步是关键，无论是否保存CPI日志都需要
-                                                                            if let Some(creator_vault) = raw_log_data.get("creator_vault").and_then(|v| v.as_str()) {
-                                                                                // 检查是否已包含金库地址信息
-                                                                                if !enhanced_data.contains("创作者金库地址:") {
-                                                                                    enhanced_data.push_str(&format!("\n\n创作者金库地址:\n{}", creator_vault));
-                                                                                    info!("[金库] Sell交易({})的创作者金库地址: {}", signature, creator_vault);
-                                                                                }
-                                                                            } else {
-                                                                                // 如果从raw_log_data中未找到，尝试从原始日志中提取
-                                                                                if let Some(cv) = extract_creator_vault_from_log(log_message.as_str()) {
-                                                                                    // 检查是否已包含金库地址信息
-                                                                                    if !enhanced_data.contains("创作者金库地址:") {
-                                                                                        enhanced_data.push_str(&format!("\n\n创作者金库地址:\n{}", cv));
-                                                                                        info!("[金库] Sell交易({})的创作者金库地址: {}", signature, cv);
-                                                                                    }
-                                                                                }
-                                                                            }
-                                                                            
-                                                                            // 缓存包含创作者金库信息的完整交易数据
-                                                                            if let Some(cache_ref) = &cache {
-                                                                                cache_ref.cache_sell_transaction(&signature, enhanced_data.clone(), Some(&mint_address));
-                                                                            }
-                                                                            
-                                                                            // 保存CPI日志到JSON文件（仅当该功能启用时）
-                                                                            if features.cpi_log_json && !features.cpi_log_json_dir.is_empty() {
-                                                                                // 保存原始日志数据
-                                                                                if let Err(e) = save_raw_cpi_log_to_json(raw_log_data.clone(), &features.cpi_log_json_dir, features.cpi_log_json_max_files) {
-                                                                                    warn!("保存原始CPI日志到JSON文件失败: {}", e);
-                                                                                }
-                                                                            }
-                                                                            
-                                                                            if is_monitored_address_involved {
-                                                                                info!("{}", log_message);
-                                                                                
-                                                                                // 记录到文件
-                                                                                if features.log_to_file {
-                                                                                    if let Some(file) = &mut log_file {
-                                                                                        // 获取当前时间戳用于日志
-                                                                                        let current_time_millis = SystemTime::now()
-                                                                                            .duration_since(UNIX_EPOCH)
-                                                                                            .expect("Time went backwards");
-                                                                                        
-                                                                                        // 创建UTC时间
-                                                                                        let utc_time = Utc.timestamp_millis_opt(
-                                                                                            current_time_millis.as_millis() as i64
-                                                                                        ).unwrap();
-                                                                                        
-                                                                                        // 转换为东八区（北京时间）
-                                                                                        let beijing_offset = FixedOffset::east_opt(8 * 3600).unwrap();
-                                                                                        let beijing_time = utc_time.with_timezone(&beijing_offset);
-                                                                                        
-                                                                                        // 格式化时间
-                                                                                        let log_time = beijing_time.format("%Y-%m-%dT%H:%M:%S%.3f+08:00").to_string();
-                                                                                        
-                                                                                        let _ = writeln!(file, "[{}] {}", log_time, log_message);
-                                                                                    }
-                                                                                }
-                                                                            } else {
-                                                                                log::debug!("{}", log_message);
-                                                                            }
-                                                                        },
-                                                                        _ => {
-                                                                            // 其他 PumpFun 指令
-                                                                            log::debug!("检测到其他 PumpFun 指令: {}", decoded_ix.name());
-                                                                        }
-                                                                    }
-                                                                } else {
-                                                                    log::debug!("无法序列化指令为JSON");
-                                                                }
-                                                            } else {
-                                                                log::debug!("无法映射账户");
-                                                            }
-                                                        } else {
-                                                            // 没有IDL文件，无法映射账户和提取mint信息
-                                                            match decoded_ix {
-                                                                PumpProgramIx::Buy(ref buy_args) => {
-                                                                    log::debug!("Buy操作 (无mint信息): Amount: {}, MaxSolCost: {}", 
-                                                                        buy_args.amount, buy_args.max_sol_cost);
-                                                                },
-                                                                PumpProgramIx::Sell(ref sell_args) => {
-                                                                    log::debug!("Sell操作 (无mint信息): Amount: {}, MinSolOutput: {}", 
-                                                                        sell_args.amount, sell_args.min_sol_output);
-                                                                },
-                                                                _ => {
-                                                                    log::debug!("其他PumpFun指令: {}", decoded_ix.name());
-                                                                }
-                                                            }
-                                                        }
-                                                    },
-                                                    Err(_) => {
-                                                        // 解析失败，不记录错误
-                                                    }
-                                                }
-                                            }
-                                        }
-                                        
-                                        // 检查是否是Token程序并且Token监控已启用
-                                        if features.token_transaction_monitoring {
-                                            if let Ok(token_program_pubkey) = Pubkey::from_str(TOKEN_PROGRAM_ID) {
-                                                let token_program_bytes = token_program_pubkey.to_bytes().to_vec();
-                                                if program_id_bytes == &token_program_bytes && is_monitored_address_involved {
-                                                    // 尝试解析Token指令
-                                                    match TokenInstruction::unpack(&instruction.data) {
-                                                        Ok(decoded_ix) => {
-                                                            let timestamp_millis = SystemTime::now()
-                                                                .duration_since(UNIX_EPOCH)
-                                                                .expect("Time went backwards");
-                                                            
-                                                            // 创建UTC时间
-                                                            let utc_datetime = Utc.timestamp_millis_opt(
-                                                                timestamp_millis.as_millis() as i64
-                                                            ).unwrap();
-                                                            
-                                                            // 转换为东八区（北京时间，UTC+8）
-                                                            let beijing_offset = FixedOffset::east_opt(8 * 3600).unwrap(); // 8小时 = 8 * 3600秒
-                                                            let beijing_time = utc_datetime.with_timezone(&beijing_offset);
-                                                            
-                                                            // 格式化为ISO 8601格式，显示+08:00时区信息
-                                                            let formatted_time = beijing_time.format("%Y-%m-%dT%H:%M:%S%.3f+08:00").to_string();
-                                                            
-                                                            let ix_name = get_instruction_name_with_typename(&decoded_ix);
-                                                            let _serializable_ix = convert_to_serializable(decoded_ix);
-                                                            
-                                                            let log_message = format!("Token指令: {}, 时间: {}, 签名: {}", 
-                                                                ix_name, 
-                                                                formatted_time, 
-                                                                signature);
-                                                            
-                                                            log::debug!("{}", log_message);
-                                                            
-                                                            // 记录到文件
-                                                            if features.log_to_file {
-                                                                if let Some(file) = &mut log_file {
-                                                                    // 获取当前时间戳用于日志
-                                                                    let current_time_millis = SystemTime::now()
-                                                                        .duration_since(UNIX_EPOCH)
-                                                                        .expect("Time went backwards");
-                                                                    
-                                                                    // 创建UTC时间
-                                                                    let utc_time = Utc.timestamp_millis_opt(
-                                                                        current_time_millis.as_millis() as i64
-                                                                    ).unwrap();
-                                                                    
-                                                                    // 转换为东八区（北京时间）
-                                                                    let beijing_offset = FixedOffset::east_opt(8 * 3600).unwrap();
-                                                                    let beijing_time = utc_time.with_timezone(&beijing_offset);
-                                                                    
-                                                                    // 格式化时间
-                                                                    let log_time = beijing_time.format("%Y-%m-%dT%H:%M:%S%.3f+08:00").to_string();
-                                                                    
-                                                                    let _ = writeln!(file, "[{}] {}", log_time, log_message);
-                                                                }
-                                                            }
-                                                        },
-                                                        Err(_) => {
-                                                            // 解析失败，不记录错误
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
+                        // 解码交给工作任务并行处理
+                        let work = TransactionWork {
+                            txn,
+                            slot: update.slot,
+                            signature,
+                            account_keys,
+                            is_monitored: is_monitored_address_involved,
+                        };
+                        if work_tx.send(work).await.is_err() {
+                            break;
                         }
+                        queue_lag.observe(queue_capacity - work_tx.capacity());
                     }
                 }
                 Some(UpdateOneof::Ping(_)) => {
@@ -1445,106 +4882,225 @@ async fn geyser_subscribe(
                 Some(UpdateOneof::Pong(_)) => {}
                 None => {
                     error!("消息中未找到更新内容");
+                    Metrics::inc(&METRICS.stream_reconnects);
+                    progress.mark_failed();
                     break;
                 }
                 _ => {}
             },
             Err(error) => {
-                error!("错误: {error:?}");
+                if from_slot.is_some() && error.code() == tonic::Code::InvalidArgument {
+                    error!("起始槽位 {:?} 不在端点保留的数据范围内: {}", from_slot, error.message());
+                } else {
+                    error!("错误: {error:?}");
+                }
+                Metrics::inc(&METRICS.stream_reconnects);
+                progress.mark_failed();
                 break;
             }
         }
     }
 
+    // 等待队列中和正在处理的交易完成
+    drop(work_tx);
+    for task in worker_tasks {
+        let _ = task.await;
+    }
+    METRICS.transaction_queue_depth.store(0, std::sync::atomic::Ordering::Relaxed);
     info!("数据流已关闭");
     Ok(())
 }
 
+/// 待解码的交易
+struct TransactionWork {
+    txn: SubscribeUpdateTransactionInfo,
+    slot: u64,
+    signature: String,
+    account_keys: AccountKeys,
+    is_monitored: bool,
+}
+
+/// 待解码交易队列的积压监控：记录队列深度，占用超过 TRANSACTION_QUEUE_LAG_PCT 时报告处理落后
+struct QueueLagMonitor {
+    capacity: usize,
+    last_warned: Option<std::time::Instant>,
+}
+
+impl QueueLagMonitor {
+    fn new(capacity: usize) -> Self {
+        Self { capacity, last_warned: None }
+    }
+
+    /// 记录当前队列深度，处理落后时返回true（日志每 TRANSACTION_QUEUE_LAG_LOG_SECS 秒最多输出一次）
+    fn observe(&mut self, depth: usize) -> bool {
+        METRICS.transaction_queue_depth.store(depth as u64, std::sync::atomic::Ordering::Relaxed);
+        if depth * 100 < self.capacity * TRANSACTION_QUEUE_LAG_PCT {
+            return false;
+        }
+        Metrics::inc(&METRICS.processing_lag);
+        if self.last_warned.is_none_or(|at| at.elapsed() >= Duration::from_secs(TRANSACTION_QUEUE_LAG_LOG_SECS)) {
+            warn!("[交易监控] 处理落后，队列深度={}/{}，数据流读取将被暂停（可调大 transaction_workers 或 transaction_queue_capacity）", depth, self.capacity);
+            self.last_warned = Some(std::time::Instant::now());
+        }
+        true
+    }
+}
+
+/// 默认的交易处理并发数：CPU核数
+fn default_transaction_workers() -> usize {
+    std::thread::available_parallelism().map_or(4, NonZeroUsize::get)
+}
+
 /// 处理账户数据更新的函数
+#[allow(clippy::too_many_arguments)]
 async fn geyser_subscribe_accounts(
-    mut client: GeyserGrpcClient<impl Interceptor>,
+    client: GeyserGrpcClient<impl Interceptor + Send + 'static>,
     request: SubscribeRequest,
     features: &Features,
     cache: Option<Arc<TransactionCache>>,
+    creator_map: Arc<CreatorMap>,
+    trade_broadcaster: Option<TradeBroadcaster>,
+    price_alerts: Option<Arc<PriceAlerts>>,
+    event_publisher: Option<EventPublisher>,
+    progress: Arc<StreamProgress>,
+    shutdown: tokio::sync::watch::Receiver<bool>,
 ) -> anyhow::Result<()> {
-    let (mut subscribe_tx, mut stream) = client.subscribe_with_request(Some(request)).await?;
+    let mut handler = EventHandler::new(features, SinkOutputs {
+        cache: cache.clone(),
+        trade_broadcaster,
+        cpi_log_writer: None,
+        event_publisher,
+        signer_filter: Arc::default(),
+    })?;
+    handler.price_alerts = price_alerts;
+    let mut events = std::pin::pin!(account_events(client, request, cache, creator_map, progress, shutdown));
+    while let Some(event) = events.next().await {
+        handler.handle(event);
+    }
+    Ok(())
+}
 
-    // 打开日志文件（如果启用）
-    let mut log_file = if features.log_to_file {
-        Some(
-            fs::OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(&features.log_file_path)?
-        )
-    } else {
-        None
-    };
+/// 订阅账户更新并返回解码后的事件流，数据流关闭或收到退出信号后结束。
+/// Token程序账户（Mint精度、曲线代币账户余额）直接写入缓存，不产生事件
+fn account_events(
+    client: GeyserGrpcClient<impl Interceptor + Send + 'static>,
+    request: SubscribeRequest,
+    cache: Option<Arc<TransactionCache>>,
+    creator_map: Arc<CreatorMap>,
+    progress: Arc<StreamProgress>,
+    shutdown: tokio::sync::watch::Receiver<bool>,
+) -> impl Stream<Item = MonitorEvent> {
+    let (events, rx) = tokio::sync::mpsc::channel(MONITOR_EVENT_CHANNEL_CAPACITY);
+    tokio::spawn(async move {
+        if let Err(e) = run_account_stream(client, request, cache, creator_map, events, &progress, shutdown).await {
+            error!("账户监控错误: {}", e);
+            progress.mark_failed();
+        }
+    });
+    receiver_stream(rx)
+}
+
+/// 读取账户数据流并解码，解码出的事件发送到通道
+#[allow(clippy::too_many_lines)]
+async fn run_account_stream(
+    mut client: GeyserGrpcClient<impl Interceptor>,
+    request: SubscribeRequest,
+    cache: Option<Arc<TransactionCache>>,
+    creator_map: Arc<CreatorMap>,
+    events: tokio::sync::mpsc::Sender<MonitorEvent>,
+    progress: &StreamProgress,
+    mut shutdown: tokio::sync::watch::Receiver<bool>,
+) -> anyhow::Result<()> {
+    let from_slot = request.from_slot;
+    let (mut subscribe_tx, mut stream) = client.subscribe_with_request(Some(request)).await?;
 
-    log::debug!("账户数据流已打开");
+    log::debug!(target: log_targets::ACCOUNT, "账户数据流已打开");
 
-    while let Some(message) = stream.next().await {
+    loop {
+        let message = tokio::select! {
+            _ = shutdown.changed() => {
+                info!(target: log_targets::ACCOUNT, "停止账户监控");
+                break;
+            }
+            message = stream.next() => match message {
+                Some(message) => message,
+                None => break,
+            },
+        };
+        if message.is_ok() {
+            METRICS.mark_message_received();
+        }
         match message {
             Ok(msg) => match msg.update_oneof {
                 Some(UpdateOneof::Account(account)) => {
+                    Metrics::inc(&METRICS.account_updates);
                     let slot = account.slot;
+                    METRICS.observe_slot(slot);
+                    progress.observe_slot(slot);
                     
                     if let Some(account_data) = account.account {
                         let pubkey_str = bs58::encode(&account_data.pubkey).into_string();
-                        // 添加下划线前缀表示故意不使用的变量
-                        let _owner = bs58::encode(&account_data.owner).into_string();
+                        let owner = bs58::encode(&account_data.owner).into_string();
                         let _lamports = account_data.lamports;
                         
+                        // Token程序账户：Mint账户记录代币精度，曲线关联代币账户记录实际余额
+                        if owner == TOKEN_PROGRAM_ID {
+                            if account_data.data.len() == spl_token::state::Mint::LEN {
+                                match decode_mint_decimals(&account_data.data) {
+                                    Some(decimals) => {
+                                        if let Some(cache_ref) = &cache {
+                                            cache_ref.cache_mint_decimals(&pubkey_str, decimals);
+                                        }
+                                    }
+                                    None => log::debug!(target: log_targets::ACCOUNT, "解析Mint账户({})失败", pubkey_str),
+                                }
+                                continue;
+                            }
+                            match spl_token::state::Account::unpack(&account_data.data) {
+                                Ok(token_account) => {
+                                    let mint = token_account.mint.to_string();
+                                    log::debug!(target: log_targets::ACCOUNT, "[代币账户] {} - Mint: {}, 所有者: {}, 余额: {}", pubkey_str, mint, token_account.owner, token_account.amount);
+                                    if let Some(cache_ref) = &cache {
+                                        cache_ref.update_token_account_balance(&mint, token_account.amount);
+                                        cache_ref.remember_token_account(&pubkey_str, &token_account.owner.to_string(), &mint);
+                                    }
+                                }
+                                Err(e) => {
+                                    log::debug!(target: log_targets::ACCOUNT, "解析代币账户({})失败: {}", pubkey_str, e);
+                                }
+                            }
+                            continue;
+                        }
+                        
                         // 尝试解码账户数据
                         match decode_account_data(&account_data.data) {
                             Ok(decoded_account) => {
                                 let account_info = match &decoded_account {
-                                    DecodedAccount::BondingCurve(bc) => {
-                                        let timestamp_millis = SystemTime::now()
-                                            .duration_since(UNIX_EPOCH)
-                                            .expect("Time went backwards");
-                                            
-                                            // 创建UTC时间
-                                            let utc_datetime = Utc.timestamp_millis_opt(
-                                                timestamp_millis.as_millis() as i64
-                                            ).unwrap();
-                                            
-                                            // 转换为东八区（北京时间，UTC+8）
-                                            let beijing_offset = FixedOffset::east_opt(8 * 3600).unwrap(); // 8小时 = 8 * 3600秒
-                                            let beijing_time = utc_datetime.with_timezone(&beijing_offset);
-                                            
-                                            // 格式化为ISO 8601格式，显示+08:00时区信息
-                                            let formatted_time = beijing_time.format("%Y-%m-%dT%H:%M:%S%.3f+08:00").to_string();
+                                    DecodedAccount::BondingCurve(bc, onchain_creator) => {
+                                            let formatted_time = format_time(Utc::now());
                                             
                                             // 构造账户信息字符串
-                                            let mut account_info_str = format!("
-                                            ACCOUNT TYPE: BondingCurve
-                                            PUBKEY: {}
-                                            VIRTUAL TOKEN RESERVES: {}
-                                            VIRTUAL SOL RESERVES: {}
-                                            REAL TOKEN RESERVES: {}
-                                            REAL SOL RESERVES: {}
-                                            TOKEN TOTAL SUPPLY: {}
-                                            COMPLETE: {}
-                                            ",
-                                            pubkey_str,
-                                            bc.virtual_token_reserves,
-                                            bc.virtual_sol_reserves,
-                                            bc.real_token_reserves,
-                                            bc.real_sol_reserves,
-                                            bc.token_total_supply,
-                                            bc.complete);
+                                            let mut account_info_str = bonding_curve_summary(&pubkey_str, bc);
                                             
                                             // 先保存一下当前账户信息，用于后面从中提取creator
                                             let temp_account_info = account_info_str.clone();
                                             
                                             // 提取mint地址（在后续步骤中需要）
-                                            let mint_address = extract_mint_address_from_account_data(&temp_account_info);
+                                            let mint_address = extract_mint_address_from_account_data(&temp_account_info, cache.as_deref());
                                             
-                                            // 获取creator信息 - 优先通过mint地址查找
-                                            let creator = if let Some(ref mint) = mint_address {
+                                            // 链上creator字段可以推出创作者金库地址，记录金库->创建者映射
+                                            if let (Some(cache_ref), Some(c)) = (&cache, onchain_creator) {
+                                                if let Some(vault) = calculate_creator_vault_from_creator(c) {
+                                                    cache_ref.remember_vault_creator(&vault, &c.to_string());
+                                                }
+                                            }
+                                            
+                                            // 获取creator信息 - 优先使用账户数据中的creator字段，其次通过mint地址查找
+                                            let creator = if let Some(c) = onchain_creator {
+                                                c.to_string()
+                                            } else if let Some(ref mint) = mint_address {
                                                 // 尝试从映射表中查找创建者
-                                                if let Some(c) = find_creator_by_mint(mint) {
+                                                if let Some(c) = creator_map.get(mint) {
                                                     c
                                                 } else {
                                                     // 如果找不到，先尝试直接在映射表中查找
@@ -1554,6 +5110,20 @@ async fn geyser_subscribe_accounts(
                                                 "未知".to_string()
                                             };
                                             
+                                            // 按账户中的储备估算市值（精度未知时使用默认精度）
+                                            let token_decimals = match (&cache, &mint_address) {
+                                                (Some(cache_ref), Some(mint)) => cache_ref.token_decimals(mint),
+                                                _ => DEFAULT_TOKEN_DECIMALS,
+                                            };
+                                            let market_value = MarketValue::estimate(
+                                                Some(calculate_price(bc.virtual_token_reserves, bc.virtual_sol_reserves, token_decimals)),
+                                                Some(bc.token_total_supply),
+                                                Some(bc.real_token_reserves),
+                                                cache.as_ref().and_then(|cache_ref| cache_ref.get_global_state()).as_ref(),
+                                                token_decimals,
+                                            );
+                                            account_info_str.push_str(&format_market_value_line(market_value.as_ref()));
+
                                             // 添加creator信息
                                             account_info_str.push_str(&format!("CREATOR: {}\n", creator));
                                             
@@ -1562,22 +5132,8 @@ async fn geyser_subscribe_accounts(
                                             
                                             account_info_str
                                     },
-                                    DecodedAccount::Global(global) => {
-                                        let timestamp_millis = SystemTime::now()
-                                            .duration_since(UNIX_EPOCH)
-                                            .expect("Time went backwards");
-                                            
-                                            // 创建UTC时间
-                                            let utc_datetime = Utc.timestamp_millis_opt(
-                                                timestamp_millis.as_millis() as i64
-                                            ).unwrap();
-                                            
-                                            // 转换为东八区（北京时间，UTC+8）
-                                            let beijing_offset = FixedOffset::east_opt(8 * 3600).unwrap(); // 8小时 = 8 * 3600秒
-                                            let beijing_time = utc_datetime.with_timezone(&beijing_offset);
-                                            
-                                            // 格式化为ISO 8601格式，显示+08:00时区信息
-                                            let formatted_time = beijing_time.format("%Y-%m-%dT%H:%M:%S%.3f+08:00").to_string();
+                                    DecodedAccount::Global(global, creator_fee_basis_points) => {
+                                            let formatted_time = format_time(Utc::now());
                                             
                                             let fee_recipient = bs58::encode(&global.fee_recipient.to_bytes()).into_string();
                                             let authority = bs58::encode(&global.authority.to_bytes()).into_string();
@@ -1593,6 +5149,7 @@ async fn geyser_subscribe_accounts(
                                             INITIAL REAL TOKEN RESERVES: {}
                                             TOKEN TOTAL SUPPLY: {}
                                             FEE BASIS POINTS: {}
+                                            CREATOR FEE BASIS POINTS: {}
                                             TIME: {}
                                             ",
                                             pubkey_str,
@@ -1604,49 +5161,28 @@ async fn geyser_subscribe_accounts(
                                             global.initial_real_token_reserves,
                                             global.token_total_supply,
                                             global.fee_basis_points,
+                                            creator_fee_basis_points.map_or("N/A".to_string(), |bps| bps.to_string()),
                                             formatted_time
                                             )
                                     }
                                 };
                                 
-                                // 如果启用缓存，将账户数据添加到缓存
-                                if let Some(cache_ref) = &cache {
-                                    cache_ref.cache_account_data(&pubkey_str, account_info.clone());
-                                }
-                                
-                                // 使用debug级别输出账户信息
-                                log::debug!("{}", account_info);
-                                
-                                // 记录到文件
-                                if features.log_to_file {
-                                    if let Some(file) = &mut log_file {
-                                        // 获取当前时间戳用于日志
-                                        let current_time_millis = SystemTime::now()
-                                            .duration_since(UNIX_EPOCH)
-                                            .expect("Time went backwards");
-                                        
-                                        // 创建UTC时间
-                                        let utc_time = Utc.timestamp_millis_opt(
-                                            current_time_millis.as_millis() as i64
-                                        ).unwrap();
-                                        
-                                        // 转换为东八区（北京时间）
-                                        let beijing_offset = FixedOffset::east_opt(8 * 3600).unwrap();
-                                        let beijing_time = utc_time.with_timezone(&beijing_offset);
-                                        
-                                        // 格式化时间
-                                        let log_time = beijing_time.format("%Y-%m-%dT%H:%M:%S%.3f+08:00").to_string();
-                                        
-                                        let _ = writeln!(file, "[{}] {}", log_time, account_info);
-                                    }
+                                let event = MonitorEvent::AccountUpdate(AccountUpdateEvent {
+                                    pubkey: pubkey_str,
+                                    slot,
+                                    account: decoded_account,
+                                    summary: account_info,
+                                });
+                                if events.send(event).await.is_err() {
+                                    break;
                                 }
                             },
                             Err(e) => {
-                                log::debug!("解析账户数据失败: {}", e.message);
+                                log::debug!(target: log_targets::ACCOUNT, "解析账户数据失败: {}", e.message);
                             }
                         }
                     } else {
-                        log::debug!("账户数据为空，槽位: {}", slot);
+                        log::debug!(target: log_targets::ACCOUNT, "账户数据为空，槽位: {}", slot);
                     }
                 },
                 Some(UpdateOneof::Ping(_)) => {
@@ -1660,21 +5196,45 @@ async fn geyser_subscribe_accounts(
                 Some(UpdateOneof::Pong(_)) => {}
                 None => {
                     error!("消息中未找到更新内容");
+                    Metrics::inc(&METRICS.stream_reconnects);
+                    progress.mark_failed();
                     break;
                 }
                 _ => {}
             },
             Err(error) => {
-                error!("错误: {error:?}");
+                if from_slot.is_some() && error.code() == tonic::Code::InvalidArgument {
+                    error!("起始槽位 {:?} 不在端点保留的数据范围内: {}", from_slot, error.message());
+                } else {
+                    error!("错误: {error:?}");
+                }
+                Metrics::inc(&METRICS.stream_reconnects);
+                progress.mark_failed();
                 break;
             }
         }
     }
 
-    info!("账户数据流已关闭");
+    info!(target: log_targets::ACCOUNT, "账户数据流已关闭");
     Ok(())
 }
 
+/// 绑定曲线账户中creator字段的偏移量：鉴别器(8) + 5个u64储备/供应量字段(40) + complete(1)
+const BONDING_CURVE_CREATOR_OFFSET: usize = 8 + 5 * 8 + 1;
+/// Global账户中creator_fee_basis_points字段的偏移量（鉴别器 + 旧版字段 + withdraw_authority + enable_migrate + pool_migration_fee）
+const GLOBAL_CREATOR_FEE_BASIS_POINTS_OFFSET: usize = 8 + 1 + 32 + 32 + 5 * 8 + 32 + 1 + 8;
+
+/// 账户数据切片需要覆盖的长度：绑定曲线到creator字段结束、Global到创作者费率结束，
+/// 订阅了Token程序账户时还需覆盖完整的代币账户（Mint账户更短，不会被截断）
+fn account_data_slice_len(include_token_accounts: bool) -> usize {
+    let pump_len = (BONDING_CURVE_CREATOR_OFFSET + 32).max(GLOBAL_CREATOR_FEE_BASIS_POINTS_OFFSET + 8);
+    if include_token_accounts {
+        pump_len.max(spl_token::state::Account::LEN)
+    } else {
+        pump_len
+    }
+}
+
 /// 解码账户数据为特定类型
 pub fn decode_account_data(buf: &[u8]) -> Result<DecodedAccount, AccountDecodeError> {
     if buf.len() < 8 {
@@ -1691,21 +5251,34 @@ pub fn decode_account_data(buf: &[u8]) -> Result<DecodedAccount, AccountDecodeEr
                 .map_err(|e| AccountDecodeError {
                     message: format!("无法反序列化BondingCurveAccount: {}", e),
                 })?;
-            log::debug!("解码的绑定曲线结构: {:#?}", data);
+            log::debug!(target: log_targets::ACCOUNT, "解码的绑定曲线结构: {:#?}", data);
             
-            // 本地BondingCurve结构体中没有creator字段，记录其他信息
-            log::debug!("绑定曲线已解析: 虚拟代币储备: {}, 虚拟SOL储备: {}", 
-                         data.0.virtual_token_reserves, data.0.virtual_sol_reserves);
+            // 新版绑定曲线在complete字段之后存储creator，旧账户数据长度不足时没有该字段；
+            // 旧账户末尾的填充全为0，读出的全零公钥不是真实的创建者
+            let creator = buf
+                .get(BONDING_CURVE_CREATOR_OFFSET..BONDING_CURVE_CREATOR_OFFSET + 32)
+                .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                .map(Pubkey::new_from_array)
+                .filter(|creator| *creator != Pubkey::default());
+            log::debug!(target: log_targets::ACCOUNT, "绑定曲线已解析: 虚拟代币储备: {}, 虚拟SOL储备: {}, creator: {:?}", 
+                         data.0.virtual_token_reserves, data.0.virtual_sol_reserves, creator);
             
-            Ok(DecodedAccount::BondingCurve(data.0))
+            Ok(DecodedAccount::BondingCurve(data.0, creator))
         }
         GLOBAL_ACCOUNT_DISCM => {
             let data = GlobalAccount::deserialize(buf)
                 .map_err(|e| AccountDecodeError {
                     message: format!("无法反序列化GlobalAccount: {}", e),
                 })?;
-            log::debug!("解码的全局结构: {:#?}", data);
-            Ok(DecodedAccount::Global(data.0))
+            log::debug!(target: log_targets::ACCOUNT, "解码的全局结构: {:#?}", data);
+            
+            // 新版Global账户在withdraw_authority、enable_migrate、pool_migration_fee之后存储创作者费率
+            let creator_fee_basis_points = buf
+                .get(GLOBAL_CREATOR_FEE_BASIS_POINTS_OFFSET..GLOBAL_CREATOR_FEE_BASIS_POINTS_OFFSET + 8)
+                .and_then(|bytes| <[u8; 8]>::try_from(bytes).ok())
+                .map(u64::from_le_bytes);
+            
+            Ok(DecodedAccount::Global(data.0, creator_fee_basis_points))
         }
         _ => Err(AccountDecodeError {
             message: "未找到账户的鉴别器".to_string(),
@@ -1715,41 +5288,43 @@ pub fn decode_account_data(buf: &[u8]) -> Result<DecodedAccount, AccountDecodeEr
 
 /// 从账户数据中提取mint地址
 /// 通过反向计算PDA的方式找到与绑定曲线账户关联的mint地址
-fn extract_mint_address_from_account_data(account_data_str: &str) -> Option<String> {
+/// 优先使用从交易中学到（或从Redis恢复）的曲线->mint映射
+fn extract_mint_address_from_account_data(account_data_str: &str, cache: Option<&TransactionCache>) -> Option<String> {
     if account_data_str.contains("BondingCurve") {
         // 从账户数据中提取pubkey
         if let Some(pubkey_line) = account_data_str.lines().find(|line| line.trim().starts_with("PUBKEY:")) {
             let pubkey_str = pubkey_line.trim().strip_prefix("PUBKEY:").unwrap_or("").trim();
+            if let Some(mint) = cache.and_then(|cache_ref| cache_ref.get_curve_mint(pubkey_str)) {
+                return Some(mint);
+            }
             if let Ok(curve_pubkey) = Pubkey::from_str(pubkey_str) {
                 // PumpFun程序ID
-                let pump_program_id = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
-                if let Ok(program_id) = Pubkey::from_str(pump_program_id) {
-                    // 从实际交易数据中看到的mint地址列表
-                    let common_mints = [
-                        "DCLjJRAP4PineCmCabTKRrTVsSaggkmfgBj8AMPapump",
-                        "4qMyinhBRrePr82BjoKheaXocfTXChBMk3TWifHypump",
-                        "7kJzws2KnTV73d16ZuifeFmSyupxYkp7CPYenV3Apump",
-                        "FqF6Ac1j71qjTxjg9mJag3zrmmnxVtXJQTxZjSPdpump",
-                        // 可以添加更多已知的mint地址
-                    ];
-                    
-                    // 遍历已知mint地址并验证
-                    for mint_str in common_mints.iter() {
-                        if let Ok(mint_pubkey) = Pubkey::from_str(mint_str) {
-                            // 验证PDA
-                            let seeds = &[b"bonding-curve", mint_pubkey.as_ref()];
-                            let (derived_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
-                            
-                            if derived_pubkey == curve_pubkey {
-                                debug!("[PDA] 成功反向计算: 曲线账户({}) -> Mint地址({})", pubkey_str, mint_str);
-                                return Some(mint_str.to_string());
-                            }
+                let program_id = constants().pump_program_id();
+                // 从实际交易数据中看到的mint地址列表
+                let common_mints = [
+                    "DCLjJRAP4PineCmCabTKRrTVsSaggkmfgBj8AMPapump",
+                    "4qMyinhBRrePr82BjoKheaXocfTXChBMk3TWifHypump",
+                    "7kJzws2KnTV73d16ZuifeFmSyupxYkp7CPYenV3Apump",
+                    "FqF6Ac1j71qjTxjg9mJag3zrmmnxVtXJQTxZjSPdpump",
+                    // 可以添加更多已知的mint地址
+                ];
+                
+                // 遍历已知mint地址并验证
+                for mint_str in common_mints.iter() {
+                    if let Ok(mint_pubkey) = Pubkey::from_str(mint_str) {
+                        // 验证PDA
+                        let seeds = &[b"bonding-curve", mint_pubkey.as_ref()];
+                        let (derived_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+                        
+                        if derived_pubkey == curve_pubkey {
+                            debug!("[PDA] 成功反向计算: 曲线账户({}) -> Mint地址({})", pubkey_str, mint_str);
+                            return Some(mint_str.to_string());
                         }
                     }
-                    
-                    // 如果没有匹配的mint，记录日志
-                    debug!("[PDA] 无法找到曲线账户({})对应的mint地址", pubkey_str);
                 }
+                
+                // 如果没有匹配的mint，记录日志
+                debug!("[PDA] 无法找到曲线账户({})对应的mint地址", pubkey_str);
             }
         }
     }
@@ -1757,7 +5332,83 @@ fn extract_mint_address_from_account_data(account_data_str: &str) -> Option<Stri
     None
 }
 
-/// 从账户数据中提取虚拟储备信息
+// 从绑定曲线账户文本中读取数值字段
+fn bonding_curve_field(account_data_str: &str, name: &str) -> Option<u64> {
+    if !account_data_str.contains("BondingCurve") {
+        return None;
+    }
+    account_data_str
+        .lines()
+        .map(str::trim)
+        .find(|line| line.starts_with(name))?
+        .split(':')
+        .next_back()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+}
+
+/// 从缓存的绑定曲线账户数据中提取真实储备（代币, SOL）
+fn extract_real_reserves_from_account_data(account_data_str: &str) -> Option<(u64, u64)> {
+    Some((
+        bonding_curve_field(account_data_str, "REAL TOKEN RESERVES")?,
+        bonding_curve_field(account_data_str, "REAL SOL RESERVES")?,
+    ))
+}
+
+/// 从缓存的绑定曲线账户数据中提取代币总供应量
+fn extract_token_total_supply_from_account_data(account_data_str: &str) -> Option<u64> {
+    bonding_curve_field(account_data_str, "TOKEN TOTAL SUPPLY")
+}
+
+// 绑定曲线账户信息字符串（不含CREATOR和TIME行），缓存和买卖处理按行从中提取储备
+fn bonding_curve_summary(pubkey: &str, bc: &BondingCurve) -> String {
+    format!("
+                                            ACCOUNT TYPE: BondingCurve
+                                            PUBKEY: {}
+                                            VIRTUAL TOKEN RESERVES: {}
+                                            VIRTUAL SOL RESERVES: {}
+                                            REAL TOKEN RESERVES: {}
+                                            REAL SOL RESERVES: {}
+                                            TOKEN TOTAL SUPPLY: {}
+                                            COMPLETE: {}
+                                            ",
+    pubkey,
+    bc.virtual_token_reserves,
+    bc.virtual_sol_reserves,
+    bc.real_token_reserves,
+    bc.real_sol_reserves,
+    bc.token_total_supply,
+    bc.complete)
+}
+
+// 发布到消息队列的账户更新事件（JSON）
+fn account_event_json(event: &AccountUpdateEvent) -> Value {
+    match &event.account {
+        DecodedAccount::BondingCurve(bc, creator) => json!({
+            "type": "bonding_curve",
+            "pubkey": event.pubkey,
+            "slot": event.slot,
+            "virtual_token_reserves": bc.virtual_token_reserves,
+            "virtual_sol_reserves": bc.virtual_sol_reserves,
+            "real_token_reserves": bc.real_token_reserves,
+            "real_sol_reserves": bc.real_sol_reserves,
+            "token_total_supply": bc.token_total_supply,
+            "complete": bc.complete,
+            "creator": creator.map(|creator| creator.to_string()),
+        }),
+        DecodedAccount::Global(global, creator_fee_basis_points) => json!({
+            "type": "global",
+            "pubkey": event.pubkey,
+            "slot": event.slot,
+            "state": GlobalState::from_account(global, *creator_fee_basis_points, event.slot),
+        }),
+    }
+}
+
+
+/// 从绑定曲线账户文本中提取虚拟储备（代币, SOL）
+/// 账户监控使用解码后的结构直接缓存储备，此函数只用于从缓存/Redis读回的账户文本
 fn extract_reserves_from_account_data(account_data_str: &str) -> Option<(u64, u64)> {
     if account_data_str.contains("BondingCurve") {
         // 查找虚拟代币储备
@@ -1768,8 +5419,8 @@ fn extract_reserves_from_account_data(account_data_str: &str) -> Option<(u64, u6
         
         if let (Some(vt_line), Some(vs_line)) = (vt_line, vs_line) {
             // 提取数值
-            let vt_str = vt_line.trim().split(':').last()?.trim();
-            let vs_str = vs_line.trim().split(':').last()?.trim();
+            let vt_str = vt_line.trim().split(':').next_back()?.trim();
+            let vs_str = vs_line.trim().split(':').next_back()?.trim();
             
             // 尝试解析为数字
             if let (Ok(vt), Ok(vs)) = (vt_str.parse::<u64>(), vs_str.parse::<u64>()) {
@@ -1788,13 +5439,10 @@ fn extract_reserves_from_account_data(account_data_str: &str) -> Option<(u64, u6
 
 /// 从mint地址计算绑定曲线账户地址
 fn calculate_curve_account_from_mint(mint: &str) -> Option<String> {
-    // PumpFun程序ID
-    let pump_program_id = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
-    
-    if let (Ok(mint_pubkey), Ok(program_id)) = (Pubkey::from_str(mint), Pubkey::from_str(pump_program_id)) {
+    if let Ok(mint_pubkey) = Pubkey::from_str(mint) {
         // 使用mint地址和程序ID计算PDA
         let seeds = &[b"bonding-curve", mint_pubkey.as_ref()];
-        let (derived_pubkey, _) = Pubkey::find_program_address(seeds, &program_id);
+        let (derived_pubkey, _) = Pubkey::find_program_address(seeds, &constants().pump_program_id());
         
         // 返回计算出的账户地址
         let curve_account = derived_pubkey.to_string();
@@ -1805,35 +5453,20 @@ fn calculate_curve_account_from_mint(mint: &str) -> Option<String> {
     None
 }
 
-/// 从mint地址查找creator（硬编码版本，实际应通过配置文件或数据库读取）
-fn find_creator_by_mint(mint: &str) -> Option<String> {
-    // 硬编码一些映射示例
-    let creator_map: HashMap<&str, &str> = [
-        // 示例数据，请替换为实际数据
-        ("DCLjJRAP4PineCmCabTKRrTVsSaggkmfgBj8AMPapump", "T5SWiQQCACjAMSjTnHEbRjFzxqQyd5xoLvHqFPRqRLw"),
-        ("4qMyinhBRrePr82BjoKheaXocfTXChBMk3TWifHypump", "2yodq5YqMk5owNYhUWjh9gNkwRxaQBYDAcJdaGC7B7vG"),
-        ("7kJzws2KnTV73d16ZuifeFmSyupxYkp7CPYenV3Apump", "J9MBJJrqxsqBSXMk46PT5XJj9qXBzj6kcGbECdmDSQoV"),
-        ("FqF6Ac1j71qjTxjg9mJag3zrmmnxVtXJQTxZjSPdpump", "F5RYi7FMPefkc7okJNh21HgKmFVtJYyGBm1xxvriDVYZ"),
-        // 修正amUfFDR5KxiFKpgibmPAPRwhaB9jrPcKWsBVJMhpump的创建者地址
-        ("amUfFDR5KxiFKpgibmPAPRwhaB9jrPcKWsBVJMhpump", "Hju3K6uRadH7AkynqHGCZgD1W63WNa47h6DuNpTk3xsG"),
-        ("A5JqPPSTf3Rc4W9R9CYLRhRowMLZLquweJgR6iDepump", "Eou3bQd3VYUzXxcLBqihFP5J5qK3W3f8Lq5CsX3EY8Yk"),
-        // 添加新的对应关系
-        ("GFVtnX25mEtpjEXc47X1AKfcd9tdPdds9FdMQoJ1pump", "HNjUCzKFHAqZVvf3mFe89X35aQdNwqKptkwViNNgUzKf"),
-        ("7v1cnL3KtzbHYar9anc8eQGV9NYDMPgYwb526ShUpump", "BYNj1SpM6PxMUVu5hLYVdJxiP5Qv8fQ5eeqZQ213APGj"),
-        ("F7ZDfpnBX13Uy5gK8J4mQLvMpDqa1zhajdUtfvwgpump", "BM2SfEe3rjG48RtNqLHk1KVJqb2EXfz6CuD6epn3U5Ku"),
-        ("85578kyWUYj7kU4GeSKZ8RYoQuhxdxiVc5CXL52spump", "ChcyLqAMCm25LGFhgP9RXAd54oCbKZ1DdDmwkh4dpQsM"),
-        // 特殊账户映射
-        ("54Pgg7FuLuP13dRQoFPTH4FdZHi141bQDzVwukt6m8Tk", "ChcyLqAMCm25LGFhgP9RXAd54oCbKZ1DdDmwkh4dpQsM"), // 这个rent实际是creator_vault
-        // 金库地址映射到创建者
-        ("7hTckgnGnLQR6sdH7YkqFTAA7VwTfYFaZ6EhEsU3saCX", "HNjUCzKFHAqZVvf3mFe89X35aQdNwqKptkwViNNgUzKf"),
-        ("HxmpdosPST3HoZwMg8uV8hg9EoYpisyCQQAP8HAqnMQK", "BM2SfEe3rjG48RtNqLHk1KVJqb2EXfz6CuD6epn3U5Ku"),
-    ].iter().cloned().collect();
+/// 从mint地址计算绑定曲线的关联代币账户地址（存放曲线持有的代币）
+fn calculate_associated_bonding_curve_from_mint(mint: &str) -> Option<String> {
+    let curve_account = Pubkey::from_str(&calculate_curve_account_from_mint(mint)?).ok()?;
+    let mint_pubkey = Pubkey::from_str(mint).ok()?;
+    let token_program_id = Pubkey::from_str(TOKEN_PROGRAM_ID).ok()?;
+    let associated_token_program_id = Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM_ID).ok()?;
     
-    creator_map.get(mint).map(|s| s.to_string())
+    let seeds = &[curve_account.as_ref(), token_program_id.as_ref(), mint_pubkey.as_ref()];
+    let (derived_pubkey, _) = Pubkey::find_program_address(seeds, &associated_token_program_id);
+    Some(derived_pubkey.to_string())
 }
 
 /// 从账户数据中提取creator信息
-fn extract_creator_from_account_data(account_data_str: &str) -> Option<String> {
+fn extract_creator_from_account_data(account_data_str: &str, creator_map: &CreatorMap, cache: Option<&TransactionCache>) -> Option<String> {
     if account_data_str.contains("BondingCurve") {
         // 优先从账户数据字符串中直接查找CREATOR字段
         let creator_line = account_data_str.lines()
@@ -1841,7 +5474,7 @@ fn extract_creator_from_account_data(account_data_str: &str) -> Option<String> {
         
         if let Some(line) = creator_line {
             // 提取creator地址
-            if let Some(creator_str) = line.trim().split(':').last() {
+            if let Some(creator_str) = line.trim().split(':').next_back() {
                 let creator_str = creator_str.trim();
                 
                 if !creator_str.is_empty() && creator_str != "未知" && creator_str != "N/A" && creator_str != "未获取到创建者地址" {
@@ -1855,11 +5488,11 @@ fn extract_creator_from_account_data(account_data_str: &str) -> Option<String> {
                 .find(|line| line.trim().contains("创作者金库地址:"));
             
             if let Some(line) = creator_vault_line {
-                if let Some(vault_str) = line.trim().split(':').last() {
+                if let Some(vault_str) = line.trim().split(':').next_back() {
                     let vault_str = vault_str.trim();
                     // 通过金库地址查找创建者
                     if !vault_str.is_empty() {
-                        if let Some(creator) = find_creator_by_vault(vault_str) {
+                        if let Some(creator) = find_creator_by_vault(creator_map, cache, vault_str) {
                             debug!("[提取] 通过金库地址({})找到创建者: {}", vault_str, creator);
                             return Some(creator);
                         }
@@ -1870,7 +5503,7 @@ fn extract_creator_from_account_data(account_data_str: &str) -> Option<String> {
             // 尝试解析原始账户数据以获取creator字段
             // 首先检查是否有缓存的原始数据
             if let Some(pubkey_line) = account_data_str.lines().find(|line| line.trim().starts_with("PUBKEY:")) {
-                if let Some(pubkey_str) = pubkey_line.trim().split(':').last() {
+                if let Some(pubkey_str) = pubkey_line.trim().split(':').next_back() {
                     let pubkey_str = pubkey_str.trim();
                     // 检查是否有数据并尝试读取原始数据
                     if let Ok(_account_pubkey) = Pubkey::from_str(pubkey_str) {
@@ -1879,8 +5512,8 @@ fn extract_creator_from_account_data(account_data_str: &str) -> Option<String> {
                         debug!("[提取] 尝试从账户({})解析创作者字段", pubkey_str);
                         
                         // 尝试从mint地址获取，这是后备方案
-                        if let Some(mint) = extract_mint_address_from_account_data(account_data_str) {
-                            if let Some(creator) = find_creator_by_mint(&mint) {
+                        if let Some(mint) = extract_mint_address_from_account_data(account_data_str, cache) {
+                            if let Some(creator) = creator_map.get(&mint) {
                                 debug!("[提取] 通过mint({})映射找到创建者: {}", mint, creator);
                                 return Some(creator);
                             }
@@ -1895,20 +5528,28 @@ fn extract_creator_from_account_data(account_data_str: &str) -> Option<String> {
 }
 
 /// 从CPI指令中获取原始日志数据
+#[allow(clippy::too_many_arguments)]
 fn extract_raw_cpi_log_data(
     ix: &PumpProgramIx, 
     signature: &str, 
-    accounts: &Value, 
+    order: EventOrder,
+    accounts: &[AccountMetadata], 
     mint_address: &str, 
     signer_address: &str,
     formatted_time: &str,
     curve_account: &Option<String>,
     vt_reserves: Option<u64>,
-    vs_reserves: Option<u64>
+    vs_reserves: Option<u64>,
+    creator_map: &CreatorMap,
+    cache: Option<&TransactionCache>,
+    global_state: Option<&GlobalState>
 ) -> Value {
     // 创建基本日志结构
     let mut log_data = json!({
         "signature": signature,
+        "slot": order.slot,
+        "transaction_index": order.transaction_index,
+        "instruction_index": order.instruction_index,
         "mint": mint_address,
         "signer": signer_address,
         "time": formatted_time,
@@ -1927,139 +5568,94 @@ fn extract_raw_cpi_log_data(
         log_data["curve_account"] = json!(curve);
     }
 
-    // 卖出操作的特殊处理 - 从associatedTokenProgram获取创建者金库地址
-    let is_sell_operation = match ix {
-        PumpProgramIx::Sell(_) => true,
-        _ => false
-    };
-
-    // 尝试从账户列表中提取创作者相关信息
-    if let Some(accounts_array) = accounts.as_array() {
-        // 查找创作者金库 - 在新IDL中，可能有多种命名方式
-        let mut creator_vault_pubkey = None;
-        
-        // 针对卖出操作的特殊处理：associatedTokenProgram账户(索引8)实际是创建者金库地址
-        if is_sell_operation {
-            // 查找associatedTokenProgram账户作为金库地址
-            let associated_token_program = accounts_array.iter().find(|obj| {
-                if let Some(name) = obj["name"].as_str() {
-                    let name_lower = name.to_lowercase();
-                    return name_lower == "associatedtokenprogram" || 
-                           name_lower == "associated_token_program" || 
-                           name_lower == "associated-token-program";
-                }
-                false
-            });
-            
-            if let Some(atp) = associated_token_program {
-                if let Some(atp_pubkey) = atp["pubkey"].as_str() {
-                    creator_vault_pubkey = Some(atp_pubkey.to_string());
-                    debug!("[金库] 卖出交易({})从associatedTokenProgram识别创作者金库地址: {}", signature, atp_pubkey);
-                }
-            }
+    // 卖出操作的特殊处理 - 从associatedTokenProgram获取创建者金库地址
+    let is_sell_operation = matches!(ix, PumpProgramIx::Sell(_));
+
+    // 按名称（不区分大小写）查找账户，IDL版本不同时同一账户可能有多种命名方式
+    let find_account = |names: &[&str]| {
+        accounts.iter().find(|account| names.contains(&account.name.to_lowercase().as_str()))
+    };
+
+    // 尝试从账户列表中提取创作者相关信息
+    // 查找创作者金库 - 在新IDL中，可能有多种命名方式
+    let mut creator_vault_pubkey = None;
+    
+    // 针对卖出操作的特殊处理：associatedTokenProgram账户(索引8)实际是创建者金库地址
+    if is_sell_operation {
+        // 查找associatedTokenProgram账户作为金库地址
+        if let Some(atp) = find_account(&["associatedtokenprogram", "associated_token_program", "associated-token-program"]) {
+            creator_vault_pubkey = Some(atp.pubkey.to_string());
+            debug!("[金库] 卖出交易({})从associatedTokenProgram识别创作者金库地址: {}", signature, atp.pubkey);
+        }
+    }
+    
+    // 如果是卖出操作但未找到associatedTokenProgram，或者是其他操作类型
+    // 继续使用原有的创建者金库识别逻辑
+    if creator_vault_pubkey.is_none() {
+        // 1. 首先查找传统的creator_vault名称
+        if let Some(vault) = find_account(&["creator_vault", "creatorvault", "creator-vault"]) {
+            creator_vault_pubkey = Some(vault.pubkey.to_string());
         }
         
-        // 如果是卖出操作但未找到associatedTokenProgram，或者是其他操作类型
-        // 继续使用原有的创建者金库识别逻辑
+        // 2. 如果没找到，检查rent字段(在某些新版本中，creator_vault被误标为rent)
         if creator_vault_pubkey.is_none() {
-            // 1. 首先查找传统的creator_vault名称
-            let creator_vault = accounts_array.iter().find(|obj| {
-                if let Some(name) = obj["name"].as_str() {
-                    let name_lower = name.to_lowercase();
-                    return name_lower == "creator_vault" || 
-                           name_lower == "creatorvault" || 
-                           name_lower == "creator-vault";
-                }
-                false
-            });
-            
-            if let Some(vault) = creator_vault {
-                creator_vault_pubkey = vault["pubkey"].as_str().map(|s| s.to_string());
-            }
-            
-            // 2. 如果没找到，检查rent字段(在某些新版本中，creator_vault被误标为rent)
-            if creator_vault_pubkey.is_none() {
-                if let Some(rent) = accounts_array.iter().find(|obj| obj["name"] == "rent") {
-                    // 确认这个rent不是实际的租金账户(实际的租金账户是固定的)
-                    let real_rent = "54Pgg7FuLuP13dRQoFPTH4FdZHi141bQDzVwukt6m8Tk";
-                    let rent_pubkey = rent["pubkey"].as_str().unwrap_or("");
-                    // 如果rent不是常规租金账户，它可能是creator_vault
-                    if rent_pubkey != "SysvarRent111111111111111111111111111111111" && 
-                       !rent_pubkey.is_empty() && rent_pubkey != "11111111111111111111111111111111" {
-                        creator_vault_pubkey = Some(rent_pubkey.to_string());
-                        debug!("[金库] 检测到rent({})可能是creator_vault", rent_pubkey);
-                    }
-                }
-            }
-            
-            // 3. 如果仍然没找到，检查feeRecipient(有些版本混淆了fee_recipient和creator_vault)
-            if creator_vault_pubkey.is_none() {
-                if let Some(fee_recipient) = accounts_array.iter().find(|obj| {
-                    if let Some(name) = obj["name"].as_str() {
-                        let name_lower = name.to_lowercase();
-                        return name_lower == "feerecipient" || name_lower == "fee_recipient";
-                    }
-                    false
-                }) {
-                    let fee_pubkey = fee_recipient["pubkey"].as_str().unwrap_or("");
-                    
-                    // 先记录fee_recipient
-                    log_data["fee_recipient"] = json!(fee_pubkey);
-                    
-                    // 在某些情况下，feeRecipient实际也是creator_vault
-                    if creator_vault_pubkey.is_none() && !fee_pubkey.is_empty() {
-                        // 只在没有找到其他creator_vault时，将fee_recipient视为creator_vault
-                        // 这是一个备选项，但不是首选
-                        debug!("[警告] 未找到明确的creator_vault，暂时使用feeRecipient({})代替", fee_pubkey);
-                    }
+            if let Some(rent) = accounts.iter().find(|account| account.name == "rent") {
+                // 确认这个rent不是实际的租金账户(实际的租金账户是固定的)
+                let rent_pubkey = rent.pubkey.to_string();
+                // 如果rent不是常规租金账户，它可能是creator_vault
+                if rent_pubkey != constants().rent_sysvar_id && rent.pubkey != Pubkey::default() {
+                    debug!("[金库] 检测到rent({})可能是creator_vault", rent_pubkey);
+                    creator_vault_pubkey = Some(rent_pubkey);
                 }
             }
         }
         
-        // 设置找到的creator_vault
-        if let Some(vault_pubkey) = creator_vault_pubkey {
-            log_data["creator_vault"] = json!(vault_pubkey);
-            debug!("[金库] 交易({})的创作者金库地址: {}", signature, vault_pubkey);
-            
-            // 尝试通过creator_vault找到creator
-            if let Some(creator) = find_creator_by_vault(&vault_pubkey) {
-                log_data["creator"] = json!(creator);
-                debug!("[Creator] 通过金库地址({})找到创建者: {}", vault_pubkey, creator);
+        // 3. 如果仍然没找到，检查feeRecipient(有些版本混淆了fee_recipient和creator_vault)
+        if creator_vault_pubkey.is_none() {
+            if let Some(fee_recipient) = find_account(&["feerecipient", "fee_recipient"]) {
+                // 先记录fee_recipient
+                log_data["fee_recipient"] = json!(fee_recipient.pubkey.to_string());
+                
+                // 在某些情况下，feeRecipient实际也是creator_vault
+                // 只在没有找到其他creator_vault时，将fee_recipient视为creator_vault
+                // 这是一个备选项，但不是首选
+                debug!("[警告] 未找到明确的creator_vault，暂时使用feeRecipient({})代替", fee_recipient.pubkey);
             }
-        } else {
-            debug!("[警告] 未找到creator_vault账户，交易类型: {}, signature: {}", ix.name(), signature);
         }
+    }
+    
+    // 设置找到的creator_vault
+    if let Some(vault_pubkey) = creator_vault_pubkey {
+        log_data["creator_vault"] = json!(vault_pubkey);
+        debug!("[金库] 交易({})的创作者金库地址: {}", signature, vault_pubkey);
         
-        // 确保fee_recipient也被记录（如果还没有）
-        if !log_data.get("fee_recipient").is_some() {
-            if let Some(fee_recipient) = accounts_array.iter().find(|obj| {
-                if let Some(name) = obj["name"].as_str() {
-                    let name_lower = name.to_lowercase();
-                    return name_lower == "feerecipient" || name_lower == "fee_recipient";
-                }
-                false
-            }) {
-                let fee_pubkey = fee_recipient["pubkey"].as_str().unwrap_or("");
-                if !fee_pubkey.is_empty() {
-                    log_data["fee_recipient"] = json!(fee_pubkey);
-                }
-            }
+        // 尝试通过creator_vault找到creator
+        if let Some(creator) = find_creator_by_vault(creator_map, cache, &vault_pubkey) {
+            log_data["creator"] = json!(creator);
+            debug!("[Creator] 通过金库地址({})找到创建者: {}", vault_pubkey, creator);
+        }
+    } else {
+        debug!("[警告] 未找到creator_vault账户，交易类型: {}, signature: {}", ix.name(), signature);
+    }
+    
+    // 确保fee_recipient也被记录（如果还没有）
+    if log_data.get("fee_recipient").is_none() {
+        if let Some(fee_recipient) = find_account(&["feerecipient", "fee_recipient"]) {
+            log_data["fee_recipient"] = json!(fee_recipient.pubkey.to_string());
         }
     }
     
     // 如果还没找到creator，尝试从mint地址查找
-    if !log_data.get("creator").is_some() {
-        if let Some(creator_address) = find_creator_by_mint(mint_address) {
+    if log_data.get("creator").is_none() {
+        if let Some(creator_address) = creator_map.get(mint_address) {
             log_data["creator"] = json!(creator_address);
             debug!("[Creator] 通过mint({})找到创建者: {}", mint_address, creator_address);
         }
     }
     
     // 添加Global账户信息（可用于获取fee_basis_points等）
-    if let Some(accounts_array) = accounts.as_array() {
-        if let Some(global) = accounts_array.iter().find(|obj| obj["name"] == "global") {
-            log_data["global_account"] = json!(global["pubkey"].as_str().unwrap_or(""));
-        }
+    if let Some(global) = accounts.iter().find(|account| account.name == "global") {
+        log_data["global_account"] = json!(global.pubkey.to_string());
     }
     
     // 根据指令类型添加特定字段
@@ -2073,15 +5669,9 @@ fn extract_raw_cpi_log_data(
             log_data["raw"] = json!({
                 "token_amount": buy_args.amount.to_string(),
                 "sol_amount": buy_args.max_sol_cost.to_string(),
-                "sol_amount_human": format!("{} SOL", buy_args.max_sol_cost as f64 / 1_000_000_000.0),
+                "sol_amount_human": Lamports(buy_args.max_sol_cost).to_string(),
             });
             
-            // 尝试计算创作者费用（这需要知道creator_fee_basis_points）
-            // 默认使用Global账户中的值或硬编码一个常见值（如100 = 1%）
-            let creator_fee_basis_points = 100; // 默认1%，实际应从Global账户获取
-            let creator_fee = calculate_creator_fee(buy_args.max_sol_cost, creator_fee_basis_points);
-            log_data["creator_fee_basis_points"] = json!(creator_fee_basis_points);
-            log_data["creator_fee"] = json!(creator_fee);
         },
         PumpProgramIx::Sell(sell_args) => {
             log_data["type"] = json!("Sell");
@@ -2092,41 +5682,45 @@ fn extract_raw_cpi_log_data(
             log_data["raw"] = json!({
                 "token_amount": sell_args.amount.to_string(),
                 "min_sol_output": sell_args.min_sol_output.to_string(),
-                "min_sol_output_human": format!("{} SOL", sell_args.min_sol_output as f64 / 1_000_000_000.0),
+                "min_sol_output_human": Lamports(sell_args.min_sol_output).to_string(),
             });
             
-            // 尝试计算创作者费用（这需要知道creator_fee_basis_points）
-            // 默认使用Global账户中的值或硬编码一个常见值（如100 = 1%）
-            let creator_fee_basis_points = 100; // 默认1%，实际应从Global账户获取
-            let creator_fee = calculate_creator_fee(sell_args.min_sol_output, creator_fee_basis_points);
-            log_data["creator_fee_basis_points"] = json!(creator_fee_basis_points);
-            log_data["creator_fee"] = json!(creator_fee);
         },
         _ => {
             log_data["type"] = json!(format!("{}", ix.name()));
         }
     }
 
+    // 计算协议费用和创作者费用（买入由max_sol_cost、卖出由min_sol_output还原交易金额）
+    if let Some(fees) = pump_trade_fees(ix, global_state) {
+        log_data["sol_amount_before_fees"] = json!(fees.sol_amount);
+        log_data["fee_basis_points"] = json!(fees.fee_basis_points);
+        log_data["fee_amount"] = json!(fees.protocol_fee);
+        log_data["creator_fee_basis_points"] = json!(fees.creator_fee_basis_points);
+        log_data["creator_fee"] = json!(fees.creator_fee);
+    }
+    
+    // 添加Global账户中的费用接收者
+    if let Some(global) = global_state {
+        log_data["global_fee_recipient"] = json!(global.fee_recipient);
+    }
+
     // 其余代码保持不变
     // 添加所有账户信息
-    if let Some(accounts_array) = accounts.as_array() {
-        // 完整保存原始账户数组
-        log_data["raw_accounts"] = accounts.clone();
-        
-        // 同时提供更易读的账户信息
-        let mut accounts_map = serde_json::Map::new();
-        for (idx, account) in accounts_array.iter().enumerate() {
-            if let (Some(name), Some(pubkey)) = (account["name"].as_str(), account["pubkey"].as_str()) {
-                accounts_map.insert(name.to_string(), json!({
-                    "pubkey": pubkey,
-                    "index": idx,
-                    "is_signer": account["is_signer"].as_bool().unwrap_or(false),
-                    "is_writable": account["is_writable"].as_bool().unwrap_or(false),
-                }));
-            }
-        }
-        log_data["accounts_by_name"] = json!(accounts_map);
+    // 完整保存原始账户数组
+    log_data["raw_accounts"] = json!(accounts);
+    
+    // 同时提供更易读的账户信息
+    let mut accounts_map = serde_json::Map::new();
+    for (idx, account) in accounts.iter().enumerate() {
+        accounts_map.insert(account.name.clone(), json!({
+            "pubkey": account.pubkey.to_string(),
+            "index": idx,
+            "is_signer": account.is_signer,
+            "is_writable": account.is_writable,
+        }));
     }
+    log_data["accounts_by_name"] = json!(accounts_map);
 
     // 添加原始指令数据和完整指令名称
     match ix {
@@ -2167,42 +5761,6 @@ fn extract_raw_cpi_log_data(
     log_data
 }
 
-/// 计算创作者费用
-fn calculate_creator_fee(amount: u64, fee_basis_points: u64) -> u64 {
-    // 计算创作者费用（amount * fee_basis_points / 10000）
-    // 使用更安全的计算方式，避免溢出
-    if amount == 0 || fee_basis_points == 0 {
-        return 0;
-    }
-    
-    // 计算 amount * fee_basis_points / 10000 前先检查是否可能溢出
-    if let Some(product) = amount.checked_mul(fee_basis_points) {
-        product / 10000
-    } else {
-        // 如果可能溢出，使用一种安全的替代计算方法
-        let amount_f64 = amount as f64;
-        let fee_percent = fee_basis_points as f64 / 10000.0;
-        (amount_f64 * fee_percent) as u64
-    }
-}
-
-// 在文件末尾添加
-/// 为了兼容创建者信息的查找，提供一个函数接口
-/// 由于BondingCurve结构体中没有creator字段，这个函数仅依赖映射表查找
-fn get_creator_for_mint(mint_address: &str) -> Option<String> {
-    find_creator_by_mint(mint_address)
-}
-
-/// 尝试通过其他方式获取创建者信息，不依赖BondingCurve结构体
-fn get_creator_for_curve(mint_address: Option<&str>) -> String {
-    if let Some(mint) = mint_address {
-        if let Some(creator) = find_creator_by_mint(mint) {
-            return creator;
-        }
-    }
-    "未知".to_string()
-}
-
 /// 从日志数据中提取创作者金库地址
 fn extract_creator_vault_from_log(log_data: &str) -> Option<String> {
     // 尝试查找包含创作者金库地址的行
@@ -2276,12 +5834,1126 @@ fn extract_creator_vault_from_log(log_data: &str) -> Option<String> {
 }
 
 /// 从金库地址查找创建者地址
-fn find_creator_by_vault(vault_address: &str) -> Option<String> {
+fn find_creator_by_vault(creator_map: &CreatorMap, cache: Option<&TransactionCache>, vault_address: &str) -> Option<String> {
     // 先尝试直接在映射中查找金库地址
-    if let Some(creator) = find_creator_by_mint(vault_address) {
+    if let Some(creator) = creator_map.get(vault_address) {
         return Some(creator);
     }
     
-    // 如果直接查找失败，尝试其他方式
-    None
-}
\ No newline at end of file
+    // 再查找从绑定曲线账户学到（或从Redis恢复）的金库映射
+    cache.and_then(|cache_ref| cache_ref.get_vault_creator(vault_address))
+}
+
+/// 从创建者地址计算创作者金库地址
+fn calculate_creator_vault_from_creator(creator: &Pubkey) -> Option<String> {
+    let seeds = &[b"creator-vault", creator.as_ref()];
+    let (derived_pubkey, _) = Pubkey::find_program_address(seeds, &constants().pump_program_id());
+    Some(derived_pubkey.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calculate_price_six_decimal_token() {
+        // pump初始储备：1,073,000,000 个代币(6位精度) 对 30 SOL
+        let price = calculate_price(1_073_000_000_000_000, 30_000_000_000, 6);
+        assert!((price - 30.0 / 1_073_000_000.0).abs() < 1e-15);
+    }
+
+    #[test]
+    fn calculate_price_nine_decimal_token() {
+        // 相同的人类可读储备，代币精度为9
+        let price = calculate_price(1_073_000_000_000_000_000, 30_000_000_000, 9);
+        assert!((price - 30.0 / 1_073_000_000.0).abs() < 1e-15);
+    }
+
+    fn bonding_curve_account_bytes(creator: &Pubkey) -> Vec<u8> {
+        let mut buf = BONDING_CURVE_ACCOUNT_DISCM.to_vec();
+        for value in [1_073_000_000_000_000u64, 30_000_000_000, 793_100_000_000_000, 0, 1_000_000_000_000_000] {
+            buf.extend_from_slice(&value.to_le_bytes());
+        }
+        buf.push(0);
+        buf.extend_from_slice(creator.as_ref());
+        buf.resize(256, 0); // 链上账户末尾有填充
+        buf
+    }
+
+    #[test]
+    fn decode_bonding_curve_from_data_slice() {
+        let creator = Pubkey::new_unique();
+        let full = bonding_curve_account_bytes(&creator);
+        let sliced = &full[..account_data_slice_len(false)];
+
+        match decode_account_data(sliced) {
+            Ok(DecodedAccount::BondingCurve(curve, decoded_creator)) => {
+                assert_eq!(curve.virtual_sol_reserves, 30_000_000_000);
+                assert_eq!(decoded_creator, Some(creator));
+            }
+            _ => panic!("切片后的绑定曲线数据应能解码"),
+        }
+    }
+
+    #[test]
+    fn decode_truncated_account_data() {
+        let full = bonding_curve_account_bytes(&Pubkey::new_unique());
+        // 缺少creator字段时仍可解码储备
+        assert!(matches!(
+            decode_account_data(&full[..BONDING_CURVE_CREATOR_OFFSET]),
+            Ok(DecodedAccount::BondingCurve(_, None))
+        ));
+        assert!(decode_account_data(&full[..20]).is_err());
+        assert!(decode_account_data(&full[..4]).is_err());
+    }
+
+    #[test]
+    fn decode_zero_creator_as_none() {
+        // 旧版账户在储备字段之后只有全零填充
+        let old_layout = bonding_curve_account_bytes(&Pubkey::default());
+        assert!(matches!(
+            decode_account_data(&old_layout),
+            Ok(DecodedAccount::BondingCurve(_, None))
+        ));
+    }
+
+    #[test]
+    fn extract_reserves_from_account_text() {
+        let text = "ACCOUNT TYPE: BondingCurve\nVIRTUAL TOKEN RESERVES: 1073000000000000\nVIRTUAL SOL RESERVES: 30000000000\n";
+        assert_eq!(extract_reserves_from_account_data(text), Some((1_073_000_000_000_000, 30_000_000_000)));
+    }
+
+    #[test]
+    fn extract_reserves_rejects_malformed_text() {
+        // 不是绑定曲线账户
+        assert_eq!(extract_reserves_from_account_data("ACCOUNT TYPE: Global\nVIRTUAL TOKEN RESERVES: 1\nVIRTUAL SOL RESERVES: 2"), None);
+        // 缺少字段
+        assert_eq!(extract_reserves_from_account_data("BondingCurve\nVIRTUAL TOKEN RESERVES: 1"), None);
+        // 数值无法解析
+        assert_eq!(extract_reserves_from_account_data("BondingCurve\nVIRTUAL TOKEN RESERVES: abc\nVIRTUAL SOL RESERVES: 2"), None);
+        assert_eq!(extract_reserves_from_account_data("BondingCurve\nVIRTUAL TOKEN RESERVES: -1\nVIRTUAL SOL RESERVES: 2"), None);
+        assert_eq!(extract_reserves_from_account_data("BondingCurve\nVIRTUAL TOKEN RESERVES:\nVIRTUAL SOL RESERVES: 2"), None);
+        assert_eq!(extract_reserves_from_account_data(""), None);
+    }
+
+    #[test]
+    fn calculate_price_zero_token_reserves() {
+        assert_eq!(calculate_price(0, 30_000_000_000, DEFAULT_TOKEN_DECIMALS), 0.0);
+    }
+
+    #[test]
+    fn pump_fee_rounds_up() {
+        assert_eq!(pump_fee(1_000_000_000, 95), 9_500_000);
+        assert_eq!(pump_fee(1_000_000_001, 95), 9_500_001);
+        assert_eq!(pump_fee(1, 5), 1);
+        assert_eq!(pump_fee(0, 95), 0);
+    }
+
+    #[test]
+    fn pump_fees_from_buy_total() {
+        // 1 SOL曲线交易金额，协议费率95bp、创作者费率5bp：用户共支付1.01 SOL
+        let fees = PumpFees::from_buy_total(1_010_000_000, 95, 5);
+        assert_eq!(fees.sol_amount, 1_000_000_000);
+        assert_eq!(fees.protocol_fee, 9_500_000);
+        assert_eq!(fees.creator_fee, 500_000);
+    }
+
+    #[test]
+    fn pump_fees_from_sell_net() {
+        // 1 SOL曲线交易金额扣除1%费用后用户收到0.99 SOL
+        let fees = PumpFees::from_sell_net(990_000_000, 95, 5);
+        assert_eq!(fees.sol_amount, 1_000_000_000);
+        assert_eq!(fees.protocol_fee, 9_500_000);
+        assert_eq!(fees.creator_fee, 500_000);
+    }
+
+    #[test]
+    fn pump_fees_match_recorded_mainnet_trades() {
+        // 主网绑定曲线在单笔买卖前后的储备（取自 logs/transactions.log，2025-05-06；当时协议费率为100bp，没有创作者费），
+        // 格式为 (曲线账户, 成交前(虚拟代币, 虚拟SOL), 成交后(虚拟代币, 虚拟SOL), 用户支付总额或收到的净额, 协议费用)
+        let trades = [
+            ("37D9KxsUfY8UyVvEGrUGNN1xab5ohX3UJJTLUXqkNa4A", (1_033_682_117_612_295u64, 31_141_101_772u64), (1_002_138_775_015_581u64, 32_121_299_791u64), 990_000_000, 9_801_981),
+            ("FeUJy19ymHmCHS2pcmFT1tyj1knE5d7ArrdpPScuvf3S", (602_375_635_877_444, 53_438_416_424), (597_944_627_469_575, 53_834_416_424), 399_960_000, 3_960_000),
+            ("5rqhTUdTt9QCy7t6HTePxFZ1Z6C2iaCCNzTbgd7ABd9Z", (790_175_881_888_549, 40_737_765_826), (806_496_592_255_475, 39_913_373_903), 816_148_003, 8_243_920),
+            ("7TB9FH2Qn7cSy9uf7yLoQVcEojbZBTfqBUN6y7wb4Cvo", (733_372_630_420_531, 43_893_102_710), (735_682_185_439_912, 43_755_307_426), 136_417_331, 1_377_953),
+        ];
+        for (curve, before, after, user_amount, protocol_fee) in trades {
+            let is_buy = after.1 > before.1;
+            // 曲线SOL储备的变化就是不含费用的交易金额
+            let sol_amount = before.1.abs_diff(after.1);
+            let fees = if is_buy {
+                PumpFees::from_buy_total(user_amount, 100, 0)
+            } else {
+                PumpFees::from_sell_net(user_amount, 100, 0)
+            };
+            assert_eq!(fees, PumpFees::on_sol_amount(sol_amount, 100, 0), "{}", curve);
+            assert_eq!(fees.protocol_fee, protocol_fee, "{}", curve);
+            assert_eq!(fees.creator_fee, 0, "{}", curve);
+            let user_side = if is_buy { sol_amount + fees.protocol_fee } else { sol_amount - fees.protocol_fee };
+            assert_eq!(user_side, user_amount, "{}", curve);
+        }
+    }
+
+    #[test]
+    fn slippage_from_balance_changes() {
+        // 签名者（索引0）买入花费0.9 SOL（另付5000 lamports手续费），卖出收到1.1 SOL
+        let buy = BalanceChanges { pre_balances: vec![2_000_000_000], post_balances: vec![1_099_995_000], fee: 5_000, ..Default::default() };
+        let sell = BalanceChanges { pre_balances: vec![1_000_000_000], post_balances: vec![2_099_995_000], fee: 5_000, ..Default::default() };
+        assert_eq!(buy.spent(0), Some(900_000_000));
+        assert_eq!(buy.spent_on_trade(0), Some(900_000_000));
+        assert_eq!(sell.received(0), Some(1_100_000_000));
+        assert_eq!(buy.received(0), None);
+        assert_eq!(slippage_pct(900_000_000, 1_000_000_000, true), Some(10.0));
+        assert!((slippage_pct(1_100_000_000, 1_000_000_000, false).unwrap() - 10.0).abs() < 1e-9);
+        assert!(slippage_pct(1_100_000_000, 1_000_000_000, true).unwrap() < 0.0);
+        // 限价为0时不计算，避免除零
+        assert_eq!(slippage_pct(900_000_000, 0, true), None);
+    }
+
+    #[test]
+    fn buy_cost_excludes_created_token_account_rent() {
+        use yellowstone_grpc_proto::prelude::TokenBalance;
+        // 签名者（索引0）买入0.9 SOL，同时为自己创建ATA（索引2，租金2039280 lamports）；索引1的代币账户交易前已存在
+        let token_balance = |account_index| TokenBalance { account_index, ..Default::default() };
+        let meta = TransactionStatusMeta {
+            fee: 5_000,
+            pre_balances: vec![2_000_000_000, 2_039_280, 0],
+            post_balances: vec![1_097_955_720, 2_039_280, 2_039_280],
+            pre_token_balances: vec![token_balance(1)],
+            post_token_balances: vec![token_balance(1), token_balance(2)],
+            ..Default::default()
+        };
+        let changes = BalanceChanges::new(Some(&meta));
+        assert_eq!(changes.spent(0), Some(902_039_280));
+        assert_eq!(changes.spent_on_trade(0), Some(900_000_000));
+    }
+
+    #[test]
+    fn malformed_account_key_skips_instruction() {
+        let account_keys = AccountKeys {
+            keys: vec![Pubkey::new_unique().to_bytes().to_vec(), vec![1, 2, 3]],
+            header: None,
+            num_static: 2,
+            num_loaded_writable: 0,
+        };
+        let valid = CompiledInstruction { program_id_index: 0, accounts: vec![0], data: vec![] };
+        let malformed = CompiledInstruction { program_id_index: 0, accounts: vec![0, 1], data: vec![] };
+        assert_eq!(build_account_metas(&valid, &account_keys).map(|metas| metas.len()), Some(1));
+        assert!(build_account_metas(&malformed, &account_keys).is_none());
+    }
+
+    #[test]
+    fn bonding_curve_filter_subscribes_global_separately() {
+        let features: Features = toml::from_str(
+            r#"
+            basic_transaction_monitoring = true
+            advanced_event_detection = true
+            token_transaction_monitoring = false
+            account_monitoring = true
+            log_to_file = false
+            log_file_path = "logs/transactions.log"
+            enable_cache = false
+            cpi_log_json = false
+            cpi_log_json_dir = "logs/cpi_json"
+            cpi_log_json_max_files = 30
+            bonding_curve_account_filter = true
+            "#,
+        )
+        .unwrap();
+        let args = Args::parse_from(["copy-bot"]);
+        let request = args.get_account_updates(PUMP_PROGRAM_ID, vec![], CommitmentLevel::Processed, None, &features).unwrap();
+        let program_filter = &request.accounts["accountData"];
+        assert_eq!(program_filter.filters.len(), 1);
+        assert!(matches!(
+            &program_filter.filters[0].filter,
+            Some(AccountsFilter::Memcmp(memcmp)) if memcmp.data == Some(MemcmpData::Bytes(BONDING_CURVE_ACCOUNT_DISCM.to_vec()))
+        ));
+        assert_eq!(request.accounts["globalAccount"].account, vec!["4wTV1YmiEkRvAtNtsSGPtUrqRYQMe5SKy2uB4Jjaxnjf".to_string()]);
+    }
+
+    #[test]
+    fn account_access_follows_message_header() {
+        // 6个账户：1个可写签名者、1个只读签名者、2个可写非签名者、2个只读非签名者
+        let header = MessageHeader {
+            num_required_signatures: 2,
+            num_readonly_signed_accounts: 1,
+            num_readonly_unsigned_accounts: 2,
+        };
+        let access: Vec<_> = (0..6).map(|i| account_access(i, &header, 6)).collect();
+        assert_eq!(
+            access,
+            vec![
+                (true, true),
+                (true, false),
+                (false, true),
+                (false, true),
+                (false, false),
+                (false, false),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn replay_reads_captured_updates_in_order() {
+        use yellowstone_grpc_proto::prelude::{SubscribeUpdatePong, SubscribeUpdateSlot};
+
+        let updates = vec![
+            SubscribeUpdate {
+                filters: vec!["client".to_string()],
+                created_at: None,
+                update_oneof: Some(UpdateOneof::Slot(SubscribeUpdateSlot { slot: 42, parent: Some(41), status: 0, dead_error: None })),
+            },
+            SubscribeUpdate {
+                filters: vec![],
+                created_at: None,
+                update_oneof: Some(UpdateOneof::Pong(SubscribeUpdatePong { id: 1 })),
+            },
+        ];
+        let mut bytes: Vec<u8> = updates.iter().flat_map(update_file::encode_record).collect();
+        let path = env::temp_dir().join(format!("copy-bot-replay-{}.bin", std::process::id()));
+        fs::write(&path, &bytes).unwrap();
+
+        let replayed: Vec<_> = update_file::replay(&path).await.unwrap().collect().await;
+        assert_eq!(replayed.into_iter().map(Result::unwrap).collect::<Vec<_>>(), updates);
+
+        // 文件末尾的记录被截断时，前面的记录照常回放，最后产生一个错误
+        bytes.extend_from_slice(&[10, 0, 0, 0, 1]);
+        fs::write(&path, &bytes).unwrap();
+        let replayed: Vec<_> = update_file::replay(&path).await.unwrap().collect().await;
+        fs::remove_file(&path).unwrap();
+        assert_eq!(replayed.len(), 3);
+        assert!(replayed[..2].iter().all(Result::is_ok));
+        assert!(replayed[2].is_err());
+    }
+
+    #[test]
+    fn validate_reports_every_invalid_address() {
+        let config: Config = toml::from_str(
+            r#"
+            grpc_endpoint = "http://127.0.0.1:10000"
+            redis_url = "redis://127.0.0.1/"
+            pump_program_id = "not-a-program"
+            monitored_addresses = [
+                "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P",
+                "bad0address",
+                "11111111111111111111111111111111",
+                "O0O0",
+            ]
+            "#,
+        )
+        .unwrap();
+        let message = config.validate().unwrap_err().to_string();
+        assert!(message.contains("3 个无效的地址"));
+        assert!(message.contains("monitored_addresses[1] = \"bad0address\""));
+        assert!(message.contains("monitored_addresses[3] = \"O0O0\""));
+        assert!(message.contains("pump_program_id = \"not-a-program\""));
+        assert!(!message.contains("monitored_addresses[0]"));
+    }
+
+    #[test]
+    fn event_log_levels_parse_off_and_reject_unknown() {
+        let levels = EventLogLevels::parse(Some("TRACE"), None, Some("off"), None).unwrap();
+        assert_eq!(levels.account, Some(log::Level::Trace));
+        assert_eq!(levels.trade, Some(log::Level::Info));
+        assert_eq!(levels.unmatched_trade, None);
+        let message = EventLogLevels::parse(None, Some("loud"), None, None).unwrap_err().to_string();
+        assert!(message.contains("trade_log_level"));
+    }
+
+    #[test]
+    fn global_state_reports_fee_recipient_change() {
+        let previous = GlobalState {
+            authority: "authority".to_string(),
+            fee_recipient: "old".to_string(),
+            fee_basis_points: 100,
+            creator_fee_basis_points: Some(5),
+            initial_virtual_token_reserves: 1,
+            initial_virtual_sol_reserves: 2,
+            initial_real_token_reserves: 3,
+            token_total_supply: 4,
+            slot: 10,
+        };
+        let unchanged = GlobalState { slot: 11, initial_virtual_sol_reserves: 20, ..previous.clone() };
+        assert!(unchanged.changes_from(&previous).is_empty());
+
+        let changed = GlobalState { fee_recipient: "new".to_string(), ..unchanged };
+        assert_eq!(changed.changes_from(&previous), vec!["fee_recipient: old -> new".to_string()]);
+    }
+
+    #[test]
+    fn queue_lag_reported_near_capacity() {
+        let mut lag = QueueLagMonitor::new(10);
+        assert!(!lag.observe(7));
+        assert!(lag.observe(8));
+        assert!(lag.observe(10));
+    }
+
+    #[test]
+    fn cache_reader_parses_cached_trade_and_reserves() {
+        let bc = BondingCurve {
+            virtual_token_reserves: 1_000_000,
+            virtual_sol_reserves: 30_000,
+            real_token_reserves: 800_000,
+            real_sol_reserves: 1_000,
+            token_total_supply: 1_000_000_000,
+            complete: false,
+        };
+        let mut account = bonding_curve_summary("Curve111", &bc);
+        account.push_str("CREATOR: Creator111\n");
+        let reserves = cache_reader::CurveReserves::parse(&account).unwrap();
+        assert_eq!((reserves.curve_account.as_str(), reserves.virtual_sol_reserves), ("Curve111", 30_000));
+        assert_eq!((reserves.real_token_reserves, reserves.complete), (800_000, false));
+        assert_eq!(reserves.creator.as_deref(), Some("Creator111"));
+
+        let trade = format!(
+            "[FAILED] TYPE: Buy\nMINT: Mint111\nTOKEN AMOUNT: 500\nSOL COST: 0.01 SOL\nMAX SOL COST: 0.02 SOL\n{}TIME: 2024-01-01 00:00:00\nSLOT: 42\nSIGNATURE: Sig111\n签名者地址: Signer111\n手续费支付者: Relayer111\n\nMINT地址:\nMint111\n\n关联曲线账户:\nCurve111\n\n绑定曲线账户数据:\n{}\n\n虚拟储备信息:\n虚拟代币储备: 1000000\n虚拟SOL储备: 30000\n\n价格信息:\n当前价格: 0.03 SOL",
+            format_slippage_line(Some(50.0)),
+            account
+        );
+        let record = cache_reader::TradeRecord::parse(&trade).unwrap();
+        assert!(record.failed);
+        assert_eq!((record.transaction_type.as_str(), record.mint.as_str(), record.token_amount), ("Buy", "Mint111", 500));
+        assert_eq!((record.sol_cost, record.max_sol_cost, record.sol_output), (Some(0.01), Some(0.02), None));
+        assert_eq!((record.slippage_pct, record.slot), (Some(50.0), Some(42)));
+        assert_eq!((record.signature.as_str(), record.signer.as_str()), ("Sig111", "Signer111"));
+        assert_eq!(record.fee_payer.as_deref(), Some("Relayer111"));
+        assert_eq!(record.curve_account.as_deref(), Some("Curve111"));
+        assert_eq!((record.virtual_token_reserves, record.price), (Some(1_000_000), Some(0.03)));
+        assert!(cache_reader::TradeRecord::parse("ACCOUNT TYPE: BondingCurve").is_none());
+    }
+
+    #[test]
+    fn trade_hash_fields_round_trip() {
+        let order = EventOrder { slot: 42, transaction_index: 3, instruction_index: 1 };
+        let mut entry = CpiLogEntry::for_trade(
+            "Sell", "Mint111", 500, 0.01, "2024-01-01 00:00:00", "Sig111", order, "Signer111",
+            Some(0.03), Some(1_000_000), None, Some("Curve111".to_string()), None,
+        );
+        entry.actual_sol_output = Some(0.012);
+        entry.fee_payer = Some("Relayer111".to_string());
+        let fields = entry.hash_fields();
+        // 空值不写入哈希
+        assert!(!fields.iter().any(|(field, _)| field == "virtual_sol_reserves" || field == "creator"));
+        let fields: HashMap<String, String> = fields.into_iter().collect();
+        assert_eq!(fields["slot"], "42");
+        assert_eq!(fields["mint"], "Mint111");
+
+        let record = cache_reader::TradeRecord::from_hash(&fields).unwrap();
+        assert_eq!((record.transaction_type.as_str(), record.token_amount, record.failed), ("Sell", 500, false));
+        assert_eq!((record.sol_output, record.min_sol_output, record.sol_cost), (Some(0.012), Some(0.01), None));
+        assert_eq!((record.slot, record.virtual_token_reserves, record.virtual_sol_reserves), (Some(42), Some(1_000_000), None));
+        assert_eq!(record.curve_account.as_deref(), Some("Curve111"));
+        assert_eq!((record.signer.as_str(), record.fee_payer.as_deref()), ("Signer111", Some("Relayer111")));
+    }
+
+    #[test]
+    fn dust_trades_use_actual_amount_when_known() {
+        let features: Features = toml::from_str(
+            r#"
+            basic_transaction_monitoring = true
+            advanced_event_detection = true
+            token_transaction_monitoring = false
+            account_monitoring = false
+            log_to_file = false
+            log_file_path = "logs/transactions.log"
+            enable_cache = false
+            cpi_log_json = false
+            cpi_log_json_dir = "logs/cpi_json"
+            cpi_log_json_max_files = 30
+            min_sol_amount = 0.01
+            dust_trade_action = "skip"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(features.dust_trade_action, Some(DustTradeAction::Skip));
+        let order = EventOrder { slot: 1, transaction_index: 0, instruction_index: 0 };
+        // 卖出的 min_sol_output 为0，但实际收到的SOL超过阈值
+        let mut sell = CpiLogEntry::for_trade("Sell", "Mint111", 500, 0.0, "", "Sig111", order, "Signer111", None, None, None, None, None);
+        assert!(features.is_dust_trade(&sell));
+        sell.actual_sol_output = Some(0.5);
+        assert!(!features.is_dust_trade(&sell));
+        let mut buy = CpiLogEntry::for_trade("Buy", "Mint111", 500, 1.0, "", "Sig222", order, "Signer111", None, None, None, None, None);
+        buy.actual_sol_cost = Some(0.001);
+        assert!(features.is_dust_trade(&buy));
+    }
+
+    #[test]
+    fn truncated_known_instruction_is_distinguished_from_unknown() {
+        let mut truncated_buy = BUY_IX_DISCM.to_vec();
+        truncated_buy.extend_from_slice(&[1, 2, 3]);
+        assert!(PumpProgramIx::deserialize(&truncated_buy).is_err());
+        assert!(is_known_pump_discriminator(&truncated_buy));
+        assert!(!is_known_pump_discriminator(&[0u8; 16]));
+        assert!(!is_known_pump_discriminator(&BUY_IX_DISCM[..4]));
+    }
+
+    #[test]
+    fn priority_fee_from_compute_budget_instructions() {
+        use solana_sdk::compute_budget::ComputeBudgetInstruction;
+        let limit = ComputeBudgetInstruction::set_compute_unit_limit(100_000);
+        let price = ComputeBudgetInstruction::set_compute_unit_price(250_000);
+        let message = Message {
+            header: Some(MessageHeader { num_required_signatures: 2, ..Default::default() }),
+            account_keys: vec![limit.program_id.to_bytes().to_vec(), PUMP_PROGRAM_ID.parse::<Pubkey>().unwrap().to_bytes().to_vec()],
+            instructions: vec![
+                CompiledInstruction { program_id_index: 0, accounts: vec![], data: limit.data },
+                CompiledInstruction { program_id_index: 0, accounts: vec![], data: price.data.clone() },
+                CompiledInstruction { program_id_index: 1, accounts: vec![], data: vec![] },
+            ],
+            ..Default::default()
+        };
+        let account_keys = AccountKeys::new(&message, None);
+        let fee = PriorityFee::from_message(&message, &account_keys);
+        assert_eq!((fee.compute_unit_limit, fee.compute_unit_price), (100_000, 250_000));
+        assert_eq!(fee.priority_fee, 25_000);
+        assert_eq!(fee.total_fee, 35_000);
+
+        // 未设置上限时按非ComputeBudget指令数使用默认上限
+        let mut without_limit = message.clone();
+        without_limit.instructions.remove(0);
+        let fee = PriorityFee::from_message(&without_limit, &account_keys);
+        assert_eq!((fee.compute_unit_limit, fee.priority_fee), (200_000, 50_000));
+    }
+
+    #[test]
+    fn cli_subcommands_share_config_flag() {
+        let args = Args::parse_from(["copy-bot"]);
+        assert!(args.command.is_none());
+        assert_eq!(args.config, PathBuf::from("config.toml"));
+
+        let args = Args::parse_from(["copy-bot", "capture", "out.bin", "--from-slot", "5", "--config", "other.toml"]);
+        assert_eq!(args.capture_path(), Some(PathBuf::from("out.bin")));
+        assert_eq!(args.monitor_args().from_slot, Some(5));
+        assert_eq!(args.config, PathBuf::from("other.toml"));
+        assert!(args.replay_path().is_none());
+
+        let args = Args::parse_from(["copy-bot", "-c", "other.toml", "replay", "in.bin"]);
+        assert_eq!(args.replay_path(), Some(PathBuf::from("in.bin")));
+        assert!(matches!(
+            Args::parse_from(["copy-bot", "query", "mint-trades", "Mint111"]).command,
+            Some(Command::Query(QueryCommand::MintTrades { limit: 20, .. }))
+        ));
+        assert!(Args::try_parse_from(["copy-bot", "replay"]).is_err());
+    }
+
+    #[test]
+    fn market_value_uses_sold_tokens_as_circulating_supply() {
+        let value = MarketValue::estimate(Some(1e-8), Some(1_000_000_000_000_000), Some(693_100_000_000_000), None, 6).unwrap();
+        assert!((value.market_cap_sol.unwrap() - 1.0).abs() < 1e-9);
+        assert!((value.fdv_sol - 10.0).abs() < 1e-9);
+        assert_eq!(format_market_value_line(Some(&value)), "MARKET CAP: 1.00 SOL\nFDV: 10.00 SOL\n");
+        // 真实储备未知时只有FDV，价格或供应量未知时不估算
+        let value = MarketValue::estimate(Some(1e-8), Some(u64::MAX), None, None, 6).unwrap();
+        assert!(value.market_cap_sol.is_none() && value.fdv_sol.is_finite());
+        assert!(MarketValue::estimate(None, Some(1), Some(0), None, 6).is_none());
+        assert!(MarketValue::estimate(Some(1e-8), Some(0), Some(0), None, 6).is_none());
+    }
+
+    #[test]
+    fn monitor_profiles_use_top_level_features_and_reject_shared_outputs() {
+        let base = r#"
+            grpc_endpoint = "http://127.0.0.1:10000"
+            redis_url = "redis://127.0.0.1/"
+            monitored_addresses = ["6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P"]
+
+            [features]
+            basic_transaction_monitoring = true
+            advanced_event_detection = false
+            token_transaction_monitoring = false
+            account_monitoring = false
+            log_to_file = true
+            log_file_path = "logs/a.log"
+            enable_cache = false
+            cpi_log_json = false
+            cpi_log_json_dir = "logs/cpi_json"
+            cpi_log_json_max_files = 30
+        "#;
+        let single: Config = toml::from_str(base).unwrap();
+        let profiles = single.monitor_profiles();
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].name, DEFAULT_MONITOR_NAME);
+        assert_eq!(profiles[0].monitored_addresses, single.monitored_addresses);
+
+        let multi = format!(
+            "{}{}",
+            base,
+            r#"
+            [[monitors]]
+            name = "whales"
+            monitored_addresses = ["11111111111111111111111111111111"]
+            watched_mints = ["DCLjJRAP4PineCmCabTKRrTVsSaggkmfgBj8AMPapump"]
+
+            [[monitors]]
+            name = "snipers"
+            [monitors.features]
+            basic_transaction_monitoring = true
+            advanced_event_detection = true
+            token_transaction_monitoring = false
+            account_monitoring = false
+            log_to_file = true
+            log_file_path = "logs/b.log"
+            enable_cache = false
+            cpi_log_json = false
+            cpi_log_json_dir = "logs/cpi_json"
+            cpi_log_json_max_files = 30
+            "#
+        );
+        let config: Config = toml::from_str(&multi).unwrap();
+        config.validate().unwrap();
+        let profiles = config.monitor_profiles();
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0].name, "whales");
+        assert_eq!(profiles[0].watched_mints.len(), 1);
+        assert!(!profiles[0].features.advanced_event_detection);
+        assert!(profiles[1].monitored_addresses.is_empty());
+        assert!(profiles[1].features.advanced_event_detection);
+
+        let shared = multi.replace("logs/b.log", "logs/a.log");
+        let config: Config = toml::from_str(&shared).unwrap();
+        let message = config.validate().unwrap_err().to_string();
+        assert!(message.contains("whales") && message.contains("snipers"));
+
+        let duplicate = multi.replace("\"snipers\"", "\"whales\"");
+        let config: Config = toml::from_str(&duplicate).unwrap();
+        assert!(config.validate().unwrap_err().to_string().contains("名称重复"));
+
+        // 第一个监控配置沿用顶层features，服务端口和NDJSON套接字不能与其他监控配置相同
+        let with_outputs = |top: &str, snipers: &str| {
+            let multi = multi.replacen("cpi_log_json_max_files = 30", &format!("cpi_log_json_max_files = 30\n{}", top), 1);
+            toml::from_str::<Config>(&format!("{}{}\n", multi, snipers)).unwrap().validate()
+        };
+        with_outputs("ws_port = 9001", "ws_port = 9002").unwrap();
+        let message = with_outputs("ws_port = 9001", "ws_port = 9001").unwrap_err().to_string();
+        assert!(message.contains("whales.ws_port") && message.contains("snipers.ws_port"), "{}", message);
+        let message = with_outputs("ws_port = 9001", "api_port = 9001").unwrap_err().to_string();
+        assert!(message.contains("snipers.api_port"), "{}", message);
+        let socket = "ndjson_unix_socket = \"/tmp/trades.sock\"";
+        assert!(with_outputs(socket, socket).unwrap_err().to_string().contains("NDJSON"));
+        // 顶层的指标端口同样不能被监控配置的服务端口占用
+        let message = with_outputs("metrics_port = 9001", "ws_port = 9001").unwrap_err().to_string();
+        assert!(message.contains("features.metrics_port") && message.contains("snipers.ws_port"), "{}", message);
+        let message = with_outputs("health_port = 9001", "ndjson_tcp_port = 9001").unwrap_err().to_string();
+        assert!(message.contains("features.health_port"), "{}", message);
+
+        // 启用缓存的监控配置不能共用Redis键前缀
+        let cached = multi.replace("enable_cache = false", "enable_cache = true");
+        let message = toml::from_str::<Config>(&cached).unwrap().validate().unwrap_err().to_string();
+        assert!(message.contains("redis_key_prefix"), "{}", message);
+        let prefixed = format!("{}redis_key_prefix = \"snipers\"\n", cached);
+        toml::from_str::<Config>(&prefixed).unwrap().validate().unwrap();
+    }
+
+    #[test]
+    fn evict_oldest_trims_to_ninety_percent_keeping_newest() {
+        let map = DashMap::new();
+        let start = UNIX_EPOCH + Duration::from_secs(1_000);
+        for index in 0..21u64 {
+            map.insert(index.to_string(), CacheItem { data: String::new(), timestamp: start + Duration::from_secs(index) });
+        }
+        assert_eq!(evict_oldest(&map, 0), 0);
+        assert_eq!(evict_oldest(&map, 30), 0);
+        assert_eq!(evict_oldest(&map, 20), 3);
+        assert_eq!(map.len(), 18);
+        assert!(!map.contains_key("2") && map.contains_key("3") && map.contains_key("20"));
+    }
+
+    #[test]
+    fn config_validates_grpc_tls() {
+        let base = r#"
+            grpc_endpoint = "https://127.0.0.1:10000"
+            redis_url = "redis://127.0.0.1/"
+
+            [grpc_tls]
+            client_cert_path = "certs/client.pem"
+        "#;
+        let config: Config = toml::from_str(base).unwrap();
+        assert!(config.validate().unwrap_err().to_string().contains("client_key_path"));
+
+        let config: Config = toml::from_str("grpc_endpoint = \"http://127.0.0.1:10000\"\nredis_url = \"redis://127.0.0.1/\"\n").unwrap();
+        config.validate().unwrap();
+        assert!(config.grpc_tls.is_none());
+    }
+
+    #[test]
+    fn grpc_x_token_is_validated_and_masked() {
+        let base = "grpc_endpoint = \"https://127.0.0.1:10000\"\nredis_url = \"redis://127.0.0.1/\"\n";
+        let config: Config = toml::from_str(&format!("{}grpc_x_token = \"abcd1234efgh5678\"\n", base)).unwrap();
+        config.validate().unwrap();
+        assert_eq!(mask_token(config.grpc_x_token.as_deref().unwrap()), "abcd****");
+        assert_eq!(mask_token("short"), "****");
+
+        let config: Config = toml::from_str(&format!("{}grpc_x_token = \"secret-token\\n\"\n", base)).unwrap();
+        let message = config.validate().unwrap_err().to_string();
+        assert!(message.contains("grpc_x_token") && !message.contains("secret-token"));
+    }
+
+    #[test]
+    fn cpi_log_entry_applies_trade_event() {
+        let sell = PumpTradeEvent {
+            mint: "mint".to_string(),
+            sol_amount: 2_000_000_000,
+            token_amount: 5_000_000,
+            is_buy: false,
+            user: "user".to_string(),
+            timestamp: 1_700_000_000,
+            virtual_sol_reserves: 31_000_000_000,
+            virtual_token_reserves: 1_000_000_000_000,
+            real_sol_reserves: Some(1_000_000_000),
+            real_token_reserves: Some(700_000_000_000),
+            fee_recipient: Some("recipient".to_string()),
+            fee_basis_points: Some(95),
+            fee: Some(9_500_000),
+            creator: Some("creator".to_string()),
+            creator_fee_basis_points: Some(5),
+            creator_fee: Some(500_000),
+        };
+        let mut entry = CpiLogEntry::for_trade(
+            "Sell", "mint", sell.token_amount, 1.0, "", "sig", EventOrder::default(), "user", None, None, None, None, None,
+        );
+        entry.apply_trade_event(&sell);
+        assert!(entry.trade_event);
+        assert_eq!(entry.fee_recipient.as_deref(), Some("recipient"));
+        assert_eq!(entry.fee_amount, Some(9_500_000));
+        assert_eq!(entry.creator_fee, Some(500_000));
+    }
+
+    #[test]
+    fn transaction_processor_decodes_without_cache() {
+        use yellowstone_grpc_proto::prelude::Transaction;
+        // 不配置缓存（不连接Redis）时，解码只依赖交易本身
+        let config: Config = toml::from_str(
+            r#"
+            grpc_endpoint = "http://127.0.0.1:10000"
+            redis_url = "redis://127.0.0.1/"
+            pump_idl_path = "idls/pump.json"
+        "#,
+        )
+        .unwrap();
+        let processor = TransactionProcessor {
+            programs: Arc::new(config.load_programs().unwrap()),
+            features: Features::fallback(),
+            cache: None,
+            creator_map: Arc::new(CreatorMap::load(None).unwrap()),
+            watched_mints: Arc::default(),
+        };
+
+        // 0: user, 1: feeRecipient, 2: bondingCurve, 3: associatedBondingCurve, 4: associatedUser,
+        // 5: global, 6: mint, 7: system, 8: token, 9: rent, 10: eventAuthority, 11: pump
+        let user = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let mut keys: Vec<Vec<u8>> = vec![user.to_bytes().to_vec()];
+        keys.extend((1..=5).map(|_| Pubkey::new_unique().to_bytes().to_vec()));
+        keys.push(mint.to_bytes().to_vec());
+        keys.push(vec![0; 32]);
+        keys.push(Pubkey::from_str(TOKEN_PROGRAM_ID).unwrap().to_bytes().to_vec());
+        keys.push(Pubkey::from_str(RENT_SYSVAR_ID).unwrap().to_bytes().to_vec());
+        keys.push(Pubkey::new_unique().to_bytes().to_vec());
+        keys.push(Pubkey::from_str(PUMP_PROGRAM_ID).unwrap().to_bytes().to_vec());
+
+        let mut buy_data = BUY_IX_DISCM.to_vec();
+        buy_data.extend_from_slice(&5_000_000u64.to_le_bytes());
+        buy_data.extend_from_slice(&1_100_000_000u64.to_le_bytes());
+        let mut transfer_data = vec![3];
+        transfer_data.extend_from_slice(&5_000_000u64.to_le_bytes());
+        let message = Message {
+            header: Some(MessageHeader { num_required_signatures: 1, num_readonly_signed_accounts: 0, num_readonly_unsigned_accounts: 7 }),
+            account_keys: keys,
+            instructions: vec![
+                CompiledInstruction { program_id_index: 11, accounts: vec![5, 1, 6, 2, 3, 4, 0, 7, 8, 9, 10, 11], data: buy_data },
+                CompiledInstruction { program_id_index: 8, accounts: vec![3, 4, 0], data: transfer_data },
+            ],
+            ..Default::default()
+        };
+        let meta = TransactionStatusMeta {
+            fee: 5_000,
+            pre_balances: vec![2_000_000_000, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            post_balances: vec![999_995_000, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            ..Default::default()
+        };
+        let account_keys = AccountKeys::new(&message, Some(&meta));
+        let txn = SubscribeUpdateTransactionInfo {
+            transaction: Some(Transaction { signatures: vec![vec![1; 64]], message: Some(message) }),
+            meta: Some(meta),
+            ..Default::default()
+        };
+
+        let events = processor.process(txn, 42, "sig".to_string(), account_keys, true);
+        let buy = events.iter().find_map(|event| match event {
+            MonitorEvent::Buy(trade) => Some(trade),
+            _ => None,
+        });
+        let buy = buy.expect("buy event");
+        assert_eq!(buy.entry.mint, mint.to_string());
+        assert_eq!(buy.entry.signer, user.to_string());
+        assert_eq!(buy.entry.actual_sol_cost, Some(1.0));
+        assert_eq!(buy.slot, 42);
+        let transfer = events.iter().find_map(|event| match event {
+            MonitorEvent::TokenInstruction(event) => event.instruction.as_ref(),
+            _ => None,
+        });
+        assert_eq!(serde_json::to_value(transfer.unwrap()).unwrap(), json!({ "Transfer": { "amount": 5_000_000 } }));
+    }
+
+    #[test]
+    fn event_handler_dispatches_to_sinks_after_dust_filter() {
+        struct RecordingSink(Arc<std::sync::Mutex<Vec<String>>>);
+        impl EventSink for RecordingSink {
+            fn handle(&self, event: &MonitorEvent) {
+                if let MonitorEvent::Buy(trade) | MonitorEvent::Sell(trade) | MonitorEvent::Create(trade) = event {
+                    self.0.lock().unwrap().push(trade.entry.signature.clone());
+                }
+            }
+        }
+        let trade = |signature: &str, sol: f64| {
+            let mut entry = CpiLogEntry::for_trade("Buy", "mint", 1, sol, "", signature, EventOrder::default(), "signer", None, None, None, None, None);
+            entry.actual_sol_cost = Some(sol);
+            MonitorEvent::Buy(TradeEvent {
+                entry,
+                raw: json!({}),
+                log_message: String::new(),
+                enhanced_data: String::new(),
+                slot: 1,
+                is_monitored: true,
+                json: OnceLock::new(),
+            })
+        };
+
+        let features = Features { min_sol_amount: Some(0.1), ..Features::fallback() };
+        let outputs = || SinkOutputs {
+            cache: None,
+            trade_broadcaster: None,
+            cpi_log_writer: None,
+            event_publisher: None,
+            signer_filter: Arc::default(),
+        };
+        // 只启用了日志输出
+        assert_eq!(build_sinks(&features, outputs()).unwrap().len(), 1);
+
+        let mut handler = EventHandler::new(&features, outputs()).unwrap();
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        handler.sinks.push(Box::new(RecordingSink(seen.clone())));
+        handler.handle(trade("dust", 0.01));
+        handler.handle(trade("real", 1.0));
+        assert_eq!(*seen.lock().unwrap(), ["real"]);
+    }
+
+    #[test]
+    fn feature_overrides_from_cli_apply_to_all_profiles() {
+        let args = Args::try_parse_from(["copy-bot", "replay", "updates.bin", "--no-account-monitoring", "--enable-cpi-json", "--log-file", "logs/debug.log"]).unwrap();
+        let mut config: Config = toml::from_str(
+            r#"
+            grpc_endpoint = "http://127.0.0.1:10000"
+            redis_url = "redis://127.0.0.1/"
+
+            [[monitors]]
+            name = "a"
+
+            [[monitors]]
+            name = "b"
+            [monitors.features]
+            basic_transaction_monitoring = true
+            advanced_event_detection = false
+            token_transaction_monitoring = true
+            account_monitoring = true
+            log_to_file = false
+            log_file_path = ""
+            enable_cache = false
+            cpi_log_json = false
+            cpi_log_json_dir = "logs/cpi_json"
+            cpi_log_json_max_files = 30
+            "#,
+        )
+        .unwrap();
+        config.apply_feature_overrides(&args.feature_overrides);
+        for profile in config.monitor_profiles() {
+            assert!(!profile.features.account_monitoring);
+            assert!(profile.features.cpi_log_json);
+            assert!(profile.features.log_to_file);
+            assert_eq!(profile.features.log_file_path, "logs/debug.log");
+        }
+        // 两个监控配置被改为写同一个日志文件
+        assert!(config.validate().is_err());
+
+        let args = Args::try_parse_from(["copy-bot"]).unwrap();
+        let mut features = Features::fallback();
+        assert!(args.feature_overrides.apply(&mut features).is_empty());
+        assert!(Args::try_parse_from(["copy-bot", "--enable-cache", "--no-cache"]).is_err());
+    }
+
+    #[test]
+    fn set_params_updates_global_state_and_keeps_authority() {
+        let previous = GlobalState {
+            authority: "authority".to_string(),
+            fee_recipient: "old".to_string(),
+            fee_basis_points: 100,
+            creator_fee_basis_points: Some(5),
+            initial_virtual_token_reserves: 1,
+            initial_virtual_sol_reserves: 2,
+            initial_real_token_reserves: 3,
+            token_total_supply: 4,
+            slot: 10,
+        };
+        let fee_recipient = Pubkey::new_unique();
+        let params = SetParamsIxArgs { fee_recipient, fee_basis_points: 95, token_total_supply: 4, ..Default::default() };
+
+        let state = GlobalState::with_params(Some(&previous), "signer", &params, 20);
+        assert_eq!(state.authority, "authority");
+        assert_eq!(state.creator_fee_basis_points, Some(5));
+        assert_eq!(state.slot, 20);
+        assert_eq!(
+            state.changes_from(&previous),
+            vec![format!("fee_recipient: old -> {}", fee_recipient), "fee_basis_points: 100 -> 95".to_string()]
+        );
+        let first = GlobalState::with_params(None, "signer", &params, 20);
+        assert_eq!((first.authority.as_str(), first.creator_fee_basis_points), ("signer", None));
+
+        let event = AdminEvent {
+            action: AdminAction::SetParams(params),
+            authority: "signer".to_string(),
+            signature: "sig".to_string(),
+            slot: 20,
+            time: String::new(),
+            failed: false,
+        };
+        assert_eq!(event.label(), "PARAMS_CHANGE");
+        let value = event.to_json();
+        assert_eq!(value["type"], "params_change");
+        assert_eq!(value["fee_recipient"], fee_recipient.to_string());
+        assert_eq!(value["fee_basis_points"], 95);
+
+        let withdraw = AdminEvent {
+            action: AdminAction::Withdraw { mint: "mint".to_string(), bonding_curve: None },
+            ..event
+        };
+        assert_eq!(withdraw.label(), "WITHDRAW");
+        assert_eq!(withdraw.to_json()["mint"], "mint");
+    }
+
+    #[test]
+    fn heartbeat_reports_uptime_counts_and_slot() {
+        let metrics = Metrics::new();
+        let message = metrics.heartbeat_message(Duration::from_secs(3725), 300, None);
+        assert!(message.contains("运行 1h02m05s"));
+        assert!(message.contains("尚未收到槽位"));
+
+        Metrics::add(&metrics.transactions_processed, 12);
+        Metrics::inc(&metrics.buys);
+        metrics.observe_slot(200);
+        metrics.observe_slot(150);
+        let message = metrics.heartbeat_message(Duration::from_secs(59), 61, None);
+        assert!(message.contains("运行 0h00m59s, 已处理 12 笔交易（买入 1, 卖出 0）"));
+        assert!(message.contains("当前槽位 200"));
+        assert!(message.ends_with("已 61 秒没有输出事件"));
+
+        assert_eq!(metrics.secs_since_last_event(), None);
+        metrics.mark_event_emitted();
+        assert!(metrics.secs_since_last_event().is_some_and(|secs| secs <= 1));
+    }
+
+    #[test]
+    fn priority_fee_log_line_shows_sol() {
+        let fee = PriorityFee { compute_unit_limit: 200_000, compute_unit_price: 1_000, priority_fee: 200, total_fee: 5_200 };
+        assert_eq!(fee.log_line(), "PRIORITY FEE: 0.0000002 SOL (CU LIMIT: 200000, CU PRICE: 1000 micro-lamports, TOTAL FEE: 0.0000052 SOL)\n");
+    }
+
+    #[test]
+    fn initialize_account_instructions_yield_token_account_owner() {
+        let keys = ["ata", "mint", "wallet"];
+        let key = |pos: usize| keys.get(pos).map(|key| key.to_string());
+        let owner = Pubkey::new_unique();
+
+        assert_eq!(
+            token_account_from_instruction(&TokenInstruction::InitializeAccount, key),
+            Some(("ata".to_string(), "wallet".to_string(), "mint".to_string()))
+        );
+        assert_eq!(
+            token_account_from_instruction(&TokenInstruction::InitializeAccount3 { owner }, key),
+            Some(("ata".to_string(), owner.to_string(), "mint".to_string()))
+        );
+        assert_eq!(token_account_from_instruction(&TokenInstruction::InitializeAccount, |pos| (pos < 2).then(|| key(pos)).flatten()), None);
+        assert_eq!(token_account_from_instruction(&TokenInstruction::Transfer { amount: 1 }, key), None);
+    }
+
+    #[test]
+    fn cache_stats_serialize_and_label_occupancy() {
+        let cache = CacheStats { buy_transactions: 2, sell_transactions: 1, account_data: 5, latest_account_data: 4, latest_reserves: 3 };
+        assert_eq!(serde_json::to_value(cache).unwrap()["latest_reserves"], 3);
+        assert_eq!(cache.by_label()[2], ("account", 5));
+    }
+
+    #[test]
+    fn price_impact_from_trade_event() {
+        assert_eq!(price_impact((1_000, 100), (800, 125)), Some((125.0 / 800.0 - 0.1) / 0.1));
+        assert_eq!(price_impact((0, 100), (800, 125)), None);
+
+        // 成交后储备 (800, 125)，买入 200 个代币花费 25 lamports：成交前为 (1000, 100)
+        let buy = PumpTradeEvent {
+            mint: "mint".to_string(),
+            sol_amount: 25,
+            token_amount: 200,
+            is_buy: true,
+            user: "user".to_string(),
+            timestamp: 0,
+            virtual_sol_reserves: 125,
+            virtual_token_reserves: 800,
+            real_sol_reserves: None,
+            real_token_reserves: None,
+            fee_recipient: None,
+            fee_basis_points: None,
+            fee: None,
+            creator: None,
+            creator_fee_basis_points: None,
+            creator_fee: None,
+        };
+        assert_eq!(buy.reserves_before(), Some((1_000, 100)));
+        let impact = trade_price_impact(&buy).unwrap();
+        assert!((impact - 0.5625).abs() < 1e-9);
+
+        // 卖出 200 个代币得到 25 lamports：成交前为 (600, 150)
+        let sell = PumpTradeEvent { is_buy: false, ..buy };
+        assert_eq!(sell.reserves_before(), Some((600, 150)));
+        let impact = trade_price_impact(&sell).unwrap();
+        assert!((impact - (0.15625 / 0.25 - 1.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn from_slot_clamped_to_retention_and_resumed_after_reconnect() {
+        assert!(clamp_from_slot(1_001, 1_000, 100).is_err());
+        assert_eq!(clamp_from_slot(950, 1_000, 100).unwrap(), 950);
+        assert_eq!(clamp_from_slot(500, 1_000, 100).unwrap(), 900);
+        assert_eq!(clamp_from_slot(0, 50, 100).unwrap(), 0);
+
+        let progress = StreamProgress::default();
+        assert_eq!(progress.resume_from(None), None);
+        assert_eq!(progress.resume_from(Some(900)), Some(900));
+        progress.observe_slot(905);
+        progress.observe_slot(903);
+        assert_eq!(progress.resume_from(Some(900)), Some(905));
+        assert_eq!(progress.resume_from(None), None);
+    }
+
+    #[test]
+    fn address_mode_subscribes_only_to_monitored_addresses() {
+        let wallet = Pubkey::new_unique();
+        let profile = |addresses: &str, all_program_transactions: bool| {
+            let config: Config = toml::from_str(&format!(
+                r#"
+                grpc_endpoint = "http://127.0.0.1:10000"
+                redis_url = "redis://127.0.0.1/"
+                monitored_addresses = [{}]
+                "#,
+                addresses
+            ))
+            .unwrap();
+            let mut profile = config.monitor_profiles().remove(0);
+            profile.features.all_program_transactions = all_program_transactions;
+            (config.load_programs().unwrap(), profile)
+        };
+        let args = Args::parse_from(["copy-bot"]);
+        let keys = |keys: &[Pubkey]| AccountKeys { keys: keys.iter().map(|key| key.to_bytes().to_vec()).collect(), ..Default::default() };
+        let pump = Pubkey::from_str(PUMP_PROGRAM_ID).unwrap();
+        let program_ids = vec![PUMP_PROGRAM_ID.to_string()];
+
+        // 按地址监听：订阅中不包含程序ID，只有涉及监听地址的交易算作监听交易
+        let (programs, watching) = profile(&format!("\"{}\"", wallet), false);
+        let request = args.get_txn_updates(&watching, &program_ids, CommitmentLevel::Processed, false, None).unwrap();
+        assert_eq!(request.transactions["client"].account_include, vec![wallet.to_string()]);
+        let monitored = monitored_addresses(&request, &programs);
+        assert_eq!(monitored, vec![wallet.to_string()]);
+        assert!(involves_monitored_address(&keys(&[wallet, pump]), &monitored));
+        assert!(!involves_monitored_address(&keys(&[Pubkey::new_unique(), pump]), &monitored));
+
+        // 全市场模式：开启 all_program_transactions 时订阅监控程序的全部交易，没有监听地址
+        let (programs, all_program) = profile("", true);
+        let request = args.get_txn_updates(&all_program, &program_ids, CommitmentLevel::Processed, false, None).unwrap();
+        assert_eq!(request.transactions["client"].account_include, program_ids);
+        let monitored = monitored_addresses(&request, &programs);
+        assert!(monitored.is_empty());
+        assert!(!involves_monitored_address(&keys(&[wallet, pump]), &monitored));
+
+        // 没有监听地址也没有开启全市场模式时拒绝订阅
+        let (_, empty) = profile("", false);
+        assert!(args.get_txn_updates(&empty, &program_ids, CommitmentLevel::Processed, false, None).is_err());
+    }
+
+    #[test]
+    fn routed_buy_matches_trade_event_by_instruction_user() {
+        use base64::Engine;
+        use yellowstone_grpc_proto::prelude::Transaction;
+        let config: Config = toml::from_str(
+            r#"
+            grpc_endpoint = "http://127.0.0.1:10000"
+            redis_url = "redis://127.0.0.1/"
+            pump_idl_path = "idls/pump.json"
+        "#,
+        )
+        .unwrap();
+        let processor = TransactionProcessor {
+            programs: Arc::new(config.load_programs().unwrap()),
+            features: Features::fallback(),
+            cache: None,
+            creator_map: Arc::new(CreatorMap::load(None).unwrap()),
+            watched_mints: Arc::default(),
+        };
+
+        // 路由程序代为调用：签名者是 0（路由钱包），指令中的user是不签名的 12
+        // 1: feeRecipient, 2: bondingCurve, 3: associatedBondingCurve, 4: associatedUser,
+        // 5: global, 6: mint, 7: system, 8: token, 9: rent, 10: eventAuthority, 11: pump
+        let user = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let mut keys: Vec<Vec<u8>> = (0..=5).map(|_| Pubkey::new_unique().to_bytes().to_vec()).collect();
+        keys.push(mint.to_bytes().to_vec());
+        keys.push(vec![0; 32]);
+        keys.push(Pubkey::from_str(TOKEN_PROGRAM_ID).unwrap().to_bytes().to_vec());
+        keys.push(Pubkey::from_str(RENT_SYSVAR_ID).unwrap().to_bytes().to_vec());
+        keys.push(Pubkey::new_unique().to_bytes().to_vec());
+        keys.push(Pubkey::from_str(PUMP_PROGRAM_ID).unwrap().to_bytes().to_vec());
+        keys.push(user.to_bytes().to_vec());
+
+        let mut buy_data = BUY_IX_DISCM.to_vec();
+        buy_data.extend_from_slice(&5_000_000u64.to_le_bytes());
+        buy_data.extend_from_slice(&1_100_000_000u64.to_le_bytes());
+        let message = Message {
+            header: Some(MessageHeader { num_required_signatures: 1, num_readonly_signed_accounts: 0, num_readonly_unsigned_accounts: 6 }),
+            account_keys: keys,
+            instructions: vec![CompiledInstruction { program_id_index: 11, accounts: vec![5, 1, 6, 2, 3, 4, 12, 7, 8, 9, 10, 11], data: buy_data }],
+            ..Default::default()
+        };
+
+        let mut event = pump_interface::TRADE_EVENT_EVENT_DISCM.to_vec();
+        event.extend_from_slice(mint.as_ref());
+        event.extend_from_slice(&1_000_000_000u64.to_le_bytes());
+        event.extend_from_slice(&5_000_000u64.to_le_bytes());
+        event.push(1);
+        event.extend_from_slice(user.as_ref());
+        event.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        event.extend_from_slice(&31_000_000_000u64.to_le_bytes());
+        event.extend_from_slice(&1_000_000_000_000u64.to_le_bytes());
+        let meta = TransactionStatusMeta {
+            log_messages: vec![
+                "Program 6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P invoke [1]".to_string(),
+                format!("Program data: {}", base64::engine::general_purpose::STANDARD.encode(event)),
+            ],
+            ..Default::default()
+        };
+        let account_keys = AccountKeys::new(&message, Some(&meta));
+        let txn = SubscribeUpdateTransactionInfo {
+            transaction: Some(Transaction { signatures: vec![vec![1; 64]], message: Some(message) }),
+            meta: Some(meta),
+            ..Default::default()
+        };
+
+        let events = processor.process(txn, 42, "sig".to_string(), account_keys, true);
+        let buy = events.iter().find_map(|event| match event {
+            MonitorEvent::Buy(trade) => Some(trade),
+            _ => None,
+        });
+        let buy = buy.expect("buy event");
+        // user不是签名者（没有缓存时无法找到钱包），仍按指令的user账户匹配到TradeEvent，使用事件中的储备和实际花费
+        assert_eq!(buy.entry.signer, "未知");
+        assert!(buy.entry.trade_event);
+        assert_eq!(buy.entry.virtual_token_reserves, Some(1_000_000_000_000));
+        assert_eq!(buy.entry.virtual_sol_reserves, Some(31_000_000_000));
+        assert_eq!(buy.entry.actual_sol_cost, Some(1.0));
+    }
+}
@@ -0,0 +1,278 @@
+use crate::TransactionCache;
+use axum::{http::header, routing::get, Json, Router};
+use log::info;
+use serde_json::{json, Value};
+use std::{
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// 全局运行指标，供Prometheus抓取
+pub static METRICS: Metrics = Metrics::new();
+
+/// 运行指标计数器
+pub struct Metrics {
+    pub transactions_processed: AtomicU64,
+    pub buys: AtomicU64,
+    pub sells: AtomicU64,
+    pub account_updates: AtomicU64,
+    pub redis_write_failures: AtomicU64,
+    pub redis_writes_skipped: AtomicU64,
+    pub stream_reconnects: AtomicU64,
+    // 待解码交易队列占用超过阈值（处理落后）的次数
+    pub processing_lag: AtomicU64,
+    // 待解码交易队列当前深度
+    pub transaction_queue_depth: AtomicU64,
+    // 未能发布到消息队列的事件数
+    pub event_publish_failures: AtomicU64,
+    // 低于 min_sol_amount 被过滤的小额买卖交易数
+    pub dust_trades_filtered: AtomicU64,
+    // 鉴别器匹配但参数解析失败的PumpFun指令数
+    pub instruction_decode_failures: AtomicU64,
+    // 超出每个代币写入配额而未写入文件的CPI日志数
+    pub cpi_logs_rate_limited: AtomicU64,
+    // 内存缓存超过条目上限时按时间戳淘汰的条目数
+    pub cache_size_evictions: AtomicU64,
+    // Redis写入断路器是否打开（1为打开）
+    pub redis_circuit_open: AtomicU64,
+    // 最近一次收到gRPC消息的Unix时间（秒），0表示尚未收到
+    pub last_message_unix_secs: AtomicU64,
+    // 最近一次输出事件的Unix时间（秒），0表示尚未输出
+    pub last_event_unix_secs: AtomicU64,
+    // 收到的交易和账户更新中最大的槽位
+    pub current_slot: AtomicU64,
+}
+
+impl Metrics {
+    pub(crate) const fn new() -> Self {
+        Self {
+            transactions_processed: AtomicU64::new(0),
+            buys: AtomicU64::new(0),
+            sells: AtomicU64::new(0),
+            account_updates: AtomicU64::new(0),
+            redis_write_failures: AtomicU64::new(0),
+            redis_writes_skipped: AtomicU64::new(0),
+            stream_reconnects: AtomicU64::new(0),
+            processing_lag: AtomicU64::new(0),
+            transaction_queue_depth: AtomicU64::new(0),
+            event_publish_failures: AtomicU64::new(0),
+            dust_trades_filtered: AtomicU64::new(0),
+            instruction_decode_failures: AtomicU64::new(0),
+            cpi_logs_rate_limited: AtomicU64::new(0),
+            cache_size_evictions: AtomicU64::new(0),
+            redis_circuit_open: AtomicU64::new(0),
+            last_message_unix_secs: AtomicU64::new(0),
+            last_event_unix_secs: AtomicU64::new(0),
+            current_slot: AtomicU64::new(0),
+        }
+    }
+
+    /// 计数器加一
+    pub fn inc(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 计数器增加指定值
+    pub fn add(counter: &AtomicU64, value: u64) {
+        counter.fetch_add(value, Ordering::Relaxed);
+    }
+
+    /// 记录收到了一条gRPC消息（交易和账户数据流都会调用）
+    pub fn mark_message_received(&self) {
+        self.last_message_unix_secs.store(unix_now_secs(), Ordering::Relaxed);
+    }
+
+    /// 距离最近一次收到gRPC消息的秒数，尚未收到时返回None
+    pub fn secs_since_last_message(&self) -> Option<u64> {
+        secs_since(&self.last_message_unix_secs)
+    }
+
+    /// 记录输出了一个事件（通过小额过滤、交给输出目标的事件）
+    pub fn mark_event_emitted(&self) {
+        self.last_event_unix_secs.store(unix_now_secs(), Ordering::Relaxed);
+    }
+
+    /// 距离最近一次输出事件的秒数，尚未输出时返回None
+    pub fn secs_since_last_event(&self) -> Option<u64> {
+        secs_since(&self.last_event_unix_secs)
+    }
+
+    /// 记录收到的槽位，只保留最大值
+    pub fn observe_slot(&self, slot: u64) {
+        self.current_slot.fetch_max(slot, Ordering::Relaxed);
+    }
+
+    /// 心跳日志内容：运行时间、处理计数、缓存统计、当前槽位和距上次事件的秒数
+    pub fn heartbeat_message(&self, uptime: Duration, idle_secs: u64, cache: Option<&TransactionCache>) -> String {
+        let uptime = uptime.as_secs();
+        let mut message = format!(
+            "[心跳] 运行 {}h{:02}m{:02}s, 已处理 {} 笔交易（买入 {}, 卖出 {}）, {} 次账户更新",
+            uptime / 3600,
+            uptime / 60 % 60,
+            uptime % 60,
+            self.transactions_processed.load(Ordering::Relaxed),
+            self.buys.load(Ordering::Relaxed),
+            self.sells.load(Ordering::Relaxed),
+            self.account_updates.load(Ordering::Relaxed),
+        );
+        if let Some(cache) = cache {
+            let stats = cache.get_stats();
+            let _ = write!(
+                message,
+                ", 缓存 {} 个买入/{} 个卖出/{} 个账户/{} 个储备",
+                stats.buy_transactions, stats.sell_transactions, stats.account_data, stats.latest_reserves
+            );
+        }
+        match self.current_slot.load(Ordering::Relaxed) {
+            0 => message.push_str(", 尚未收到槽位"),
+            slot => {
+                let _ = write!(message, ", 当前槽位 {}", slot);
+            }
+        }
+        let _ = write!(message, ", 已 {} 秒没有输出事件", idle_secs);
+        message
+    }
+
+    /// 以Prometheus文本格式输出所有指标
+    pub fn render(&self, cache: Option<&TransactionCache>) -> String {
+        let mut out = String::new();
+        let counters = [
+            ("pump_transactions_processed_total", "已处理的交易总数", &self.transactions_processed),
+            ("pump_buys_total", "检测到的买入交易数", &self.buys),
+            ("pump_sells_total", "检测到的卖出交易数", &self.sells),
+            ("pump_account_updates_total", "已处理的账户更新数", &self.account_updates),
+            ("pump_redis_write_failures_total", "Redis写入失败次数", &self.redis_write_failures),
+            ("pump_redis_writes_skipped_total", "断路器打开期间跳过的Redis写入数", &self.redis_writes_skipped),
+            ("pump_stream_reconnects_total", "数据流断开需要重连的次数", &self.stream_reconnects),
+            ("pump_processing_lag_total", "待解码交易队列接近满（处理落后）的次数", &self.processing_lag),
+            ("pump_event_publish_failures_total", "未能发布到消息队列的事件数", &self.event_publish_failures),
+            ("pump_dust_trades_filtered_total", "低于 min_sol_amount 被过滤的小额买卖交易数", &self.dust_trades_filtered),
+            ("pump_instruction_decode_failures_total", "鉴别器匹配但参数解析失败的PumpFun指令数", &self.instruction_decode_failures),
+            ("pump_cpi_logs_rate_limited_total", "超出每个代币写入配额而未写入文件的CPI日志数", &self.cpi_logs_rate_limited),
+            ("pump_cache_size_evictions_total", "内存缓存超过条目上限时淘汰的最旧条目数", &self.cache_size_evictions),
+        ];
+        for (name, help, counter) in counters {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            let _ = writeln!(out, "{} {}", name, counter.load(Ordering::Relaxed));
+        }
+        let _ = writeln!(out, "# HELP pump_redis_circuit_open Redis写入断路器是否打开");
+        let _ = writeln!(out, "# TYPE pump_redis_circuit_open gauge");
+        let _ = writeln!(out, "pump_redis_circuit_open {}", self.redis_circuit_open.load(Ordering::Relaxed));
+        let _ = writeln!(out, "# HELP pump_transaction_queue_depth 待解码交易队列当前深度");
+        let _ = writeln!(out, "# TYPE pump_transaction_queue_depth gauge");
+        let _ = writeln!(out, "pump_transaction_queue_depth {}", self.transaction_queue_depth.load(Ordering::Relaxed));
+
+        if let Some(cache) = cache {
+            let _ = writeln!(out, "# HELP pump_cache_size 内存缓存中的条目数");
+            let _ = writeln!(out, "# TYPE pump_cache_size gauge");
+            for (label, value) in cache.get_stats().by_label() {
+                let _ = writeln!(out, "pump_cache_size{{cache=\"{}\"}} {}", label, value);
+            }
+        }
+
+        out
+    }
+
+    /// `/stats` 的JSON内容：累计处理数、内存缓存当前条目数（未启用缓存时为null）、当前槽位和空闲时间
+    pub fn stats_json(&self, cache: Option<&TransactionCache>) -> Value {
+        json!({
+            "totals": {
+                "transactions_processed": self.transactions_processed.load(Ordering::Relaxed),
+                "buys": self.buys.load(Ordering::Relaxed),
+                "sells": self.sells.load(Ordering::Relaxed),
+                "account_updates": self.account_updates.load(Ordering::Relaxed),
+                "cache_size_evictions": self.cache_size_evictions.load(Ordering::Relaxed),
+            },
+            "cache": cache.map(TransactionCache::get_stats),
+            "current_slot": match self.current_slot.load(Ordering::Relaxed) {
+                0 => None,
+                slot => Some(slot),
+            },
+            "secs_since_last_message": self.secs_since_last_message(),
+            "secs_since_last_event": self.secs_since_last_event(),
+        })
+    }
+}
+
+fn unix_now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+fn secs_since(unix_secs: &AtomicU64) -> Option<u64> {
+    match unix_secs.load(Ordering::Relaxed) {
+        0 => None,
+        last => Some(unix_now_secs().saturating_sub(last)),
+    }
+}
+
+/// 每 `interval` 检查一次，连续 `interval` 以上没有输出事件时以info级别输出一行心跳日志
+pub async fn heartbeat(interval: Duration, cache: Option<Arc<TransactionCache>>) {
+    let started = std::time::Instant::now();
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        // 尚未输出过事件时从启动开始计算
+        let idle_secs = METRICS.secs_since_last_event().unwrap_or_else(|| started.elapsed().as_secs());
+        if idle_secs >= interval.as_secs() {
+            info!("{}", METRICS.heartbeat_message(started.elapsed(), idle_secs, cache.as_deref()));
+        }
+    }
+}
+
+/// 启动指标HTTP服务，在 `/metrics` 路径提供Prometheus格式数据，在 `/stats` 路径提供JSON格式的统计
+pub async fn serve(port: u16, cache: Option<Arc<TransactionCache>>) -> anyhow::Result<()> {
+    let stats_cache = cache.clone();
+    let app = Router::new()
+        .route(
+            "/metrics",
+            get(move || {
+                let cache = cache.clone();
+                async move {
+                    (
+                        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+                        METRICS.render(cache.as_deref()),
+                    )
+                }
+            }),
+        )
+        .route(
+            "/stats",
+            get(move || {
+                let cache = stats_cache.clone();
+                async move { Json(METRICS.stats_json(cache.as_deref())) }
+            }),
+        );
+
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await?;
+    info!("[Metrics] 指标服务已启动: http://0.0.0.0:{}/metrics, 统计: /stats", port);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_json_separates_totals_from_cache_occupancy() {
+        let metrics = Metrics::new();
+        Metrics::add(&metrics.buys, 3);
+        Metrics::inc(&metrics.account_updates);
+        let stats = metrics.stats_json(None);
+        assert_eq!(stats["totals"]["buys"], 3);
+        assert_eq!(stats["totals"]["account_updates"], 1);
+        assert!(stats["cache"].is_null());
+        assert!(stats["current_slot"].is_null());
+
+        metrics.observe_slot(42);
+        assert_eq!(metrics.stats_json(None)["current_slot"], 42);
+    }
+}
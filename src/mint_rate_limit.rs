@@ -0,0 +1,77 @@
+use dashmap::DashMap;
+use std::time::{Duration, Instant};
+
+const REFILL_PERIOD: Duration = Duration::from_secs(60); // 令牌按每分钟的配额匀速补充
+
+// 单个mint的令牌桶
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// 按mint的令牌桶限流：每个mint每分钟最多 `per_minute` 次，允许突发到 `per_minute` 次
+pub struct MintRateLimiter {
+    per_minute: u32,
+    buckets: DashMap<String, Bucket>, // mint -> 令牌桶
+}
+
+impl MintRateLimiter {
+    pub fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute: per_minute.max(1),
+            buckets: DashMap::new(),
+        }
+    }
+
+    pub fn per_minute(&self) -> u32 {
+        self.per_minute
+    }
+
+    /// 取一个令牌，该mint的配额已用完时返回false
+    pub fn try_acquire(&self, mint: &str) -> bool {
+        self.try_acquire_at(mint, Instant::now())
+    }
+
+    pub(crate) fn try_acquire_at(&self, mint: &str, now: Instant) -> bool {
+        let capacity = f64::from(self.per_minute);
+        let mut bucket = self
+            .buckets
+            .entry(mint.to_string())
+            .or_insert_with(|| Bucket { tokens: capacity, updated: now });
+        let elapsed = now.saturating_duration_since(bucket.updated);
+        bucket.tokens = (bucket.tokens + capacity * elapsed.as_secs_f64() / REFILL_PERIOD.as_secs_f64()).min(capacity);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 删除已补满（超过一个补充周期未使用）的令牌桶，返回删除的数量
+    pub fn prune(&self) -> usize {
+        let before = self.buckets.len();
+        self.buckets.retain(|_, bucket| bucket.updated.elapsed() < REFILL_PERIOD);
+        before - self.buckets.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mint_rate_limiter_refills_per_minute() {
+        let limiter = MintRateLimiter::new(2);
+        let start = std::time::Instant::now();
+        assert!(limiter.try_acquire_at("busy", start));
+        assert!(limiter.try_acquire_at("busy", start));
+        assert!(!limiter.try_acquire_at("busy", start));
+        // 其他代币有独立的配额
+        assert!(limiter.try_acquire_at("rare", start));
+        // 30秒补充一个令牌
+        assert!(limiter.try_acquire_at("busy", start + Duration::from_secs(30)));
+        assert!(!limiter.try_acquire_at("busy", start + Duration::from_secs(31)));
+    }
+}
@@ -0,0 +1,66 @@
+use crate::ws_server::TradeBroadcaster;
+use log::{debug, info, warn};
+use tokio::{
+    io::{AsyncWrite, AsyncWriteExt},
+    net::TcpListener,
+    sync::broadcast::{self, error::RecvError},
+};
+
+/// 启动TCP NDJSON服务，每个连接的客户端按行收到所有交易JSON（可直接用 `nc` 读取）
+pub async fn serve_tcp(port: u16, broadcaster: TradeBroadcaster) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", port)).await?;
+    info!("[NDJSON] TCP交易流已启动: 0.0.0.0:{}", port);
+
+    loop {
+        let (stream, addr) = listener.accept().await?;
+        let client = addr.to_string();
+        tokio::spawn(write_lines(stream, client, broadcaster.subscribe()));
+    }
+}
+
+/// 启动Unix域套接字NDJSON服务（可直接用 `socat` 读取），启动前删除残留的套接字文件
+#[cfg(unix)]
+pub async fn serve_unix(path: String, broadcaster: TradeBroadcaster) -> anyhow::Result<()> {
+    use tokio::net::UnixListener;
+
+    if std::path::Path::new(&path).exists() {
+        std::fs::remove_file(&path)?;
+    }
+    let listener = UnixListener::bind(&path)?;
+    info!("[NDJSON] Unix套接字交易流已启动: {}", path);
+
+    let mut next_id = 0u64;
+    loop {
+        let (stream, _) = listener.accept().await?;
+        next_id += 1;
+        let client = format!("{}#{}", path, next_id);
+        tokio::spawn(write_lines(stream, client, broadcaster.subscribe()));
+    }
+}
+
+#[cfg(not(unix))]
+pub async fn serve_unix(path: String, _broadcaster: TradeBroadcaster) -> anyhow::Result<()> {
+    Err(anyhow::anyhow!("当前平台不支持Unix域套接字: {}", path))
+}
+
+/// 向客户端逐行写入交易JSON；客户端读取太慢时丢弃落后的消息，不阻塞订阅循环
+async fn write_lines<W: AsyncWrite + Unpin>(mut writer: W, client: String, mut rx: broadcast::Receiver<String>) {
+    info!("[NDJSON] 客户端已连接: {}", client);
+    loop {
+        match rx.recv().await {
+            Ok(line) => {
+                let mut bytes = line.into_bytes();
+                bytes.push(b'\n');
+                if let Err(e) = writer.write_all(&bytes).await {
+                    debug!("[NDJSON] 客户端({})写入失败: {}", client, e);
+                    break;
+                }
+            }
+            Err(RecvError::Lagged(skipped)) => {
+                warn!("[NDJSON] 客户端({})落后，丢弃 {} 条消息", client, skipped);
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+    info!("[NDJSON] 客户端已断开: {}", client);
+}
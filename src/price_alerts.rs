@@ -0,0 +1,184 @@
+use chrono::Utc;
+use log::{error, info, warn};
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::Duration,
+};
+
+const WEBHOOK_TIMEOUT_SECS: u64 = 10; // 单次webhook请求超时（秒）
+const REARM_RATIO: f64 = 0.01; // 价格需回到阈值内侧1%以上才会再次触发，避免在阈值附近反复提醒
+
+/// 单个价格提醒配置
+#[derive(Debug, Deserialize, Clone)]
+pub struct PriceAlertConfig {
+    pub mint: String,
+    pub above: Option<f64>, // 价格涨到该值以上时提醒（SOL）
+    pub below: Option<f64>, // 价格跌到该值以下时提醒（SOL）
+    pub webhook_url: String,
+}
+
+/// 提醒的触发状态，已触发的方向在价格回到阈值内侧之前不会再次触发
+#[derive(Default)]
+struct AlertState {
+    above_fired: bool,
+    below_fired: bool,
+}
+
+struct PriceAlert {
+    config: PriceAlertConfig,
+    state: Mutex<AlertState>,
+}
+
+impl PriceAlert {
+    fn new(config: PriceAlertConfig) -> Self {
+        Self { config, state: Mutex::new(AlertState::default()) }
+    }
+
+    // 按最新价格更新触发状态，返回本次新越过的阈值 (方向, 阈值)
+    fn crossed(&self, price: f64) -> Vec<(&'static str, f64)> {
+        let mut state = self.state.lock().expect("price alert lock poisoned");
+        let mut triggered = Vec::new();
+        if let Some(above) = self.config.above {
+            if price >= above && !state.above_fired {
+                state.above_fired = true;
+                triggered.push(("above", above));
+            } else if price < above * (1.0 - REARM_RATIO) {
+                state.above_fired = false;
+            }
+        }
+        if let Some(below) = self.config.below {
+            if price <= below && !state.below_fired {
+                state.below_fired = true;
+                triggered.push(("below", below));
+            } else if price > below * (1.0 + REARM_RATIO) {
+                state.below_fired = false;
+            }
+        }
+        triggered
+    }
+}
+
+/// 价格提醒：账户监控更新储备后检查价格，越过阈值时向webhook发送POST请求
+pub struct PriceAlerts {
+    client: reqwest::Client,
+    alerts: HashMap<String, Vec<PriceAlert>>, // mint -> 提醒列表
+}
+
+impl PriceAlerts {
+    pub fn new(configs: Vec<PriceAlertConfig>) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+            .build()?;
+        let mut alerts: HashMap<String, Vec<PriceAlert>> = HashMap::new();
+        for config in configs {
+            if config.above.is_none() && config.below.is_none() {
+                warn!("[价格提醒] 代币({})未配置 above 或 below，忽略", config.mint);
+                continue;
+            }
+            info!("[价格提醒] 代币: {}, 高于: {:?}, 低于: {:?}", config.mint, config.above, config.below);
+            alerts.entry(config.mint.clone()).or_default().push(PriceAlert::new(config));
+        }
+        Ok(Self { client, alerts })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.alerts.is_empty()
+    }
+
+    /// 检查代币的最新价格，越过阈值时在后台发送webhook（发送失败只记录日志）
+    pub fn check(&self, mint: &str, price: f64, virtual_token_reserves: u64, virtual_sol_reserves: u64) {
+        let Some(alerts) = self.alerts.get(mint) else {
+            return;
+        };
+        for alert in alerts {
+            for (direction, threshold) in alert.crossed(price) {
+                info!("[价格提醒] 代币({})价格 {:.10} SOL 越过阈值 {} {}", mint, price, direction, threshold);
+                let payload = json!({
+                    "mint": mint,
+                    "price": price,
+                    "direction": direction,
+                    "threshold": threshold,
+                    "reserves": {
+                        "virtual_token_reserves": virtual_token_reserves,
+                        "virtual_sol_reserves": virtual_sol_reserves,
+                    },
+                    "timestamp": crate::format_time(Utc::now()),
+                });
+                tokio::spawn(post_webhook(self.client.clone(), alert.config.webhook_url.clone(), payload));
+            }
+        }
+    }
+}
+
+async fn post_webhook(client: reqwest::Client, url: String, payload: serde_json::Value) {
+    match client.post(&url).json(&payload).send().await {
+        Ok(response) if response.status().is_success() => {}
+        Ok(response) => error!("[价格提醒] webhook({})返回错误状态: {}", url, response.status()),
+        Err(e) => error!("[价格提醒] 发送webhook({})失败: {}", url, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(above: Option<f64>, below: Option<f64>) -> PriceAlert {
+        PriceAlert::new(PriceAlertConfig {
+            mint: "mint".to_string(),
+            above,
+            below,
+            webhook_url: "http://127.0.0.1/hook".to_string(),
+        })
+    }
+
+    #[test]
+    fn above_fires_once_until_rearmed() {
+        let alert = alert(Some(1.0), None);
+        assert!(alert.crossed(0.9).is_empty());
+        assert_eq!(alert.crossed(1.0), vec![("above", 1.0)]);
+        // 停留在阈值以上或只回落到阈值附近时不重复触发
+        assert!(alert.crossed(1.2).is_empty());
+        assert!(alert.crossed(0.995).is_empty());
+        assert!(alert.crossed(1.01).is_empty());
+        // 回落到阈值内侧1%以上后重新布防
+        assert!(alert.crossed(0.98).is_empty());
+        assert_eq!(alert.crossed(1.05), vec![("above", 1.0)]);
+    }
+
+    #[test]
+    fn below_fires_once_until_rearmed() {
+        let alert = alert(None, Some(1.0));
+        assert!(alert.crossed(1.1).is_empty());
+        assert_eq!(alert.crossed(0.9), vec![("below", 1.0)]);
+        assert!(alert.crossed(0.5).is_empty());
+        assert!(alert.crossed(1.005).is_empty());
+        assert!(alert.crossed(0.99).is_empty());
+        assert!(alert.crossed(1.02).is_empty());
+        assert_eq!(alert.crossed(1.0), vec![("below", 1.0)]);
+    }
+
+    #[test]
+    fn both_directions_tracked_independently() {
+        let alert = alert(Some(2.0), Some(1.0));
+        assert_eq!(alert.crossed(2.5), vec![("above", 2.0)]);
+        assert_eq!(alert.crossed(0.5), vec![("below", 1.0)]);
+        // 跌破下限的同时上限已重新布防
+        assert_eq!(alert.crossed(2.0), vec![("above", 2.0)]);
+        assert!(alert.crossed(2.1).is_empty());
+    }
+
+    #[test]
+    fn alerts_without_thresholds_ignored() {
+        let alerts = PriceAlerts::new(vec![PriceAlertConfig {
+            mint: "mint".to_string(),
+            above: None,
+            below: None,
+            webhook_url: "http://127.0.0.1/hook".to_string(),
+        }])
+        .unwrap();
+        assert!(alerts.is_empty());
+    }
+}
@@ -0,0 +1,231 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use pump_interface::TRADE_EVENT_EVENT_DISCM;
+use yellowstone_grpc_proto::prelude::TransactionStatusMeta;
+
+const PROGRAM_DATA_PREFIX: &str = "Program data: "; // Anchor `emit!` 事件在程序日志中的前缀
+const EVENT_IX_TAG: [u8; 8] = [0xe4, 0x45, 0xa5, 0x2e, 0x51, 0xcb, 0x9a, 0x1d]; // Anchor `emit_cpi!` 自调用指令的前缀
+
+/// PumpFun程序在买卖成交后发出的TradeEvent，金额和储备以链上实际成交为准
+///
+/// 较早版本的事件只有前8个字段，之后追加的字段按版本依次解码，不存在时为None。
+#[derive(Debug, Clone, PartialEq)]
+pub struct PumpTradeEvent {
+    pub mint: String,
+    pub sol_amount: u64,             // 进出曲线的SOL数量（不含费用）
+    pub token_amount: u64,           // 实际成交的代币数量
+    pub is_buy: bool,
+    pub user: String,
+    pub timestamp: i64,
+    pub virtual_sol_reserves: u64,   // 成交后的虚拟SOL储备
+    pub virtual_token_reserves: u64, // 成交后的虚拟代币储备
+    pub real_sol_reserves: Option<u64>,
+    pub real_token_reserves: Option<u64>,
+    pub fee_recipient: Option<String>,
+    pub fee_basis_points: Option<u64>,
+    pub fee: Option<u64>,            // 协议费（lamports）
+    pub creator: Option<String>,
+    pub creator_fee_basis_points: Option<u64>,
+    pub creator_fee: Option<u64>,    // 创作者费（lamports）
+}
+
+// 按Borsh格式顺序读取字段
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (head, rest) = self.0.split_first_chunk::<N>()?;
+        self.0 = rest;
+        Some(*head)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.bytes().map(u64::from_le_bytes)
+    }
+
+    fn i64(&mut self) -> Option<i64> {
+        self.bytes().map(i64::from_le_bytes)
+    }
+
+    fn bool(&mut self) -> Option<bool> {
+        self.bytes::<1>().map(|[byte]| byte != 0)
+    }
+
+    fn pubkey(&mut self) -> Option<String> {
+        self.bytes::<32>().map(|key| bs58::encode(key).into_string())
+    }
+}
+
+impl PumpTradeEvent {
+    /// 解码以事件discriminator开头的事件数据，不是TradeEvent或数据不完整时返回None
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let mut reader = Reader(data.strip_prefix(&TRADE_EVENT_EVENT_DISCM)?);
+        let mut event = Self {
+            mint: reader.pubkey()?,
+            sol_amount: reader.u64()?,
+            token_amount: reader.u64()?,
+            is_buy: reader.bool()?,
+            user: reader.pubkey()?,
+            timestamp: reader.i64()?,
+            virtual_sol_reserves: reader.u64()?,
+            virtual_token_reserves: reader.u64()?,
+            real_sol_reserves: None,
+            real_token_reserves: None,
+            fee_recipient: None,
+            fee_basis_points: None,
+            fee: None,
+            creator: None,
+            creator_fee_basis_points: None,
+            creator_fee: None,
+        };
+        if let (Some(sol), Some(token)) = (reader.u64(), reader.u64()) {
+            event.real_sol_reserves = Some(sol);
+            event.real_token_reserves = Some(token);
+        }
+        if let (Some(recipient), Some(basis_points), Some(fee)) = (reader.pubkey(), reader.u64(), reader.u64()) {
+            event.fee_recipient = Some(recipient);
+            event.fee_basis_points = Some(basis_points);
+            event.fee = Some(fee);
+        }
+        if let (Some(creator), Some(basis_points), Some(fee)) = (reader.pubkey(), reader.u64(), reader.u64()) {
+            event.creator = Some(creator);
+            event.creator_fee_basis_points = Some(basis_points);
+            event.creator_fee = Some(fee);
+        }
+        Some(event)
+    }
+
+    fn total_fees(&self) -> u64 {
+        self.fee.unwrap_or(0) + self.creator_fee.unwrap_or(0)
+    }
+
+    /// 买入的实际SOL花费：进入曲线的SOL加上协议费和创作者费
+    pub fn sol_cost(&self) -> u64 {
+        self.sol_amount + self.total_fees()
+    }
+
+    /// 卖出实际收到的SOL：曲线付出的SOL扣除协议费和创作者费
+    pub fn sol_output(&self) -> u64 {
+        self.sol_amount.saturating_sub(self.total_fees())
+    }
+
+    /// 由成交后的虚拟储备和成交数量倒推成交前的虚拟储备（代币, SOL），数据不一致时返回None
+    pub fn reserves_before(&self) -> Option<(u64, u64)> {
+        if self.is_buy {
+            Some((
+                self.virtual_token_reserves.checked_add(self.token_amount)?,
+                self.virtual_sol_reserves.checked_sub(self.sol_amount)?,
+            ))
+        } else {
+            Some((
+                self.virtual_token_reserves.checked_sub(self.token_amount)?,
+                self.virtual_sol_reserves.checked_add(self.sol_amount)?,
+            ))
+        }
+    }
+}
+
+/// 一笔交易中的所有TradeEvent，按买卖指令依次取用
+#[derive(Default)]
+pub struct TradeEvents {
+    events: Vec<Option<PumpTradeEvent>>,
+}
+
+impl TradeEvents {
+    /// 从程序日志的 `Program data:` 行和 `emit_cpi!` 自调用内部指令中收集TradeEvent
+    pub fn from_meta(meta: Option<&TransactionStatusMeta>) -> Self {
+        let Some(meta) = meta else {
+            return Self::default();
+        };
+        let from_logs = meta
+            .log_messages
+            .iter()
+            .filter_map(|line| line.strip_prefix(PROGRAM_DATA_PREFIX))
+            .filter_map(|data| STANDARD.decode(data.trim()).ok())
+            .filter_map(|data| PumpTradeEvent::decode(&data));
+        let from_inner_instructions = meta
+            .inner_instructions
+            .iter()
+            .flat_map(|inner| inner.instructions.iter())
+            .filter_map(|instruction| PumpTradeEvent::decode(instruction.data.strip_prefix(&EVENT_IX_TAG)?));
+        Self {
+            events: from_logs.chain(from_inner_instructions).map(Some).collect(),
+        }
+    }
+
+    /// 取出第一笔匹配代币、方向和用户的事件，同一事件只会被取用一次
+    pub fn take(&mut self, mint: &str, is_buy: bool, user: &str) -> Option<PumpTradeEvent> {
+        self.events
+            .iter_mut()
+            .find(|event| {
+                event
+                    .as_ref()
+                    .is_some_and(|event| event.mint == mint && event.is_buy == is_buy && event.user == user)
+            })?
+            .take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+    use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions};
+
+    #[test]
+    fn trade_events_decode_from_logs_and_self_cpi() {
+        let mint = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let creator = Pubkey::new_unique();
+        let encode = |is_buy: bool, sol_amount: u64, extended: bool| {
+            let mut data = TRADE_EVENT_EVENT_DISCM.to_vec();
+            data.extend_from_slice(mint.as_ref());
+            data.extend_from_slice(&sol_amount.to_le_bytes());
+            data.extend_from_slice(&5_000_000u64.to_le_bytes());
+            data.push(u8::from(is_buy));
+            data.extend_from_slice(user.as_ref());
+            data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+            data.extend_from_slice(&31_000_000_000u64.to_le_bytes());
+            data.extend_from_slice(&1_000_000_000_000u64.to_le_bytes());
+            if extended {
+                data.extend_from_slice(&1_000_000_000u64.to_le_bytes());
+                data.extend_from_slice(&700_000_000_000u64.to_le_bytes());
+                data.extend_from_slice(Pubkey::new_unique().as_ref());
+                data.extend_from_slice(&95u64.to_le_bytes());
+                data.extend_from_slice(&9_500_000u64.to_le_bytes());
+                data.extend_from_slice(creator.as_ref());
+                data.extend_from_slice(&5u64.to_le_bytes());
+                data.extend_from_slice(&500_000u64.to_le_bytes());
+            }
+            data
+        };
+
+        let mut self_cpi = EVENT_IX_TAG.to_vec();
+        self_cpi.extend(encode(false, 2_000_000_000, true));
+        let meta = TransactionStatusMeta {
+            log_messages: vec![
+                "Program 6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P invoke [1]".to_string(),
+                format!("{}{}", PROGRAM_DATA_PREFIX, STANDARD.encode(encode(true, 1_000_000_000, false))),
+                "Program data: not-base64!".to_string(),
+            ],
+            inner_instructions: vec![InnerInstructions {
+                index: 0,
+                instructions: vec![InnerInstruction { program_id_index: 0, accounts: vec![], data: self_cpi, stack_height: Some(2) }],
+            }],
+            ..Default::default()
+        };
+        let mut events = TradeEvents::from_meta(Some(&meta));
+
+        assert!(events.take(&mint.to_string(), true, &Pubkey::new_unique().to_string()).is_none());
+        let buy = events.take(&mint.to_string(), true, &user.to_string()).unwrap();
+        assert_eq!(buy.token_amount, 5_000_000);
+        assert_eq!(buy.virtual_token_reserves, 1_000_000_000_000);
+        assert_eq!(buy.real_sol_reserves, None);
+        assert_eq!(buy.sol_cost(), 1_000_000_000);
+        assert!(events.take(&mint.to_string(), true, &user.to_string()).is_none());
+
+        let sell = events.take(&mint.to_string(), false, &user.to_string()).unwrap();
+        assert_eq!(sell.real_token_reserves, Some(700_000_000_000));
+        assert_eq!(sell.creator, Some(creator.to_string()));
+        assert_eq!(sell.sol_output(), 2_000_000_000 - 9_500_000 - 500_000);
+    }
+}
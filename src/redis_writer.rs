@@ -0,0 +1,299 @@
+use crate::{
+    log_targets,
+    metrics::{Metrics, METRICS},
+};
+use log::{debug, error, info, warn};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::sync::mpsc;
+
+const REDIS_WRITE_QUEUE_SIZE: usize = 10_000; // 写入队列容量
+const REDIS_BATCH_SIZE: usize = 256; // 单个pipeline最多包含的命令数
+const REDIS_FLUSH_INTERVAL_MS: u64 = 5; // 批量刷新间隔（毫秒）
+const BREAKER_FAILURE_THRESHOLD: u32 = 5; // 连续失败多少次后打开断路器
+const BREAKER_PROBE_INTERVAL_SECS: u64 = 10; // 断路器打开期间探测Redis的间隔（秒）
+
+/// 一条待写入Redis的数据
+pub enum RedisWrite {
+    /// `SET key value EX ttl`
+    Set { key: String, value: String, ttl_secs: u64 },
+    /// `HSET key field value`（不过期）
+    HashSet { key: String, field: String, value: String },
+    /// `HDEL key field`
+    HashDelete { key: String, field: String },
+    /// `HSET key field value [field value ...]` 后 `EXPIRE key ttl`
+    Hash { key: String, fields: Vec<(String, String)>, ttl_secs: u64 },
+    /// `ZADD key score member`，同时删除score早于 `score - retention` 的成员并刷新过期时间
+    SortedSetAdd { key: String, score: i64, member: String, retention_secs: u64 },
+    /// `ZADD key score member`，只保留score最高的 `max_len` 个成员并刷新过期时间
+    CappedSortedSetAdd { key: String, score: u64, member: String, max_len: usize, ttl_secs: u64 },
+}
+
+impl RedisWrite {
+    fn key(&self) -> &str {
+        match self {
+            Self::Set { key, .. }
+            | Self::HashSet { key, .. }
+            | Self::HashDelete { key, .. }
+            | Self::Hash { key, .. }
+            | Self::SortedSetAdd { key, .. }
+            | Self::CappedSortedSetAdd { key, .. } => key,
+        }
+    }
+}
+
+/// Redis写入断路器
+///
+/// 连续失败达到阈值后打开：暂停所有Redis写入（只保留内存缓存），由后台任务定期探测，
+/// Redis恢复后关闭。打开和关闭只各输出一次日志，Redis不可用期间不会每次写入都报错。
+#[derive(Default)]
+struct CircuitBreaker {
+    consecutive_failures: AtomicU32,
+    open: AtomicBool,
+}
+
+impl CircuitBreaker {
+    fn is_open(&self) -> bool {
+        self.open.load(Ordering::SeqCst)
+    }
+
+    fn record_success(&self) {
+        self.consecutive_failures.store(0, Ordering::SeqCst);
+        if self.open.swap(false, Ordering::SeqCst) {
+            info!(target: log_targets::REDIS, "[Redis] 已恢复连接，关闭断路器，继续写入Redis");
+            METRICS.redis_circuit_open.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn record_failure(&self) {
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::SeqCst) + 1;
+        if failures >= BREAKER_FAILURE_THRESHOLD && !self.open.swap(true, Ordering::SeqCst) {
+            warn!(
+                target: log_targets::REDIS,
+                "[Redis] 连续 {} 次写入失败，打开断路器：暂停写入Redis只保留内存缓存，每 {} 秒探测一次",
+                failures, BREAKER_PROBE_INTERVAL_SECS
+            );
+            METRICS.redis_circuit_open.store(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Redis后台批量写入器
+///
+/// 缓存方法只需把数据放入队列，由单个后台任务合并为pipeline（`SET key value EX ttl`、`HSET` 或 `ZADD`）
+/// 每隔几毫秒或攒够一批后统一发送，避免每条数据单独建任务、多次往返。
+/// 断路器打开期间直接丢弃新数据并计入跳过的写入数。
+#[derive(Clone)]
+pub struct RedisWriter {
+    tx: mpsc::Sender<RedisWrite>,
+    // 已入队但尚未完成写入（成功或失败）的条目数
+    pending: Arc<AtomicUsize>,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl RedisWriter {
+    /// 创建写入器并启动后台写入任务和断路器探测任务（需在tokio运行时中调用）
+    pub fn spawn(client: Arc<redis::Client>) -> Self {
+        let (tx, rx) = mpsc::channel(REDIS_WRITE_QUEUE_SIZE);
+        let pending = Arc::new(AtomicUsize::new(0));
+        let breaker = Arc::new(CircuitBreaker::default());
+        tokio::spawn(run_writer(Arc::clone(&client), rx, Arc::clone(&pending), Arc::clone(&breaker)));
+        tokio::spawn(probe_while_open(client, Arc::clone(&breaker)));
+        Self { tx, pending, breaker }
+    }
+
+    /// 将数据放入写入队列，队列已满时丢弃并计入写入失败
+    pub fn write(&self, key: String, value: String, ttl_secs: u64) {
+        self.enqueue(RedisWrite::Set { key, value, ttl_secs });
+    }
+
+    /// 将哈希字段放入写入队列（不设置过期时间）
+    pub fn hset(&self, key: String, field: String, value: String) {
+        self.enqueue(RedisWrite::HashSet { key, field, value });
+    }
+
+    /// 将哈希字段的删除放入写入队列
+    pub fn hdel(&self, key: String, field: String) {
+        self.enqueue(RedisWrite::HashDelete { key, field });
+    }
+
+    /// 将整个哈希放入写入队列，写入后设置过期时间
+    pub fn write_hash(&self, key: String, fields: Vec<(String, String)>, ttl_secs: u64) {
+        if fields.is_empty() {
+            return;
+        }
+        self.enqueue(RedisWrite::Hash { key, fields, ttl_secs });
+    }
+
+    /// 将有序集合成员放入写入队列，只保留最近 `retention_secs` 内的成员（score为Unix秒）
+    pub fn zadd(&self, key: String, score: i64, member: String, retention_secs: u64) {
+        self.enqueue(RedisWrite::SortedSetAdd { key, score, member, retention_secs });
+    }
+
+    /// 将有序集合成员放入写入队列，只保留score最高的 `max_len` 个成员
+    pub fn zadd_capped(&self, key: String, score: u64, member: String, max_len: usize, ttl_secs: u64) {
+        self.enqueue(RedisWrite::CappedSortedSetAdd { key, score, member, max_len, ttl_secs });
+    }
+
+    /// 断路器未打开，Redis可以访问
+    pub fn is_available(&self) -> bool {
+        !self.breaker.is_open()
+    }
+
+    fn enqueue(&self, item: RedisWrite) {
+        if self.breaker.is_open() {
+            Metrics::inc(&METRICS.redis_writes_skipped);
+            return;
+        }
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        if let Err(e) = self.tx.try_send(item) {
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+            let item = match e {
+                mpsc::error::TrySendError::Full(item) | mpsc::error::TrySendError::Closed(item) => item,
+            };
+            warn!(target: log_targets::REDIS, "[Redis] 写入队列已满或已关闭，丢弃数据 (key: {})", item.key());
+            Metrics::inc(&METRICS.redis_write_failures);
+        }
+    }
+
+    /// 等待队列中的数据全部写入，超时返回false
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        while self.pending.load(Ordering::SeqCst) > 0 {
+            if tokio::time::Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(Duration::from_millis(REDIS_FLUSH_INTERVAL_MS)).await;
+        }
+        true
+    }
+}
+
+async fn run_writer(
+    client: Arc<redis::Client>,
+    mut rx: mpsc::Receiver<RedisWrite>,
+    pending: Arc<AtomicUsize>,
+    breaker: Arc<CircuitBreaker>,
+) {
+    let mut connection = None;
+    let mut batch = Vec::with_capacity(REDIS_BATCH_SIZE);
+
+    // 阻塞等待第一条数据，然后在刷新间隔内尽量攒满一批
+    while let Some(first) = rx.recv().await {
+        batch.push(first);
+        let deadline = tokio::time::Instant::now() + Duration::from_millis(REDIS_FLUSH_INTERVAL_MS);
+        while batch.len() < REDIS_BATCH_SIZE {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(item)) => batch.push(item),
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        let count = batch.len();
+        if breaker.is_open() {
+            // 断路器打开前已经入队的数据直接丢弃，不再尝试连接
+            Metrics::add(&METRICS.redis_writes_skipped, count as u64);
+            batch.clear();
+        } else {
+            flush(&client, &mut connection, &mut batch, &breaker).await;
+        }
+        pending.fetch_sub(count, Ordering::SeqCst);
+    }
+}
+
+/// 断路器打开期间定期用PING探测Redis，成功后关闭断路器
+async fn probe_while_open(client: Arc<redis::Client>, breaker: Arc<CircuitBreaker>) {
+    let mut ticker = tokio::time::interval(Duration::from_secs(BREAKER_PROBE_INTERVAL_SECS));
+    loop {
+        ticker.tick().await;
+        if !breaker.is_open() {
+            continue;
+        }
+        let probe = async {
+            let mut con = client.get_multiplexed_tokio_connection().await?;
+            redis::cmd("PING").query_async::<_, String>(&mut con).await
+        };
+        match tokio::time::timeout(Duration::from_secs(BREAKER_PROBE_INTERVAL_SECS), probe).await {
+            Ok(Ok(_)) => breaker.record_success(),
+            Ok(Err(e)) => debug!(target: log_targets::REDIS, "[Redis] 断路器探测失败: {}", e),
+            Err(_) => debug!(target: log_targets::REDIS, "[Redis] 断路器探测超时"),
+        }
+    }
+}
+
+async fn flush(
+    client: &redis::Client,
+    connection: &mut Option<redis::aio::MultiplexedConnection>,
+    batch: &mut Vec<RedisWrite>,
+    breaker: &CircuitBreaker,
+) {
+    if connection.is_none() {
+        match client.get_multiplexed_tokio_connection().await {
+            Ok(c) => *connection = Some(c),
+            Err(e) => {
+                error!(target: log_targets::REDIS, "[Redis] 获取连接失败，丢弃 {} 条数据: {}", batch.len(), e);
+                Metrics::add(&METRICS.redis_write_failures, batch.len() as u64);
+                breaker.record_failure();
+                batch.clear();
+                return;
+            }
+        }
+    }
+    let Some(con) = connection.as_mut() else {
+        return;
+    };
+
+    let mut pipe = redis::pipe();
+    for item in batch.iter() {
+        match item {
+            RedisWrite::Set { key, value, ttl_secs } => {
+                pipe.cmd("SET").arg(key).arg(value).arg("EX").arg(*ttl_secs).ignore();
+            }
+            RedisWrite::HashSet { key, field, value } => {
+                pipe.cmd("HSET").arg(key).arg(field).arg(value).ignore();
+            }
+            RedisWrite::HashDelete { key, field } => {
+                pipe.cmd("HDEL").arg(key).arg(field).ignore();
+            }
+            RedisWrite::Hash { key, fields, ttl_secs } => {
+                let command = pipe.cmd("HSET").arg(key);
+                for (field, value) in fields {
+                    command.arg(field).arg(value);
+                }
+                command.ignore();
+                pipe.cmd("EXPIRE").arg(key).arg(*ttl_secs).ignore();
+            }
+            RedisWrite::SortedSetAdd { key, score, member, retention_secs } => {
+                let oldest = score.saturating_sub(*retention_secs as i64);
+                pipe.cmd("ZADD").arg(key).arg(*score).arg(member).ignore();
+                pipe.cmd("ZREMRANGEBYSCORE").arg(key).arg("-inf").arg(format!("({}", oldest)).ignore();
+                pipe.cmd("EXPIRE").arg(key).arg(*retention_secs).ignore();
+            }
+            RedisWrite::CappedSortedSetAdd { key, score, member, max_len, ttl_secs } => {
+                pipe.cmd("ZADD").arg(key).arg(*score).arg(member).ignore();
+                pipe.cmd("ZREMRANGEBYRANK").arg(key).arg(0).arg(-(*max_len as i64) - 1).ignore();
+                pipe.cmd("EXPIRE").arg(key).arg(*ttl_secs).ignore();
+            }
+        }
+    }
+
+    match pipe.query_async::<_, ()>(con).await {
+        Ok(()) => {
+            debug!(target: log_targets::REDIS, "[Redis] 批量写入 {} 条数据", batch.len());
+            breaker.record_success();
+        }
+        Err(e) => {
+            error!(target: log_targets::REDIS, "[Redis] 批量写入 {} 条数据失败: {}", batch.len(), e);
+            Metrics::add(&METRICS.redis_write_failures, batch.len() as u64);
+            breaker.record_failure();
+            // 连接可能已断开，下次刷新时重新建立
+            *connection = None;
+        }
+    }
+    batch.clear();
+}
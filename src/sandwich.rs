@@ -0,0 +1,139 @@
+use dashmap::DashMap;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const RECENT_TRADES_PER_MINT: usize = 32; // 每个mint保留的最近成交笔数
+
+/// 用于匹配夹子交易的成交记录
+#[derive(Debug, Clone)]
+pub struct RecentTrade {
+    pub signer: String,
+    pub is_buy: bool,
+    pub slot: u64,
+    pub signature: String,
+}
+
+/// 疑似夹子交易：同一签名者先买后卖，中间夹着其他签名者的买入
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SandwichSuspect {
+    pub mint: String,
+    pub attacker: String,           // 前后两笔交易的签名者
+    pub victim: String,             // 被夹的买入签名者
+    pub front_run_signature: String,
+    pub victim_signature: String,
+    pub back_run_signature: String,
+    pub start_slot: u64,
+    pub end_slot: u64,
+}
+
+/// 按mint保留最近成交的环形缓冲区，卖出时检查是否构成夹子
+///
+/// 只按到达顺序和槽位匹配，同一槽位内的先后顺序无法确定，结果可能误报，仅供参考。
+pub struct SandwichDetector {
+    window_slots: u64,
+    recent: DashMap<String, VecDeque<RecentTrade>>, // mint -> 最近成交（按到达顺序）
+    latest_slot: AtomicU64,                         // 最近一笔成交的槽位，用于清理不活跃的mint
+}
+
+impl SandwichDetector {
+    pub fn new(window_slots: u64) -> Self {
+        Self {
+            window_slots,
+            recent: DashMap::new(),
+            latest_slot: AtomicU64::new(0),
+        }
+    }
+
+    pub fn window_slots(&self) -> u64 {
+        self.window_slots
+    }
+
+    /// 记录一笔成交；卖出与窗口内同一签名者的买入夹住其他签名者的买入时返回疑似夹子
+    pub fn record(&self, mint: &str, trade: RecentTrade) -> Option<SandwichSuspect> {
+        self.latest_slot.fetch_max(trade.slot, Ordering::Relaxed);
+        let mut trades = self.recent.entry(mint.to_string()).or_default();
+        let suspect = if trade.is_buy { None } else { self.find_sandwich(mint, &trades, &trade) };
+        if trades.len() >= RECENT_TRADES_PER_MINT {
+            trades.pop_front();
+        }
+        trades.push_back(trade);
+        suspect
+    }
+
+    fn find_sandwich(&self, mint: &str, trades: &VecDeque<RecentTrade>, back_run: &RecentTrade) -> Option<SandwichSuspect> {
+        let earliest_slot = back_run.slot.saturating_sub(self.window_slots);
+        let in_window = |trade: &RecentTrade| trade.slot >= earliest_slot && trade.slot <= back_run.slot;
+        // 从最近的成交往前找被夹的买入，再找它之前同一签名者的买入
+        for (victim_index, victim) in trades.iter().enumerate().rev() {
+            if !in_window(victim) {
+                continue;
+            }
+            if !victim.is_buy || victim.signer == back_run.signer {
+                continue;
+            }
+            let front_run = trades
+                .iter()
+                .take(victim_index)
+                .rev()
+                .find(|trade| trade.is_buy && trade.signer == back_run.signer && in_window(trade) && trade.slot <= victim.slot);
+            if let Some(front_run) = front_run {
+                return Some(SandwichSuspect {
+                    mint: mint.to_string(),
+                    attacker: back_run.signer.clone(),
+                    victim: victim.signer.clone(),
+                    front_run_signature: front_run.signature.clone(),
+                    victim_signature: victim.signature.clone(),
+                    back_run_signature: back_run.signature.clone(),
+                    start_slot: front_run.slot,
+                    end_slot: back_run.slot,
+                });
+            }
+        }
+        None
+    }
+
+    /// 删除最近一笔成交已超出窗口的mint，返回删除的数量
+    pub fn prune(&self) -> usize {
+        let earliest_slot = self.latest_slot.load(Ordering::Relaxed).saturating_sub(self.window_slots);
+        let before = self.recent.len();
+        self.recent.retain(|_, trades| trades.back().is_some_and(|trade| trade.slot >= earliest_slot));
+        before - self.recent.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sandwich_detected_within_slot_window() {
+        let trade = |signer: &str, is_buy: bool, slot: u64, signature: &str| RecentTrade {
+            signer: signer.to_string(),
+            is_buy,
+            slot,
+            signature: signature.to_string(),
+        };
+        let detector = SandwichDetector::new(2);
+        assert_eq!(detector.record("mint", trade("bot", true, 100, "front")), None);
+        assert_eq!(detector.record("mint", trade("user", true, 100, "victim")), None);
+        let suspect = detector.record("mint", trade("bot", false, 101, "back")).unwrap();
+        assert_eq!(suspect.attacker, "bot");
+        assert_eq!(suspect.victim, "user");
+        assert_eq!(
+            (suspect.front_run_signature.as_str(), suspect.victim_signature.as_str(), suspect.back_run_signature.as_str()),
+            ("front", "victim", "back")
+        );
+
+        // 超出槽位窗口或没有其他签名者的买入时不报告
+        let detector = SandwichDetector::new(2);
+        detector.record("mint", trade("bot", true, 100, "front"));
+        detector.record("mint", trade("user", true, 100, "victim"));
+        assert_eq!(detector.record("mint", trade("bot", false, 110, "late")), None);
+        detector.record("other", trade("bot", true, 110, "front2"));
+        assert_eq!(detector.record("other", trade("bot", false, 110, "back2")), None);
+        // 最近成交超出窗口的代币在清理时删除
+        detector.record("fresh", trade("user", true, 200, "new"));
+        assert_eq!(detector.prune(), 2);
+    }
+}
@@ -3,23 +3,6 @@ use solana_program::{pubkey::Pubkey, program_option::COption};
 use spl_token::instruction::{AuthorityType, TokenInstruction};
 use serde_with::{serde_as, DisplayFromStr};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TokenBalance {
-    pub account_index: i64,
-    pub mint: String,
-    pub owner: Option<String>,
-    pub program_id: Option<String>,
-    pub ui_token_amount: Option<UiTokenAmountWithPrice>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct UiTokenAmountWithPrice {
-    pub amount: String,
-    pub decimals: i64,
-    pub ui_amount: f64,
-    pub ui_amount_string: String,
-}
-
 // Helper function to convert COption<Pubkey> to Option<Pubkey>
 fn convert_coption<T>(coption: COption<T>) -> Option<T> {
     match coption {
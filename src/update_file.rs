@@ -0,0 +1,158 @@
+use futures::stream::{self, Stream};
+use log::{debug, error, info, warn};
+use std::{
+    fs,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    io::{AsyncReadExt, BufReader},
+    sync::mpsc,
+};
+use yellowstone_grpc_proto::{prelude::SubscribeUpdate, prost::Message};
+
+const CAPTURE_QUEUE_SIZE: usize = 4096; // 待写入的原始更新队列容量
+const MAX_RECORD_BYTES: usize = 1024 * 1024 * 1024; // 单条记录的最大长度，与gRPC最大消息大小一致
+pub const DEFAULT_CAPTURE_FILE_BYTES: u64 = 256 * 1024 * 1024; // 单个录制文件的默认滚动大小
+
+/// 原始gRPC更新录制器
+///
+/// 文件由连续的记录组成，每条记录为4字节小端长度加上 `SubscribeUpdate` 的protobuf编码，
+/// 可通过 `replay` 子命令按原顺序回放。写文件在独立的阻塞任务中进行，队列已满时丢弃更新并输出警告。
+#[derive(Clone)]
+pub struct UpdateCapture {
+    tx: mpsc::Sender<Vec<u8>>,
+}
+
+impl UpdateCapture {
+    /// 创建录制文件并启动后台写入任务（需在tokio运行时中调用）
+    ///
+    /// 录制文件名为 `path` 加上创建时的毫秒时间戳（如 `capture.bin` -> `capture_1718000000000.bin`），
+    /// 单个文件达到 `max_file_bytes` 时切换到新文件，每个文件都可单独回放。
+    pub fn spawn(path: &Path, max_file_bytes: u64) -> anyhow::Result<Self> {
+        let mut file = RollingCapture::create(path.to_path_buf(), max_file_bytes)?;
+        let (tx, mut rx) = mpsc::channel::<Vec<u8>>(CAPTURE_QUEUE_SIZE);
+        tokio::task::spawn_blocking(move || {
+            while let Some(record) = rx.blocking_recv() {
+                // 队列暂时没有更多数据时刷新，避免进程退出时丢失缓冲区中的记录
+                if let Err(e) = file.append(&record, rx.is_empty()) {
+                    error!("[录制] 写入录制文件({:?})失败: {}", file.current, e);
+                }
+            }
+            if let Err(e) = file.writer.flush() {
+                error!("[录制] 写入录制文件({:?})失败: {}", file.current, e);
+            }
+        });
+        Ok(Self { tx })
+    }
+
+    /// 将一条更新放入写入队列
+    pub fn record(&self, update: &SubscribeUpdate) {
+        if self.tx.try_send(encode_record(update)).is_err() {
+            warn!("[录制] 写入队列已满或已关闭，丢弃一条更新");
+        }
+    }
+}
+
+/// 正在写入的录制文件，达到大小上限时切换到新文件
+struct RollingCapture {
+    base: PathBuf,
+    max_file_bytes: u64,
+    current: PathBuf,
+    writer: BufWriter<fs::File>,
+    size: u64,
+}
+
+impl RollingCapture {
+    fn create(base: PathBuf, max_file_bytes: u64) -> anyhow::Result<Self> {
+        if let Some(dir) = base.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let (current, writer) = open_capture_file(&base)?;
+        Ok(Self { base, max_file_bytes, current, writer, size: 0 })
+    }
+
+    fn append(&mut self, record: &[u8], flush: bool) -> anyhow::Result<()> {
+        if self.size > 0 && self.size + record.len() as u64 > self.max_file_bytes {
+            self.writer.flush()?;
+            (self.current, self.writer) = open_capture_file(&self.base)?;
+            self.size = 0;
+        }
+        self.writer.write_all(record)?;
+        self.size += record.len() as u64;
+        if flush {
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// 以当前毫秒时间戳命名并创建新的录制文件
+fn open_capture_file(base: &Path) -> anyhow::Result<(PathBuf, BufWriter<fs::File>)> {
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    let stem = base.file_stem().and_then(|stem| stem.to_str()).unwrap_or("capture");
+    let file_name = match base.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) => format!("{}_{}.{}", stem, millis, extension),
+        None => format!("{}_{}", stem, millis),
+    };
+    let path = base.with_file_name(file_name);
+    let file = fs::OpenOptions::new().create(true).append(true).open(&path)?;
+    info!("[录制] 原始gRPC更新写入: {:?}", path);
+    Ok((path, BufWriter::new(file)))
+}
+
+/// 编码一条录制记录：4字节小端长度 + protobuf编码
+pub fn encode_record(update: &SubscribeUpdate) -> Vec<u8> {
+    let body = update.encode_to_vec();
+    let mut record = Vec::with_capacity(4 + body.len());
+    record.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    record.extend_from_slice(&body);
+    record
+}
+
+/// 按录制顺序读取文件中的更新；文件截断或内容损坏时产生一个错误后结束
+pub async fn replay(path: &Path) -> anyhow::Result<impl Stream<Item = Result<SubscribeUpdate, tonic::Status>>> {
+    let file = tokio::fs::File::open(path).await?;
+    info!("[回放] 从录制文件读取更新: {:?}", path);
+    let state = Some((BufReader::new(file), path.to_path_buf(), 0u64));
+    Ok(stream::unfold(state, |state| async move {
+        let (mut reader, path, count) = state?;
+        match read_record(&mut reader).await {
+            Ok(Some(update)) => Some((Ok(update), Some((reader, path, count + 1)))),
+            Ok(None) => {
+                debug!("[回放] 录制文件({:?})读取完毕，共 {} 条更新", path, count);
+                None
+            }
+            Err(e) => Some((
+                Err(tonic::Status::data_loss(format!("录制文件({:?})第 {} 条记录无效: {}", path, count + 1, e))),
+                None,
+            )),
+        }
+    }))
+}
+
+/// 读取一条记录，文件正好在记录边界结束时返回None
+async fn read_record(reader: &mut BufReader<tokio::fs::File>) -> anyhow::Result<Option<SubscribeUpdate>> {
+    let mut len_bytes = [0u8; 4];
+    let mut filled = 0;
+    while filled < len_bytes.len() {
+        let read = reader.read(&mut len_bytes[filled..]).await?;
+        if read == 0 {
+            if filled == 0 {
+                return Ok(None);
+            }
+            return Err(anyhow::anyhow!("记录长度被截断"));
+        }
+        filled += read;
+    }
+
+    let len = u32::from_le_bytes(len_bytes) as usize;
+    if len > MAX_RECORD_BYTES {
+        return Err(anyhow::anyhow!("记录长度 {} 超出上限", len));
+    }
+    let mut body = vec![0u8; len];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(SubscribeUpdate::decode(body.as_slice())?))
+}
+
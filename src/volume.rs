@@ -0,0 +1,137 @@
+use dashmap::DashMap;
+use serde::Serialize;
+use std::collections::VecDeque;
+
+// 窗口内的一笔成交
+struct VolumeSample {
+    timestamp_ms: i64,
+    is_buy: bool,
+    sol: f64,
+}
+
+// 单个mint的滑动窗口
+#[derive(Default)]
+struct MintWindow {
+    samples: VecDeque<VolumeSample>, // 按到达顺序
+    spiking: bool,                   // 窗口成交额是否已在阈值以上（回落后才会再次触发）
+}
+
+impl MintWindow {
+    fn expire(&mut self, earliest_ms: i64) {
+        while self.samples.front().is_some_and(|sample| sample.timestamp_ms < earliest_ms) {
+            self.samples.pop_front();
+        }
+    }
+
+    fn summarize(&self, window_secs: u64) -> MintVolume {
+        let mut volume = MintVolume { window_secs, buys: 0, sells: 0, buy_volume_sol: 0.0, sell_volume_sol: 0.0, volume_sol: 0.0 };
+        for sample in &self.samples {
+            if sample.is_buy {
+                volume.buys += 1;
+                volume.buy_volume_sol += sample.sol;
+            } else {
+                volume.sells += 1;
+                volume.sell_volume_sol += sample.sol;
+            }
+        }
+        volume.volume_sol = volume.buy_volume_sol + volume.sell_volume_sol;
+        volume
+    }
+}
+
+/// 代币在滑动窗口内的买卖笔数和SOL成交额
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct MintVolume {
+    pub window_secs: u64,
+    pub buys: u64,
+    pub sells: u64,
+    pub buy_volume_sol: f64,
+    pub sell_volume_sol: f64,
+    pub volume_sol: f64, // 买入和卖出成交额之和
+}
+
+/// 按mint统计最近 `window_secs` 秒内的成交，成交额越过阈值时报告成交量突增
+pub struct VolumeTracker {
+    window_secs: u64,
+    spike_threshold_sol: Option<f64>,
+    mints: DashMap<String, MintWindow>, // mint -> 滑动窗口
+}
+
+impl VolumeTracker {
+    pub fn new(window_secs: u64, spike_threshold_sol: Option<f64>) -> Self {
+        Self {
+            window_secs: window_secs.max(1),
+            spike_threshold_sol,
+            mints: DashMap::new(),
+        }
+    }
+
+    pub fn window_secs(&self) -> u64 {
+        self.window_secs
+    }
+
+    pub fn spike_threshold_sol(&self) -> Option<f64> {
+        self.spike_threshold_sol
+    }
+
+    fn earliest_ms(&self, now_ms: i64) -> i64 {
+        now_ms.saturating_sub(self.window_secs as i64 * 1000)
+    }
+
+    /// 记录一笔成交；窗口成交额从阈值以下越过阈值时返回当前统计，回落到阈值以下后才会再次返回
+    pub fn record(&self, mint: &str, timestamp_ms: i64, is_buy: bool, sol: f64) -> Option<MintVolume> {
+        let mut window = self.mints.entry(mint.to_string()).or_default();
+        window.expire(self.earliest_ms(timestamp_ms));
+        let threshold = self.spike_threshold_sol;
+        // 没有新成交期间成交额已随窗口移动回落到阈值以下
+        if window.spiking && threshold.is_some_and(|threshold| window.summarize(self.window_secs).volume_sol < threshold) {
+            window.spiking = false;
+        }
+        window.samples.push_back(VolumeSample { timestamp_ms, is_buy, sol });
+        let threshold = threshold?;
+        let volume = window.summarize(self.window_secs);
+        let above = volume.volume_sol >= threshold;
+        let crossed = above && !window.spiking;
+        window.spiking = above;
+        crossed.then_some(volume)
+    }
+
+    /// 代币当前窗口内的成交统计，窗口内没有成交时返回None
+    pub fn volume(&self, mint: &str, now_ms: i64) -> Option<MintVolume> {
+        let mut window = self.mints.get_mut(mint)?;
+        window.expire(self.earliest_ms(now_ms));
+        (!window.samples.is_empty()).then(|| window.summarize(self.window_secs))
+    }
+
+    /// 删除窗口内已没有成交的mint，返回删除的数量
+    pub fn prune(&self, now_ms: i64) -> usize {
+        let earliest_ms = self.earliest_ms(now_ms);
+        let before = self.mints.len();
+        self.mints.retain(|_, window| window.samples.back().is_some_and(|sample| sample.timestamp_ms >= earliest_ms));
+        before - self.mints.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volume_tracker_reports_spike_once_until_volume_falls_back() {
+        let tracker = VolumeTracker::new(60, Some(10.0));
+        assert_eq!(tracker.record("mint", 1_000, true, 4.0), None);
+        assert_eq!(tracker.record("mint", 2_000, false, 3.0), None);
+        let spike = tracker.record("mint", 3_000, true, 5.0).unwrap();
+        assert_eq!((spike.buys, spike.sells, spike.volume_sol), (2, 1, 12.0));
+        assert_eq!(spike.buy_volume_sol, 9.0);
+        // 仍在阈值以上时不重复报告
+        assert_eq!(tracker.record("mint", 4_000, true, 1.0), None);
+
+        // 窗口移动后前面的成交过期，成交额回落后再次越过阈值时重新报告
+        assert_eq!(tracker.volume("mint", 62_500).map(|volume| volume.buys), Some(2));
+        assert!(tracker.record("mint", 70_000, true, 11.0).is_some());
+        assert_eq!(tracker.volume("other", 70_000), None);
+        assert_eq!(tracker.prune(200_000), 1);
+        assert_eq!(tracker.volume("mint", 200_000), None);
+    }
+}
@@ -0,0 +1,88 @@
+use futures::{SinkExt, StreamExt};
+use log::{debug, info, warn};
+use std::net::SocketAddr;
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::broadcast::{self, error::RecvError},
+};
+use tokio_tungstenite::tungstenite::Message;
+
+const TRADE_CHANNEL_CAPACITY: usize = 1024; // 广播通道容量，客户端落后超过该数量时断开
+
+/// 实时交易广播：订阅循环发布交易JSON，所有WebSocket和NDJSON客户端接收
+#[derive(Clone)]
+pub struct TradeBroadcaster {
+    tx: broadcast::Sender<String>,
+}
+
+impl Default for TradeBroadcaster {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TradeBroadcaster {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(TRADE_CHANNEL_CAPACITY);
+        Self { tx }
+    }
+
+    /// 发布一条交易消息，没有客户端连接时直接丢弃
+    pub fn publish(&self, message: String) {
+        let _ = self.tx.send(message);
+    }
+
+    /// 订阅交易消息
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.tx.subscribe()
+    }
+}
+
+/// 启动WebSocket服务，每个连接的客户端都会收到所有交易消息
+pub async fn serve(port: u16, broadcaster: TradeBroadcaster) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", port)).await?;
+    info!("[WebSocket] 交易推送服务已启动: ws://0.0.0.0:{}", port);
+
+    loop {
+        let (stream, addr) = listener.accept().await?;
+        let rx = broadcaster.subscribe();
+        tokio::spawn(async move {
+            if let Err(e) = handle_client(stream, addr, rx).await {
+                debug!("[WebSocket] 客户端({})连接错误: {}", addr, e);
+            }
+        });
+    }
+}
+
+async fn handle_client(
+    stream: TcpStream,
+    addr: SocketAddr,
+    mut rx: broadcast::Receiver<String>,
+) -> anyhow::Result<()> {
+    let ws = tokio_tungstenite::accept_async(stream).await?;
+    let (mut sink, mut incoming) = ws.split();
+    info!("[WebSocket] 客户端已连接: {}", addr);
+
+    loop {
+        tokio::select! {
+            message = rx.recv() => match message {
+                Ok(text) => sink.send(Message::Text(text)).await?,
+                Err(RecvError::Lagged(skipped)) => {
+                    // 客户端处理太慢，断开连接而不是阻塞发布方
+                    warn!("[WebSocket] 客户端({})落后 {} 条消息，断开连接", addr, skipped);
+                    let _ = sink.send(Message::Close(None)).await;
+                    break;
+                }
+                Err(RecvError::Closed) => break,
+            },
+            message = incoming.next() => match message {
+                Some(Ok(Message::Close(_))) | None => break,
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.into()),
+            },
+        }
+    }
+
+    info!("[WebSocket] 客户端已断开: {}", addr);
+    Ok(())
+}